@@ -1,4 +1,4 @@
-/**!
+/*!
 freq
 
 Counts the frequencies of words read from the standard input, and print
@@ -14,6 +14,22 @@ marks, written in ASCII :
 
 The input terminates with EOF.
 
+It may be UTF-8, Latin-1 or UTF-16; see `--encoding` below.
+
+USAGE:
+
+    freq [--encoding auto|utf8|latin1|utf16] [--progress] [--timings] < input
+
+--encoding picks how the input bytes are decoded. The default, auto,
+follows a byte-order mark if present and otherwise takes UTF-8, falling
+back to Latin-1 when the start of the input isn't valid UTF-8.
+
+--progress shows bytes counted so far on stderr, with an ETA when the
+input is redirected from a file.
+
+--timings prints the time spent reading, tokenizing, counting, sorting
+and writing to stderr at exit.
+
 
 OUTPUT:
 
@@ -26,24 +42,109 @@ word-frequency counts, in descending order
 
 Assumptions:
 
- - Words are seperated with each other by space.
+ - Words are seperated with each other by whitespace.
 
- - Uppercase and lowercase are treated as the same
+ - Uppercase and lowercase are treated as the same, and so are curly
+   and straight apostrophes
 
  - Punctuation marks are not considered as part of word, they are trimed
- if appears right after some word
+   if appears right after some word
 
- - Only consider "." "," ":" "'" """ "?" "!" "(" ")" "[" "]" "-", other marks will be
- ignored
+ - Only the marks textkit's tokenizer trims, as the spellchecker does,
+   are considered ("." "," ":" ";" "'" """ "?" "!" "(" ")" "[" "]" "-"
+   and curly quotes), other marks will be ignored
 
-**/
+*/
 
-use std::io::{BufRead,BufReader,Read,stdin};
+extern crate textkit;
+
+use std::env;
+use std::io::{self,BufRead,BufReader,Read,stdin};
 use std::io::{Write, stdout};
+use std::process::exit;
+
+use textkit::encoding::{Decoder, Encoding};
+use textkit::progress;
+use textkit::timings::Timings;
+use textkit::tokenize::tokenize;
 
 fn main() {
-    let htable =  read_n_count_words(stdin());
-    write_word_frequency(htable, &mut stdout());
+    let options = parse_args(env::args().skip(1)).unwrap_or_else(|msg| {
+        eprintln!("freq: {}", msg);
+        eprintln!("usage: freq [--encoding auto|utf8|latin1|utf16] [--progress] [--timings]");
+        exit(2);
+    });
+
+    let mut timings = Timings::new(options.timings);
+
+    let bar = progress::bytes(options.progress, progress::stdin_len(), "counting");
+    let input = Decoder::new(bar.wrap_read(stdin()), options.encoding);
+    let htable = read_n_count_words(input, &mut timings).unwrap_or_else(|e| {
+        eprintln!("freq: {}", e);
+        exit(1);
+    });
+    bar.finish_and_clear();
+
+    write_word_frequency(htable, &mut stdout(), &mut timings);
+    timings.report();
+}
+
+#[derive(Debug, Default, PartialEq)]
+struct Options {
+    encoding: Encoding,
+    progress: bool,
+    timings:  bool,
+}
+
+fn parse_args<I: Iterator<Item = String>>(mut args: I) -> Result<Options, String> {
+    let mut options = Options::default();
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--encoding" => {
+                let value = args.next().ok_or("--encoding needs a value")?;
+                options.encoding = value.parse()?;
+            }
+            "--progress" => options.progress = true,
+            "--timings"  => options.timings = true,
+            _ => return Err(format!("unexpected argument `{}'", arg)),
+        }
+    }
+
+    Ok(options)
+}
+
+#[cfg(test)]
+mod parse_args_tests {
+    use super::{parse_args, Options};
+    use textkit::encoding::Encoding;
+
+    fn parse(args: &[&str]) -> Result<Options, String> {
+        parse_args(args.iter().map(|s| s.to_string()))
+    }
+
+    #[test]
+    fn defaults() {
+        assert_eq!(Ok(Options::default()), parse(&[]));
+    }
+
+    #[test]
+    fn reads_encoding() {
+        assert_eq!(Encoding::Latin1, parse(&["--encoding", "latin1"]).unwrap().encoding);
+    }
+
+    #[test]
+    fn reads_flags() {
+        assert!(parse(&["--progress"]).unwrap().progress);
+        assert!(parse(&["--timings"]).unwrap().timings);
+    }
+
+    #[test]
+    fn rejects_junk() {
+        assert!(parse(&["--encoding"]).is_err());
+        assert!(parse(&["--encoding", "ebcdic"]).is_err());
+        assert!(parse(&["words.txt"]).is_err());
+    }
 }
 
 type CountTable = std::collections::HashMap<String, usize>;
@@ -93,40 +194,39 @@ mod increment_word_tests {
         h.insert("two".to_owned(), 2);
         h.insert("three".to_owned(), 3);
 
-        return h;
-
+        h
     }
 }
 
-fn read_n_count_words<R: Read>(reader: R) -> CountTable {
+fn read_n_count_words<R: Read>(reader: R, timings: &mut Timings) -> io::Result<CountTable> {
     let mut table = CountTable::new();
     let mut lines = BufReader::new(reader).lines();
-    let marks: &[_] = &[',','.','!','?',':',';','(',')','\'','\"','[',']','-'];
 
-    while let Some(Ok(line)) = lines.next() {
-        let words: Vec<&str> = line.split(' ').collect();
+    while let Some(line) = timings.time("read", || lines.next()) {
+        let line = line?;
+        let words = timings.time("tokenize", || tokenize(&line));
 
-        for word in &words {
-            let word = &(*word).trim_matches(marks).to_lowercase();
-            if word.len() > 0 {
-                increment_word(&mut table, (*word).to_owned());
+        timings.time("count", || {
+            for word in words {
+                increment_word(&mut table, word);
             }
-        }
+        });
     }
 
-    return table;
+    Ok(table)
 }
 
 #[cfg(test)]
 mod read_n_count_test {
     use super::{read_n_count_words, CountTable};
     use std::io::{Read, Result};
+    use textkit::timings::Timings;
 
 
     #[test]
     fn read_five_words() {
         let mock_read = StringReader::new("two three\n two three three\n".to_owned());
-        let under_test = read_n_count_words(mock_read);
+        let under_test = read_n_count_words(mock_read, &mut Timings::default()).unwrap();
         let expected = fixture();
 
         assert_eq!(under_test.to_owned(), expected);
@@ -136,7 +236,7 @@ mod read_n_count_test {
     #[test]
     fn read_words_uppercase() {
         let mock_read = StringReader::new("Two  tHree\n TWO THREE three\n".to_owned());
-        let under_test = read_n_count_words(mock_read);
+        let under_test = read_n_count_words(mock_read, &mut Timings::default()).unwrap();
         let expected = fixture();
 
         assert_eq!(under_test.to_owned(), expected);
@@ -146,7 +246,7 @@ mod read_n_count_test {
     #[test]
     fn read_words_n_marks() {
         let mock_read = StringReader::new("\'one\' two, : \"three\"\n two? three (three)\n".to_owned());
-        let under_test = read_n_count_words(mock_read);
+        let under_test = read_n_count_words(mock_read, &mut Timings::default()).unwrap();
         let mut expected = fixture();
         expected.insert("one".to_owned(), 1);
 
@@ -154,13 +254,22 @@ mod read_n_count_test {
     }
 
 
+    #[test]
+    fn read_words_n_curly_quotes() {
+        let text = "\u{201c}two three\u{201d}; \u{2018}two\u{2019} three three\n";
+        let mock_read = StringReader::new(text.to_owned());
+        let under_test = read_n_count_words(mock_read, &mut Timings::default()).unwrap();
+
+        assert_eq!(under_test.to_owned(), fixture());
+    }
+
+
     fn fixture() -> CountTable {
         let mut h = CountTable::new();
         h.insert("two".to_owned(), 2);
         h.insert("three".to_owned(), 3);
 
-        return h;
-
+        h
     }
 
     struct StringReader {
@@ -187,38 +296,44 @@ mod read_n_count_test {
                 self.position += 1;
             }
 
-            return Ok(count);
+            Ok(count)
         }
     }
 }
 
-fn write_word_frequency<W: Write>(table: CountTable, writer: &mut W) {
+fn write_word_frequency<W: Write>(table: CountTable, writer: &mut W, timings: &mut Timings) {
 
-    let mut wf_pairs: Vec<(String, usize)> = Vec::new();
-    for (word, freq) in &table {
-        wf_pairs.push((word.to_owned(), freq.to_owned()));
-    }
+    let wf_pairs = timings.time("sort", || {
+        let mut wf_pairs: Vec<(String, usize)> = Vec::new();
+        for (word, freq) in &table {
+            wf_pairs.push((word.to_owned(), freq.to_owned()));
+        }
 
-    wf_pairs.sort_by(|a, b| b.1.cmp(&(a.1)));
+        wf_pairs.sort_by_key(|wf| std::cmp::Reverse(wf.1));
+        wf_pairs
+    });
 
-    for wf in &wf_pairs {
-        if let Err(x) = (*writer).write(&*(format!("{}\t:\t{}\n",wf.0, wf.1).into_bytes())){
-            panic!("Fail writing");
+    timings.time("write", || {
+        for wf in &wf_pairs {
+            if writer.write(&format!("{}\t:\t{}\n",wf.0, wf.1).into_bytes()).is_err() {
+                panic!("Fail writing");
+            }
         }
-    }
+    });
 }
 
 
 #[cfg(test)]
 mod write_counttable_test {
     use super::{write_word_frequency,CountTable};
+    use textkit::timings::Timings;
 
     #[test]
     fn write_empty_table() {
         let table = CountTable::new();
         let mut buf: Vec<u8> = Vec::new();
 
-        write_word_frequency(table, &mut buf);
+        write_word_frequency(table, &mut buf, &mut Timings::default());
         assert_eq!(String::from_utf8(buf).unwrap(), "");
     }
 
@@ -227,7 +342,7 @@ mod write_counttable_test {
         let table = fixture();
         let mut buf: Vec<u8> = Vec::new();
 
-        write_word_frequency(table, &mut buf);
+        write_word_frequency(table, &mut buf, &mut Timings::default());
         assert_eq!(String::from_utf8(buf).unwrap(), "three\t:\t3\ntwo\t:\t2\n");
     }
 
@@ -238,7 +353,7 @@ mod write_counttable_test {
         let mut buf: Vec<u8> = Vec::new();
 
         table.insert("one".to_owned(), 1);
-        write_word_frequency(table, &mut buf);
+        write_word_frequency(table, &mut buf, &mut Timings::default());
         assert_eq!(String::from_utf8(buf).unwrap(), "three\t:\t3\ntwo\t:\t2\none\t:\t1\n");
     }
 
@@ -248,8 +363,7 @@ mod write_counttable_test {
         h.insert("two".to_owned(), 2);
         h.insert("three".to_owned(), 3);
 
-        return h;
-
+        h
     }
 
 
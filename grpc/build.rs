@@ -0,0 +1,15 @@
+// Compiles proto/textkit.proto with protox, so building doesn't need a
+// protoc binary.
+
+fn main() {
+    #[cfg(feature = "grpc")]
+    {
+        println!("cargo:rerun-if-changed=proto/textkit.proto");
+
+        let descriptors = protox::compile(["proto/textkit.proto"], ["proto"])
+            .expect("compiling proto/textkit.proto");
+        tonic_prost_build::configure()
+            .compile_fds(descriptors)
+            .expect("generating gRPC code");
+    }
+}
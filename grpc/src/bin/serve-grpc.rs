@@ -0,0 +1,118 @@
+//! serve-grpc
+//!
+//! Trains a spellchecker on a corpus file and serves it, together with
+//! word counting, over gRPC (see `proto/textkit.proto`).
+//!
+//! USAGE:
+//!
+//!     serve-grpc [--listen ADDR] [--encoding auto|utf8|latin1|utf16] [--timings] CORPUS
+//!
+//! The server listens on 127.0.0.1:50051 unless --listen says otherwise.
+//! --timings prints the time spent reading, tokenizing and training to
+//! stderr once the model is ready, before the server starts.
+
+use std::env;
+use std::net::SocketAddr;
+use std::process::exit;
+
+use spellchecker::{train_from_file, Encoding, SpellChecker};
+use textkit::timings::Timings;
+use textkit_grpc::proto::text_kit_server::TextKitServer;
+use textkit_grpc::TextKitService;
+
+const USAGE: &str =
+    "usage: serve-grpc [--listen ADDR] [--encoding auto|utf8|latin1|utf16] [--timings] CORPUS";
+
+#[tokio::main]
+async fn main() {
+    let options = parse_args(env::args().skip(1)).unwrap_or_else(|msg| {
+        eprintln!("serve-grpc: {}", msg);
+        eprintln!("{}", USAGE);
+        exit(2);
+    });
+
+    let mut timings = Timings::new(options.timings);
+    let model = train_from_file(&options.corpus, options.encoding, false, 1, &mut timings)
+        .unwrap_or_else(|e| {
+            eprintln!("serve-grpc: {}: {}", options.corpus, e);
+            exit(1);
+        });
+    timings.report();
+    let service = TextKitService::new(SpellChecker::new(model));
+
+    eprintln!("serve-grpc: listening on {}", options.listen);
+    let served = tonic::transport::Server::builder()
+        .add_service(TextKitServer::new(service))
+        .serve(options.listen)
+        .await;
+
+    if let Err(e) = served {
+        eprintln!("serve-grpc: {}", e);
+        exit(1);
+    }
+}
+
+#[derive(Debug, PartialEq)]
+struct Options {
+    listen:   SocketAddr,
+    corpus:   String,
+    encoding: Encoding,
+    timings:  bool,
+}
+
+fn parse_args<I: Iterator<Item = String>>(mut args: I) -> Result<Options, String> {
+    let mut listen = SocketAddr::from(([127, 0, 0, 1], 50051));
+    let mut encoding = Encoding::Auto;
+    let mut corpus = None;
+    let mut timings = false;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--listen" => {
+                let value = args.next().ok_or("--listen needs a value")?;
+                listen = value.parse().map_err(|_| format!("bad address `{}'", value))?;
+            }
+            "--encoding" => {
+                let value = args.next().ok_or("--encoding needs a value")?;
+                encoding = value.parse()?;
+            }
+            "--timings" => timings = true,
+            _ if arg.starts_with("--") => return Err(format!("unknown option `{}'", arg)),
+            _ if corpus.is_none() => corpus = Some(arg),
+            _ => return Err(format!("unexpected argument `{}'", arg)),
+        }
+    }
+
+    Ok(Options {
+        listen,
+        corpus: corpus.ok_or("no corpus given")?,
+        encoding,
+        timings,
+    })
+}
+
+#[cfg(test)]
+mod parse_args_tests {
+    use super::parse_args;
+
+    #[test]
+    fn reads_listen_address() {
+        let args = ["--listen", "0.0.0.0:7000", "big.txt"].iter().map(|s| s.to_string());
+        let options = parse_args(args).unwrap();
+
+        assert_eq!("0.0.0.0:7000".parse(), Ok(options.listen));
+        assert_eq!("big.txt", options.corpus);
+    }
+
+    #[test]
+    fn reads_timings() {
+        let args = ["--timings", "big.txt"].iter().map(|s| s.to_string());
+        assert!(parse_args(args).unwrap().timings);
+    }
+
+    #[test]
+    fn rejects_bad_address() {
+        let args = ["--listen", "nowhere", "big.txt"].iter().map(|s| s.to_string());
+        assert!(parse_args(args).is_err());
+    }
+}
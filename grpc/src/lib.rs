@@ -0,0 +1,58 @@
+//! A gRPC front end for the spellchecker and freq engines, so that
+//! services in other languages can call them with generated clients
+//! instead of parsing command-line output. The protocol lives in
+//! `proto/textkit.proto`; the server is the `serve-grpc` binary.
+//!
+//! Everything that touches tonic is behind the `grpc` feature. The
+//! request handling itself doesn't need it and is always built.
+//!
+//! `cargo build --features grpc` fetches tonic, prost and protox from
+//! crates.io, so it needs the network or a registry cache holding them;
+//! `build.rs` compiles the protocol with protox, so no `protoc` is
+//! needed. Cargo.lock isn't committed, here as anywhere in the
+//! repository: the server was last built with tonic 0.14.6, prost
+//! 0.14.4, protox 0.9.1 and tokio 1.53.2, on Rust 1.95.
+
+use std::cmp::Reverse;
+use std::collections::HashMap;
+
+use textkit::tokenize::tokenize;
+
+#[cfg(feature = "grpc")]
+pub mod proto {
+    tonic::include_proto!("textkit");
+}
+
+#[cfg(feature = "grpc")]
+mod service;
+
+#[cfg(feature = "grpc")]
+pub use service::TextKitService;
+
+/// The `k` most frequent words of `text`, tokenized as freq and the
+/// spellchecker tokenize a corpus (see `textkit::tokenize`), most
+/// frequent first and alphabetical among equals.
+pub fn count_top_k(text: &str, k: usize) -> Vec<(String, usize)> {
+    let mut table: HashMap<String, usize> = HashMap::new();
+    for line in text.lines() {
+        for word in tokenize(line) {
+            *table.entry(word).or_insert(0) += 1;
+        }
+    }
+
+    let mut counts: Vec<_> = table.into_iter().collect();
+    counts.sort_by(|a, b| (Reverse(a.1), &a.0).cmp(&(Reverse(b.1), &b.0)));
+    counts.truncate(k);
+    counts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::count_top_k;
+
+    #[test]
+    fn counts_top_k() {
+        let counts = count_top_k("b a, B\nc (a) b", 2);
+        assert_eq!(vec![("b".to_owned(), 3), ("a".to_owned(), 2)], counts);
+    }
+}
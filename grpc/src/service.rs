@@ -0,0 +1,122 @@
+//! The tonic service implementation.
+
+use tonic::{Request, Response, Status};
+
+use spellchecker::{fold_word, CheckResult, SpellChecker};
+
+use crate::proto::text_kit_server::TextKit;
+use crate::proto::{
+    Candidate, CheckRequest, CheckResponse, Correction, CountTopKRequest, CountTopKResponse,
+    SuggestRequest, SuggestResponse, WordCount,
+};
+
+pub struct TextKitService {
+    checker: SpellChecker,
+}
+
+impl TextKitService {
+    pub fn new(checker: SpellChecker) -> Self {
+        TextKitService { checker }
+    }
+}
+
+#[tonic::async_trait]
+impl TextKit for TextKitService {
+    async fn check(&self, request: Request<CheckRequest>)
+                   -> Result<Response<CheckResponse>, Status> {
+        let corrections = request.into_inner().words.into_iter()
+            .map(|word| {
+                let word = fold_word(word.trim());
+                let (known, suggestion) = match self.checker.check_word(None, &word, 1) {
+                    CheckResult::Correct => (true, word.clone()),
+                    CheckResult::Suspect(mut suggestions) => (true, suggestions.remove(0).word),
+                    CheckResult::Corrected(mut suggestions) => {
+                        (false, suggestions.remove(0).word)
+                    }
+                    CheckResult::Unknown => (false, String::new()),
+                };
+                Correction { word, known, suggestion }
+            })
+            .collect();
+
+        Ok(Response::new(CheckResponse { corrections }))
+    }
+
+    async fn suggest(&self, request: Request<SuggestRequest>)
+                     -> Result<Response<SuggestResponse>, Status> {
+        let request = request.into_inner();
+        let word = fold_word(request.word.trim());
+
+        let candidates = self.checker.suggest(&word, request.n as usize)
+            .into_iter()
+            .map(|(word, count, distance)| Candidate {
+                word,
+                distance: distance as u32,
+                count:    count as u64,
+            })
+            .collect();
+
+        Ok(Response::new(SuggestResponse { candidates }))
+    }
+
+    async fn count_top_k(&self, request: Request<CountTopKRequest>)
+                         -> Result<Response<CountTopKResponse>, Status> {
+        let request = request.into_inner();
+
+        let counts = crate::count_top_k(&request.text, request.k as usize)
+            .into_iter()
+            .map(|(word, count)| WordCount { word, count: count as u64 })
+            .collect();
+
+        Ok(Response::new(CountTopKResponse { counts }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TextKitService;
+    use crate::proto::text_kit_server::TextKit;
+    use crate::proto::{CheckRequest, CountTopKRequest, SuggestRequest};
+    use spellchecker::{SpellChecker, Trie};
+    use tonic::Request;
+
+    #[tokio::test]
+    async fn answers_each_rpc() {
+        let service = fixture();
+
+        let check = service.check(Request::new(CheckRequest {
+            words: vec!["Hello".to_owned(), "wrld".to_owned(), "zzzzzz".to_owned(),
+                        "Cafe\u{301}".to_owned(), "DON\u{2019}T".to_owned()],
+        })).await.unwrap().into_inner();
+        let summary: Vec<_> = check.corrections.iter()
+            .map(|c| (c.word.as_str(), c.known, c.suggestion.as_str()))
+            .collect();
+        assert_eq!(vec![("hello", true, "hello"), ("wrld", false, "world"), ("zzzzzz", false, ""),
+                        ("café", true, "café"), ("don't", true, "don't")],
+                   summary);
+
+        let suggest = service.suggest(Request::new(SuggestRequest {
+            word: "wrld".to_owned(),
+            n:    1,
+        })).await.unwrap().into_inner();
+        assert_eq!(1, suggest.candidates.len());
+        assert_eq!("world", suggest.candidates[0].word);
+        assert_eq!(1, suggest.candidates[0].distance);
+
+        let count = service.count_top_k(Request::new(CountTopKRequest {
+            text: "a b a".to_owned(),
+            k:    1,
+        })).await.unwrap().into_inner();
+        assert_eq!(1, count.counts.len());
+        assert_eq!(("a", 2), (count.counts[0].word.as_str(), count.counts[0].count));
+    }
+
+    fn fixture() -> TextKitService {
+        let mut model = Trie::new();
+        model.insert("hello");
+        model.insert("world");
+        model.insert("café");
+        model.insert("don't");
+        TextKitService::new(SpellChecker::new(model))
+    }
+}
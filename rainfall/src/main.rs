@@ -1,4 +1,4 @@
-/**!
+/*!
  * rainfall
  *
  * Reads a sequence of rainfall measurements from the standard input and
@@ -37,11 +37,11 @@
  * ASSUMPTIONS
  *
  *  - Numbers are read according to the language’s number reading
- *  routines, in particular the trait FromStr for type f64. This means
- *  that scientific notation ("3.4E22") is accepted, but hex is not.
+ *    routines, in particular the trait FromStr for type f64. This means
+ *    that scientific notation ("3.4E22") is accepted, but hex is not.
  *
  *  - A line containing more than one number is noise and should be
- *  ignored.
+ *    ignored.
  *
  *  - The terminator is a line of text "999", not a line of text that
  *    when interpreted is merely the number 999.0.
@@ -51,13 +51,38 @@
  *
  *  - If there are no measurements to read then there is no mean value
  *    to print, so
+ *
+ * OPTIONS
+ *
+ *  --timings prints the time spent reading, calculating and writing to
+ *  stderr at exit.
  */
 
+extern crate textkit;
+
+use std::env;
 use std::io::{BufRead,BufReader,Read,stdin};
+use std::process::exit;
+
+use textkit::timings::Timings;
 
 fn main() {
-    let measurements = read_measurements(stdin());
-    produce_output(&calculate_results(&measurements));
+    let mut timings = Timings::new(false);
+    for arg in env::args().skip(1) {
+        match arg.as_str() {
+            "--timings" => timings = Timings::new(true),
+            _ => {
+                eprintln!("rainfall: unexpected argument `{}'", arg);
+                eprintln!("usage: rainfall [--timings]");
+                exit(2);
+            }
+        }
+    }
+
+    let measurements = timings.time("read", || read_measurements(stdin()));
+    let results = timings.time("calculate", || calculate_results(&measurements));
+    timings.time("write", || produce_output(&results));
+    timings.report();
 }
 
 struct Results {
@@ -80,7 +105,7 @@ fn read_measurements<R: Read>(reader: R) -> Vec<f64> {
         }
     }
 
-    return measurements;
+    measurements
 }
 
 #[cfg(test)]
@@ -138,7 +163,7 @@ mod read_measurements_test {
                 self.position += 1;
             }
 
-            return Ok(count);
+            Ok(count)
         }
     }
 }
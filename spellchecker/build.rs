@@ -0,0 +1,27 @@
+// With the header feature, regenerates include/spellchecker.h from the
+// C interface in src/ffi.rs with cbindgen. Without it the header that
+// is checked in is left as it is, and nothing is needed to build.
+
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+
+    #[cfg(feature = "header")]
+    {
+        println!("cargo:rerun-if-changed=src/ffi.rs");
+
+        let config = cbindgen::Config {
+            language: cbindgen::Language::C,
+            include_guard: Some("SPELLCHECKER_H".to_owned()),
+            header: Some("/* Generated from src/ffi.rs by cbindgen; build with --features \
+                          header to update. */".to_owned()),
+            usize_is_size_t: true,
+            ..Default::default()
+        };
+        cbindgen::Builder::new()
+            .with_config(config)
+            .with_src("src/ffi.rs")
+            .generate()
+            .expect("generating include/spellchecker.h")
+            .write_to_file("include/spellchecker.h");
+    }
+}
@@ -0,0 +1,99 @@
+/* Generated from src/ffi.rs by cbindgen; build with --features header to update. */
+
+#ifndef SPELLCHECKER_H
+#define SPELLCHECKER_H
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/**
+ * A model for C programs to train and check words against.
+ */
+typedef struct Speller Speller;
+
+/**
+ * A new model, knowing no words.
+ */
+struct Speller *spellchecker_new(void);
+
+/**
+ * Counts the words of `text`, running text tokenized as a corpus is.
+ * Returns 0, or -1 if `text` isn't UTF-8.
+ *
+ * # Safety
+ *
+ * `speller` must come from `spellchecker_new` and not have been freed,
+ * and `text` must be a NUL-terminated string.
+ */
+int spellchecker_train(struct Speller *speller, const char *text);
+
+/**
+ * Counts the words of the corpus file at `path`, which may be gzip or
+ * zstd compressed. Returns 0, or -1 if it can't be read.
+ *
+ * # Safety
+ *
+ * As for `spellchecker_train`, with `path` for `text`.
+ */
+int spellchecker_train_file(struct Speller *speller, const char *path);
+
+/**
+ * The best correction for `word`, as `check_spelling` finds it: the
+ * word itself if it is known, and NULL if nothing is close enough.
+ * Free it with `spellchecker_free_string`.
+ *
+ * # Safety
+ *
+ * `speller` must come from `spellchecker_new` and not have been freed,
+ * and `word` must be a NUL-terminated string.
+ */
+char *spellchecker_check(const struct Speller *speller, const char *word);
+
+/**
+ * Up to `n` corrections for `word`, best first, as `suggest` finds
+ * them, in an array of `*count` strings; NULL, with `*count` 0, if there
+ * are none. Free it with `spellchecker_free_suggestions`.
+ *
+ * # Safety
+ *
+ * As for `spellchecker_check`, and `count` must point to a `size_t`.
+ */
+char **spellchecker_suggest(const struct Speller *speller,
+                            const char *word,
+                            size_t n,
+                            size_t *count);
+
+/**
+ * Frees a string from `spellchecker_check`.
+ *
+ * # Safety
+ *
+ * `string` must come from `spellchecker_check`, or be NULL, and not
+ * have been freed already.
+ */
+void spellchecker_free_string(char *string);
+
+/**
+ * Frees the `count` strings from `spellchecker_suggest`.
+ *
+ * # Safety
+ *
+ * `suggestions` must come from `spellchecker_suggest`, with the count
+ * it gave, or be NULL, and not have been freed already.
+ */
+void spellchecker_free_suggestions(char **suggestions, size_t count);
+
+/**
+ * Frees a model from `spellchecker_new`.
+ *
+ * # Safety
+ *
+ * `speller` must come from `spellchecker_new`, or be NULL, and not have
+ * been freed already.
+ */
+void spellchecker_free(struct Speller *speller);
+
+#endif  /* SPELLCHECKER_H */
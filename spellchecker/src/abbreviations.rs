@@ -0,0 +1,73 @@
+/*!
+Abbreviations: shorthand to expand rather than correct.
+
+An abbreviation file has an abbreviation on each line, then whitespace,
+then what it stands for, which may be several words:
+
+```text
+govt   government
+asap   as soon as possible
+```
+
+Abbreviations are lowercased the way the corpus tokenizer does it, and
+blank lines and lines starting with `#` are skipped, as in a personal
+dictionary.
+*/
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read};
+use std::path::Path;
+
+use textkit::tokenize::fold_word;
+
+/// Reads abbreviations and their expansions from `reader`, which must
+/// produce UTF-8.
+pub fn read_abbreviations<R: Read>(reader: R) -> io::Result<HashMap<String, String>> {
+    let mut abbreviations = HashMap::new();
+
+    for (i, line) in BufReader::new(reader).lines().enumerate() {
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        match line.split_once(char::is_whitespace) {
+            Some((abbreviation, expansion)) => {
+                abbreviations.insert(fold_word(abbreviation), expansion.trim().to_owned());
+            }
+            None => {
+                return Err(io::Error::new(io::ErrorKind::InvalidData,
+                                          format!("line {}: expected an abbreviation and its \
+                                                   expansion", i + 1)));
+            }
+        }
+    }
+
+    Ok(abbreviations)
+}
+
+/// Reads the abbreviations at `path`.
+pub fn load_abbreviations<P: AsRef<Path>>(path: P) -> io::Result<HashMap<String, String>> {
+    read_abbreviations(File::open(path)?)
+}
+
+#[cfg(test)]
+mod read_abbreviations_tests {
+    use super::read_abbreviations;
+
+    #[test]
+    fn reads_expansions() {
+        let text = "# shorthand\nGovt\tgovernment\n\n  asap  as soon  as possible \n";
+        let abbreviations = read_abbreviations(text.as_bytes()).unwrap();
+
+        assert_eq!(2, abbreviations.len());
+        assert_eq!("government", abbreviations["govt"]);
+        assert_eq!("as soon  as possible", abbreviations["asap"]);
+    }
+
+    #[test]
+    fn rejects_bare_abbreviations() {
+        assert!(read_abbreviations(&b"govt government\ndept\n"[..]).is_err());
+    }
+}
@@ -0,0 +1,225 @@
+/*!
+A trie over bytes, for models with nothing but ASCII in them.
+
+English text rarely needs more than a `u8` per letter, and a trie that
+can never be added to again needn't keep a list of children per node.
+An `AsciiTrie` numbers the nodes of a `Trie` breadth first, so the
+children of each node are a run of consecutive nodes; all a node has to
+keep is its count, the byte leading to it and where its children start.
+That takes a fraction of the room of a `Trie`'s nodes and keeps the
+search's walk to a few flat arrays.
+
+`SpellChecker` keeps one as its model, in place of the trie it was
+trained as, whenever the model is all ASCII.
+*/
+
+use std::collections::VecDeque;
+use std::mem;
+
+use trie::{self, rank, Graph, Stats, Trie, ROOT};
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct AsciiTrie {
+    /// The children of node `n` are nodes `first[n]..first[n + 1]`, sorted
+    /// by byte.
+    first:  Vec<u32>,
+    /// The byte leading to each node; the root's is never read.
+    keys:   Vec<u8>,
+    counts: Vec<usize>,
+}
+
+impl Trie {
+    /// An `AsciiTrie` of the same words, or `None` if any of them is not
+    /// ASCII.
+    pub fn to_ascii(&self) -> Option<AsciiTrie> {
+        let mut first = Vec::new();
+        let mut keys = vec![0];
+        let mut counts = Vec::new();
+
+        // Each node's children are numbered as it is taken off the queue,
+        // right after those of the node before it.
+        let mut queue = VecDeque::from(vec![ROOT]);
+        while let Some(id) = queue.pop_front() {
+            let node = self.node(id);
+            first.push(keys.len() as u32);
+            counts.push(node.count);
+            for &(c, child) in &node.children {
+                if !c.is_ascii() {
+                    return None;
+                }
+                keys.push(c as u8);
+                queue.push_back(child);
+            }
+        }
+        first.push(keys.len() as u32);
+        // Kept for as long as the checker is, so it shouldn't keep the
+        // room the vectors grew into.
+        first.shrink_to_fit();
+        keys.shrink_to_fit();
+        counts.shrink_to_fit();
+
+        Some(AsciiTrie { first, keys, counts })
+    }
+}
+
+impl AsciiTrie {
+    /// How many times `word` was inserted into the trie this came from.
+    pub fn search(&self, word: &str) -> usize {
+        let mut node = 0;
+        for c in word.chars() {
+            match self.child(node, c) {
+                Some(child) => node = child,
+                None => return 0,
+            }
+        }
+        self.counts[node as usize]
+    }
+
+    /// See `Trie::search_with_k_edit`.
+    pub fn search_with_k_edit(&self, word: &str, k: usize) -> Vec<(String, usize, usize)> {
+        let word: Vec<char> = word.chars().collect();
+        let mut found = trie::search_with_k_edit(self, &word, k);
+        rank(&mut found);
+        found
+    }
+
+    /// See `Trie::complete`.
+    pub fn complete(&self, prefix: &str, n: usize) -> Vec<(String, usize)> {
+        trie::complete(self, prefix, n)
+    }
+
+    /// Every word in the trie with its count, in alphabetical order.
+    pub(crate) fn words(&self) -> Vec<(String, usize)> {
+        let mut words = Vec::new();
+        let mut stack = vec![(0, String::new())];
+        while let Some((node, word)) = stack.pop() {
+            if self.counts[node as usize] > 0 {
+                words.push((word.clone(), self.counts[node as usize]));
+            }
+            // Last child first, so the first comes off the stack first.
+            for child in (self.first[node as usize]..self.first[node as usize + 1]).rev() {
+                let mut longer = word.clone();
+                longer.push(self.keys[child as usize] as char);
+                stack.push((child, longer));
+            }
+        }
+        words
+    }
+
+    /// How big the trie is; the same as for the trie this came from, but
+    /// for `heap_bytes`.
+    pub fn stats(&self) -> Stats {
+        // Children come after their parents, so working backwards finds
+        // the words below each node, and the longest of them, in order.
+        let nodes = self.counts.len();
+        let mut words = vec![0; nodes];
+        let mut depth = vec![0; nodes];
+        for id in (0..nodes).rev() {
+            let children = self.first[id] as usize..self.first[id + 1] as usize;
+            words[id] = (self.counts[id] > 0) as usize
+                + children.clone().map(|child| words[child]).sum::<usize>();
+            depth[id] = children.map(|child| depth[child] + 1).max().unwrap_or(0);
+        }
+
+        Stats {
+            words: words[0],
+            nodes,
+            depth: depth[0],
+            heap_bytes: self.first.capacity() * mem::size_of::<u32>()
+                + self.keys.capacity()
+                + self.counts.capacity() * mem::size_of::<usize>(),
+        }
+    }
+}
+
+impl Graph for &AsciiTrie {
+    type Node = u32;
+
+    fn root(self) -> u32 {
+        0
+    }
+
+    fn nodes(self) -> usize {
+        self.counts.len()
+    }
+
+    fn index(self, node: u32) -> usize {
+        node as usize
+    }
+
+    fn count(self, node: u32) -> usize {
+        self.counts[node as usize]
+    }
+
+    fn child(self, node: u32, c: char) -> Option<u32> {
+        if !c.is_ascii() {
+            return None;
+        }
+        let start = self.first[node as usize];
+        let end = self.first[node as usize + 1];
+        self.keys[start as usize..end as usize].binary_search(&(c as u8))
+            .ok()
+            .map(|i| start + i as u32)
+    }
+
+    fn for_each_child<F: FnMut(char, u32)>(self, node: u32, mut f: F) {
+        for child in self.first[node as usize]..self.first[node as usize + 1] {
+            f(self.keys[child as usize] as char, child);
+        }
+    }
+}
+
+#[cfg(test)]
+mod to_ascii_tests {
+    use trie::Trie;
+
+    #[test]
+    fn answers_the_same_queries() {
+        let t = fixture();
+        let ascii = t.to_ascii().unwrap();
+
+        assert_eq!(t.words(), ascii.words());
+        for word in &["walking", "talked", "walk", "walkin", "", "xyzzy", "wälked"] {
+            assert_eq!(t.search(word), ascii.search(word), "{:?}", word);
+            assert_eq!(t.complete(word, 3), ascii.complete(word, 3), "{:?}", word);
+            for k in 0..3 {
+                assert_eq!(t.search_with_k_edit(word, k), ascii.search_with_k_edit(word, k),
+                           "{:?} within {}", word, k);
+            }
+        }
+    }
+
+    #[test]
+    fn keeps_the_same_shape() {
+        let t = fixture();
+        let (trie, ascii) = (t.stats(), t.to_ascii().unwrap().stats());
+
+        assert_eq!((trie.words, trie.nodes, trie.depth), (ascii.words, ascii.nodes, ascii.depth));
+        assert!(ascii.heap_bytes < trie.heap_bytes);
+    }
+
+    #[test]
+    fn refuses_other_characters() {
+        let mut t = fixture();
+        t.insert("naïve");
+
+        assert_eq!(None, t.to_ascii());
+    }
+
+    #[test]
+    fn skips_pruned_nodes() {
+        let mut t = fixture();
+        t.insert("walkway");
+        t.remove("walkway");
+
+        assert_eq!(fixture().to_ascii(), t.to_ascii());
+    }
+
+    fn fixture() -> Trie {
+        let mut t = Trie::new();
+        for word in &["walking", "talking", "walked", "talked", "talked"] {
+            t.insert(word);
+        }
+        t
+    }
+}
@@ -0,0 +1,179 @@
+/*!
+Word pairs.
+
+A `Bigrams` counts how often each word follows each other word in a
+corpus, so that of several corrections the one that fits after the
+word before it can be picked: "peace" after "world", "piece" after "a".
+*/
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read};
+use std::path::Path;
+
+use textkit::encoding::{Decoder, Encoding};
+use textkit::tokenize::tokenize;
+
+use compress::decompress;
+
+/// How much a word's own frequency counts for, against the words seen
+/// after a particular one, when it was never seen after that one
+/// ("stupid backoff").
+const BACKOFF: f64 = 0.4;
+
+/// How often each word follows each other word.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Bigrams {
+    following: HashMap<String, Following>,
+    /// How many words were counted in all.
+    words: usize,
+}
+
+/// The words seen after one word.
+#[derive(Clone, Debug, Default, PartialEq)]
+struct Following {
+    total: usize,
+    words: HashMap<String, usize>,
+}
+
+impl Bigrams {
+    pub fn new() -> Self {
+        Bigrams::default()
+    }
+
+    /// Counts `words`, each after the one before it.
+    pub fn insert(&mut self, words: &[String]) {
+        self.words += words.len();
+        for pair in words.windows(2) {
+            self.insert_pair(&pair[0], &pair[1]);
+        }
+    }
+
+    /// Counts `word` after `prev`, without counting either word.
+    fn insert_pair(&mut self, prev: &str, word: &str) {
+        let following = self.following.entry(prev.to_owned()).or_default();
+        following.total += 1;
+        *following.words.entry(word.to_owned()).or_insert(0) += 1;
+    }
+
+    /// How many times `word` was seen right after `prev`.
+    pub fn count(&self, prev: &str, word: &str) -> usize {
+        self.following.get(prev)
+            .and_then(|following| following.words.get(word))
+            .cloned()
+            .unwrap_or(0)
+    }
+
+    /// How likely `word`, which the corpus has `count` times, is to come
+    /// after `prev`: the share of the words after `prev` that were `word`,
+    /// or, if `word` was never seen there, a fraction of its share of all
+    /// words.
+    pub fn score(&self, prev: &str, word: &str, count: usize) -> f64 {
+        match self.following.get(prev) {
+            Some(following) if following.words.contains_key(word) => {
+                following.words[word] as f64 / following.total as f64
+            }
+            _ if self.words > 0 => BACKOFF * count as f64 / self.words as f64,
+            _ => 0.0,
+        }
+    }
+
+    /// Adds the counts of `other` to these.
+    pub fn merge(&mut self, other: Bigrams) {
+        self.words += other.words;
+        for (prev, theirs) in other.following {
+            let ours = self.following.entry(prev).or_default();
+            ours.total += theirs.total;
+            for (word, count) in theirs.words {
+                *ours.words.entry(word).or_insert(0) += count;
+            }
+        }
+    }
+}
+
+/// Counts the word pairs of `reader`, which must produce UTF-8, either
+/// as it is or gzip or zstd compressed. Lines run on into each other, as
+/// the sentences of a corpus do.
+pub fn read_bigrams<R: Read>(reader: R) -> io::Result<Bigrams> {
+    let mut bigrams = Bigrams::new();
+    let mut last: Option<String> = None;
+
+    for line in BufReader::new(decompress(reader)?).lines() {
+        let words = tokenize(&line?);
+        if let (Some(prev), Some(first)) = (&last, words.first()) {
+            bigrams.insert_pair(prev, first);
+        }
+        bigrams.insert(&words);
+        last = words.last().cloned().or(last);
+    }
+
+    Ok(bigrams)
+}
+
+/// Counts the word pairs of the corpus file at `path`, decoded according
+/// to `encoding`.
+pub fn train_bigrams_from_file<P: AsRef<Path>>(path: P, encoding: Encoding)
+                                               -> io::Result<Bigrams> {
+    read_bigrams(Decoder::new(decompress(File::open(path)?)?, encoding))
+}
+
+#[cfg(test)]
+mod bigrams_tests {
+    use super::{read_bigrams, Bigrams};
+
+    #[test]
+    fn counts_pairs() {
+        let bigrams = fixture();
+
+        assert_eq!(2, bigrams.count("a", "piece"));
+        assert_eq!(1, bigrams.count("world", "peace"));
+        assert_eq!(0, bigrams.count("a", "peace"));
+        assert_eq!(0, bigrams.count("peace", "world"));
+        assert_eq!(0, bigrams.count("xyzzy", "a"));
+    }
+
+    #[test]
+    fn counts_across_lines() {
+        let bigrams = read_bigrams(&b"world\n\npeace and\nquiet\n"[..]).unwrap();
+        assert_eq!(1, bigrams.count("world", "peace"));
+        assert_eq!(1, bigrams.count("and", "quiet"));
+
+        let mut expected = Bigrams::new();
+        expected.insert(&words("world peace and quiet"));
+        assert_eq!(expected, bigrams);
+    }
+
+    #[test]
+    fn backs_off_to_word_counts() {
+        let bigrams = fixture();
+
+        // Two of the three words after "a" were "piece".
+        assert_eq!(2.0 / 3.0, bigrams.score("a", "piece", 2));
+        // "peace" is one of the eleven words, but never after "a".
+        assert_eq!(0.4 / 11.0, bigrams.score("a", "peace", 1));
+        assert!(bigrams.score("a", "piece", 2) > bigrams.score("a", "peace", 1));
+        assert!(bigrams.score("world", "peace", 1) > bigrams.score("world", "piece", 2));
+
+        assert_eq!(0.0, Bigrams::new().score("a", "piece", 2));
+    }
+
+    #[test]
+    fn merges() {
+        let mut merged = read_bigrams(&b"a piece of cake"[..]).unwrap();
+        merged.merge(read_bigrams(&b"world peace, a piece"[..]).unwrap());
+
+        let mut expected = Bigrams::new();
+        expected.insert(&words("a piece of cake"));
+        expected.insert(&words("world peace a piece"));
+        assert_eq!(expected, merged);
+        assert_eq!(2, merged.count("a", "piece"));
+    }
+
+    fn fixture() -> Bigrams {
+        read_bigrams(&b"A piece of cake; world peace.\nA piece of a pie.\n"[..]).unwrap()
+    }
+
+    fn words(text: &str) -> Vec<String> {
+        text.split_whitespace().map(|w| w.to_owned()).collect()
+    }
+}
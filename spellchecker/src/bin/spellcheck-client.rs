@@ -0,0 +1,186 @@
+//! spellcheck-client
+//!
+//! Checks the words on the standard input with a running
+//! `spellchecker serve`, and prints what the server makes of them.
+//!
+//! USAGE:
+//!
+//!     spellcheck-client [--connect ADDRESS] [--encoding auto|utf8|latin1|utf16] [--timings]
+//!                       < words
+//!
+//! It connects to --connect ADDRESS (default 127.0.0.1:7000, where the
+//! server listens unless told otherwise). The words are read as
+//! `spellchecker` reads them, one to a line, trimmed and folded, blank
+//! lines skipped, and each is sent as a query of its own. The answers
+//! are printed without the empty line that ends each, so the output is
+//! what `spellchecker` would have printed for the same words, in the
+//! format the server was started with, byte for byte:
+//!
+//!     $ spellchecker serve big.txt &
+//!     $ spellcheck-client < words      # as spellchecker big.txt < words
+//!
+//! The words are sent as fast as they are read, while the answers come
+//! back, so a long list doesn't wait on a round trip for each word.
+//!
+//! --timings prints the time spent connecting, and checking the words
+//! with the server, to stderr at exit.
+//!
+//! Each word is checked on its own, so a server with --context doesn't
+//! weigh it after the word before, as `spellchecker` would. --join-words,
+//! --format table and --list need the whole list, and a server can't be
+//! started with them.
+
+extern crate spellchecker;
+extern crate textkit;
+
+use std::env;
+use std::io::{self, stdin, stdout, BufRead, BufReader, BufWriter, Read, Write};
+use std::net::{Shutdown, TcpStream};
+use std::process::exit;
+use std::thread;
+
+use spellchecker::{fold_word, Encoding};
+use textkit::encoding::Decoder;
+use textkit::timings::Timings;
+
+const USAGE: &str = "\
+usage: spellcheck-client [--connect ADDRESS] [--encoding auto|utf8|latin1|utf16] [--timings] \
+                         < words";
+
+fn main() {
+    let options = parse_args(env::args().skip(1)).unwrap_or_else(|msg| {
+        eprintln!("spellcheck-client: {}", msg);
+        eprintln!("{}", USAGE);
+        exit(2);
+    });
+
+    let mut timings = Timings::new(options.timings);
+    let stream = timings.time("connect", || TcpStream::connect(&options.connect))
+        .unwrap_or_else(|e| {
+            eprintln!("spellcheck-client: {}: {}", options.connect, e);
+            exit(1);
+        });
+    let server = stream.try_clone().unwrap_or_else(|e| {
+        eprintln!("spellcheck-client: {}", e);
+        exit(1);
+    });
+
+    let encoding = options.encoding;
+    let sender = thread::spawn(move || {
+        let input = BufReader::new(Decoder::new(stdin(), encoding));
+        let sent = send_words(input, BufWriter::new(&server));
+        // However that went, the server has all it will get.
+        let _ = server.shutdown(Shutdown::Write);
+        sent
+    });
+
+    let stdout = stdout();
+    // The words go out while the answers come in, so the two are timed
+    // as one.
+    let checked = timings.time("check", || {
+        let copied = copy_answers(&stream, stdout.lock());
+        let sent = sender.join().expect("sending thread panicked");
+        copied.and(sent)
+    });
+    if let Err(e) = checked {
+        eprintln!("spellcheck-client: {}", e);
+        exit(1);
+    }
+    timings.report();
+}
+
+#[derive(Debug, PartialEq)]
+struct Options {
+    connect:  String,
+    encoding: Encoding,
+    timings:  bool,
+}
+
+fn parse_args<I: Iterator<Item = String>>(mut args: I) -> Result<Options, String> {
+    let mut connect = "127.0.0.1:7000".to_owned();
+    let mut encoding = Encoding::Auto;
+    let mut timings = false;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--connect" => connect = args.next().ok_or("--connect needs a value")?,
+            "--encoding" => {
+                let value = args.next().ok_or("--encoding needs a value")?;
+                encoding = value.parse()?;
+            }
+            "--timings" => timings = true,
+            _ if arg.starts_with("--") => return Err(format!("unknown option `{}'", arg)),
+            _ => return Err(format!("unexpected argument `{}'", arg)),
+        }
+    }
+
+    Ok(Options { connect, encoding, timings })
+}
+
+/// Writes each word on `input` to `server` as a query of its own,
+/// trimmed and folded as `spellchecker` reads a list of words, skipping
+/// blank lines.
+fn send_words<R: BufRead, W: Write>(input: R, mut server: W) -> io::Result<()> {
+    for line in input.lines() {
+        let word = fold_word(line?.trim());
+        if !word.is_empty() {
+            server.write_all(word.as_bytes())?;
+            server.write_all(b"\n")?;
+        }
+    }
+    server.flush()
+}
+
+/// Copies the answers on `server` to `output` until the server hangs
+/// up, leaving out the empty line that ends each answer.
+fn copy_answers<R: Read, W: Write>(server: R, output: W) -> io::Result<()> {
+    let mut server = BufReader::new(server);
+    let mut output = BufWriter::new(output);
+    let mut line = Vec::new();
+    loop {
+        line.clear();
+        if server.read_until(b'\n', &mut line)? == 0 {
+            break;
+        }
+        if line != b"\n" {
+            output.write_all(&line)?;
+        }
+        // Don't hold back answers that are all there is for now.
+        if server.buffer().is_empty() {
+            output.flush()?;
+        }
+    }
+    output.flush()
+}
+
+#[cfg(test)]
+mod spellcheck_client_tests {
+    use super::{copy_answers, parse_args, send_words};
+
+    #[test]
+    fn reads_options() {
+        let parse = |args: &[&str]| parse_args(args.iter().map(|s| s.to_string()));
+
+        assert_eq!("127.0.0.1:7000", parse(&[]).unwrap().connect);
+        assert!(!parse(&[]).unwrap().timings);
+        assert!(parse(&["--timings"]).unwrap().timings);
+        assert_eq!("example.com:8000", parse(&["--connect", "example.com:8000"]).unwrap().connect);
+        assert!(parse(&["--connect"]).is_err());
+        assert!(parse(&["--encoding", "ebcdic"]).is_err());
+        assert!(parse(&["big.txt"]).is_err());
+    }
+
+    #[test]
+    fn sends_a_word_a_line() {
+        let mut sent = Vec::new();
+        send_words(&b"  Hello \n\n\tWRLD\n"[..], &mut sent).unwrap();
+        assert_eq!("hello\nwrld\n", String::from_utf8(sent).unwrap());
+    }
+
+    #[test]
+    fn copies_answers_without_their_ends() {
+        let mut output = Vec::new();
+        copy_answers(&b"word,count\nhello\n\nwrld, world\n\n\n"[..], &mut output).unwrap();
+        assert_eq!("word,count\nhello\nwrld, world\n", String::from_utf8(output).unwrap());
+    }
+}
@@ -0,0 +1,166 @@
+/*!
+A BK-tree: words arranged by their distance from one another.
+
+Each node's children are keyed on their distance from it. Distance is a
+metric, so a word within `k` of the query can only lie under a child
+whose key is within `k` of the node's own distance from the query, and
+every other subtree is skipped. How much that prunes depends on `k`
+rather than on the number of edits that could be made to the query, so
+unlike the trie walk a large `k` stays affordable.
+
+The metric is `damerau_levenshtein`, which `edit_distance` (what the
+trie measures) can only exceed, so a query for `k` finds everything the
+trie would find within `k` and perhaps a little more.
+*/
+
+use distance::damerau_levenshtein;
+use trie::{rank, Trie};
+
+#[derive(Clone, Debug, Default)]
+pub struct BkTree {
+    nodes: Vec<Node>,
+}
+
+#[derive(Clone, Debug)]
+struct Node {
+    word:     String,
+    count:    usize,
+    /// `(distance, node)` for each child.
+    children: Vec<(usize, usize)>,
+}
+
+impl BkTree {
+    pub fn new() -> Self {
+        BkTree::default()
+    }
+
+    /// A tree of every word in `model`, with its count.
+    pub fn from_trie(model: &Trie) -> Self {
+        // The trie hands its words out in order, so the tree has the same
+        // shape every time.
+        let mut tree = BkTree::new();
+        for (word, count) in model {
+            tree.add(&word, count);
+        }
+        tree
+    }
+
+    /// The number of distinct words in the tree.
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    /// Records one more occurrence of `word`.
+    pub fn insert(&mut self, word: &str) {
+        self.add(word, 1);
+    }
+
+    fn add(&mut self, word: &str, count: usize) {
+        if self.nodes.is_empty() {
+            self.nodes.push(Node { word: word.to_owned(), count, children: Vec::new() });
+            return;
+        }
+
+        let mut at = 0;
+        loop {
+            let distance = damerau_levenshtein(word, &self.nodes[at].word);
+            if distance == 0 {
+                self.nodes[at].count += count;
+                return;
+            }
+
+            match self.nodes[at].children.iter().find(|&&(d, _)| d == distance) {
+                Some(&(_, child)) => at = child,
+                None => {
+                    let child = self.nodes.len();
+                    self.nodes.push(Node { word: word.to_owned(), count, children: Vec::new() });
+                    self.nodes[at].children.push((distance, child));
+                    return;
+                }
+            }
+        }
+    }
+
+    /// Every word within `k` of `word` by `damerau_levenshtein`, as
+    /// `(word, count, distance)`, closest first, then most frequent, then
+    /// alphabetical.
+    pub fn query(&self, word: &str, k: usize) -> Vec<(String, usize, usize)> {
+        let mut found = Vec::new();
+        let mut stack = if self.nodes.is_empty() { vec![] } else { vec![0] };
+
+        while let Some(at) = stack.pop() {
+            let node = &self.nodes[at];
+            let distance = damerau_levenshtein(word, &node.word);
+            if distance <= k {
+                found.push((node.word.clone(), node.count, distance));
+            }
+
+            for &(d, child) in &node.children {
+                if d + k >= distance && d <= distance + k {
+                    stack.push(child);
+                }
+            }
+        }
+
+        rank(&mut found);
+        found
+    }
+}
+
+#[cfg(test)]
+mod bk_tree_tests {
+    use super::BkTree;
+    use distance::damerau_levenshtein;
+    use trie::{rank, Trie};
+
+    #[test]
+    fn empty_tree_finds_nothing() {
+        assert!(BkTree::new().query("hello", 3).is_empty());
+        assert!(BkTree::new().is_empty());
+    }
+
+    #[test]
+    fn counts_insertions() {
+        let mut tree = BkTree::new();
+        tree.insert("hello");
+        tree.insert("help");
+        tree.insert("hello");
+
+        assert_eq!(2, tree.len());
+        assert_eq!(vec![("hello".to_owned(), 2, 0), ("help".to_owned(), 1, 2)],
+                   tree.query("hello", 2));
+    }
+
+    #[test]
+    fn matches_brute_force() {
+        let tree = BkTree::from_trie(&fixture());
+        let words = fixture().words();
+
+        for query in &["", "a", "hello", "hlelo", "wrold", "ca", "abc", "sword", "xyzzy", "hep"] {
+            for k in 0..5 {
+                let mut expected: Vec<_> = words.iter()
+                    .map(|&(ref word, count)| {
+                        (word.clone(), count, damerau_levenshtein(query, word))
+                    })
+                    .filter(|&(_, _, distance)| distance <= k)
+                    .collect();
+                rank(&mut expected);
+
+                assert_eq!(expected, tree.query(query, k), "{:?} within {}", query, k);
+            }
+        }
+    }
+
+    fn fixture() -> Trie {
+        let mut t = Trie::new();
+        for word in &["hello", "hello", "help", "hell", "world", "word", "sword", "he", "a",
+                      "abc", "ac", "cab", "world", "would", "could", "cold", "hold"] {
+            t.insert(word);
+        }
+        t
+    }
+}
@@ -0,0 +1,184 @@
+/*!
+Language bundles: what the checker needs to know of a language, as data.
+
+A bundle is a directory with a `bundle.toml` in it, which names the
+language, the file of its words, and optionally the layout of its
+keyboard and the punctuation its words may be written with:
+
+```toml
+language = "de"
+# A word list, as `load_dictionary` reads it; or `model` for a model
+# saved with `save_model`, or `hunspell` for a Hunspell dictionary.
+dictionary = "de.dict"
+
+[keyboard]
+# The rows of letter keys, top to bottom (see `Costs::keyboard`).
+rows = ["qwertzuiopü", "asdfghjklöä", "yxcvbnm"]
+
+[punctuation]
+# Trimmed from either end of a word, as well as the usual marks.
+trim = "«»„‹›"
+```
+
+Files are named relative to the directory, and `language` defaults to
+the directory's name, so supporting another language takes a bundle
+rather than a change to the code.
+*/
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use textkit::encoding::Encoding;
+
+use costs::Costs;
+use dictionary::load_dictionary;
+use hunspell::load_hunspell;
+use model::load_model;
+use trie::Trie;
+
+/// The name of the file describing a bundle, in its directory.
+pub const BUNDLE_FILE: &str = "bundle.toml";
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Bundle {
+    /// The name of the language.
+    pub language:    String,
+    /// What edits cost typing on the language's keyboard, if the bundle
+    /// has one.
+    pub keyboard:    Option<Costs>,
+    /// Punctuation to trim from words besides the usual marks; see
+    /// `SpellChecker::with_punctuation`.
+    pub punctuation: Vec<char>,
+    words:           Words,
+}
+
+/// Where a bundle's words are.
+#[derive(Clone, Debug, PartialEq)]
+enum Words {
+    Dictionary(PathBuf),
+    Model(PathBuf),
+    Hunspell(PathBuf),
+}
+
+impl Bundle {
+    /// Reads the bundle's words, decoding a word list as `encoding`.
+    pub fn load_model(&self, encoding: Encoding) -> io::Result<Trie> {
+        match self.words {
+            Words::Dictionary(ref path) => load_dictionary(path, encoding),
+            Words::Model(ref path) => load_model(path),
+            Words::Hunspell(ref path) => load_hunspell(path),
+        }
+    }
+}
+
+/// Reads the `bundle.toml` of the bundle in `dir`. Its words are only
+/// read by `Bundle::load_model`.
+pub fn load_bundle<P: AsRef<Path>>(dir: P) -> io::Result<Bundle> {
+    let dir = dir.as_ref();
+    let text = fs::read_to_string(dir.join(BUNDLE_FILE))?;
+    read_bundle(&text, dir).map_err(|msg| {
+        io::Error::new(io::ErrorKind::InvalidData, format!("{}: {}", BUNDLE_FILE, msg))
+    })
+}
+
+/// Reads the `bundle.toml` `text` of the bundle in `dir`.
+pub fn read_bundle(text: &str, dir: &Path) -> Result<Bundle, String> {
+    let table: toml::Table = text.parse().map_err(|e: toml::de::Error| e.message().to_owned())?;
+    let mut language = dir.file_name().and_then(|name| name.to_str()).map(str::to_owned);
+    let mut words = Vec::new();
+    let mut keyboard = None;
+    let mut punctuation = Vec::new();
+
+    for (key, value) in &table {
+        match key.as_str() {
+            "language" => language = Some(string(key, value)?.to_owned()),
+            "dictionary" => words.push(Words::Dictionary(dir.join(string(key, value)?))),
+            "model" => words.push(Words::Model(dir.join(string(key, value)?))),
+            "hunspell" => words.push(Words::Hunspell(dir.join(string(key, value)?))),
+            "keyboard" => {
+                let rows = value.get("rows").and_then(|rows| rows.as_array())
+                    .ok_or("`keyboard' should have `rows', a list of strings")?;
+                let rows = rows.iter().map(|row| string("rows", row))
+                    .collect::<Result<Vec<_>, _>>()?;
+                keyboard = Some(Costs::keyboard(&rows));
+            }
+            "punctuation" => {
+                let trim = value.get("trim").ok_or("`punctuation' should have `trim'")?;
+                punctuation = string("trim", trim)?.chars().collect();
+            }
+            _ => return Err(format!("unknown key `{}'", key)),
+        }
+    }
+
+    let language = language.filter(|language| !language.is_empty())
+        .ok_or("no `language' given")?;
+    if words.len() != 1 {
+        return Err("give one of `dictionary', `model' or `hunspell'".to_owned());
+    }
+    Ok(Bundle { language, keyboard, punctuation, words: words.remove(0) })
+}
+
+fn string<'a>(key: &str, value: &'a toml::Value) -> Result<&'a str, String> {
+    value.as_str().ok_or_else(|| format!("`{}' should be a string", key))
+}
+
+#[cfg(test)]
+mod bundle_tests {
+    use super::{load_bundle, read_bundle, Words, BUNDLE_FILE};
+    use costs::Costs;
+    use std::env;
+    use std::fs;
+    use std::path::Path;
+    use textkit::encoding::Encoding;
+
+    #[test]
+    fn reads_bundle_toml() {
+        let text = "language = \"de\"\ndictionary = \"de.dict\"\n\
+                    [keyboard]\nrows = [\"qwertz\", \"asdfgh\"]\n\
+                    [punctuation]\ntrim = \"«»\"\n";
+        let bundle = read_bundle(text, Path::new("bundles/german")).unwrap();
+
+        assert_eq!("de", bundle.language);
+        assert_eq!(Words::Dictionary(Path::new("bundles/german/de.dict").to_owned()),
+                   bundle.words);
+        assert_eq!(Some(Costs::keyboard(&["qwertz", "asdfgh"])), bundle.keyboard);
+        assert_eq!(vec!['«', '»'], bundle.punctuation);
+    }
+
+    #[test]
+    fn names_language_after_directory() {
+        let bundle = read_bundle("model = \"fr.model\"\n", Path::new("fr")).unwrap();
+
+        assert_eq!("fr", bundle.language);
+        assert_eq!(Words::Model(Path::new("fr/fr.model").to_owned()), bundle.words);
+        assert_eq!((None, Vec::new()), (bundle.keyboard, bundle.punctuation));
+    }
+
+    #[test]
+    fn rejects_bad_bundles() {
+        for text in &["", "dictionary = \"a\"\nmodel = \"b\"", "dictionary = 1",
+                      "dictionary = \"a\"\ncolour = \"red\"", "dictionary = \"a\"\nkeyboard = 1",
+                      "dictionary = \"a\"\n[keyboard]\nrows = [1]",
+                      "dictionary = \"a\"\n[punctuation]\ntrim = [\"«\"]", "language = "] {
+            assert!(read_bundle(text, Path::new("de")).is_err(), "{:?}", text);
+        }
+        assert!(read_bundle("dictionary = \"a\"", Path::new("")).is_err());
+    }
+
+    #[test]
+    fn loads_words() {
+        let dir = env::temp_dir().join(format!("spellchecker-bundle-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join(BUNDLE_FILE), "language = \"en\"\ndictionary = \"words\"\n").unwrap();
+        fs::write(dir.join("words"), "hello\t3\nworld\t2\n").unwrap();
+
+        let bundle = load_bundle(&dir).unwrap();
+        let model = bundle.load_model(Encoding::Auto).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!("en", bundle.language);
+        assert_eq!((3, 2), (model.search("hello"), model.search("world")));
+        assert!(load_bundle(&dir).is_err());
+    }
+}
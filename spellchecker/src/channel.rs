@@ -0,0 +1,375 @@
+/*!
+Noisy-channel ranking.
+
+Ranking by distance, then count, always prefers a word one edit away to
+one two edits away, however rare the first and common the second. The
+noisy-channel model weighs both: the chance of meaning a word, `P(word)`,
+which is how often the corpus has it, times the chance of typing what
+was typed when meaning it, `P(error|word)`, which falls with every edit
+and depends on what kind of edit it is and where.
+
+The error probabilities are rough figures for typed English, not
+trained ones: a character is mistyped about once in a hundred, most
+often replaced or left out, less often doubled up or swapped with its
+neighbour, and seldom at the start of a word.
+
+A `Ranker` orders candidates some other way altogether, such as by a
+model of the user's own; `Ranking` is one too.
+*/
+
+use std::cmp::Ordering;
+use std::fmt;
+use std::str::FromStr;
+
+use trie::rank;
+
+/// How likely any one character is to be mistyped.
+const TYPO_RATE: f64 = 0.01;
+
+/// How a mistyped character went wrong, as a share of all mistakes.
+const REPLACE_SHARE: f64 = 0.4;
+const DELETE_SHARE: f64 = 0.25;
+const INSERT_SHARE: f64 = 0.25;
+const TRANSPOSE_SHARE: f64 = 0.1;
+
+/// How much less likely a mistake is in a word's first character.
+const FIRST_CHARACTER: f64 = 0.2;
+
+/// One edit turning the intended word into what was typed, at a
+/// character position in the intended word.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Edit {
+    /// A character of the word was typed as another.
+    Replace(usize),
+    /// A character of the word was left out.
+    Delete(usize),
+    /// An extra character was typed before this one.
+    Insert(usize),
+    /// This character and the next were typed the other way round.
+    Transpose(usize),
+}
+
+/// `P(error|word)`: roughly how likely someone meaning to type `word` is
+/// to type `typed` instead. Counts the same edits as `edit_distance`.
+pub fn error_probability(typed: &str, word: &str) -> f64 {
+    let typed: Vec<char> = typed.chars().collect();
+    let word: Vec<char> = word.chars().collect();
+
+    let edits = edits(&typed, &word);
+    let correct = word.len().saturating_sub(edits.len());
+
+    edits.iter().fold((1.0 - TYPO_RATE).powi(correct as i32), |p, &edit| {
+        let (share, at) = match edit {
+            Edit::Replace(at)   => (REPLACE_SHARE, at),
+            Edit::Delete(at)    => (DELETE_SHARE, at),
+            Edit::Insert(at)    => (INSERT_SHARE, at),
+            Edit::Transpose(at) => (TRANSPOSE_SHARE, at),
+        };
+        let position = if at == 0 { FIRST_CHARACTER } else { 1.0 };
+        p * TYPO_RATE * share * position
+    })
+}
+
+/// How likely `typed` is to be a mistyped `word`, which the corpus has
+/// `count` times: `P(word) * P(error|word)`, but for dividing by the size
+/// of the corpus, which is the same for every candidate.
+pub fn channel_score(typed: &str, word: &str, count: usize) -> f64 {
+    count as f64 * error_probability(typed, word)
+}
+
+/// The cheapest edits turning `word` into `typed`, found by walking back
+/// through the table `edit_distance` fills in.
+fn edits(typed: &[char], word: &[char]) -> Vec<Edit> {
+    let mut d = vec![vec![0; word.len() + 1]; typed.len() + 1];
+    for (i, row) in d.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, cell) in d[0].iter_mut().enumerate() {
+        *cell = j;
+    }
+
+    let swapped = |i: usize, j: usize| {
+        i > 1 && j > 1 && typed[i - 1] == word[j - 2] && typed[i - 2] == word[j - 1]
+    };
+
+    for i in 1..typed.len() + 1 {
+        for j in 1..word.len() + 1 {
+            let replace = if typed[i - 1] == word[j - 1] { 0 } else { 1 };
+            d[i][j] = (d[i - 1][j] + 1).min(d[i][j - 1] + 1).min(d[i - 1][j - 1] + replace);
+            if swapped(i, j) {
+                d[i][j] = d[i][j].min(d[i - 2][j - 2] + 1);
+            }
+        }
+    }
+
+    let mut edits = Vec::new();
+    let (mut i, mut j) = (typed.len(), word.len());
+    while i > 0 || j > 0 {
+        if i > 0 && j > 0 && typed[i - 1] == word[j - 1] && d[i][j] == d[i - 1][j - 1] {
+            i -= 1;
+            j -= 1;
+        } else if swapped(i, j) && d[i][j] == d[i - 2][j - 2] + 1 {
+            edits.push(Edit::Transpose(j - 2));
+            i -= 2;
+            j -= 2;
+        } else if i > 0 && j > 0 && d[i][j] == d[i - 1][j - 1] + 1 {
+            edits.push(Edit::Replace(j - 1));
+            i -= 1;
+            j -= 1;
+        } else if j > 0 && d[i][j] == d[i][j - 1] + 1 {
+            edits.push(Edit::Delete(j - 1));
+            j -= 1;
+        } else {
+            edits.push(Edit::Insert(j));
+            i -= 1;
+        }
+    }
+    edits.reverse();
+    edits
+}
+
+#[cfg(test)]
+mod error_probability_tests {
+    use super::{edits, error_probability, Edit};
+    use distance::edit_distance;
+
+    #[test]
+    fn finds_edits() {
+        for &(typed, word, edit) in &[("helo", "hello", Edit::Delete(2)),
+                                      ("helllo", "hello", Edit::Insert(2)),
+                                      ("hallo", "hello", Edit::Replace(1)),
+                                      ("hlelo", "hello", Edit::Transpose(1)),
+                                      ("xhello", "hello", Edit::Insert(0))] {
+            let typed: Vec<char> = typed.chars().collect();
+            let word: Vec<char> = word.chars().collect();
+            assert_eq!(vec![edit], edits(&typed, &word));
+        }
+    }
+
+    #[test]
+    fn counts_edits_as_edit_distance_does() {
+        for &(typed, word) in &[("", ""), ("", "abc"), ("abc", ""), ("ca", "abc"),
+                                ("kitten", "sitting"), ("café", "cafe"), ("wrold", "world")] {
+            let (t, w): (Vec<char>, Vec<char>) = (typed.chars().collect(), word.chars().collect());
+            assert_eq!(edit_distance(typed, word), edits(&t, &w).len(), "{:?} {:?}", typed, word);
+        }
+    }
+
+    #[test]
+    fn falls_with_every_edit() {
+        let exact = error_probability("hello", "hello");
+        let one = error_probability("helo", "hello");
+        let two = error_probability("hel", "hello");
+        assert!(exact > 0.9);
+        assert!(exact > one && one > two && two > 0.0);
+    }
+
+    #[test]
+    fn weighs_kind_and_position() {
+        assert!(error_probability("hallo", "hello") > error_probability("hlelo", "hello"));
+        assert!(error_probability("helo", "hello") > error_probability("ello", "hello"));
+    }
+}
+
+/// How a `SpellChecker` orders its candidates, picked with
+/// `--ranking distance|noisy-channel`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum Ranking {
+    /// Closest first, then most frequent, then alphabetical.
+    #[default]
+    Distance,
+    /// Likeliest first by `channel_score`, then closest, then
+    /// alphabetical.
+    NoisyChannel,
+}
+
+impl Ranking {
+    /// Sorts the candidate corrections of `word`, as `(word, count,
+    /// distance)`.
+    pub fn rank(self, word: &str, found: &mut Vec<(String, usize, usize)>) {
+        match self {
+            Ranking::Distance => rank(found),
+            Ranking::NoisyChannel => self.rank_by(word, found, |_, count| count as f64),
+        }
+    }
+
+    /// Sorts the candidate corrections of `word` as `rank` does, but with
+    /// `prior(candidate, count)` for how likely each candidate is to be
+    /// meant in the first place, instead of its count.
+    pub fn rank_by<F>(self, word: &str, found: &mut Vec<(String, usize, usize)>, prior: F)
+        where F: Fn(&str, usize) -> f64
+    {
+        let mut scored: Vec<_> = found.drain(..)
+            .map(|candidate| {
+                let mut score = prior(&candidate.0, candidate.1);
+                if self == Ranking::NoisyChannel {
+                    score *= error_probability(word, &candidate.0);
+                }
+                (score, candidate)
+            })
+            .collect();
+        scored.sort_by(|a, b| {
+            let likelier = || b.0.partial_cmp(&a.0).unwrap_or(Ordering::Equal);
+            let closer = || a.1 .2.cmp(&b.1 .2);
+            match self {
+                Ranking::Distance => closer().then_with(likelier),
+                Ranking::NoisyChannel => likelier().then_with(closer),
+            }
+            .then_with(|| a.1 .0.cmp(&b.1 .0))
+        });
+        found.extend(scored.into_iter().map(|(_, candidate)| candidate));
+    }
+}
+
+/// What a `Ranker` knows of where a candidate would go: the word it
+/// would correct, and the word before that in the text, if any.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RankContext<'a> {
+    pub word: &'a str,
+    pub prev: Option<&'a str>,
+}
+
+/// Orders a `SpellChecker`'s candidate corrections, in place of its
+/// `Ranking`; see `SpellChecker::with_ranker`. A ranker is shared by the
+/// threads checking with the same checker, so it can't be changed by
+/// scoring.
+pub trait Ranker: fmt::Debug + Send + Sync {
+    /// How good a correction `candidate` is, `distance` edits from the
+    /// word and seen `count` times in the corpus: the higher the better.
+    fn score(&self, candidate: &str, distance: usize, count: usize, context: &RankContext)
+             -> f64;
+
+    /// Sorts the candidate corrections `found`, as `(word, count,
+    /// distance)`, best first: by default by `score`, then closest, then
+    /// alphabetically.
+    fn rank(&self, context: &RankContext, found: &mut Vec<(String, usize, usize)>) {
+        let mut scored: Vec<_> = found.drain(..)
+            .map(|candidate| {
+                (self.score(&candidate.0, candidate.2, candidate.1, context), candidate)
+            })
+            .collect();
+        scored.sort_by(|a, b| {
+            b.0.partial_cmp(&a.0).unwrap_or(Ordering::Equal)
+                .then_with(|| (a.1 .2, &a.1 .0).cmp(&(b.1 .2, &b.1 .0)))
+        });
+        found.extend(scored.into_iter().map(|(_, candidate)| candidate));
+    }
+}
+
+/// Scores candidates by their counts, or by `channel_score`, and ranks
+/// them as `Ranking::rank` does: by distance first with `Distance`, the
+/// default.
+impl Ranker for Ranking {
+    fn score(&self, candidate: &str, _: usize, count: usize, context: &RankContext) -> f64 {
+        match *self {
+            Ranking::Distance => count as f64,
+            Ranking::NoisyChannel => channel_score(context.word, candidate, count),
+        }
+    }
+
+    fn rank(&self, context: &RankContext, found: &mut Vec<(String, usize, usize)>) {
+        Ranking::rank(*self, context.word, found);
+    }
+}
+
+impl FromStr for Ranking {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, String> {
+        match s {
+            "distance"      => Ok(Ranking::Distance),
+            "noisy-channel" => Ok(Ranking::NoisyChannel),
+            _ => Err(format!("unknown ranking `{}' (expected distance or noisy-channel)", s)),
+        }
+    }
+}
+
+impl fmt::Display for Ranking {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match *self {
+            Ranking::Distance     => "distance",
+            Ranking::NoisyChannel => "noisy-channel",
+        })
+    }
+}
+
+#[cfg(test)]
+mod ranking_tests {
+    use super::{RankContext, Ranker, Ranking};
+
+    #[test]
+    fn parses_rankings() {
+        assert_eq!(Ok(Ranking::Distance), "distance".parse());
+        assert_eq!(Ok(Ranking::NoisyChannel), "noisy-channel".parse());
+        assert!("noisy".parse::<Ranking>().is_err());
+        assert_eq!("noisy-channel", Ranking::NoisyChannel.to_string());
+    }
+
+    #[test]
+    fn weighs_frequency_against_edits() {
+        let mut found = vec![("wad".to_owned(), 1, 1), ("weird".to_owned(), 5_000, 2),
+                             ("wed".to_owned(), 3, 1)];
+        Ranking::Distance.rank("wrd", &mut found);
+        assert_eq!(vec!["wed", "wad", "weird"], words(&found));
+
+        // "weird" is two edits away, but so much commoner it is the
+        // likelier.
+        Ranking::NoisyChannel.rank("wrd", &mut found);
+        assert_eq!(vec!["weird", "wed", "wad"], words(&found));
+    }
+
+    #[test]
+    fn ranks_by_prior() {
+        let mut found = vec![("wad".to_owned(), 1, 1), ("weird".to_owned(), 5_000, 2),
+                             ("wed".to_owned(), 3, 1)];
+        let prior = |word: &str, _| if word == "wad" { 10.0 } else { 1.0 };
+
+        Ranking::Distance.rank_by("wrd", &mut found, prior);
+        assert_eq!(vec!["wad", "wed", "weird"], words(&found));
+        Ranking::NoisyChannel.rank_by("wrd", &mut found, prior);
+        assert_eq!(vec!["wad", "wed", "weird"], words(&found));
+
+        Ranking::Distance.rank_by("wrd", &mut found, |_, count| count as f64);
+        let mut ranked = found.clone();
+        Ranking::Distance.rank("wrd", &mut ranked);
+        assert_eq!(ranked, found);
+    }
+
+    #[test]
+    fn breaks_ties_by_distance_then_alphabetically() {
+        let mut found = vec![("hat".to_owned(), 1, 1), ("bat".to_owned(), 1, 1)];
+        Ranking::NoisyChannel.rank("mat", &mut found);
+        assert_eq!(vec!["bat", "hat"], words(&found));
+    }
+
+    #[test]
+    fn ranks_as_rankers() {
+        #[derive(Debug)]
+        struct Shortest;
+
+        impl Ranker for Shortest {
+            fn score(&self, candidate: &str, _: usize, _: usize, _: &RankContext) -> f64 {
+                -(candidate.len() as f64)
+            }
+        }
+
+        let context = RankContext { word: "wrd", prev: None };
+        let mut found = vec![("weird".to_owned(), 5_000, 2), ("wed".to_owned(), 3, 1),
+                             ("wad".to_owned(), 1, 1)];
+        Shortest.rank(&context, &mut found);
+        assert_eq!(vec!["wad", "wed", "weird"], words(&found));
+
+        for &ranking in &[Ranking::Distance, Ranking::NoisyChannel] {
+            let mut ranked = found.clone();
+            Ranker::rank(&ranking, &context, &mut ranked);
+            let mut expected = found.clone();
+            ranking.rank("wrd", &mut expected);
+            assert_eq!(expected, ranked);
+        }
+        assert_eq!(5_000.0, Ranking::Distance.score("weird", 2, 5_000, &context));
+    }
+
+    fn words(found: &[(String, usize, usize)]) -> Vec<&str> {
+        found.iter().map(|c| c.0.as_str()).collect()
+    }
+}
@@ -0,0 +1,2014 @@
+/*!
+Correcting words against a trained `Trie`.
+*/
+
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::io::BufRead;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use regex::Regex;
+use textkit::tokenize::fold_word;
+
+use bigrams::Bigrams;
+use ascii::AsciiTrie;
+use bktree::BkTree;
+use channel::{error_probability, RankContext, Ranker, Ranking};
+use costs::Costs;
+use dawg::Dawg;
+use distance::edit_distance;
+use explain::{explain, Edit};
+use phonetic::PhoneticIndex;
+use profile::Profile;
+use stem::stems;
+use symspell::SymSpell;
+use transducer::Transducer;
+use train::{hyphen_parts, strip_diacritics, Hyphens, Punctuation};
+use trie::{rank, search_within, subtree_counts, Limits, Stats, Trie};
+
+/// The largest number of edits a correction may be from the word it
+/// corrects.
+pub const MAX_EDITS: usize = 2;
+
+/// How many edits a correction may be from `word` when the number scales
+/// with its length (see `SpellChecker::with_adaptive_edits`): 1 for
+/// words of up to 4 characters, 2 for up to 8, and 3 for longer ones, as
+/// two edits leave little of a three-letter word.
+pub fn adaptive_edits(word: &str) -> usize {
+    match word.chars().count() {
+        0..=4 => 1,
+        5..=8 => 2,
+        _ => 3,
+    }
+}
+
+/// The best correction for `word`: the word itself if the model knows it,
+/// otherwise the known word the fewest edits away, preferring the more
+/// frequent of equally distant words and then the alphabetically first.
+/// `None` when nothing is within `MAX_EDITS`.
+pub fn check_spelling(model: &Trie, word: &str) -> Option<String> {
+    if model.search(word) > 0 {
+        return Some(word.to_owned());
+    }
+
+    model.search_with_k_edit(word, MAX_EDITS)
+        .into_iter()
+        .next()
+        .map(|(suggestion, _, _)| suggestion)
+}
+
+/// Up to `n` candidate corrections for `word`, as `(word, count,
+/// distance)`: everything within `MAX_EDITS`, closest first, then most
+/// frequent, then alphabetical. A known word comes first as its own
+/// candidate, at distance 0.
+pub fn suggest(model: &Trie, word: &str, n: usize) -> Vec<(String, usize, usize)> {
+    let mut candidates = model.search_with_k_edit(word, MAX_EDITS);
+    candidates.truncate(n);
+    candidates
+}
+
+#[cfg(test)]
+mod suggest_tests {
+    use super::suggest;
+    use trie::Trie;
+
+    #[test]
+    fn orders_by_distance_then_count() {
+        let words: Vec<_> = suggest(&fixture(), "wor", 4).into_iter().map(|c| c.0).collect();
+        assert_eq!(vec!["word", "work", "world", "sword"], words);
+    }
+
+    #[test]
+    fn breaks_ties_alphabetically() {
+        let mut t = Trie::new();
+        for word in &["hat", "cat", "bat"] {
+            t.insert(word);
+        }
+
+        let words: Vec<_> = suggest(&t, "at", 3).into_iter().map(|c| c.0).collect();
+        assert_eq!(vec!["bat", "cat", "hat"], words);
+    }
+
+    #[test]
+    fn limits_to_n() {
+        assert_eq!(vec![("word".to_owned(), 3, 1)], suggest(&fixture(), "wor", 1));
+        assert!(suggest(&fixture(), "wor", 0).is_empty());
+    }
+
+    #[test]
+    fn known_word_comes_first() {
+        assert_eq!(("work".to_owned(), 2, 0), suggest(&fixture(), "work", 2)[0]);
+    }
+
+    fn fixture() -> Trie {
+        let mut t = Trie::new();
+        for &(word, count) in &[("word", 3), ("work", 2), ("world", 5), ("sword", 1)] {
+            for _ in 0..count {
+                t.insert(word);
+            }
+        }
+        t
+    }
+}
+
+#[cfg(test)]
+mod check_spelling_tests {
+    use super::check_spelling;
+    use trie::Trie;
+
+    #[test]
+    fn accepts_known_word() {
+        assert_eq!(Some("hello".to_owned()), check_spelling(&fixture(), "hello"));
+    }
+
+    #[test]
+    fn prefers_fewer_edits() {
+        // "helloo" is one edit from "hello" but two from the commoner "hell".
+        assert_eq!(Some("hello".to_owned()), check_spelling(&fixture(), "helloo"));
+    }
+
+    #[test]
+    fn prefers_more_frequent() {
+        // "hel" is one edit from both "help" and "hell".
+        assert_eq!(Some("help".to_owned()), check_spelling(&fixture(), "hel"));
+    }
+
+    #[test]
+    fn breaks_ties_alphabetically() {
+        // Inserted last, so the tie isn't settled by which came first.
+        let mut t = Trie::new();
+        for word in &["hat", "cat", "bat"] {
+            t.insert(word);
+        }
+
+        assert_eq!(Some("bat".to_owned()), check_spelling(&t, "at"));
+    }
+
+    #[test]
+    fn corrects_long_words() {
+        let word: String = "abcdefghij".chars().cycle().take(10_000).collect();
+        let mut t = Trie::new();
+        t.insert(&word);
+
+        let mut typo = word.clone();
+        typo.remove(9_999);
+        typo.remove(10);
+
+        assert_eq!(Some(word), check_spelling(&t, &typo));
+    }
+
+    #[test]
+    fn gives_up_beyond_max_edits() {
+        assert_eq!(None, check_spelling(&fixture(), "xyzzy"));
+    }
+
+    fn fixture() -> Trie {
+        let mut t = Trie::new();
+        t.insert("hello");
+        t.insert("hell");
+        t.insert("hell");
+        for _ in 0..3 {
+            t.insert("help");
+        }
+        t
+    }
+}
+
+/// How a `SpellChecker` finds candidate corrections, picked with
+/// `--engine trie|symspell|bktree|fst`. All find exactly the same
+/// candidates.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum Engine {
+    /// Walk the trie, branching on every possible edit.
+    #[default]
+    Trie,
+    /// Look the word's deletions up in a `SymSpell` index, which is built
+    /// up front and takes a lot more memory.
+    SymSpell,
+    /// Search a `BkTree` of the known words, which is built up front.
+    BkTree,
+    /// Walk a `Transducer` of the known words, built up front in place
+    /// of the trie, which takes much less memory.
+    Fst,
+}
+
+impl FromStr for Engine {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, String> {
+        match s {
+            "trie"     => Ok(Engine::Trie),
+            "symspell" => Ok(Engine::SymSpell),
+            "bktree"   => Ok(Engine::BkTree),
+            "fst"      => Ok(Engine::Fst),
+            _ => Err(format!("unknown engine `{}' (expected trie, symspell, bktree or fst)", s)),
+        }
+    }
+}
+
+impl fmt::Display for Engine {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match *self {
+            Engine::Trie     => "trie",
+            Engine::SymSpell => "symspell",
+            Engine::BkTree   => "bktree",
+            Engine::Fst      => "fst",
+        })
+    }
+}
+
+/// How `SpellChecker::check_written` treats a capitalized word or an
+/// acronym, picked with `--capitalized` and `--acronyms`. Names and
+/// acronyms are seldom in a corpus, so checking them turns up many false
+/// misspellings.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum CasePolicy {
+    /// Check it like any other word, ignoring case.
+    #[default]
+    Check,
+    /// Never question it.
+    Skip,
+    /// Question it only if it has a correction, never as unknown, and
+    /// never as a suspect real word.
+    Lenient,
+}
+
+impl FromStr for CasePolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, String> {
+        match s {
+            "check"   => Ok(CasePolicy::Check),
+            "skip"    => Ok(CasePolicy::Skip),
+            "lenient" => Ok(CasePolicy::Lenient),
+            _ => Err(format!("unknown case policy `{}' (expected check, skip or lenient)", s)),
+        }
+    }
+}
+
+impl fmt::Display for CasePolicy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match *self {
+            CasePolicy::Check   => "check",
+            CasePolicy::Skip    => "skip",
+            CasePolicy::Lenient => "lenient",
+        })
+    }
+}
+
+/// How a `SpellChecker` treats a word with a digit in it, all digits or
+/// mixed ("3rd", "x86_64", "mp3"), picked with `--numbers`. No corpus has
+/// every number, so checking them turns up nothing but false misspellings
+/// and useless corrections.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum Numbers {
+    /// Check it like any other word.
+    #[default]
+    Check,
+    /// Take it as correct.
+    Accept,
+    /// Leave it out of a text altogether, as if it weren't a word.
+    Skip,
+}
+
+impl FromStr for Numbers {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, String> {
+        match s {
+            "check"  => Ok(Numbers::Check),
+            "accept" => Ok(Numbers::Accept),
+            "skip"   => Ok(Numbers::Skip),
+            _ => Err(format!("unknown number policy `{}' (expected check, accept or skip)", s)),
+        }
+    }
+}
+
+impl fmt::Display for Numbers {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match *self {
+            Numbers::Check  => "check",
+            Numbers::Accept => "accept",
+            Numbers::Skip   => "skip",
+        })
+    }
+}
+
+/// Whether `word` has a digit in it, which `Numbers` is about.
+fn has_digit(word: &str) -> bool {
+    word.chars().any(char::is_numeric)
+}
+
+/// Which letters of a word, as written, are capitals.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Capitals {
+    /// None, or not the first.
+    None,
+    /// The first, but not all of them.
+    First,
+    /// All of two or more: an acronym.
+    All,
+}
+
+impl Capitals {
+    fn of(word: &str) -> Capitals {
+        let mut letters = word.chars().filter(|c| c.is_alphabetic());
+        match letters.next() {
+            Some(first) if first.is_uppercase() => {
+                let mut rest = letters.peekable();
+                if rest.peek().is_some() && rest.all(char::is_uppercase) {
+                    Capitals::All
+                } else {
+                    Capitals::First
+                }
+            }
+            _ => Capitals::None,
+        }
+    }
+}
+
+/// What became of one checked word; see `SpellChecker::check_word`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum CheckResult {
+    /// A known word.
+    Correct,
+    /// An unknown word, and its best corrections.
+    Corrected(Vec<Suggestion>),
+    /// A known word that looks like a mistake for one of these in its
+    /// context; see `SpellChecker::suspect_after`.
+    Suspect(Vec<Suggestion>),
+    /// An unknown word with nothing close enough to correct it to.
+    Unknown,
+}
+
+/// A correction offered for a word.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Suggestion {
+    pub word:       String,
+    /// How many edits it is from the word.
+    pub distance:   usize,
+    /// How many times the corpus has it.
+    pub count:      usize,
+    /// How likely it is to be the word meant, from 0 to 1: its noisy
+    /// channel score (see `channel_score`) as a share of the scores of
+    /// every candidate, including the word itself if it is known. The
+    /// suggestions for a word add up to 1 if there were no more than were
+    /// asked for.
+    pub confidence: f64,
+}
+
+/// What one word of a batch came to; see `SpellChecker::check_all`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Correction {
+    /// The word as it was given.
+    pub original:   String,
+    /// The word meant: the word itself if it is correct, its best
+    /// correction if it isn't (or is suspect), and `None` if nothing is
+    /// close enough to correct it to.
+    pub suggestion: Option<String>,
+    /// How many edits the suggestion is from the word.
+    pub distance:   usize,
+    /// How many times the corpus has the suggestion.
+    pub count:      usize,
+}
+
+/// A trained model, ready to answer queries.
+///
+/// A query takes `&self` and writes nothing, so a checker is
+/// `Send + Sync`, and one behind an `Arc` can answer any number of
+/// threads at once without a lock. What a query costs goes in a
+/// `Profile` its caller keeps; see `check_word_profiled`.
+#[derive(Clone, Debug, Default)]
+pub struct SpellChecker {
+    model: Model,
+    index: Index,
+    costs: Option<Costs>,
+    phonetic: Option<PhoneticIndex>,
+    ranking: Ranking,
+    ranker: Option<Arc<dyn Ranker>>,
+    /// Added to each candidate's count where it is weighed against its
+    /// edits.
+    smoothing: f64,
+    context: Option<Bigrams>,
+    real_words: Option<f64>,
+    split_compounds: bool,
+    join_words: bool,
+    capitalized: CasePolicy,
+    acronyms: CasePolicy,
+    numbers: Numbers,
+    personal: HashSet<String>,
+    /// What each abbreviation stands for.
+    abbreviations: HashMap<String, String>,
+    ignore: Vec<Regex>,
+    hyphens: Hyphens,
+    punctuation: Punctuation,
+    fold_diacritics: bool,
+    inflections: bool,
+    adaptive_edits: bool,
+    beam: Option<Beam>,
+    /// How long `with_budget` gives each search.
+    budget: Option<Duration>,
+    /// The most characters `with_max_word_len` lets a word have.
+    max_len: Option<usize>,
+}
+
+// Servers share a checker between their threads as it is; anything
+// that would stop them sharing it shouldn't build.
+const _: fn() = || {
+    fn shared<T: Send + Sync>() {}
+    shared::<Trie>();
+    shared::<SpellChecker>();
+};
+
+/// How `with_beam` narrows the search: to the `width` children of a node
+/// with the most words below them, by the `totals` of the model's nodes.
+#[derive(Clone, Debug)]
+struct Beam {
+    width:  usize,
+    totals: Vec<usize>,
+}
+
+/// The trained model, as trained, keyed by bytes if it is all ASCII,
+/// minimized, or made a transducer for the fst engine.
+#[derive(Clone, Debug)]
+enum Model {
+    Trie(Trie),
+    Ascii(AsciiTrie),
+    Dawg(Dawg),
+    Fst(Transducer),
+}
+
+impl Default for Model {
+    fn default() -> Self {
+        Model::Trie(Trie::new())
+    }
+}
+
+/// Whatever the engine searches besides the model itself.
+#[derive(Clone, Debug, Default)]
+enum Index {
+    #[default]
+    Trie,
+    SymSpell(SymSpell),
+    BkTree(BkTree),
+    /// Nothing: the model is a `Transducer`, which is walked.
+    Fst,
+}
+
+impl SpellChecker {
+    pub fn new(model: Trie) -> Self {
+        SpellChecker::with_engine(model, Engine::Trie)
+    }
+
+    /// A checker using `engine`, building its index if it needs one, or
+    /// what takes the trie's place: for the trie engine, its `AsciiTrie`,
+    /// if it is all ASCII, and for the fst engine, its transducer.
+    pub fn with_engine(model: Trie, engine: Engine) -> Self {
+        let index = match engine {
+            Engine::Trie => Index::Trie,
+            Engine::SymSpell => Index::SymSpell(SymSpell::new(&model, MAX_EDITS)),
+            Engine::BkTree => Index::BkTree(BkTree::from_trie(&model)),
+            Engine::Fst => Index::Fst,
+        };
+        let model = match engine {
+            Engine::Trie => model.to_ascii().map_or(Model::Trie(model), Model::Ascii),
+            Engine::Fst => Model::Fst(Transducer::from_trie(&model)),
+            _ => Model::Trie(model),
+        };
+        SpellChecker {
+            model,
+            index,
+            costs: None,
+            phonetic: None,
+            ranking: Ranking::Distance,
+            ranker: None,
+            smoothing: 0.0,
+            context: None,
+            real_words: None,
+            split_compounds: false,
+            join_words: false,
+            capitalized: CasePolicy::Check,
+            acronyms: CasePolicy::Check,
+            numbers: Numbers::Check,
+            personal: HashSet::new(),
+            abbreviations: HashMap::new(),
+            ignore: Vec::new(),
+            hyphens: Hyphens::Keep,
+            punctuation: Punctuation::default(),
+            fold_diacritics: false,
+            inflections: false,
+            adaptive_edits: false,
+            beam: None,
+            budget: None,
+            max_len: None,
+        }
+    }
+
+    /// A checker of `model` using the fst engine, as `with_engine` makes
+    /// one, for a transducer mapped with `map_model` rather than built.
+    pub fn from_transducer(model: Transducer) -> Self {
+        SpellChecker { model: Model::Fst(model), index: Index::Fst, ..SpellChecker::default() }
+    }
+
+    /// Weighs edits by `costs` instead of counting them. Weighted searches
+    /// walk the model itself, so the engine's index goes unused.
+    pub fn with_costs(self, costs: Costs) -> Self {
+        SpellChecker { costs: Some(costs), ..self }
+    }
+
+    /// Orders candidates by `ranking` rather than by distance.
+    pub fn with_ranking(self, ranking: Ranking) -> Self {
+        SpellChecker { ranking, ..self }
+    }
+
+    /// Orders candidates by `ranker` instead of by the ranking, context
+    /// and smoothing. Personal words still go ahead of other candidates
+    /// as far from the word, and the confidence of suggestions is still
+    /// their share of the noisy channel's likelihood.
+    pub fn with_ranker(self, ranker: Arc<dyn Ranker>) -> Self {
+        SpellChecker { ranker: Some(ranker), ..self }
+    }
+
+    /// Adds `k` to the count of every candidate where counts are weighed
+    /// against how likely the edits are: in noisy-channel ranking, and in
+    /// the confidence of suggestions. With raw counts a word seen twice is
+    /// twice as likely as one seen once, whatever the edits; the bigger
+    /// `k`, the less rare words' counts matter next to their edits, while
+    /// common words keep their lead. Candidates ranked by distance keep
+    /// their order, as adding the same to every count can't change it.
+    pub fn with_smoothing(self, k: f64) -> Self {
+        SpellChecker { smoothing: k, ..self }
+    }
+
+    /// How likely a candidate the corpus has `count` times is to be meant,
+    /// as the smoothing of `with_smoothing` has it.
+    fn prior(&self, count: usize) -> f64 {
+        count as f64 + self.smoothing
+    }
+
+    /// Weighs candidates by how often each followed the word before, as
+    /// counted in `bigrams`, when there is a word before; see
+    /// `check_after`.
+    pub fn with_context(self, bigrams: Bigrams) -> Self {
+        SpellChecker { context: Some(bigrams), ..self }
+    }
+
+    /// Questions known words, too, when the context has them far less
+    /// likely than a word close to them: see `suspect_after`. `threshold`
+    /// is how unlikely, as a fraction of the likelier word's score; the
+    /// higher it is, the more words are questioned.
+    pub fn with_real_words(self, threshold: f64) -> Self {
+        SpellChecker { real_words: Some(threshold), ..self }
+    }
+
+    /// Falls back to splitting the word in two known words, as in "hello
+    /// world" for "helloworld", when no candidate is within reach by
+    /// editing. A split counts as one edit, and is as common as the rarer
+    /// of its words.
+    pub fn with_compound_splits(self) -> Self {
+        SpellChecker { split_compounds: true, ..self }
+    }
+
+    /// Lets corrections be as many edits from a word as `adaptive_edits`
+    /// says for its length, rather than `MAX_EDITS`. The `SymSpell`
+    /// index only reaches `MAX_EDITS`, so with `Engine::SymSpell` long
+    /// words get no more than that. Weighted edits keep to their costs'
+    /// `max_cost`.
+    pub fn with_adaptive_edits(self) -> Self {
+        SpellChecker { adaptive_edits: true, ..self }
+    }
+
+    /// How many edits a correction may be from `word`.
+    fn max_edits(&self, word: &str) -> usize {
+        if self.adaptive_edits { adaptive_edits(word) } else { MAX_EDITS }
+    }
+
+    /// Looks for words split in two across whitespace: see `join`.
+    pub fn with_word_joins(self) -> Self {
+        SpellChecker { join_words: true, ..self }
+    }
+
+    /// Treats capitalized words and acronyms by these policies in
+    /// `check_written`.
+    pub fn with_case_policies(self, capitalized: CasePolicy, acronyms: CasePolicy) -> Self {
+        SpellChecker { capitalized, acronyms, ..self }
+    }
+
+    /// Treats words with digits in them as `numbers` says.
+    pub fn with_numbers(self, numbers: Numbers) -> Self {
+        SpellChecker { numbers, ..self }
+    }
+
+    /// Whether `word` is no word at all to check, but to be left out of
+    /// a text, as `with_numbers` may say of a number. `word_spans` leaves
+    /// such words out; a caller with words of its own should too.
+    pub fn is_skipped(&self, word: &str) -> bool {
+        self.numbers == Numbers::Skip && has_digit(word)
+    }
+
+    /// Takes the words in `personal`, a personal dictionary, as known,
+    /// however seldom the corpus has them, and never questions them. They
+    /// are suggested too, within reach of a word, ahead of the candidates
+    /// as close.
+    pub fn with_personal(self, personal: HashSet<String>) -> Self {
+        let personal = if self.fold_diacritics {
+            personal.iter().map(|word| strip_diacritics(word)).collect()
+        } else {
+            personal
+        };
+        SpellChecker { personal, ..self }
+    }
+
+    /// Expands an unknown word `abbreviations` has to what it stands for,
+    /// "govt" to "government", rather than correcting it to whatever word
+    /// is closest; that is its one correction, however many edits away.
+    pub fn with_abbreviations(self, abbreviations: HashMap<String, String>) -> Self {
+        SpellChecker { abbreviations, ..self }
+    }
+
+    /// Strips the diacritics of the words it is asked about, so that
+    /// "café" is checked as "cafe", for a model whose words had theirs
+    /// stripped too (see `fold_diacritics`). Corrections come without
+    /// diacritics.
+    pub fn with_diacritic_folding(self) -> Self {
+        let personal = self.personal.iter().map(|word| strip_diacritics(word)).collect();
+        SpellChecker { fold_diacritics: true, personal, ..self }
+    }
+
+    /// Takes an inflection of a known word as known too, "running" for
+    /// "run" or "tries" for "try", as `stems` finds them, for a corpus too
+    /// small to have every form of its words.
+    pub fn with_inflections(self) -> Self {
+        SpellChecker { inflections: true, ..self }
+    }
+
+    /// Takes any word one of `patterns` matches anywhere in it as correct,
+    /// without looking it up: URLs, hashes, ticket numbers and the like,
+    /// which would otherwise be corrected into nonsense. `check_written`
+    /// matches the word as it is written, everything else the lowercased
+    /// word it is given.
+    pub fn with_ignore(self, patterns: Vec<Regex>) -> Self {
+        SpellChecker { ignore: patterns, ..self }
+    }
+
+    /// Whether one of the patterns of `with_ignore` matches `word`.
+    pub fn is_ignored(&self, word: &str) -> bool {
+        self.ignore.iter().any(|pattern| pattern.is_match(word))
+    }
+
+    /// Finds the words of a text by `hyphens` in `word_spans`, as the
+    /// model was trained (see `Hyphens::apply`).
+    pub fn with_hyphens(self, hyphens: Hyphens) -> Self {
+        SpellChecker { hyphens, ..self }
+    }
+
+    /// Trims `punctuation` from either end of the words of a text in
+    /// `word_spans`, for a language written with more punctuation than
+    /// `MARKS`, such as guillemets, as the model was trained (see
+    /// `Punctuation::apply`).
+    pub fn with_punctuation(self, punctuation: Punctuation) -> Self {
+        SpellChecker { punctuation, ..self }
+    }
+
+    /// Falls back to words that sound like the word when no candidate is
+    /// within reach, building a `PhoneticIndex` of the model.
+    ///
+    /// # Panics
+    ///
+    /// If the model has already been minimized, or made a transducer by
+    /// the fst engine.
+    pub fn with_phonetic(self) -> Self {
+        let phonetic = match self.model {
+            Model::Trie(ref trie) => PhoneticIndex::new(trie),
+            Model::Ascii(ref trie) => PhoneticIndex::new(trie.words()),
+            Model::Dawg(_) => panic!("with_phonetic needs the trie; call it before minimize"),
+            Model::Fst(_) => panic!("with_phonetic needs the trie, which the fst engine drops"),
+        };
+        SpellChecker { phonetic: Some(phonetic), ..self }
+    }
+
+    /// Searches the trie no further than the `width` children of each node
+    /// with the most words below them, wherever it would otherwise try
+    /// them all, which is much faster on a big dictionary but can miss a
+    /// correction among rare words. Only searches by the trie engine
+    /// without weighted edits are narrowed; see `Profile::pruned`.
+    pub fn with_beam(self, width: usize) -> Self {
+        let totals = match self.model {
+            Model::Trie(ref trie) => subtree_counts(trie),
+            Model::Ascii(ref trie) => subtree_counts(trie),
+            Model::Dawg(ref dawg) => subtree_counts(dawg),
+            Model::Fst(_) => Vec::new(),
+        };
+        SpellChecker { beam: Some(Beam { width, totals }), ..self }
+    }
+
+    /// Gives each search for candidates no more than `time`, after which
+    /// it settles for what it has found so far, which may be nothing.
+    /// Like the beam of `with_beam`, it only applies to searches by the
+    /// trie engine without weighted edits; see `Profile::timed_out`.
+    pub fn with_budget(self, time: Duration) -> Self {
+        SpellChecker { budget: Some(time), ..self }
+    }
+
+    /// Takes any word longer than `chars` characters as correct without
+    /// searching for it: base64, hashes, minified code and the like, which
+    /// no correction fits, and which take the search the longest. See
+    /// `Profile::skipped`.
+    pub fn with_max_word_len(self, chars: usize) -> Self {
+        SpellChecker { max_len: Some(chars), ..self }
+    }
+
+    /// Whether the word before a word can change what becomes of it: with
+    /// a context (see `with_context`), or a `Ranker` that may look at it.
+    pub fn is_contextual(&self) -> bool {
+        self.context.is_some() || self.ranker.is_some()
+    }
+
+    /// Whether `word` is taken as correct without looking it up: it is
+    /// ignored, too long, or has digits in it and the number policy
+    /// doesn't check those.
+    fn is_let_by(&self, word: &str, profile: &mut Profile) -> bool {
+        self.is_ignored(word) || self.is_too_long(word, profile)
+            || (self.numbers != Numbers::Check && has_digit(word))
+    }
+
+    /// Whether `word` is too long to check, counting it in `profile` if
+    /// so.
+    fn is_too_long(&self, word: &str, profile: &mut Profile) -> bool {
+        match self.max_len {
+            Some(chars) if word.chars().count() > chars => {
+                profile.record_skip();
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Swaps the model for its `Dawg`, which answers the same but takes
+    /// less memory. Any engine index was already built from the trie, and
+    /// stays; a beam is measured again on the `Dawg`. An `AsciiTrie` is
+    /// smaller than its `Dawg` would be, and a transducer as small, and
+    /// they stay.
+    pub fn minimize(self) -> Self {
+        let model = match self.model {
+            Model::Trie(trie) => Model::Dawg(trie.minimize()),
+            model => model,
+        };
+        let beam = self.beam.map(|beam| match model {
+            Model::Dawg(ref dawg) => Beam { totals: subtree_counts(dawg), ..beam },
+            _ => beam,
+        });
+        SpellChecker { model, beam, ..self }
+    }
+
+    /// The trained trie, unless it has been made an `AsciiTrie`,
+    /// minimized or made a transducer.
+    pub fn model(&self) -> Option<&Trie> {
+        match self.model {
+            Model::Trie(ref trie) => Some(trie),
+            Model::Ascii(_) | Model::Dawg(_) | Model::Fst(_) => None,
+        }
+    }
+
+    /// How big the model is.
+    pub fn stats(&self) -> Stats {
+        match self.model {
+            Model::Trie(ref trie) => trie.stats(),
+            Model::Ascii(ref trie) => trie.stats(),
+            Model::Dawg(ref dawg) => dawg.stats(),
+            Model::Fst(ref fst) => fst.stats(),
+        }
+    }
+
+    pub fn engine(&self) -> Engine {
+        match self.index {
+            Index::Trie => Engine::Trie,
+            Index::SymSpell(_) => Engine::SymSpell,
+            Index::BkTree(_) => Engine::BkTree,
+            Index::Fst => Engine::Fst,
+        }
+    }
+
+    /// Whether `word` occurs in the training corpus, or the personal
+    /// dictionary has it, or, with `with_inflections`, it is an
+    /// inflection of a word that does.
+    pub fn is_known(&self, word: &str) -> bool {
+        self.is_listed(word)
+            || self.inflections && stems(word).iter().any(|stem| self.is_listed(stem))
+    }
+
+    /// Whether the corpus or the personal dictionary has `word`.
+    fn is_listed(&self, word: &str) -> bool {
+        self.count(word) > 0 || self.personal.contains(word)
+    }
+
+    /// How many times `word` occurs in the training corpus.
+    pub fn count(&self, word: &str) -> usize {
+        match self.model {
+            Model::Trie(ref trie) => trie.search(word),
+            Model::Ascii(ref trie) => trie.search(word),
+            Model::Dawg(ref dawg) => dawg.search(word),
+            Model::Fst(ref fst) => fst.search(word),
+        }
+    }
+
+    /// Up to `n` of the words of the model starting with `prefix`, as
+    /// `(word, count)`, most frequent first and then alphabetical (see
+    /// `Trie::complete`), whatever the model has been made.
+    pub fn complete(&self, prefix: &str, n: usize) -> Vec<(String, usize)> {
+        let prefix = &*self.folded(prefix);
+        match self.model {
+            Model::Trie(ref trie) => trie.complete(prefix, n),
+            Model::Ascii(ref trie) => trie.complete(prefix, n),
+            Model::Dawg(ref dawg) => dawg.complete(prefix, n),
+            Model::Fst(ref fst) => fst.complete(prefix, n),
+        }
+    }
+
+    /// The edits turning `word` into `correction`, by the costs of
+    /// `with_costs` if it was given any (see `explain`).
+    pub fn explain(&self, word: &str, correction: &str) -> Vec<Edit> {
+        match self.costs {
+            Some(ref costs) => explain(word, correction, costs),
+            None => explain(word, correction, &Costs::default()),
+        }
+    }
+
+    /// See `check_spelling`.
+    pub fn check(&self, word: &str) -> Option<String> {
+        self.check_after(None, word)
+    }
+
+    /// See `suggest`.
+    pub fn suggest(&self, word: &str, n: usize) -> Vec<(String, usize, usize)> {
+        self.suggest_after(None, word, n)
+    }
+
+    /// `check`, for `word` coming right after `prev` in a text. With a
+    /// context (see `with_context`), a candidate is likelier the more
+    /// often it followed `prev` in the corpus, instead of the more often
+    /// it was seen at all.
+    pub fn check_after(&self, prev: Option<&str>, word: &str) -> Option<String> {
+        let profile = &mut Profile::default();
+        if self.is_let_by(word, profile) {
+            return Some(word.to_owned());
+        }
+        let word = &*self.folded(word);
+        if self.is_known(word) {
+            let suspect = self.suspects(prev, word, 1, profile).into_iter().next();
+            return Some(suspect.map_or_else(|| word.to_owned(), |(suggestion, _, _)| suggestion));
+        }
+        self.candidates_after(prev, word, profile).into_iter().next()
+            .map(|(suggestion, _, _)| suggestion)
+    }
+
+    /// `suggest`, for `word` coming right after `prev` in a text, as
+    /// `check_after` weighs it. A word `check_word` takes as correct
+    /// without looking it up has no suggestions.
+    pub fn suggest_after(&self, prev: Option<&str>, word: &str, n: usize)
+                         -> Vec<(String, usize, usize)> {
+        let profile = &mut Profile::default();
+        if self.is_let_by(word, profile) {
+            return Vec::new();
+        }
+        let mut candidates = self.candidates_after(prev, &self.folded(word), profile);
+        candidates.truncate(n);
+        candidates
+    }
+
+    /// Up to `n` words `word`, a known word coming right after `prev`, is
+    /// likely a mistake for, as `suggest_after` ranks them. With a context
+    /// and real-word checking (see `with_real_words`), those are the
+    /// candidates for `word` that have been seen after `prev`, and that
+    /// `word` scores less than the threshold's fraction of after it;
+    /// otherwise there are none.
+    pub fn suspect_after(&self, prev: Option<&str>, word: &str, n: usize)
+                         -> Vec<(String, usize, usize)> {
+        self.suspects(prev, word, n, &mut Profile::default())
+    }
+
+    fn suspects(&self, prev: Option<&str>, word: &str, n: usize, profile: &mut Profile)
+                -> Vec<(String, usize, usize)> {
+        let (bigrams, prev, threshold) = match (&self.context, prev, self.real_words) {
+            (Some(bigrams), Some(prev), Some(threshold)) => (bigrams, prev, threshold),
+            _ => return Vec::new(),
+        };
+        if self.personal.contains(word) {
+            return Vec::new();
+        }
+
+        let score = bigrams.score(prev, word, self.count(word));
+        let mut found: Vec<_> = self.candidates(word, profile).into_iter()
+            .filter(|&(ref candidate, count, _)| {
+                candidate != word && bigrams.count(prev, candidate) > 0
+                    && score < threshold * bigrams.score(prev, candidate, count)
+            })
+            .collect();
+        match self.ranker {
+            Some(ref ranker) => ranker.rank(&RankContext { word, prev: Some(prev) }, &mut found),
+            None => self.ranking.rank_by(word, &mut found, |candidate, count| {
+                bigrams.score(prev, candidate, count)
+            }),
+        }
+        found.truncate(n);
+        found
+    }
+
+    /// The known word `first` and `second`, two words in a row, make
+    /// together, if they look like one word split in two: when either
+    /// isn't a known word, or when both are but the corpus has the word
+    /// they make more often than the two of them in a row, as counted in
+    /// the context (see `with_context`). `None` unless word joins are on
+    /// (see `with_word_joins`).
+    pub fn join(&self, first: &str, second: &str) -> Option<String> {
+        if !self.join_words {
+            return None;
+        }
+
+        let joined = format!("{}{}", first, second);
+        let count = self.count(&joined);
+        let apart = if self.is_known(first) && self.is_known(second) {
+            match self.context {
+                Some(ref bigrams) => bigrams.count(first, second),
+                None => return None,
+            }
+        } else {
+            0
+        };
+
+        if count > apart { Some(joined) } else { None }
+    }
+
+    /// Checks `word`, which came right after `prev` if anything did,
+    /// keeping up to `n` suggestions if it's misspelled (or, with
+    /// real-word checking, suspect).
+    pub fn check_word(&self, prev: Option<&str>, word: &str, n: usize) -> CheckResult {
+        self.check_word_profiled(prev, word, n, &mut Profile::default())
+    }
+
+    /// `check_word`, adding what the check cost to `profile`: how long it
+    /// took, how the searches for candidates went, and whether the word
+    /// was skipped for its length. A caller checking on several threads
+    /// keeps a profile for each, and merges them (see `Profile::merge`).
+    pub fn check_word_profiled(&self, prev: Option<&str>, word: &str, n: usize,
+                               profile: &mut Profile) -> CheckResult {
+        let start = Instant::now();
+        let result = self.classify(prev, word, n, profile);
+        profile.record_check(start.elapsed());
+        result
+    }
+
+    fn classify(&self, prev: Option<&str>, word: &str, n: usize, profile: &mut Profile)
+                -> CheckResult {
+        if self.is_let_by(word, profile) {
+            return CheckResult::Correct;
+        }
+        let word = &*self.folded(word);
+        if self.is_known(word) {
+            let suspects = self.suspects(prev, word, usize::MAX, profile);
+            if suspects.is_empty() {
+                return CheckResult::Correct;
+            }
+            let own = (word.to_owned(), self.count(word), 0);
+            return CheckResult::Suspect(self.suggestions(prev, word, suspects, Some(own), n));
+        }
+
+        if let Some((word, count, distance)) = self.expansion(word) {
+            // However seldom the corpus has it, it is the word meant.
+            let suggestion = Suggestion { word, distance, count, confidence: 1.0 };
+            return CheckResult::Corrected(Some(suggestion).into_iter().take(n).collect());
+        }
+        let found = self.candidates_after(prev, word, profile);
+        if found.is_empty() {
+            CheckResult::Unknown
+        } else {
+            CheckResult::Corrected(self.suggestions(prev, word, found, None, n))
+        }
+    }
+
+    /// Checks `written`, a word as a text has it, as `check_word` checks
+    /// it folded (see `fold_word`), unless it is capitalized or an acronym and the case
+    /// policy for it says otherwise, or it is ignored. A word the policy
+    /// lets by is `Correct`.
+    pub fn check_written(&self, prev: Option<&str>, written: &str, n: usize) -> CheckResult {
+        self.check_written_profiled(prev, written, n, &mut Profile::default())
+    }
+
+    /// `check_written`, adding what the check cost to `profile` as
+    /// `check_word_profiled` does.
+    pub fn check_written_profiled(&self, prev: Option<&str>, written: &str, n: usize,
+                                  profile: &mut Profile) -> CheckResult {
+        let policy = match Capitals::of(written) {
+            Capitals::None => CasePolicy::Check,
+            Capitals::First => self.capitalized,
+            Capitals::All => self.acronyms,
+        };
+        if policy == CasePolicy::Skip || self.is_ignored(written) {
+            return CheckResult::Correct;
+        }
+        match self.check_word_profiled(prev, &fold_word(written), n, profile) {
+            CheckResult::Corrected(suggestions) => CheckResult::Corrected(suggestions),
+            _ if policy == CasePolicy::Lenient => CheckResult::Correct,
+            result => result,
+        }
+    }
+
+    /// `word`, with its diacritics stripped if they are folded.
+    pub(crate) fn folded<'a>(&self, word: &'a str) -> Cow<'a, str> {
+        if self.fold_diacritics && !word.is_ascii() {
+            Cow::Owned(strip_diacritics(word))
+        } else {
+            Cow::Borrowed(word)
+        }
+    }
+
+    /// Checks each of `words` after the one before it, as `check_word`
+    /// does, making a `Correction` of each, in order.
+    pub fn check_all<I: IntoIterator<Item = String>>(&self, words: I) -> Vec<Correction> {
+        self.correct_each(words.into_iter()).collect()
+    }
+
+    /// Checks the running text `reader` has as `check_all` checks a
+    /// batch, a line at a time as the corrections are asked for, so that
+    /// a text of any size takes no more memory than its longest line.
+    /// The words are those `word_spans` finds, folded (see `fold_word`).
+    /// The corrections stop at the first line that can't be read.
+    pub fn corrections<'a, R: BufRead + 'a>(&'a self, reader: R)
+                                            -> impl Iterator<Item = Correction> + 'a {
+        let words = reader.lines()
+            .map_while(Result::ok)
+            .flat_map(move |line| {
+                self.word_spans(&line).into_iter()
+                    .map(|(_, word)| fold_word(word))
+                    .collect::<Vec<_>>()
+            });
+        self.correct_each(words)
+    }
+
+    /// The `Correction` of each of `words`, each checked after the one
+    /// before it.
+    fn correct_each<'a, I: Iterator<Item = String> + 'a>(&'a self, words: I)
+                                                        -> impl Iterator<Item = Correction> + 'a {
+        let mut prev: Option<String> = None;
+        words.map(move |word| {
+            let correction = self.correction(prev.as_deref(), word);
+            prev = Some(correction.original.clone());
+            correction
+        })
+    }
+
+    /// The `Correction` of `word`, which came right after `prev` if
+    /// anything did.
+    fn correction(&self, prev: Option<&str>, word: String) -> Correction {
+        let best = match self.check_word(prev, &word, 1) {
+            CheckResult::Correct => Some((word.clone(), 0, self.count(&word))),
+            CheckResult::Corrected(suggestions) | CheckResult::Suspect(suggestions) => {
+                suggestions.into_iter().next().map(|s| (s.word, s.distance, s.count))
+            }
+            CheckResult::Unknown => None,
+        };
+        match best {
+            Some((suggestion, distance, count)) => {
+                Correction { original: word, suggestion: Some(suggestion), distance, count }
+            }
+            None => Correction { original: word, suggestion: None, distance: 0, count: 0 },
+        }
+    }
+
+    /// The words of `line` to check, as they are written there, each with
+    /// the byte offset it starts at: those `word_spans` finds, trimmed of
+    /// the punctuation of `with_punctuation`, and hyphenated ones split
+    /// into their parts as the hyphen policy says. Words `is_skipped` says
+    /// to leave out are left out.
+    pub fn word_spans<'a>(&self, line: &'a str) -> Vec<(usize, &'a str)> {
+        let mut spans = Vec::new();
+        for (start, word) in self.punctuation.word_spans(line) {
+            if self.is_skipped(word) {
+                continue;
+            }
+            let whole = match self.hyphens {
+                Hyphens::Keep => true,
+                Hyphens::Split => false,
+                Hyphens::Both => self.is_known(&fold_word(word)),
+            };
+            if whole || !word.contains('-') {
+                spans.push((start, word));
+            } else {
+                spans.extend(hyphen_parts(start, word));
+            }
+        }
+        spans
+    }
+
+    /// The first `n` of the candidates `found` for `word`, with their
+    /// confidence, out of all of them and `own`, the word itself.
+    fn suggestions(&self, prev: Option<&str>, word: &str, found: Vec<(String, usize, usize)>,
+                   own: Option<(String, usize, usize)>, n: usize) -> Vec<Suggestion> {
+        // A personal word is taken to be as common as the commonest
+        // candidate.
+        let top = found.iter().map(|&(_, count, _)| count).max().unwrap_or(0);
+        let likelihood = |&(ref candidate, count, _): &(String, usize, usize)| {
+            let count = if self.personal.contains(candidate) { count.max(top) } else { count };
+            let prior = match (&self.context, prev) {
+                (Some(bigrams), Some(prev)) => bigrams.score(prev, candidate, count),
+                _ => self.prior(count),
+            };
+            prior * error_probability(word, candidate)
+        };
+        let likelihoods: Vec<f64> = found.iter().map(likelihood).collect();
+        let total: f64 = likelihoods.iter().sum::<f64>() + own.as_ref().map_or(0.0, likelihood);
+
+        found.into_iter().zip(likelihoods).take(n)
+            .map(|((word, count, distance), likelihood)| Suggestion {
+                word,
+                distance,
+                count,
+                confidence: if total > 0.0 { likelihood / total } else { 0.0 },
+            })
+            .collect()
+    }
+
+    fn candidates_after(&self, prev: Option<&str>, word: &str, profile: &mut Profile)
+                        -> Vec<(String, usize, usize)> {
+        if let Some(expansion) = self.expansion(word) {
+            return vec![expansion];
+        }
+        let mut found = self.candidates(word, profile);
+        if let Some(ref ranker) = self.ranker {
+            ranker.rank(&RankContext { word, prev }, &mut found);
+        } else if let (Some(bigrams), Some(prev)) = (&self.context, prev) {
+            self.ranking.rank_by(word, &mut found, |candidate, count| {
+                bigrams.score(prev, candidate, count)
+            });
+        }
+        if !self.personal.is_empty() {
+            self.boost_personal(word, &mut found);
+        }
+        found
+    }
+
+    /// What `word` stands for, if it is an abbreviation, as a candidate.
+    fn expansion(&self, word: &str) -> Option<(String, usize, usize)> {
+        self.abbreviations.get(word).map(|expansion| {
+            (expansion.clone(), self.count(expansion), edit_distance(word, expansion))
+        })
+    }
+
+    /// Adds the personal words within reach of `word` to the candidates
+    /// `found`, each ahead of the first other candidate no closer than it.
+    fn boost_personal(&self, word: &str, found: &mut Vec<(String, usize, usize)>) {
+        let mut personal: Vec<_> = self.personal.iter()
+            .map(|candidate| (candidate, edit_distance(word, candidate)))
+            .filter(|&(_, distance)| distance > 0 && distance <= self.max_edits(word))
+            .map(|(candidate, distance)| (candidate.clone(), self.count(candidate), distance))
+            .collect();
+        personal.sort_by(|a, b| (b.2, &b.0).cmp(&(a.2, &a.0)));
+        found.retain(|(candidate, _, _)| !self.personal.contains(candidate));
+        for candidate in personal {
+            let at = found.iter().position(|c| c.2 >= candidate.2).unwrap_or(found.len());
+            found.insert(at, candidate);
+        }
+    }
+
+    fn candidates(&self, word: &str, profile: &mut Profile) -> Vec<(String, usize, usize)> {
+        let mut found = self.edit_candidates(word, profile);
+        if self.split_compounds && found.is_empty() {
+            found = self.splits(word);
+        }
+        if let (Some(phonetic), true) = (&self.phonetic, found.is_empty()) {
+            found = phonetic.search(word);
+        }
+        if self.ranking != Ranking::Distance && self.ranker.is_none() {
+            self.ranking.rank_by(word, &mut found, |_, count| self.prior(count));
+        }
+        found
+    }
+
+    /// Every way of splitting `word` into two known words.
+    fn splits(&self, word: &str) -> Vec<(String, usize, usize)> {
+        let mut found: Vec<_> = word.char_indices().skip(1)
+            .filter_map(|(i, _)| {
+                let (first, second) = word.split_at(i);
+                match (self.count(first), self.count(second)) {
+                    (0, _) | (_, 0) => None,
+                    (a, b) => Some((format!("{} {}", first, second), a.min(b), 1)),
+                }
+            })
+            .collect();
+        rank(&mut found);
+        found
+    }
+
+    /// The words within reach of `word` that a walk of the model finds
+    /// within `limits`, ranked as `Trie::search_with_k_edit` ranks them,
+    /// adding how the walk went to `profile`.
+    fn walk(&self, word: &str, limits: Limits, profile: &mut Profile)
+            -> Vec<(String, usize, usize)> {
+        let k = self.max_edits(word);
+        let chars: Vec<char> = word.chars().collect();
+        let (mut found, outcome) = match self.model {
+            Model::Trie(ref trie) => search_within(trie, &chars, k, limits),
+            Model::Ascii(ref trie) => search_within(trie, &chars, k, limits),
+            Model::Dawg(ref dawg) => search_within(dawg, &chars, k, limits),
+            Model::Fst(ref fst) => search_within(fst, &chars, k, limits),
+        };
+        profile.record_walk(&outcome);
+        rank(&mut found);
+        found
+    }
+
+    fn edit_candidates(&self, word: &str, profile: &mut Profile) -> Vec<(String, usize, usize)> {
+        if let Some(ref costs) = self.costs {
+            return match self.model {
+                Model::Trie(ref trie) => trie.search_with_costs(word, costs),
+                Model::Ascii(ref trie) => trie.search_with_costs(word, costs),
+                Model::Dawg(ref dawg) => dawg.search_with_costs(word, costs),
+                Model::Fst(ref fst) => fst.search_with_costs(word, costs),
+            };
+        }
+
+        let k = self.max_edits(word);
+        match self.index {
+            // Only the trie engine's searches are narrowed by the beam of
+            // `with_beam` and cut short by `with_budget`.
+            Index::Trie => {
+                let limits = Limits {
+                    beam:     self.beam.as_ref().map(|beam| (beam.width, &beam.totals[..])),
+                    deadline: self.budget.map(|time| Instant::now() + time),
+                };
+                self.walk(word, limits, profile)
+            }
+            Index::Fst => self.walk(word, Limits::default(), profile),
+            Index::SymSpell(ref index) => index.search(word, k.min(MAX_EDITS)),
+            Index::BkTree(ref tree) => {
+                // The tree may find a few words that are only within reach
+                // by editing a swapped pair again; measure them the trie's
+                // way.
+                let mut found: Vec<_> = tree.query(word, k).into_iter()
+                    .map(|(candidate, count, _)| {
+                        let distance = edit_distance(word, &candidate);
+                        (candidate, count, distance)
+                    })
+                    .filter(|&(_, _, distance)| distance <= k)
+                    .collect();
+                rank(&mut found);
+                found
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod spell_checker_tests {
+    use std::collections::{HashMap, HashSet};
+    use std::io::Read;
+    use std::sync::Arc;
+    use std::thread;
+    use std::time::Duration;
+
+    use regex::Regex;
+
+    use super::{adaptive_edits, CasePolicy, CheckResult, Correction, Engine, Model, Numbers,
+                SpellChecker};
+    use bigrams::read_bigrams;
+    use train::{Hyphens, Punctuation};
+    use channel::{RankContext, Ranker, Ranking};
+    use costs::Costs;
+    use profile::Profile;
+    use transducer::Transducer;
+    use trie::Trie;
+
+    #[test]
+    fn parses_engines() {
+        assert_eq!(Ok(Engine::Trie), "trie".parse());
+        assert_eq!(Ok(Engine::SymSpell), "symspell".parse());
+        assert_eq!(Ok(Engine::BkTree), "bktree".parse());
+        assert_eq!(Ok(Engine::Fst), "fst".parse());
+        assert!("bk".parse::<Engine>().is_err());
+        assert_eq!("symspell", Engine::SymSpell.to_string());
+    }
+
+    #[test]
+    fn engines_agree() {
+        let trie = SpellChecker::new(fixture());
+        assert_eq!(Engine::Trie, trie.engine());
+
+        for &engine in &[Engine::SymSpell, Engine::BkTree, Engine::Fst] {
+            let other = SpellChecker::with_engine(fixture(), engine);
+            assert_eq!(engine, other.engine());
+
+            for word in &["hello", "helo", "hel", "wrld", "wordl", "xyzzy", "", "ca", "abc"] {
+                assert_eq!(trie.check(word), other.check(word), "{} {:?}", engine, word);
+                assert_eq!(trie.suggest(word, 5), other.suggest(word, 5), "{} {:?}", engine, word);
+            }
+        }
+    }
+
+    #[test]
+    fn keeps_a_transducer_for_fst() {
+        let fst = SpellChecker::with_engine(fixture(), Engine::Fst);
+        assert!(fst.model().is_none());
+        assert_eq!(SpellChecker::new(fixture()).stats().words, fst.stats().words);
+        assert!(fst.stats().heap_bytes < SpellChecker::new(fixture()).stats().heap_bytes);
+        assert_eq!(Engine::Fst, fst.clone().minimize().engine());
+
+        let costs = Costs::default();
+        let trie = SpellChecker::new(fixture()).with_costs(costs.clone());
+        assert_eq!(trie.suggest("helo", 5), fst.clone().with_costs(costs).suggest("helo", 5));
+
+        let built = SpellChecker::from_transducer(Transducer::from_trie(&fixture()));
+        assert_eq!(Engine::Fst, built.engine());
+        assert_eq!(fst.suggest("wrld", 5), built.suggest("wrld", 5));
+    }
+
+    #[test]
+    fn walks_ascii_by_byte() {
+        let ascii = SpellChecker::new(fixture());
+        let other = SpellChecker::new(unicode_fixture());
+
+        assert!(matches!(ascii.model, Model::Ascii(_)));
+        assert!(matches!(other.model, Model::Trie(_)));
+        assert!(ascii.model().is_none());
+        assert_eq!(Engine::Trie, ascii.engine());
+        assert_eq!(Engine::Trie, other.engine());
+        assert_eq!(Some("world".to_owned()), ascii.check("wrld"));
+        // It takes the trie's place, and is smaller even than its `Dawg`,
+        // so it stays minimized.
+        assert!(ascii.stats().heap_bytes < fixture().minimize().stats().heap_bytes);
+        assert!(matches!(ascii.clone().minimize().model, Model::Ascii(_)));
+
+        let costs = Costs::default();
+        for word in &["hello", "helo", "wrld", "xyzzy", ""] {
+            assert_eq!(other.suggest(word, 5), ascii.suggest(word, 5), "{:?}", word);
+            assert_eq!(other.clone().with_costs(costs.clone()).suggest(word, 5),
+                       ascii.clone().with_costs(costs.clone()).suggest(word, 5), "{:?}", word);
+            assert_eq!(other.clone().with_phonetic().suggest(word, 5),
+                       ascii.clone().with_phonetic().suggest(word, 5), "{:?}", word);
+            assert_eq!(other.clone().with_beam(1).suggest(word, 5),
+                       ascii.clone().with_beam(1).suggest(word, 5), "{:?}", word);
+        }
+    }
+
+    #[test]
+    fn completes_any_model() {
+        let trie = SpellChecker::new(fixture());
+        assert_eq!(vec![("help".to_owned(), 3), ("hell".to_owned(), 2)], trie.complete("he", 2));
+        assert_eq!(vec![("word".to_owned(), 1), ("world".to_owned(), 1)], trie.complete("wor", 5));
+        assert!(trie.complete("wrd", 5).is_empty());
+
+        let dawg = SpellChecker::new(unicode_fixture()).minimize();
+        let fst = SpellChecker::with_engine(fixture(), Engine::Fst);
+        for prefix in &["", "h", "hel", "wor", "world", "xyz"] {
+            assert_eq!(trie.complete(prefix, 3), dawg.complete(prefix, 3), "{:?}", prefix);
+            assert_eq!(trie.complete(prefix, 3), fst.complete(prefix, 3), "{:?}", prefix);
+        }
+    }
+
+    #[test]
+    fn minimized_agrees() {
+        let trie = SpellChecker::new(unicode_fixture());
+        let dawg = SpellChecker::new(unicode_fixture()).minimize();
+        assert!(trie.model().is_some());
+        assert!(dawg.model().is_none());
+        assert_eq!(trie.stats().words, dawg.stats().words);
+
+        for word in &["hello", "helo", "hel", "wrld", "wordl", "xyzzy", ""] {
+            assert_eq!(trie.is_known(word), dawg.is_known(word), "{:?}", word);
+            assert_eq!(trie.suggest(word, 5), dawg.suggest(word, 5), "{:?}", word);
+        }
+    }
+
+    #[test]
+    fn weighs_edits() {
+        let plain = SpellChecker::new(fixture());
+        let weighted = SpellChecker::new(fixture()).with_costs(Costs::default());
+        for word in &["hello", "helo", "wrld", "xyzzy"] {
+            assert_eq!(plain.suggest(word, 5), weighted.suggest(word, 5), "{:?}", word);
+        }
+
+        // "helx" is a replacement from both "hell" and the commoner "help",
+        // until "x" for "l" is made cheap.
+        let mut costs: Costs = "replace = 2\nmax_cost = 2\n".parse().unwrap();
+        costs.set_replace_cost('x', 'l', 1);
+        assert_eq!(Some("help".to_owned()), plain.check("helx"));
+        let weighted = SpellChecker::new(fixture()).with_costs(costs).minimize();
+        assert_eq!(Some("hell".to_owned()), weighted.check("helx"));
+        assert_eq!(Some("hello".to_owned()), weighted.check("hello"));
+    }
+
+    #[test]
+    fn falls_back_to_sounds() {
+        let mut model = fixture();
+        model.insert("photograph");
+        let plain = SpellChecker::new(model.clone());
+        let phonetic = SpellChecker::new(model).with_phonetic().minimize();
+
+        assert_eq!(None, plain.check("fotograf"));
+        assert_eq!(Some("photograph".to_owned()), phonetic.check("fotograf"));
+        assert_eq!(vec![("photograph".to_owned(), 1, 4)], phonetic.suggest("fotograf", 5));
+        // Words within reach are still found by editing.
+        assert_eq!(plain.suggest("helo", 5), phonetic.suggest("helo", 5));
+        assert_eq!(None, phonetic.check("xyzzy"));
+    }
+
+    #[test]
+    fn ranks_by_noisy_channel() {
+        let mut model = fixture();
+        for _ in 0..1_000 {
+            model.insert("hole");
+        }
+        let plain = SpellChecker::new(model.clone());
+        let channel = SpellChecker::new(model).with_ranking(Ranking::NoisyChannel);
+
+        // "hole" is two edits from "helo", but much the commonest.
+        assert_eq!(Some("help".to_owned()), plain.check("helo"));
+        assert_eq!(Some("hole".to_owned()), channel.check("helo"));
+        assert_eq!(plain.suggest("helo", 10).len(), channel.suggest("helo", 10).len());
+        assert_eq!(Some("help".to_owned()), channel.check("help"));
+    }
+
+    #[test]
+    fn takes_rankers() {
+        #[derive(Debug)]
+        struct Longest;
+
+        impl Ranker for Longest {
+            fn score(&self, candidate: &str, _: usize, _: usize, context: &RankContext) -> f64 {
+                assert_eq!(("helo", Some("the")), (context.word, context.prev));
+                candidate.len() as f64
+            }
+        }
+
+        let plain = SpellChecker::new(fixture());
+        let longest = plain.clone().with_ranker(Arc::new(Longest));
+        assert_eq!(Some("help".to_owned()), plain.check_after(Some("the"), "helo"));
+        assert_eq!(Some("hello".to_owned()), longest.check_after(Some("the"), "helo"));
+
+        let default = plain.clone().with_ranker(Arc::new(Ranking::Distance));
+        assert_eq!(plain.suggest("wrld", 5), default.suggest("wrld", 5));
+    }
+
+    #[test]
+    fn smooths_counts() {
+        let mut model = Trie::new();
+        for word in &["hart", "hart", "hot"] {
+            model.insert(word);
+        }
+        let channel = SpellChecker::new(model).with_ranking(Ranking::NoisyChannel);
+
+        // Seen twice to once, "hart" beats the likelier edit to "hot",
+        // until the counts are smoothed.
+        assert_eq!(Some("hart".to_owned()), channel.check("hat"));
+        let smoothed = channel.clone().with_smoothing(10.0);
+        assert_eq!(Some("hot".to_owned()), smoothed.check("hat"));
+
+        let plain = SpellChecker::new(fixture());
+        assert_eq!(plain.suggest("wrld", 5), plain.clone().with_smoothing(10.0).suggest("wrld", 5));
+    }
+
+    #[test]
+    fn weighs_context() {
+        let mut model = fixture();
+        for word in &["peace", "piece", "piece", "world", "a"] {
+            model.insert(word);
+        }
+        let bigrams = read_bigrams(&b"world peace. a piece, a piece"[..]).unwrap();
+        let plain = SpellChecker::new(model.clone());
+        let context = SpellChecker::new(model).with_context(bigrams);
+
+        assert_eq!(Some("piece".to_owned()), plain.check_after(Some("world"), "peice"));
+        assert_eq!(Some("peace".to_owned()), context.check_after(Some("world"), "peice"));
+        assert_eq!(Some("piece".to_owned()), context.check_after(Some("a"), "peice"));
+        assert_eq!(Some("piece".to_owned()), context.check_after(None, "peice"));
+        assert_eq!(Some("piece".to_owned()), context.check_after(Some("xyzzy"), "peice"));
+        // Closer words still come first.
+        assert_eq!(Some("help".to_owned()), context.check_after(Some("world"), "hel"));
+        assert_eq!(plain.suggest("peice", 5), context.suggest("peice", 5));
+    }
+
+    #[test]
+    fn questions_real_words() {
+        let mut model = Trie::new();
+        for word in &["over", "there", "their", "their", "their", "house", "car", "here"] {
+            model.insert(word);
+        }
+        let bigrams = read_bigrams(&b"over there. their house, their car, over here"[..]).unwrap();
+        let checker = SpellChecker::new(model.clone()).with_context(bigrams.clone());
+        let real_words = SpellChecker::new(model).with_context(bigrams).with_real_words(0.5);
+
+        assert!(checker.suspect_after(Some("over"), "their", 5).is_empty());
+        assert_eq!(Some("their".to_owned()), checker.check_after(Some("over"), "their"));
+
+        let words: Vec<_> = real_words.suspect_after(Some("over"), "their", 5).into_iter()
+            .map(|c| c.0)
+            .collect();
+        assert_eq!(vec!["there"], words);
+        assert_eq!(Some("there".to_owned()), real_words.check_after(Some("over"), "their"));
+
+        // Fitting words, and words after words never seen, pass.
+        assert_eq!(Some("there".to_owned()), real_words.check_after(Some("over"), "there"));
+        assert_eq!(Some("their".to_owned()), real_words.check_after(Some("xyzzy"), "their"));
+        assert_eq!(Some("their".to_owned()), real_words.check_after(None, "their"));
+        assert_eq!(Some("their".to_owned()), real_words.check_after(Some("house"), "their"));
+    }
+
+    #[test]
+    fn splits_compounds() {
+        let mut model = fixture();
+        for word in &["never", "theless", "nevert", "nevert", "heless", "heless"] {
+            model.insert(word);
+        }
+        let plain = SpellChecker::new(model.clone());
+        let split = SpellChecker::new(model).with_compound_splits().minimize();
+
+        assert_eq!(None, plain.check("helloworld"));
+        assert_eq!(Some("hello world".to_owned()), split.check("helloworld"));
+        // The commoner split wins.
+        assert_eq!(vec![("nevert heless".to_owned(), 2, 1), ("never theless".to_owned(), 1, 1)],
+                   split.suggest("nevertheless", 5));
+        // Words within reach are still found by editing.
+        assert_eq!(plain.suggest("helo", 5), split.suggest("helo", 5));
+        assert_eq!(None, split.check("xyzzyworld"));
+    }
+
+    #[test]
+    fn joins_words() {
+        let mut model = fixture();
+        for word in &["some", "thing", "something", "something", "every", "one", "everyone"] {
+            model.insert(word);
+        }
+        let bigrams = read_bigrams(&b"some thing, something, every one of them"[..]).unwrap();
+        let plain = SpellChecker::new(model.clone());
+        let joins = SpellChecker::new(model.clone()).with_word_joins();
+        let context = SpellChecker::new(model).with_context(bigrams).with_word_joins();
+
+        assert_eq!(None, plain.join("hel", "lo"));
+        assert_eq!(Some("hello".to_owned()), joins.join("hel", "lo"));
+        assert_eq!(Some("hello".to_owned()), joins.join("hell", "o"));
+        assert_eq!(None, joins.join("hel", "x"));
+        assert_eq!(None, joins.join("some", "thing"));
+
+        // "something" is commoner than "some thing", but "everyone" isn't
+        // commoner than "every one".
+        assert_eq!(Some("something".to_owned()), context.join("some", "thing"));
+        assert_eq!(None, context.join("every", "one"));
+    }
+
+    #[test]
+    fn classifies_words() {
+        let checker = SpellChecker::new(fixture());
+
+        assert_eq!(CheckResult::Correct, checker.check_word(None, "hello", 1));
+        assert_eq!(CheckResult::Unknown, checker.check_word(None, "xyzzy", 1));
+
+        let suggestions = match checker.check_word(None, "wrld", 5) {
+            CheckResult::Corrected(suggestions) => suggestions,
+            result => panic!("{:?}", result),
+        };
+        let words: Vec<_> = suggestions.iter()
+            .map(|s| (s.word.as_str(), s.distance, s.count))
+            .collect();
+        assert_eq!(vec![("world", 1, 1), ("word", 2, 1)], words);
+        assert!(suggestions[0].confidence > 0.9);
+        let total: f64 = suggestions.iter().map(|s| s.confidence).sum();
+        assert!((total - 1.0).abs() < 1e-9);
+
+        match checker.check_word(None, "wrld", 1) {
+            CheckResult::Corrected(first) => assert_eq!(suggestions[..1], first[..]),
+            result => panic!("{:?}", result),
+        }
+    }
+
+    #[test]
+    fn applies_case_policies() {
+        let corrected = |result| match result {
+            CheckResult::Corrected(suggestions) => suggestions[0].word.clone(),
+            result => panic!("{:?}", result),
+        };
+        let checker = SpellChecker::new(fixture());
+        assert_eq!(CheckResult::Correct, checker.check_written(None, "Hello", 1));
+        assert_eq!("world", corrected(checker.check_written(None, "Wrld", 1)));
+        assert_eq!(CheckResult::Unknown, checker.check_written(None, "XYZZY", 1));
+
+        let checker = SpellChecker::new(fixture())
+            .with_case_policies(CasePolicy::Lenient, CasePolicy::Skip);
+        assert_eq!("world", corrected(checker.check_written(None, "Wrld", 1)));
+        assert_eq!(CheckResult::Correct, checker.check_written(None, "Xyzzy", 1));
+        assert_eq!(CheckResult::Correct, checker.check_written(None, "WRLD", 1));
+        assert_eq!(CheckResult::Correct, checker.check_written(None, "XYZZY", 1));
+        assert_eq!(CheckResult::Unknown, checker.check_written(None, "xyzzy", 1));
+        // A single capital is a capitalized word, not an acronym.
+        assert_eq!("ca", corrected(checker.check_written(None, "Q", 1)));
+        assert_eq!(Ok(CasePolicy::Lenient), "lenient".parse());
+        assert!("ignore".parse::<CasePolicy>().is_err());
+        assert_eq!("skip", CasePolicy::Skip.to_string());
+    }
+
+    #[test]
+    fn applies_number_policy() {
+        let line = "the 3rd wrld, 42";
+        let checker = SpellChecker::new(fixture());
+        assert_eq!(CheckResult::Unknown, checker.check_word(None, "x86_64", 1));
+        assert_eq!(vec![(0, "the"), (4, "3rd"), (8, "wrld"), (14, "42")],
+                   checker.word_spans(line));
+
+        let checker = checker.with_numbers(Numbers::Accept);
+        assert_eq!(CheckResult::Correct, checker.check_word(None, "x86_64", 1));
+        assert_eq!(CheckResult::Correct, checker.check_written(None, "3RD", 1));
+        assert_eq!(4, checker.word_spans(line).len());
+        assert!(!checker.is_skipped("3rd"));
+
+        let checker = checker.with_numbers(Numbers::Skip);
+        assert_eq!(vec![(0, "the"), (8, "wrld")], checker.word_spans(line));
+        assert!(checker.is_skipped("3rd") && !checker.is_skipped("third"));
+
+        assert_eq!(Ok(Numbers::Accept), "accept".parse());
+        assert!("ignore".parse::<Numbers>().is_err());
+        assert_eq!("skip", Numbers::Skip.to_string());
+    }
+
+    #[test]
+    fn checks_and_suggests_as_check_word_does() {
+        let abbreviations: HashMap<String, String> =
+            Some(("wrl".to_owned(), "world wide".to_owned())).into_iter().collect();
+        let checker = SpellChecker::new(fixture())
+            .with_ignore(vec![Regex::new("^https?://").unwrap()])
+            .with_abbreviations(abbreviations)
+            .with_max_word_len(12);
+
+        for &numbers in &[Numbers::Check, Numbers::Accept, Numbers::Skip] {
+            let checker = checker.clone().with_numbers(numbers);
+            for &word in &["helo", "wrl", "http://wrld", "wrld2", "x86", "hello", "zzzzz",
+                           "wrldwrldwrldwrld"] {
+                let suggestions = checker.suggest(word, 3);
+                match checker.check_word(None, word, 3) {
+                    CheckResult::Correct => {
+                        assert_eq!(Some(word.to_owned()), checker.check(word), "{:?}", numbers);
+                        // Nothing, or the word itself first, if it is known.
+                        assert!(suggestions.first().is_none_or(|s| s.0 == word),
+                                "{} {:?}", word, numbers);
+                    }
+                    CheckResult::Corrected(expected) => {
+                        assert_eq!(Some(expected[0].word.clone()), checker.check(word));
+                        let words: Vec<_> = expected.into_iter().map(|s| s.word).collect();
+                        assert_eq!(words, suggestions.into_iter().map(|s| s.0).collect::<Vec<_>>(),
+                                   "{} {:?}", word, numbers);
+                    }
+                    result => {
+                        assert_eq!(CheckResult::Unknown, result, "{} {:?}", word, numbers);
+                        assert_eq!(None, checker.check(word), "{} {:?}", word, numbers);
+                        assert!(suggestions.is_empty(), "{} {:?}", word, numbers);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn folds_diacritics() {
+        let mut model = fixture();
+        model.insert("cafe");
+        let checker = SpellChecker::new(model).with_diacritic_folding();
+
+        assert_eq!(CheckResult::Correct, checker.check_word(None, "café", 1));
+        assert_eq!(Some("cafe".to_owned()), checker.check("cafés"));
+        assert_eq!(Some("hello".to_owned()), checker.check("hellö"));
+        assert_eq!(CheckResult::Correct, checker.check_written(None, "CAFÉ", 1));
+    }
+
+    #[test]
+    fn splits_hyphenated_words() {
+        let mut model = fixture();
+        model.insert("hell-bent");
+        let line = "hell-bent, wrld-help";
+
+        let checker = SpellChecker::new(model.clone());
+        assert_eq!(vec![(0, "hell-bent"), (11, "wrld-help")], checker.word_spans(line));
+        let checker = SpellChecker::new(model.clone()).with_hyphens(Hyphens::Split);
+        assert_eq!(vec![(0, "hell"), (5, "bent"), (11, "wrld"), (16, "help")],
+                   checker.word_spans(line));
+        let checker = SpellChecker::new(model).with_hyphens(Hyphens::Both);
+        assert_eq!(vec![(0, "hell-bent"), (11, "wrld"), (16, "help")], checker.word_spans(line));
+    }
+
+    #[test]
+    fn trims_more_punctuation() {
+        let line = "«hello», ¿wrld?";
+
+        let checker = SpellChecker::new(fixture());
+        assert_eq!(vec![(0, "«hello»"), (11, "¿wrld")], checker.word_spans(line));
+        let checker = checker.with_punctuation(Punctuation::new(vec!['«', '»', '¿']));
+        assert_eq!(vec![(2, "hello"), (13, "wrld")], checker.word_spans(line));
+    }
+
+    #[test]
+    fn accepts_inflections() {
+        let checker = SpellChecker::new(fixture());
+        assert!(!checker.is_known("helped"));
+
+        let checker = checker.with_inflections();
+        assert!(checker.is_known("helping"));
+        assert_eq!(CheckResult::Correct, checker.check_word(None, "helped", 1));
+        assert_eq!(CheckResult::Correct, checker.check_written(None, "Worlds", 1));
+        assert_eq!(0, checker.count("helped"));
+        assert_eq!(Some("help".to_owned()), checker.check("helpz"));
+    }
+
+    #[test]
+    fn ignores_patterns() {
+        let patterns = vec![Regex::new("^https?://").unwrap(),
+                            Regex::new("^[A-Z]+-[0-9]+$").unwrap()];
+        let checker = SpellChecker::new(fixture()).with_ignore(patterns);
+
+        assert!(checker.is_ignored("https://example.com/helo"));
+        assert_eq!(CheckResult::Correct, checker.check_word(None, "http://wrld", 1));
+        assert_eq!(CheckResult::Correct, checker.check_written(None, "HELO-42", 1));
+        assert_eq!(CheckResult::Unknown, checker.check_written(None, "Helo-42", 1));
+        assert_eq!(Some("http://wrld".to_owned()), checker.check("http://wrld"));
+        assert_eq!(Some("help".to_owned()), checker.check("helo"));
+    }
+
+    #[test]
+    fn expands_abbreviations() {
+        let abbreviations: HashMap<String, String> =
+            [("wld", "would"), ("wrl", "world wide")].iter()
+                .map(|&(abbreviation, expansion)| (abbreviation.to_owned(), expansion.to_owned()))
+                .collect();
+        let checker = SpellChecker::new(fixture());
+        assert_eq!(Some("word".to_owned()), checker.check("wld"));
+
+        // The expansion needn't be known.
+        let checker = checker.with_abbreviations(abbreviations);
+        assert_eq!(Some("would".to_owned()), checker.check("wld"));
+        assert_eq!(vec![("world wide".to_owned(), 0, 7)], checker.suggest("wrl", 3));
+        match checker.check_word(None, "wrl", 3) {
+            CheckResult::Corrected(suggestions) => {
+                assert_eq!(("world wide", 1.0), (&*suggestions[0].word, suggestions[0].confidence));
+            }
+            result => panic!("{:?}", result),
+        }
+        // A known word is left as it is.
+        assert_eq!(CheckResult::Correct, checker.check_word(None, "help", 1));
+    }
+
+    #[test]
+    fn takes_personal_words() {
+        let personal: HashSet<String> = ["wirld", "helo"].iter().map(|&w| w.to_owned()).collect();
+        let checker = SpellChecker::new(fixture()).with_personal(personal);
+
+        assert!(checker.is_known("wirld"));
+        assert_eq!(CheckResult::Correct, checker.check_word(None, "helo", 1));
+        assert_eq!(0, checker.count("helo"));
+        // "wirld" is as close to "wrld" as "world", and goes first.
+        assert_eq!(vec![("wirld".to_owned(), 0, 1), ("world".to_owned(), 1, 1)],
+                   checker.suggest("wrld", 2));
+        match checker.check_word(None, "wrld", 2) {
+            CheckResult::Corrected(suggestions) => {
+                assert_eq!("wirld", suggestions[0].word);
+                assert!(suggestions[0].confidence >= suggestions[1].confidence);
+            }
+            result => panic!("{:?}", result),
+        }
+        // But not ahead of closer ones.
+        assert_eq!(Some("word".to_owned()), checker.check("wordd"));
+        assert_eq!("wirld", checker.suggest("wordd", 3)[2].0);
+    }
+
+    #[test]
+    fn scores_suspects_against_the_word() {
+        let mut model = Trie::new();
+        for word in &["over", "there", "their", "their"] {
+            model.insert(word);
+        }
+        let bigrams = read_bigrams(&b"over there, their"[..]).unwrap();
+        let checker = SpellChecker::new(model).with_context(bigrams).with_real_words(0.5);
+
+        match checker.check_word(Some("over"), "their", 5) {
+            CheckResult::Suspect(suggestions) => {
+                assert_eq!(1, suggestions.len());
+                assert_eq!(("there", 2, 1), (suggestions[0].word.as_str(),
+                                             suggestions[0].distance, suggestions[0].count));
+                assert!(suggestions[0].confidence < 1.0);
+            }
+            result => panic!("{:?}", result),
+        }
+        assert_eq!(CheckResult::Correct, checker.check_word(Some("over"), "there", 5));
+    }
+
+    #[test]
+    fn checks_batches() {
+        let checker = SpellChecker::new(fixture());
+        let words = vec!["hello", "wrld", "xyzzy"].into_iter().map(str::to_owned);
+        let correction = |original: &str, suggestion: Option<&str>, distance, count| Correction {
+            original: original.to_owned(),
+            suggestion: suggestion.map(str::to_owned),
+            distance,
+            count,
+        };
+        assert_eq!(vec![correction("hello", Some("hello"), 0, 1),
+                        correction("wrld", Some("world"), 1, 1),
+                        correction("xyzzy", None, 0, 0)],
+                   checker.check_all(words));
+        assert!(checker.check_all(Vec::new()).is_empty());
+    }
+
+    #[test]
+    fn corrects_lazily() {
+        let checker = SpellChecker::new(fixture());
+        let text = "Hello, wrld!\n\nxyzzy HELP";
+        let corrected: Vec<(String, Option<String>)> = checker.corrections(text.as_bytes())
+            .map(|c| (c.original, c.suggestion))
+            .collect();
+        assert_eq!(vec![("hello".to_owned(), Some("hello".to_owned())),
+                        ("wrld".to_owned(), Some("world".to_owned())),
+                        ("xyzzy".to_owned(), None),
+                        ("help".to_owned(), Some("help".to_owned()))],
+                   corrected);
+
+        // A line that isn't UTF-8 ends them.
+        let text = text.as_bytes().chain(&b"\n\xff wrld\nwrld"[..]);
+        assert_eq!(4, checker.corrections(text).count());
+    }
+
+    #[test]
+    fn scales_edits_with_length() {
+        assert_eq!(vec![1, 1, 2, 2, 3], ["", "word", "words", "wordsmit", "wordsmith"].iter()
+                   .map(|word| adaptive_edits(word)).collect::<Vec<_>>());
+
+        let mut model = fixture();
+        model.insert("helloworld");
+        for &engine in &[Engine::Trie, Engine::BkTree] {
+            let checker = SpellChecker::with_engine(model.clone(), engine);
+            assert_eq!(Some("help".to_owned()), checker.check("hlo"));
+            assert_eq!(None, checker.check("hxllowrdl"));
+
+            let checker = checker.with_adaptive_edits();
+            assert_eq!(None, checker.check("hlo"));
+            assert_eq!(Some("world".to_owned()), checker.check("wrld"));
+            assert_eq!(Some("helloworld".to_owned()), checker.check("hxllowrdl"));
+        }
+        let checker = SpellChecker::with_engine(model, Engine::SymSpell).with_adaptive_edits();
+        assert_eq!(None, checker.check("hxllowrdl"));
+    }
+
+    #[test]
+    fn narrows_to_the_beam() {
+        // More words start with "j" than with "h" or "c", so only that
+        // branch is searched.
+        let mut model = fixture();
+        model.insert_with_count("jello", 7);
+        model.insert("cello");
+        assert_eq!(Some("help".to_owned()), SpellChecker::new(model.clone()).check("hellp"));
+        let checker = SpellChecker::new(model).with_beam(1);
+        let mut profile = Profile::default();
+
+        assert_eq!(Some("jello".to_owned()), first(checker.check_word_profiled(None, "hellp", 1,
+                                                                                &mut profile)));
+        assert_eq!(1, profile.pruned());
+        checker.check_word_profiled(None, "hello", 1, &mut profile);
+        let checker = checker.minimize();
+        assert_eq!(Some("jello".to_owned()), first(checker.check_word_profiled(None, "hellp", 1,
+                                                                                &mut profile)));
+        assert_eq!(2, profile.pruned());
+
+        let mut profile = Profile::default();
+        SpellChecker::new(fixture()).check_word_profiled(None, "hellp", 1, &mut profile);
+        assert_eq!(0, profile.pruned());
+    }
+
+    #[test]
+    fn skips_long_words() {
+        let checker = SpellChecker::new(fixture()).with_max_word_len(5);
+
+        assert_eq!(Some("help".to_owned()), checker.check("helpp"));
+        assert_eq!(Some("hellloooo".to_owned()), checker.check("hellloooo"));
+        assert_eq!(CheckResult::Correct, checker.check_word(None, "wrldwrld", 1));
+        assert!(checker.suggest("wrldwrld", 1).is_empty());
+
+        let mut profile = Profile::default();
+        for word in &["helpp", "hellloooo", "wrldwrld"] {
+            checker.check_word_profiled(None, word, 1, &mut profile);
+            SpellChecker::new(fixture()).check_word_profiled(None, word, 1, &mut profile);
+        }
+        assert_eq!(2, profile.skipped());
+    }
+
+    #[test]
+    fn keeps_to_the_budget() {
+        let checker = SpellChecker::new(fixture()).with_budget(Duration::from_secs(0));
+        let mut profile = Profile::default();
+        assert_eq!(None, first(checker.check_word_profiled(None, "wrld", 1, &mut profile)));
+        assert_eq!(Some("hello".to_owned()), checker.check("hello"));
+        assert_eq!(1, profile.timed_out());
+
+        let checker = SpellChecker::new(fixture()).with_budget(Duration::from_secs(60));
+        let mut profile = Profile::default();
+        assert_eq!(Some("world".to_owned()),
+                   first(checker.check_word_profiled(None, "wrld", 1, &mut profile)));
+        assert_eq!(0, profile.timed_out());
+    }
+
+    #[test]
+    fn profiles_checks() {
+        for engine in &[Engine::Trie, Engine::Fst, Engine::SymSpell] {
+            let checker = SpellChecker::with_engine(fixture(), *engine);
+            let mut profile = Profile::default();
+            for word in &["wrld", "hello", "xyzzy"] {
+                assert_eq!(checker.check_word(None, word, 2),
+                           checker.check_word_profiled(None, word, 2, &mut profile));
+            }
+            checker.check_written_profiled(None, "Wrld", 2, &mut profile);
+
+            assert_eq!(4, profile.latency().count());
+            // The known word needs no search, and the index isn't walked.
+            let searches = if *engine == Engine::SymSpell { 0 } else { 3 };
+            assert_eq!(searches, profile.visited().count(), "{}", engine);
+            assert!(searches == 0 || profile.visited().max() > 1);
+        }
+    }
+
+    #[test]
+    fn answers_threads_at_once() {
+        let checker = SpellChecker::new(fixture()).with_beam(1);
+        let words: Vec<String> = ["wrld", "hello", "helo", "hellp", "xyzzy", "wor", "hlelo"]
+            .iter().map(|w| w.to_string()).collect();
+        let mut alone = Profile::default();
+        let expected: Vec<_> = words.iter()
+            .map(|w| checker.check_word_profiled(None, w, 3, &mut alone))
+            .collect();
+
+        let checker = Arc::new(checker);
+        let (threads, rounds) = (8, 50);
+        let workers: Vec<_> = (0..threads)
+            .map(|i| {
+                let checker = Arc::clone(&checker);
+                let (words, expected) = (words.clone(), expected.clone());
+                thread::spawn(move || {
+                    let mut profile = Profile::default();
+                    for round in 0..rounds {
+                        // Each thread asks for the words in an order of its own.
+                        for j in 0..words.len() {
+                            let k = (i + round + j) % words.len();
+                            let result = checker.check_word_profiled(None, &words[k], 3,
+                                                                     &mut profile);
+                            assert_eq!(expected[k], result);
+                        }
+                    }
+                    profile
+                })
+            })
+            .collect();
+        let mut profile = Profile::default();
+        for worker in workers {
+            profile.merge(&worker.join().unwrap());
+        }
+
+        let checks = threads * rounds * words.len();
+        assert_eq!(checks, profile.latency().count());
+        assert_eq!(threads * rounds * alone.pruned(), profile.pruned());
+        assert_eq!(threads * rounds * alone.visited().sum() as usize,
+                   profile.visited().sum() as usize);
+    }
+
+    #[test]
+    fn knows_when_context_matters() {
+        assert!(!SpellChecker::new(fixture()).is_contextual());
+        assert!(SpellChecker::new(fixture()).with_ranker(Arc::new(Ranking::Distance))
+            .is_contextual());
+    }
+
+    #[test]
+    #[should_panic(expected = "before minimize")]
+    fn needs_phonetic_before_minimize() {
+        SpellChecker::new(unicode_fixture()).minimize().with_phonetic();
+    }
+
+    /// The best suggestion of `result`, if it has any.
+    fn first(result: CheckResult) -> Option<String> {
+        match result {
+            CheckResult::Corrected(suggestions) | CheckResult::Suspect(suggestions) => {
+                suggestions.into_iter().next().map(|s| s.word)
+            }
+            _ => None,
+        }
+    }
+
+    fn fixture() -> Trie {
+        let mut t = Trie::new();
+        for word in &["hello", "hell", "hell", "help", "help", "help", "world", "word", "abc",
+                      "ca"] {
+            t.insert(word);
+        }
+        t
+    }
+
+    /// `fixture`, with a word that isn't ASCII, far from the others.
+    fn unicode_fixture() -> Trie {
+        let mut t = fixture();
+        t.insert("naïve");
+        t
+    }
+}
@@ -0,0 +1,129 @@
+/*!
+spellchecker diff: the words and counts two models differ in.
+*/
+
+use std::io::{self, stdout, BufWriter, Write};
+use std::process::exit;
+
+use spellchecker::{diff_models, load_model, ModelDiff};
+
+use USAGE;
+
+/// Runs `spellchecker diff`, with the arguments after it, and exits: with
+/// status 0 if the models are the same, 1 if they aren't, and 2 if they
+/// couldn't be compared.
+pub(crate) fn diff<I: Iterator<Item = String>>(args: I) -> ! {
+    let options = parse_diff_args(args).unwrap_or_else(|msg| {
+        eprintln!("spellchecker: {}", msg);
+        eprintln!("{}", USAGE);
+        exit(2);
+    });
+    let load = |path: &str| load_model(path).unwrap_or_else(|e| {
+        eprintln!("spellchecker: {}: {}", path, e);
+        exit(2);
+    });
+    let (a, b) = (load(&options.models.0), load(&options.models.1));
+
+    let diff = diff_models(&a, &b, options.ratio, options.min_count);
+    write_diff(&diff, &mut BufWriter::new(stdout())).unwrap_or_else(|e| {
+        eprintln!("spellchecker: {}", e);
+        exit(2);
+    });
+    let plural = |n| if n == 1 { "" } else { "s" };
+    eprintln!("spellchecker: {} word{} only in {}, {} only in {}, {} changed by {}x or more",
+              diff.only_in_a.len(), plural(diff.only_in_a.len()), options.models.0,
+              diff.only_in_b.len(), options.models.1, diff.changed.len(), options.ratio);
+    exit(if diff.is_empty() { 0 } else { 1 });
+}
+
+#[derive(Debug, PartialEq)]
+struct DiffOptions {
+    models:    (String, String),
+    ratio:     f64,
+    min_count: usize,
+}
+
+fn parse_diff_args<I: Iterator<Item = String>>(mut args: I) -> Result<DiffOptions, String> {
+    let mut models = Vec::new();
+    let mut ratio = 2.0;
+    let mut min_count = 1;
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--ratio" => {
+                let value = args.next().ok_or("--ratio needs a value")?;
+                ratio = match value.parse() {
+                    Ok(r) if r >= 1.0 => r,
+                    _ => return Err(format!("bad ratio `{}' (expected at least 1)", value)),
+                };
+            }
+            "--min-count" => {
+                let value = args.next().ok_or("--min-count needs a value")?;
+                min_count = value.parse().map_err(|_| format!("bad count `{}'", value))?;
+            }
+            _ if arg.starts_with("--") => return Err(format!("unknown option `{}' for diff", arg)),
+            _ => models.push(arg),
+        }
+    }
+
+    let mut models = models.into_iter();
+    match (models.next(), models.next(), models.next()) {
+        (Some(a), Some(b), None) => Ok(DiffOptions { models: (a, b), ratio, min_count }),
+        _ => Err("diff needs two models".to_owned()),
+    }
+}
+
+/// Writes `diff` out as `spellchecker diff` shows it: each word only the
+/// first model has after a `-`, each only the second has after a `+`,
+/// and each that changed after a `~`, with its counts.
+fn write_diff<W: Write>(diff: &ModelDiff, writer: &mut W) -> io::Result<()> {
+    for (word, count) in &diff.only_in_a {
+        writeln!(writer, "- {}\t{}", word, count)?;
+    }
+    for (word, count) in &diff.only_in_b {
+        writeln!(writer, "+ {}\t{}", word, count)?;
+    }
+    for (word, a, b) in &diff.changed {
+        writeln!(writer, "~ {}\t{} -> {}", word, a, b)?;
+    }
+    writer.flush()
+}
+
+#[cfg(test)]
+mod diff_tests {
+    use super::{parse_diff_args, write_diff};
+    use spellchecker::ModelDiff;
+
+    #[test]
+    fn reads_options() {
+        let parse = |args: &[&str]| parse_diff_args(args.iter().map(|s| s.to_string()));
+
+        let options = parse(&["old.model", "new.model"]).unwrap();
+        assert_eq!(("old.model".to_owned(), "new.model".to_owned()), options.models);
+        assert_eq!((2.0, 1), (options.ratio, options.min_count));
+        let options = parse(&["--ratio", "1.5", "a", "--min-count", "10", "b"]).unwrap();
+        assert_eq!((1.5, 10), (options.ratio, options.min_count));
+        assert!(parse(&["a"]).is_err());
+        assert!(parse(&["a", "b", "c"]).is_err());
+        assert!(parse(&["--ratio", "0.5", "a", "b"]).is_err());
+        assert!(parse(&["--min-count", "some", "a", "b"]).is_err());
+        assert!(parse(&["--suggestions", "3", "a", "b"]).is_err());
+    }
+
+    #[test]
+    fn writes_each_side() {
+        let diff = ModelDiff {
+            only_in_a: vec![("colour".to_owned(), 12)],
+            only_in_b: vec![("color".to_owned(), 40), ("flavor".to_owned(), 3)],
+            changed:   vec![("rain".to_owned(), 2, 40)],
+        };
+        let mut output = Vec::new();
+        write_diff(&diff, &mut output).unwrap();
+        assert_eq!("- colour\t12\n+ color\t40\n+ flavor\t3\n~ rain\t2 -> 40\n",
+                   String::from_utf8(output).unwrap());
+
+        let mut output = Vec::new();
+        write_diff(&ModelDiff::default(), &mut output).unwrap();
+        assert!(output.is_empty());
+    }
+}
@@ -0,0 +1,486 @@
+/*!
+Checking running text: where each misspelling of a --document is,
+the corrections --annotate writes into it, and the text --fix writes
+back corrected.
+*/
+
+use std::io::{self, BufRead, BufReader, Read, Write};
+
+use spellchecker::{fold_word, identifier_parts, CheckResult, Filter, Languages, Masker, Profile,
+                   SpellChecker};
+use textkit::timings::Timings;
+
+use cli::words::checker_for;
+
+/// Checks every word of the running text on `reader`, each after the one
+/// before it, writing a line for every word that isn't correct, with
+/// where it is, and flushing after each line of text. Only what `filter`
+/// takes for prose is checked, and in code, the parts of identifiers.
+/// With `list`, only the words are written, as they are in the text.
+/// Each line is checked in the language of its words, if any of
+/// `languages` knows more of them than the rest, or else in that of the
+/// line before. Returns how many words weren't correct.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn check_document<R: Read, W: Write>(languages: &Languages, reader: R, filter: Filter,
+                                                list: bool, n: usize, writer: &mut W,
+                                                timings: &mut Timings, profile: &mut Profile)
+                                                -> io::Result<usize> {
+    let mut lines = BufReader::new(reader).lines().enumerate();
+    let mut masker = Masker::new(filter);
+    let identifiers = matches!(filter, Filter::Code(_));
+    let mut prev: Option<String> = None;
+    let mut language = None;
+    let mut misspelled = 0;
+
+    while let Some((number, line)) = timings.time("read", || lines.next()) {
+        let line = line?;
+        let mut report = String::new();
+
+        let prose = masker.mask(&line);
+        language = languages.detect(&prose).or(language);
+        let checker = checker_for(languages, language);
+        let misspellings = timings.time("search", || {
+            line_misspellings(checker, &mut prev, &prose, identifiers, n, profile)
+        });
+        misspelled += misspellings.len();
+        for (offset, written, identifier, suggestions) in misspellings {
+            if list {
+                report.push_str(&format!("{}\n", written));
+                continue;
+            }
+            // Entities decoded before it in the same run of text move a
+            // word, and may leave its offset inside a character of the line.
+            let column = line.get(..offset).unwrap_or(&prose[..offset]).chars().count() + 1;
+            let suggestions = if suggestions.is_empty() {
+                "-".to_owned()
+            } else {
+                suggestions.join(" ")
+            };
+            let written = match identifier {
+                Some(identifier) => format!("{} ({})", identifier, written),
+                None => written.to_owned(),
+            };
+            report.push_str(&format!("{}:{}: {} -> {}\n", number + 1, column, written,
+                                     suggestions));
+        }
+
+        if !report.is_empty() {
+            timings.time("write", || {
+                writer.write_all(report.as_bytes())?;
+                writer.flush()
+            })?;
+        }
+    }
+
+    Ok(misspelled)
+}
+
+/// Writes out the text on `reader` as it is, but for the corrections of
+/// each word `check_document` would report, in brackets right after it,
+/// as in `wrod[->word]`, a line at a time, and returns how many words
+/// there were.
+pub(crate) fn annotate_document<R: Read, W: Write>(languages: &Languages, reader: R, filter: Filter,
+                                                   n: usize, writer: &mut W, timings: &mut Timings,
+                                                   profile: &mut Profile) -> io::Result<usize> {
+    let mut reader = BufReader::new(reader);
+    let mut masker = Masker::new(filter);
+    let identifiers = matches!(filter, Filter::Code(_));
+    let mut prev: Option<String> = None;
+    let mut language = None;
+    let mut misspelled = 0;
+    let mut line = String::new();
+
+    loop {
+        line.clear();
+        if timings.time("read", || reader.read_line(&mut line))? == 0 {
+            break;
+        }
+
+        let prose = masker.mask(line.trim_end_matches(&['\r', '\n'][..]));
+        language = languages.detect(&prose).or(language);
+        let checker = checker_for(languages, language);
+        let misspellings = timings.time("search", || {
+            line_misspellings(checker, &mut prev, &prose, identifiers, n, profile)
+        });
+        misspelled += misspellings.len();
+        let annotated = annotate_line(&line, misspellings);
+
+        timings.time("write", || {
+            writer.write_all(annotated.as_bytes())?;
+            writer.flush()
+        })?;
+    }
+
+    Ok(misspelled)
+}
+
+/// `line` with the corrections of each of `misspellings`, as
+/// `line_misspellings` finds them, in brackets right after it, or `-` if
+/// there are none.
+fn annotate_line(line: &str, misspellings: Vec<(usize, &str, Option<&str>, Vec<String>)>)
+                 -> String {
+    let mut annotated = String::with_capacity(line.len());
+    let mut copied = 0;
+
+    for (offset, written, _, suggestions) in misspellings {
+        let end = offset + written.len();
+        // Entities decoded before a word in the same run of text move it,
+        // and it can't be annotated where it isn't.
+        if line.get(offset..end) != Some(written) {
+            continue;
+        }
+        let suggestions = if suggestions.is_empty() {
+            "-".to_owned()
+        } else {
+            suggestions.join(" ")
+        };
+        annotated.push_str(&line[copied..end]);
+        annotated.push_str(&format!("[->{}]", suggestions));
+        copied = end;
+    }
+
+    annotated.push_str(&line[copied..]);
+    annotated
+}
+
+#[cfg(test)]
+mod annotate_document_tests {
+    use super::annotate_document;
+    use spellchecker::{Filter, Language, Languages, Profile, SpellChecker, Trie};
+    use std::sync::Arc;
+    use textkit::timings::Timings;
+
+    #[test]
+    fn annotates_in_place() {
+        assert_eq!("Hello, wrold[->world word]!\r\n\n  (hello) \"Zzzzzz[->-]\" wor[->word world]",
+                   annotate("Hello, wrold!\r\n\n  (hello) \"Zzzzzz\" wor", Filter::Text, 2));
+        assert_eq!("Wrold[->World] WROLD[->WORLD]\n", annotate("Wrold WROLD\n", Filter::Text, 1));
+        assert_eq!("", annotate("", Filter::Text, 1));
+    }
+
+    #[test]
+    fn annotates_the_prose() {
+        let text = "// Wrold\nlet wrold = \"helo\";\n";
+        assert_eq!("// Wrold[->World]\nlet wrold = \"helo[->hello]\";\n",
+                   annotate(text, Filter::Code(Language::C), 1));
+        // An entity moves the rest of its word, which is left as it is.
+        assert_eq!("<p>helo[->hello] <i>wrold[->world]</i> AT&amp;T's</p>\n",
+                   annotate("<p>helo <i>wrold</i> AT&amp;T's</p>\n", Filter::Html, 1));
+    }
+
+    fn annotate(text: &str, filter: Filter, n: usize) -> String {
+        let mut t = Trie::new();
+        for word in &["hello", "world", "world", "word"] {
+            t.insert(word);
+        }
+        let mut languages = Languages::new();
+        languages.add("", Arc::new(SpellChecker::new(t)));
+        let mut output = Vec::new();
+        annotate_document(&languages, text.as_bytes(), filter, n, &mut output,
+                          &mut Timings::default(), &mut Profile::default()).unwrap();
+        String::from_utf8(output).unwrap()
+    }
+}
+
+/// The words of `line` that aren't correct, each checked after the one
+/// before it, starting with `prev`, which is left at the last word of the
+/// line: where each starts, how it is written, the identifier it is part
+/// of if it is one of the parts `identifiers` are split into (see
+/// `identifier_parts`), and its corrections, capitalized as it is (see
+/// `match_case`). What checking them cost is added to `profile`.
+pub(crate) fn line_misspellings<'a>(checker: &SpellChecker, prev: &mut Option<String>,
+                                    line: &'a str, identifiers: bool, n: usize,
+                                    profile: &mut Profile)
+                                    -> Vec<(usize, &'a str, Option<&'a str>, Vec<String>)> {
+    let mut misspellings = Vec::new();
+
+    for (start, whole) in checker.word_spans(line) {
+        let parts = if identifiers { identifier_parts(start, whole) } else { vec![(start, whole)] };
+        let identifier = if parts.len() > 1 { Some(whole) } else { None };
+        for (offset, written) in parts {
+            let word = fold_word(written);
+            let result = checker.check_written_profiled(prev.as_deref(), written, n, profile);
+            let suggestions = match result {
+                CheckResult::Correct => None,
+                CheckResult::Corrected(suggestions) | CheckResult::Suspect(suggestions) => {
+                    Some(suggestions.iter().map(|s| match_case(written, &s.word)).collect())
+                }
+                CheckResult::Unknown => Some(Vec::new()),
+            };
+            if let Some(suggestions) = suggestions {
+                misspellings.push((offset, written, identifier, suggestions));
+            }
+            *prev = Some(word);
+        }
+    }
+
+    misspellings
+}
+
+#[cfg(test)]
+mod check_document_tests {
+    use super::check_document;
+    use spellchecker::{CasePolicy, Filter, Language, Languages, Profile, SpellChecker, Trie};
+    use std::sync::Arc;
+    use textkit::timings::Timings;
+
+    #[test]
+    fn reports_where_misspellings_are() {
+        let text = "Hello, wrold!\n\n  (hello) \"Zzzzzz\" wor\n";
+        assert_eq!("1:8: wrold -> world word\n3:12: Zzzzzz -> -\n3:20: wor -> word world\n",
+                   document(text, 2));
+    }
+
+    #[test]
+    fn counts_characters() {
+        assert_eq!("1:7: wrold -> world\n", document("héllo wrold", 1));
+    }
+
+    #[test]
+    fn keeps_capitals() {
+        assert_eq!("1:1: Wrold -> World Word\n1:7: WROLD -> WORLD WORD\n\
+                    1:13: wRold -> world word\n",
+                   document("Wrold WROLD wRold", 2));
+    }
+
+    #[test]
+    fn keeps_quiet_about_correct_text() {
+        assert_eq!("", document("Hello world. Hello, word!", 1));
+        assert_eq!("", document("", 1));
+    }
+
+    #[test]
+    fn follows_case_policies() {
+        let text = "Zzzzzz met Wrold at NASA, zzzzzz WROLD";
+        assert_eq!("1:1: Zzzzzz -> -\n1:12: Wrold -> World\n1:21: NASA -> -\n\
+                    1:27: zzzzzz -> -\n1:34: WROLD -> WORLD\n",
+                   document(text, 1));
+        let checker = fixture().with_case_policies(CasePolicy::Lenient, CasePolicy::Skip);
+        assert_eq!("1:12: Wrold -> World\n1:27: zzzzzz -> -\n",
+                   check(&checker, text, Filter::Text, 1));
+    }
+
+    #[test]
+    fn filters_markdown() {
+        let text = "---\nlayout: wrold\n---\nHello Wrold\n\n```\nwrold()\n```\n\
+                    hello `wrold` at [wrold](http://wrold.io) at <wrold@wrold.io>, wrold.\n";
+        assert_eq!("4:7: Wrold -> World\n9:19: wrold -> world\n9:64: wrold -> world\n",
+                   check(&fixture(), text, Filter::Markdown, 1));
+        let unfiltered = document(text, 1);
+        assert!(unfiltered.contains("2:9: wrold") && unfiltered.contains("7:1: wrold"));
+    }
+
+    #[test]
+    fn filters_html() {
+        let text = "<p title=\"wrold\">Hello <b>wrold</b> caf&eacute;</p>\n\
+                    <script>\nwrold()\n</script><!-- wrold --> hello&nbsp;Wrold\n";
+        assert_eq!("1:27: wrold -> world\n1:37: café -> -\n4:36: Wrold -> World\n",
+                   check(&fixture(), text, Filter::Html, 1));
+    }
+
+    #[test]
+    fn filters_code() {
+        let text = "/// Hello wrold.\nfn wrold() -> &'static str {\n\
+                    \"hello\\nwrold\" // Wrold\n}\n";
+        assert_eq!("1:11: wrold -> world\n3:9: wrold -> world\n3:19: Wrold -> World\n",
+                   check(&fixture(), text, Filter::Code(Language::Rust), 1));
+    }
+
+    #[test]
+    fn splits_identifiers_in_code() {
+        let text = "// hello_wrold, at helloWorld at HelloWrold.\n";
+        assert_eq!("1:10: hello_wrold (wrold) -> world\n1:39: HelloWrold (Wrold) -> World\n",
+                   check(&fixture(), text, Filter::Code(Language::C), 1));
+        assert!(document(text, 1).contains("1:4: hello_wrold -> -"));
+    }
+
+    #[test]
+    fn lists_misspellings() {
+        let text = "Hello, wrold!\n\n  (hello) \"Zzzzzz\" wor\n";
+        let mut output = Vec::new();
+        let mut languages = Languages::new();
+        languages.add("", Arc::new(fixture()));
+        let misspelled = check_document(&languages, text.as_bytes(), Filter::Text, true, 2,
+                                        &mut output, &mut Timings::default(),
+                                        &mut Profile::default()).unwrap();
+        assert_eq!(3, misspelled);
+        assert_eq!("wrold\nZzzzzz\nwor\n", String::from_utf8(output).unwrap());
+    }
+
+    #[test]
+    fn checks_each_line_in_its_language() {
+        let mut german = Trie::new();
+        for word in &["hallo", "welt", "die"] {
+            german.insert(word);
+        }
+        let mut languages = Languages::new();
+        languages.add("en", Arc::new(fixture()));
+        languages.add("de", Arc::new(SpellChecker::new(german)));
+        let text = "Hallo Welt, die wlet.\nHello world, wrold.\nwlet\n";
+        let mut output = Vec::new();
+        check_document(&languages, text.as_bytes(), Filter::Text, false, 1, &mut output,
+                       &mut Timings::default(), &mut Profile::default()).unwrap();
+
+        // The last line says nothing of its language, and is checked in
+        // that of the line before.
+        assert_eq!("1:17: wlet -> welt\n2:14: wrold -> world\n3:1: wlet -> met\n",
+                   String::from_utf8(output).unwrap());
+    }
+
+    fn document(text: &str, n: usize) -> String {
+        check(&fixture(), text, Filter::Text, n)
+    }
+
+    fn check(checker: &SpellChecker, text: &str, filter: Filter, n: usize) -> String {
+        let mut languages = Languages::new();
+        languages.add("", Arc::new(checker.clone()));
+        let mut output = Vec::new();
+        check_document(&languages, text.as_bytes(), filter, false, n, &mut output,
+                       &mut Timings::default(), &mut Profile::default()).unwrap();
+        String::from_utf8(output).unwrap()
+    }
+
+    fn fixture() -> SpellChecker {
+        let mut t = Trie::new();
+        for word in &["hello", "héllo", "world", "world", "word", "met", "at"] {
+            t.insert(word);
+        }
+        SpellChecker::new(t)
+    }
+}
+
+/// How much of the likelihood of all its candidates a correction must
+/// have more than for --fix to make it.
+const FIX_CONFIDENCE: f64 = 0.5;
+
+/// Copies the running text on `reader` to `writer`, with every word the
+/// checker confidently corrects replaced, or, if `dry_run`, writes only
+/// the lines that would change, as diff hunks. Flushes after each line.
+pub(crate) fn fix_document<R: Read, W: Write>(checker: &SpellChecker, reader: R, dry_run: bool,
+                                              writer: &mut W, timings: &mut Timings,
+                                              profile: &mut Profile) -> io::Result<()> {
+    let mut reader = BufReader::new(reader);
+    let mut prev: Option<String> = None;
+    let mut line = String::new();
+    let mut number = 0;
+
+    loop {
+        line.clear();
+        if timings.time("read", || reader.read_line(&mut line))? == 0 {
+            break;
+        }
+        number += 1;
+
+        let fixed = timings.time("search", || fix_line(checker, &mut prev, &line, profile));
+        let output = if !dry_run {
+            fixed
+        } else if fixed != line {
+            let eol = &['\r', '\n'][..];
+            format!("@@ -{0} +{0} @@\n-{1}\n+{2}\n", number, line.trim_end_matches(eol),
+                    fixed.trim_end_matches(eol))
+        } else {
+            continue;
+        };
+
+        timings.time("write", || {
+            writer.write_all(output.as_bytes())?;
+            writer.flush()
+        })?;
+    }
+
+    Ok(())
+}
+
+/// `line` with every word the checker confidently corrects replaced,
+/// each checked after the one before it, corrected, starting with `prev`,
+/// which is left at the last word of the line.
+fn fix_line(checker: &SpellChecker, prev: &mut Option<String>, line: &str, profile: &mut Profile)
+            -> String {
+    let mut fixed = String::with_capacity(line.len());
+    let mut copied = 0;
+
+    for (offset, written) in checker.word_spans(line) {
+        let mut word = fold_word(written);
+        let result = checker.check_written_profiled(prev.as_deref(), written, 1, profile);
+        if let CheckResult::Corrected(suggestions) = result {
+            if let Some(best) = suggestions.into_iter().find(|s| s.confidence > FIX_CONFIDENCE) {
+                fixed.push_str(&line[copied..offset]);
+                fixed.push_str(&match_case(written, &best.word));
+                copied = offset + written.len();
+                word = best.word;
+            }
+        }
+        *prev = Some(word);
+    }
+
+    fixed.push_str(&line[copied..]);
+    fixed
+}
+
+/// `word` capitalized as `like` is: in capitals if all of its letters
+/// are (and there are several), with a capital first letter if that is,
+/// and as it is otherwise.
+pub(crate) fn match_case(like: &str, word: &str) -> String {
+    let letters: Vec<char> = like.chars().filter(|c| c.is_alphabetic()).collect();
+    match letters.first() {
+        Some(first) if first.is_uppercase() => {
+            if letters.len() > 1 && letters.iter().all(|c| c.is_uppercase()) {
+                word.to_uppercase()
+            } else {
+                let mut chars = word.chars();
+                chars.next()
+                    .map(|first| first.to_uppercase().chain(chars).collect())
+                    .unwrap_or_default()
+            }
+        }
+        _ => word.to_owned(),
+    }
+}
+
+#[cfg(test)]
+mod fix_document_tests {
+    use super::{fix_document, match_case};
+    use spellchecker::{Profile, SpellChecker, Trie};
+    use textkit::timings::Timings;
+
+    #[test]
+    fn corrects_in_place() {
+        assert_eq!("The world, \"the  WORLD\"!\r\nzzzzzz (hello)\n",
+                   fix("Teh wrold, \"teh  WROLD\"!\r\nzzzzzz (helo)\n", false));
+        assert_eq!("no newline, world", fix("no newline, wrold", false));
+    }
+
+    #[test]
+    fn leaves_doubtful_corrections() {
+        // "wurd" is as likely to be "word" as "ward".
+        assert_eq!("wurd\n", fix("wurd\n", false));
+    }
+
+    #[test]
+    fn previews_changes() {
+        assert_eq!("@@ -2 +2 @@\n-Teh end\n+The end\n",
+                   fix("hello world\nTeh end\r\n", true));
+        assert_eq!("", fix("hello world\n", true));
+    }
+
+    #[test]
+    fn matches_case() {
+        assert_eq!("The", match_case("Teh", "the"));
+        assert_eq!("THE", match_case("TEH", "the"));
+        assert_eq!("the", match_case("teh", "the"));
+        assert_eq!("I", match_case("I", "i"));
+        assert_eq!("Don't", match_case("Dont", "don't"));
+        assert_eq!("Éclair", match_case("Eclair", "éclair"));
+    }
+
+    fn fix(text: &str, dry_run: bool) -> String {
+        let mut t = Trie::new();
+        for word in &["the", "hello", "world", "word", "ward", "end", "no", "newline"] {
+            t.insert(word);
+        }
+        let mut output = Vec::new();
+        fix_document(&SpellChecker::new(t), text.as_bytes(), dry_run, &mut output,
+                     &mut Timings::default(), &mut Profile::default()).unwrap();
+        String::from_utf8(output).unwrap()
+    }
+}
@@ -0,0 +1,430 @@
+/*!
+How the results of checking words are written: --format text, csv,
+tsv, json and table, and the --explain and --list layouts.
+*/
+
+use std::iter;
+use std::str::FromStr;
+
+use spellchecker::{CheckResult, SpellChecker, Suggestion};
+
+use cli::json::json_string;
+
+/// How results are written, picked with
+/// `--format text|scores|scored|json|csv|tsv`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Format {
+    /// The word, and its corrections if it needs any.
+    Text,
+    /// A line per suggestion, with its distance and confidence.
+    Scores,
+    /// A line per word, with the distance, count and confidence of each
+    /// suggestion.
+    Scored,
+    /// A JSON object per word.
+    Json,
+    /// A table with a header row, a row per suggestion, and the fields
+    /// separated by commas and quoted as needed.
+    Csv,
+    /// The same table, with the fields separated by tabs, and tabs and
+    /// line breaks in them escaped.
+    Tsv,
+    /// The word, suggestion, distance and count columns of that table,
+    /// lined up, with the cells boxed in if `boxed`.
+    Table { boxed: bool },
+    /// Only the words that aren't correct (`--list`).
+    List,
+    /// Each suggestion with the edits that make it (`--explain`), as
+    /// `format_checked` writes it; anywhere else, as `Text`.
+    Explain,
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, String> {
+        match s {
+            "text"   => Ok(Format::Text),
+            "scores" => Ok(Format::Scores),
+            "scored" => Ok(Format::Scored),
+            "json"   => Ok(Format::Json),
+            "csv"    => Ok(Format::Csv),
+            "tsv"    => Ok(Format::Tsv),
+            "table"  => Ok(Format::Table { boxed: false }),
+            "table=box" => Ok(Format::Table { boxed: true }),
+            _ => Err(format!("unknown format `{}' (expected text, scores, scored, json, csv, tsv, \
+                              table or table=box)", s)),
+        }
+    }
+}
+
+/// The columns of a `Format::Csv` or `Format::Tsv` table, in order.
+const COLUMNS: [&str; 6] = ["word", "status", "suggestion", "distance", "count", "confidence"];
+
+/// What to write before any result, if anything.
+pub(crate) fn format_header(format: Format) -> Option<String> {
+    match format {
+        Format::Csv => Some(format!("{}\n", COLUMNS.join(","))),
+        Format::Tsv => Some(format!("{}\n", COLUMNS.join("\t"))),
+        _ => None,
+    }
+}
+
+/// The output for `word`, written as `format` says.
+pub(crate) fn format_result(word: &str, result: CheckResult, format: Format) -> String {
+    let words = |suggestions: &[Suggestion]| {
+        suggestions.iter().map(|s| s.word.as_str()).collect::<Vec<_>>().join(" ")
+    };
+    let scores = |word: &str, suggestions: &[Suggestion]| {
+        suggestions.iter()
+            .map(|s| format!("{}, {}, {}, {:.3}\n", word, s.word, s.distance, s.confidence))
+            .collect()
+    };
+    let scored = |suggestions: &[Suggestion]| {
+        suggestions.iter()
+            .map(|s| format!("{} {}:{}:{:.3}", s.word, s.distance, s.count, s.confidence))
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+
+    match (format, result) {
+        (Format::Json, result) => format!("{}\n", json_result(word, &result)),
+        (Format::Csv, result) => table_rows(word, &result).iter()
+            .map(|row| {
+                let fields: Vec<_> = row.iter().map(|field| csv_field(field)).collect();
+                format!("{}\n", fields.join(","))
+            })
+            .collect(),
+        (Format::Tsv, result) => table_rows(word, &result).iter()
+            .map(|row| {
+                let fields: Vec<_> = row.iter().map(|field| tsv_field(field)).collect();
+                format!("{}\n", fields.join("\t"))
+            })
+            .collect(),
+        (Format::Table { boxed }, result) => format_table(&text_rows(word, &result), boxed),
+        (Format::List, CheckResult::Correct) => String::new(),
+        (Format::List, _) => format!("{}\n", word),
+        (Format::Text | Format::Explain, CheckResult::Correct) => format!("{}\n", word),
+        (Format::Text | Format::Explain, CheckResult::Corrected(s)) => {
+            format!("{}, {}\n", word, words(&s))
+        }
+        (Format::Text | Format::Explain, CheckResult::Suspect(s)) => {
+            format!("{}?, {}\n", word, words(&s))
+        }
+        (Format::Scores, CheckResult::Correct) => format!("{}, {}, 0, 1.000\n", word, word),
+        (Format::Scores, CheckResult::Corrected(s)) => scores(word, &s),
+        (Format::Scores, CheckResult::Suspect(s)) => scores(&format!("{}?", word), &s),
+        (Format::Scored, CheckResult::Correct) => format!("{}\n", word),
+        (Format::Scored, CheckResult::Corrected(s)) => format!("{}, {}\n", word, scored(&s)),
+        (Format::Scored, CheckResult::Suspect(s)) => format!("{}?, {}\n", word, scored(&s)),
+        (_, CheckResult::Unknown) => format!("{}, -\n", word),
+    }
+}
+
+/// The output for `word`, written as `format` says, with the edits
+/// `checker` finds for each suggestion if it is `Format::Explain`.
+pub(crate) fn format_checked(checker: &SpellChecker, word: &str, result: CheckResult,
+                             format: Format) -> String {
+    let (shown, suggestions) = match (format, result) {
+        (Format::Explain, CheckResult::Corrected(s)) => (word.to_owned(), s),
+        (Format::Explain, CheckResult::Suspect(s)) => (format!("{}?", word), s),
+        (format, result) => return format_result(word, result, format),
+    };
+    suggestions.iter()
+        .map(|s| {
+            let edits: Vec<_> = checker.explain(word, &s.word).iter()
+                .map(|edit| edit.to_string())
+                .collect();
+            format!("{} -> {}: {}\n", shown, s.word, edits.join(", "))
+        })
+        .collect()
+}
+
+/// `result` for `word` as a JSON object: the word, its status (correct,
+/// corrected, suspect or unknown), and the best suggestion, if any, with
+/// its distance, count and confidence. When there are several
+/// suggestions, they are all listed too, best first.
+pub(crate) fn json_result(word: &str, result: &CheckResult) -> String {
+    let (status, suggestions) = match *result {
+        CheckResult::Correct => ("correct", &[][..]),
+        CheckResult::Corrected(ref suggestions) => ("corrected", &suggestions[..]),
+        CheckResult::Suspect(ref suggestions) => ("suspect", &suggestions[..]),
+        CheckResult::Unknown => ("unknown", &[][..]),
+    };
+    let fields = |s: &Suggestion| {
+        format!("\"distance\":{},\"count\":{},\"confidence\":{:.3}",
+                s.distance, s.count, s.confidence)
+    };
+
+    let mut json = format!("{{\"word\":{},\"status\":\"{}\"", json_string(word), status);
+    match suggestions.first() {
+        Some(best) => {
+            json += &format!(",\"suggestion\":{},{}", json_string(&best.word), fields(best));
+        }
+        None if status == "unknown" => json += ",\"suggestion\":null",
+        None => {}
+    }
+    if suggestions.len() > 1 {
+        let all: Vec<_> = suggestions.iter()
+            .map(|s| format!("{{\"word\":{},{}}}", json_string(&s.word), fields(s)))
+            .collect();
+        json += &format!(",\"suggestions\":[{}]", all.join(","));
+    }
+    json + "}"
+}
+
+/// The rows of the table for `result` for `word`, with a field for each
+/// of `COLUMNS`: a row per suggestion, or a single row with only the word
+/// and its status if there are none.
+fn table_rows(word: &str, result: &CheckResult) -> Vec<[String; 6]> {
+    let (status, suggestions) = match *result {
+        CheckResult::Correct => ("correct", &[][..]),
+        CheckResult::Corrected(ref suggestions) => ("corrected", &suggestions[..]),
+        CheckResult::Suspect(ref suggestions) => ("suspect", &suggestions[..]),
+        CheckResult::Unknown => ("unknown", &[][..]),
+    };
+
+    if suggestions.is_empty() {
+        return vec![[word.to_owned(), status.to_owned(), String::new(), String::new(),
+                     String::new(), String::new()]];
+    }
+    suggestions.iter()
+        .map(|s| {
+            [word.to_owned(), status.to_owned(), s.word.clone(), s.distance.to_string(),
+             s.count.to_string(), format!("{:.3}", s.confidence)]
+        })
+        .collect()
+}
+
+/// The columns of a `Format::Table`, and whether each is aligned to the
+/// right.
+const TEXT_COLUMNS: [(&str, bool); 4] =
+    [("word", false), ("suggestion", false), ("distance", true), ("count", true)];
+
+/// The rows of the `Format::Table` for `result` for `word`: those of its
+/// `table_rows`, with a question mark after a suspect word and a dash for
+/// an unknown one's suggestion.
+pub(crate) fn text_rows(word: &str, result: &CheckResult) -> Vec<[String; 4]> {
+    table_rows(word, result).into_iter()
+        .map(|[word, status, suggestion, distance, count, _]| {
+            let word = if status == "suspect" { word + "?" } else { word };
+            let suggestion = if status == "unknown" { "-".to_owned() } else { suggestion };
+            [word, suggestion, distance, count]
+        })
+        .collect()
+}
+
+/// `rows` under a header row, each cell padded to the widest in its
+/// column, in characters; with `boxed`, inside box-drawing lines.
+pub(crate) fn format_table(rows: &[[String; 4]], boxed: bool) -> String {
+    let header = TEXT_COLUMNS.map(|(name, _)| name.to_owned());
+    let mut widths = [0; 4];
+    for row in iter::once(&header).chain(rows) {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+    let line = |row: &[String; 4]| {
+        let cells: Vec<_> = row.iter().zip(&widths).zip(&TEXT_COLUMNS)
+            .map(|((cell, &width), &(_, right))| {
+                let padding = " ".repeat(width - cell.chars().count());
+                if right { padding + cell } else { cell.clone() + &padding }
+            })
+            .collect();
+        if boxed {
+            format!("│ {} │\n", cells.join(" │ "))
+        } else {
+            format!("{}\n", cells.join("  ").trim_end())
+        }
+    };
+    let rule = |left: &str, middle: &str, right: &str| {
+        let lines: Vec<_> = widths.iter().map(|&width| "─".repeat(width + 2)).collect();
+        format!("{}{}{}\n", left, lines.join(middle), right)
+    };
+
+    let mut table = String::new();
+    if boxed {
+        table += &rule("┌", "┬", "┐");
+    }
+    table += &line(&header);
+    if boxed {
+        table += &rule("├", "┼", "┤");
+    }
+    for row in rows {
+        table += &line(row);
+    }
+    if boxed {
+        table += &rule("└", "┴", "┘");
+    }
+    table
+}
+
+/// `field` for a CSV file: quoted, with its quotes doubled, if it has a
+/// comma, a quote or a line break in it.
+fn csv_field(field: &str) -> String {
+    if field.contains(&[',', '"', '\n', '\r'][..]) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_owned()
+    }
+}
+
+/// `field` for a TSV file, with backslashes, tabs and line breaks escaped
+/// as `\\`, `\t`, `\n` and `\r`.
+fn tsv_field(field: &str) -> String {
+    field.replace('\\', "\\\\")
+        .replace('\t', "\\t")
+        .replace('\n', "\\n")
+        .replace('\r', "\\r")
+}
+
+#[cfg(test)]
+mod format_result_tests {
+    use super::{csv_field, format_checked, format_header, format_result, format_table,
+                json_string, text_rows, tsv_field, Format};
+    use spellchecker::{CheckResult, Costs, SpellChecker, Suggestion, Trie};
+
+    #[test]
+    fn writes_text() {
+        assert_eq!("hello\n", format_result("hello", CheckResult::Correct, Format::Text));
+        assert_eq!("wor, world word\n", format_result("wor", corrected(), Format::Text));
+        assert_eq!("zzz, -\n", format_result("zzz", CheckResult::Unknown, Format::Text));
+    }
+
+    #[test]
+    fn lists_misspellings() {
+        assert_eq!("", format_result("hello", CheckResult::Correct, Format::List));
+        assert_eq!("wor\n", format_result("wor", corrected(), Format::List));
+        assert_eq!("zzz\n", format_result("zzz", CheckResult::Unknown, Format::List));
+        assert!("list".parse::<Format>().is_err());
+    }
+
+    #[test]
+    fn writes_scores() {
+        assert_eq!("hello, hello, 0, 1.000\n",
+                   format_result("hello", CheckResult::Correct, Format::Scores));
+        assert_eq!("wor, world, 1, 0.750\nwor, word, 1, 0.250\n",
+                   format_result("wor", corrected(), Format::Scores));
+        assert_eq!("zzz, -\n", format_result("zzz", CheckResult::Unknown, Format::Scores));
+    }
+
+    #[test]
+    fn explains_corrections() {
+        let mut t = Trie::new();
+        for word in &["word", "world", "com"] {
+            t.insert(word);
+        }
+        let checker = SpellChecker::new(t);
+
+        assert_eq!("wrod -> word: transpose(r,o)@1\n\
+                    wrod -> world: insert(o)@1, replace(o,l)@2\n",
+                   format_checked(&checker, "wrod", checker.check_word(None, "wrod", 2),
+                                  Format::Explain));
+        assert_eq!("word\n", format_checked(&checker, "word", CheckResult::Correct,
+                                            Format::Explain));
+        assert_eq!("wor, world word\n", format_checked(&checker, "wor", corrected(), Format::Text));
+        let checker = checker.with_costs(Costs::ocr());
+        assert_eq!("corn -> com: substitute(rn,m)@2\n",
+                   format_checked(&checker, "corn", checker.check_word(None, "corn", 1),
+                                  Format::Explain));
+    }
+
+    #[test]
+    fn writes_scored() {
+        assert_eq!("hello\n", format_result("hello", CheckResult::Correct, Format::Scored));
+        assert_eq!("wor, world 1:3:0.750, word 1:1:0.250\n",
+                   format_result("wor", corrected(), Format::Scored));
+        assert_eq!("zzz, -\n", format_result("zzz", CheckResult::Unknown, Format::Scored));
+    }
+
+    #[test]
+    fn writes_json() {
+        assert_eq!("{\"word\":\"hello\",\"status\":\"correct\"}\n",
+                   format_result("hello", CheckResult::Correct, Format::Json));
+        assert_eq!("{\"word\":\"zzz\",\"status\":\"unknown\",\"suggestion\":null}\n",
+                   format_result("zzz", CheckResult::Unknown, Format::Json));
+
+        let mut first = corrected();
+        if let CheckResult::Corrected(ref mut suggestions) = first {
+            suggestions.truncate(1);
+        }
+        assert_eq!("{\"word\":\"wor\",\"status\":\"corrected\",\"suggestion\":\"world\",\
+                    \"distance\":1,\"count\":3,\"confidence\":0.750}\n",
+                   format_result("wor", first, Format::Json));
+        assert_eq!("{\"word\":\"wor\",\"status\":\"corrected\",\"suggestion\":\"world\",\
+                    \"distance\":1,\"count\":3,\"confidence\":0.750,\"suggestions\":[\
+                    {\"word\":\"world\",\"distance\":1,\"count\":3,\"confidence\":0.750},\
+                    {\"word\":\"word\",\"distance\":1,\"count\":1,\"confidence\":0.250}]}\n",
+                   format_result("wor", corrected(), Format::Json));
+    }
+
+    #[test]
+    fn escapes_json_strings() {
+        assert_eq!(r#""say \"hi\"""#, json_string("say \"hi\""));
+        assert_eq!(r#""a\\b\tc\n\u0001""#, json_string("a\\b\tc\n\u{1}"));
+        assert_eq!("\"café\"", json_string("café"));
+    }
+
+    #[test]
+    fn writes_tables() {
+        assert_eq!(Some("word,status,suggestion,distance,count,confidence\n".to_owned()),
+                   format_header(Format::Csv));
+        assert_eq!("wor,corrected,world,1,3,0.750\nwor,corrected,word,1,1,0.250\n",
+                   format_result("wor", corrected(), Format::Csv));
+        assert_eq!("hello,correct,,,,\n",
+                   format_result("hello", CheckResult::Correct, Format::Csv));
+
+        assert_eq!(Some("word\tstatus\tsuggestion\tdistance\tcount\tconfidence\n".to_owned()),
+                   format_header(Format::Tsv));
+        assert_eq!("zzz\tunknown\t\t\t\t\n",
+                   format_result("zzz", CheckResult::Unknown, Format::Tsv));
+        assert_eq!(None, format_header(Format::Text));
+    }
+
+    #[test]
+    fn lines_up_tables() {
+        let rows: Vec<_> = [("hello", CheckResult::Correct), ("wor", corrected()),
+                            ("zzz", CheckResult::Unknown)].iter()
+            .flat_map(|(word, result)| text_rows(word, result))
+            .collect();
+        assert_eq!("word   suggestion  distance  count\n\
+                    hello\n\
+                    wor    world              1      3\n\
+                    wor    word               1      1\n\
+                    zzz    -\n",
+                   format_table(&rows, false));
+        assert_eq!("┌──────┬────────────┬──────────┬───────┐\n\
+                    │ word │ suggestion │ distance │ count │\n\
+                    ├──────┼────────────┼──────────┼───────┤\n\
+                    │ zzz  │ -          │          │       │\n\
+                    └──────┴────────────┴──────────┴───────┘\n",
+                   format_result("zzz", CheckResult::Unknown, Format::Table { boxed: true }));
+    }
+
+    #[test]
+    fn escapes_table_fields() {
+        assert_eq!("plain", csv_field("plain"));
+        assert_eq!(r#""a,b""#, csv_field("a,b"));
+        assert_eq!(r#""say ""hi""""#, csv_field(r#"say "hi""#));
+        assert_eq!("a\\tb\\\\c", tsv_field("a\tb\\c"));
+        assert_eq!("a,\"b\"", tsv_field("a,\"b\""));
+    }
+
+    #[test]
+    fn parses_formats() {
+        assert_eq!(Ok(Format::Scores), "scores".parse());
+        assert_eq!(Ok(Format::Scored), "scored".parse());
+        assert_eq!(Ok(Format::Json), "json".parse());
+        assert_eq!(Ok(Format::Tsv), "tsv".parse());
+        assert_eq!(Ok(Format::Table { boxed: false }), "table".parse());
+        assert_eq!(Ok(Format::Table { boxed: true }), "table=box".parse());
+        assert!("json5".parse::<Format>().is_err());
+    }
+
+    fn corrected() -> CheckResult {
+        CheckResult::Corrected(vec![
+            Suggestion { word: "world".to_owned(), distance: 1, count: 3, confidence: 0.75 },
+            Suggestion { word: "word".to_owned(), distance: 1, count: 1, confidence: 0.25 },
+        ])
+    }
+}
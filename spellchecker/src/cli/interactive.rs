@@ -0,0 +1,345 @@
+/*!
+Checking with the user: going through a file a misspelling at a time
+with --interactive, and the prompt of the repl command.
+*/
+
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::{self, stdin, stdout, BufRead, Read, Write};
+use std::path::Path;
+
+use spellchecker::{append_personal, fold_word, CheckResult, Encoding, Profile, SpellChecker};
+use textkit::encoding::Decoder;
+
+use cli::document::match_case;
+use cli::format::{format_header, format_result, Format};
+use cli::replace::replace_file;
+
+/// Goes through the file at `path` with the user, on the standard input
+/// and output, and writes it back corrected, unless they quit, in the
+/// encoding it was read in.
+pub(crate) fn correct_file(checker: &SpellChecker, path: &str, n: usize, encoding: Encoding,
+                           personal: Option<&Path>, profile: &mut Profile) -> io::Result<()> {
+    let mut text = String::new();
+    let mut decoder = Decoder::new(File::open(path)?, encoding);
+    decoder.read_to_string(&mut text)?;
+    let detected = decoder.detected().expect("the file read to the end");
+
+    let stdin = stdin();
+    let corrected = correct_interactively(checker, &text, n, personal, &mut stdin.lock(),
+                                          &mut stdout(), profile)?;
+    match corrected {
+        Some(corrected) => {
+            let bytes = detected.encode(&corrected)?;
+            replace_file(Path::new(path), |file| file.write_all(&bytes))
+        }
+        None => Ok(()),
+    }
+}
+
+/// What to do with a word in an --interactive session.
+#[derive(Debug, PartialEq)]
+enum Action {
+    Replace(String),
+    Ignore,
+    Add,
+    Save,
+    Quit,
+}
+
+/// `text`, with the words the checker doesn't take as correct shown on
+/// `output` one by one and corrected as the answers on `input` say;
+/// `None` if the user quit. Words the user adds are appended to the
+/// `personal` dictionary, if there is one.
+fn correct_interactively<R: BufRead, W: Write>(checker: &SpellChecker, text: &str, n: usize,
+                                               personal: Option<&Path>, input: &mut R,
+                                               output: &mut W, profile: &mut Profile)
+                                               -> io::Result<Option<String>> {
+    let mut corrected = String::with_capacity(text.len());
+    let mut added = HashSet::new();
+    let mut prev: Option<String> = None;
+    let mut saving = false;
+
+    for (number, line) in text.split_inclusive('\n').enumerate() {
+        let mut copied = 0;
+
+        for (offset, written) in checker.word_spans(line) {
+            if saving {
+                break;
+            }
+            let mut word = fold_word(written);
+            let result = if added.contains(&word) {
+                CheckResult::Correct
+            } else {
+                checker.check_written_profiled(prev.as_deref(), written, n, profile)
+            };
+            let suggestions: Option<Vec<String>> = match result {
+                CheckResult::Correct => None,
+                CheckResult::Corrected(suggestions) | CheckResult::Suspect(suggestions) => {
+                    Some(suggestions.iter().map(|s| match_case(written, &s.word)).collect())
+                }
+                CheckResult::Unknown => Some(Vec::new()),
+            };
+
+            if let Some(suggestions) = suggestions {
+                let column = line[..offset].chars().count() + 1;
+                writeln!(output, "{}:{}: {}", number + 1, column, written)?;
+                writeln!(output, "    {}", line.trim_end_matches(&['\r', '\n'][..]))?;
+                let numbered: Vec<_> = suggestions.iter().enumerate()
+                    .map(|(i, suggestion)| format!("{}) {}", i + 1, suggestion))
+                    .collect();
+                writeln!(output, "    {}", if numbered.is_empty() {
+                    "(no corrections)".to_owned()
+                } else {
+                    numbered.join("  ")
+                })?;
+
+                match read_action(&suggestions, input, output)? {
+                    Action::Replace(replacement) => {
+                        corrected.push_str(&line[copied..offset]);
+                        corrected.push_str(&replacement);
+                        copied = offset + written.len();
+                        word = fold_word(&replacement);
+                    }
+                    Action::Ignore => {}
+                    Action::Add => {
+                        if let Some(path) = personal {
+                            append_personal(path, &word)?;
+                        }
+                        added.insert(word.clone());
+                    }
+                    Action::Save => saving = true,
+                    Action::Quit => return Ok(None),
+                }
+            }
+            prev = Some(word);
+        }
+
+        corrected.push_str(&line[copied..]);
+    }
+
+    Ok(Some(corrected))
+}
+
+/// Asks on `output` what to do with a word with `suggestions` until
+/// `input` gives a good answer. The end of the input saves.
+fn read_action<R: BufRead, W: Write>(suggestions: &[String], input: &mut R, output: &mut W)
+                                     -> io::Result<Action> {
+    loop {
+        let choices = match suggestions.len() {
+            0 => String::new(),
+            1 => "1, ".to_owned(),
+            n => format!("1-{}, ", n),
+        };
+        write!(output, "[{}r)eplace, i)gnore, a)dd, x) save and exit, q)uit]? ", choices)?;
+        output.flush()?;
+
+        let mut answer = String::new();
+        if input.read_line(&mut answer)? == 0 {
+            return Ok(Action::Save);
+        }
+        match answer.trim() {
+            "" | "i" => return Ok(Action::Ignore),
+            "a" => return Ok(Action::Add),
+            "x" => return Ok(Action::Save),
+            "q" => return Ok(Action::Quit),
+            "r" => {
+                write!(output, "replace with: ")?;
+                output.flush()?;
+                let mut replacement = String::new();
+                input.read_line(&mut replacement)?;
+                if !replacement.trim().is_empty() {
+                    return Ok(Action::Replace(replacement.trim().to_owned()));
+                }
+            }
+            answer => match answer.parse::<usize>() {
+                Ok(i) if i >= 1 && i <= suggestions.len() => {
+                    return Ok(Action::Replace(suggestions[i - 1].clone()));
+                }
+                _ => writeln!(output, "unknown answer `{}'", answer)?,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod correct_interactively_tests {
+    use std::env;
+    use std::fs;
+    use std::path::Path;
+
+    use super::correct_interactively;
+    use spellchecker::{Profile, SpellChecker, Trie};
+
+    #[test]
+    fn corrects_as_told() {
+        let (corrected, shown) = session("Teh wrold,\nzzz wrold!\n", "1\nr\nplanet\n\n1\n");
+
+        assert_eq!(Some("The planet,\nzzz world!\n".to_owned()), corrected);
+        assert_eq!("1:1: Teh\n    Teh wrold,\n    1) The\n\
+                    [1, r)eplace, i)gnore, a)dd, x) save and exit, q)uit]? ",
+                   &shown[..shown.find("1:5").unwrap()]);
+        assert!(shown.contains("2:1: zzz\n    zzz wrold!\n    (no corrections)\n\
+                                [r)eplace, i)gnore, a)dd, x) save and exit, q)uit]? "));
+    }
+
+    #[test]
+    fn adds_words() {
+        let (corrected, shown) = session("wrold wrold wrold", "a\n");
+        assert_eq!(Some("wrold wrold wrold".to_owned()), corrected);
+        assert_eq!(1, shown.matches(": wrold\n").count());
+    }
+
+    #[test]
+    fn adds_words_to_the_personal_dictionary() {
+        let dir = env::temp_dir().join(format!("spellchecker-add-{}", std::process::id()));
+        let path = dir.join("personal.dict");
+
+        session_with("Wrold zzz", "a\na\n", Some(&path));
+        assert_eq!("wrold\nzzz\n", fs::read_to_string(&path).unwrap());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn asks_again() {
+        let (corrected, shown) = session("wrold", "7\nmaybe\n1\n");
+        assert_eq!(Some("world".to_owned()), corrected);
+        assert!(shown.contains("unknown answer `7'") && shown.contains("unknown answer `maybe'"));
+    }
+
+    #[test]
+    fn saves_or_quits() {
+        assert_eq!(Some("world wrold\n".to_owned()), session("wrold wrold\n", "1\nx\n").0);
+        assert_eq!(Some("world wrold\n".to_owned()), session("wrold wrold\n", "1\n").0);
+        assert_eq!(None, session("wrold wrold\n", "1\nq\n").0);
+    }
+
+    fn session(text: &str, answers: &str) -> (Option<String>, String) {
+        session_with(text, answers, None)
+    }
+
+    fn session_with(text: &str, answers: &str, personal: Option<&Path>)
+                    -> (Option<String>, String) {
+        let mut t = Trie::new();
+        for word in &["the", "world", "world", "word"] {
+            t.insert(word);
+        }
+        let mut shown = Vec::new();
+        let corrected = correct_interactively(&SpellChecker::new(t), text, 1, personal,
+                                              &mut answers.as_bytes(), &mut shown,
+                                              &mut Profile::default()).unwrap();
+        (corrected, String::from_utf8(shown).unwrap())
+    }
+}
+
+/// Prompts on `output` for words and commands on `input`, answering
+/// each line as it comes, until `:quit` or the end of the input.
+pub(crate) fn repl<R: BufRead, W: Write>(checker: &SpellChecker, n: usize, format: Format,
+                                         input: &mut R, output: &mut W, profile: &mut Profile)
+                                         -> io::Result<()> {
+    let mut added = HashSet::new();
+
+    if let Some(header) = format_header(format) {
+        output.write_all(header.as_bytes())?;
+    }
+    loop {
+        write!(output, "> ")?;
+        output.flush()?;
+
+        let mut line = String::new();
+        if input.read_line(&mut line)? == 0 {
+            return Ok(());
+        }
+        let mut fields = line.split_whitespace();
+
+        match fields.next() {
+            Some(":quit") => return Ok(()),
+            Some(":add") => match (fields.next(), fields.next()) {
+                (Some(word), None) => {
+                    added.insert(fold_word(word));
+                }
+                _ => writeln!(output, ":add needs a word")?,
+            },
+            Some(":suggest") => {
+                let count = fields.next().and_then(|count| count.parse::<usize>().ok());
+                match (count, fields.next(), fields.next()) {
+                    (Some(count), Some(word), None) if count > 0 => {
+                        let word = fold_word(word);
+                        let result = checker.check_word_profiled(None, &word, count, profile);
+                        output.write_all(format_result(&word, result, format).as_bytes())?;
+                    }
+                    _ => writeln!(output, ":suggest needs a count and a word")?,
+                }
+            }
+            Some(command) if command.starts_with(':') => {
+                writeln!(output, "unknown command `{}' (expected :add, :suggest or :quit)",
+                         command)?;
+            }
+            _ => {
+                let answer = check_line(checker, &line, n, format, &added, profile);
+                output.write_all(answer.as_bytes())?;
+            }
+        }
+    }
+}
+
+/// The output for each word of `line`, read as running text, taking the
+/// words in `added` as correct.
+pub(crate) fn check_line(checker: &SpellChecker, line: &str, n: usize, format: Format,
+                         added: &HashSet<String>, profile: &mut Profile) -> String {
+    let mut output = String::new();
+    let mut prev: Option<String> = None;
+
+    for (_, written) in checker.word_spans(line) {
+        let word = fold_word(written);
+        let result = if added.contains(&word) {
+            CheckResult::Correct
+        } else {
+            checker.check_written_profiled(prev.as_deref(), written, n, profile)
+        };
+        output.push_str(&format_result(&word, result, format));
+        prev = Some(word);
+    }
+
+    output
+}
+
+#[cfg(test)]
+mod repl_tests {
+    use super::{repl, Format};
+    use spellchecker::{Profile, SpellChecker, Trie};
+
+    #[test]
+    fn answers_each_line() {
+        assert_eq!("> wrold, world\n> hello\nwrod, word\n> > ",
+                   session("Wrold\nhello, wrod!\n\n", Format::Text));
+        assert_eq!("> {\"word\":\"hello\",\"status\":\"correct\"}\n> ",
+                   session("hello\n", Format::Json));
+    }
+
+    #[test]
+    fn runs_commands() {
+        assert_eq!("> wrod, word world\n> > wrod\n> ",
+                   session(":suggest 2 wrod\n:add Wrod\nwrod\n:quit\nwrold\n", Format::Text));
+    }
+
+    #[test]
+    fn explains_bad_commands() {
+        assert_eq!("> :add needs a word\n\
+                    > :suggest needs a count and a word\n\
+                    > unknown command `:frobnicate' (expected :add, :suggest or :quit)\n> ",
+                   session(":add\n:suggest wrod\n:frobnicate\n", Format::Text));
+    }
+
+    fn session(input: &str, format: Format) -> String {
+        let mut t = Trie::new();
+        for word in &["hello", "world", "world", "word", "word", "word"] {
+            t.insert(word);
+        }
+        let mut output = Vec::new();
+        repl(&SpellChecker::new(t), 1, format, &mut input.as_bytes(), &mut output,
+             &mut Profile::default()).unwrap();
+        String::from_utf8(output).unwrap()
+    }
+}
@@ -0,0 +1,290 @@
+/*!
+Just enough JSON for the requests of serve --http and --lsp: a
+`Json` value read from text and written back, and strings quoted for
+writing.
+*/
+
+use std::fmt;
+use std::iter::Peekable;
+use std::str::Chars;
+
+/// `s` as a JSON string literal.
+pub(crate) fn json_string(s: &str) -> String {
+    let mut json = String::with_capacity(s.len() + 2);
+    json.push('"');
+    for c in s.chars() {
+        match c {
+            '"'  => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            c if c < ' ' => json.push_str(&format!("\\u{:04x}", c as u32)),
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+    json
+}
+
+/// A JSON value, as read from a request.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) enum Json {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+impl Json {
+    /// The value written in `text`.
+    pub(crate) fn parse(text: &str) -> Result<Json, String> {
+        let mut reader = JsonReader { chars: text.chars().peekable(), depth: 0 };
+        let value = reader.value()?;
+        reader.skip_whitespace();
+        match reader.chars.next() {
+            None => Ok(value),
+            Some(c) => Err(format!("unexpected `{}'", c)),
+        }
+    }
+
+    /// The value of the field `key`, if this is an object with one.
+    pub(crate) fn get(&self, key: &str) -> Option<&Json> {
+        match *self {
+            Json::Object(ref fields) => {
+                fields.iter().find(|field| field.0 == key).map(|field| &field.1)
+            }
+            _ => None,
+        }
+    }
+
+    pub(crate) fn as_str(&self) -> Option<&str> {
+        match *self {
+            Json::String(ref s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn as_array(&self) -> Option<&[Json]> {
+        match *self {
+            Json::Array(ref values) => Some(values),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Json {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Json::Null => f.write_str("null"),
+            Json::Bool(b) => write!(f, "{}", b),
+            Json::Number(n) => write!(f, "{}", n),
+            Json::String(ref s) => f.write_str(&json_string(s)),
+            Json::Array(ref values) => {
+                let values: Vec<_> = values.iter().map(|value| value.to_string()).collect();
+                write!(f, "[{}]", values.join(","))
+            }
+            Json::Object(ref fields) => {
+                let fields: Vec<_> = fields.iter()
+                    .map(|(key, value)| format!("{}:{}", json_string(key), value))
+                    .collect();
+                write!(f, "{{{}}}", fields.join(","))
+            }
+        }
+    }
+}
+
+/// How many arrays and objects deep `Json::parse` reads a value, as each
+/// level takes a call of `JsonReader::value`, and a request nested any
+/// deeper would run the thread out of stack.
+const MAX_JSON_DEPTH: usize = 128;
+
+/// Reads `Json` values a character at a time.
+struct JsonReader<'a> {
+    chars: Peekable<Chars<'a>>,
+    /// How many arrays and objects the value being read is in.
+    depth: usize,
+}
+
+impl<'a> JsonReader<'a> {
+    fn skip_whitespace(&mut self) {
+        while self.chars.peek().is_some_and(|c| c.is_whitespace()) {
+            self.chars.next();
+        }
+    }
+
+    /// Whether `c` comes next, after any whitespace, taking it if so.
+    fn next_is(&mut self, c: char) -> bool {
+        self.skip_whitespace();
+        self.chars.next_if_eq(&c).is_some()
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), String> {
+        if self.next_is(c) {
+            Ok(())
+        } else {
+            Err(format!("expected `{}'", c))
+        }
+    }
+
+    /// Takes the `[` or `{` that opens an array or object, unless it is
+    /// nested more than `MAX_JSON_DEPTH` deep.
+    fn open(&mut self) -> Result<(), String> {
+        if self.depth == MAX_JSON_DEPTH {
+            return Err(format!("nested more than {} deep", MAX_JSON_DEPTH));
+        }
+        self.depth += 1;
+        self.chars.next();
+        Ok(())
+    }
+
+    fn value(&mut self) -> Result<Json, String> {
+        self.skip_whitespace();
+        match self.chars.peek().cloned() {
+            Some('"') => self.string().map(Json::String),
+            Some('[') => {
+                self.open()?;
+                let mut values = Vec::new();
+                if !self.next_is(']') {
+                    loop {
+                        values.push(self.value()?);
+                        if !self.next_is(',') {
+                            self.expect(']')?;
+                            break;
+                        }
+                    }
+                }
+                self.depth -= 1;
+                Ok(Json::Array(values))
+            }
+            Some('{') => {
+                self.open()?;
+                let mut fields = Vec::new();
+                if !self.next_is('}') {
+                    loop {
+                        let key = self.string()?;
+                        self.expect(':')?;
+                        fields.push((key, self.value()?));
+                        if !self.next_is(',') {
+                            self.expect('}')?;
+                            break;
+                        }
+                    }
+                }
+                self.depth -= 1;
+                Ok(Json::Object(fields))
+            }
+            _ => {
+                let mut word = String::new();
+                let literal = |c: &char| c.is_alphanumeric() || "+-.".contains(*c);
+                while let Some(c) = self.chars.next_if(literal) {
+                    word.push(c);
+                }
+                match word.as_str() {
+                    "null"  => Ok(Json::Null),
+                    "true"  => Ok(Json::Bool(true)),
+                    "false" => Ok(Json::Bool(false)),
+                    "" => Err("expected a value".to_owned()),
+                    _ => word.parse().map(Json::Number)
+                        .map_err(|_| format!("unexpected `{}'", word)),
+                }
+            }
+        }
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        self.expect('"')?;
+        let mut s = String::new();
+        loop {
+            match self.chars.next().ok_or("unterminated string")? {
+                '"' => return Ok(s),
+                '\\' => match self.chars.next().ok_or("unterminated string")? {
+                    'b' => s.push('\u{8}'),
+                    'f' => s.push('\u{c}'),
+                    'n' => s.push('\n'),
+                    'r' => s.push('\r'),
+                    't' => s.push('\t'),
+                    'u' => {
+                        let mut code = self.hex()?;
+                        if (0xd800..0xdc00).contains(&code) {
+                            // Only a low surrogate right after it makes a
+                            // pair; anything else is left to be read as it
+                            // is, and the high one alone is replaced.
+                            let mut ahead = self.chars.clone();
+                            if ahead.next() == Some('\\') && ahead.next() == Some('u') {
+                                let digits: String = ahead.by_ref().take(4).collect();
+                                let low = u32::from_str_radix(&digits, 16).unwrap_or(0);
+                                if (0xdc00..0xe000).contains(&low) {
+                                    code = 0x10000 + ((code - 0xd800) << 10) + (low - 0xdc00);
+                                    self.chars = ahead;
+                                }
+                            }
+                        }
+                        s.push(char::from_u32(code).unwrap_or(char::REPLACEMENT_CHARACTER));
+                    }
+                    c => s.push(c),
+                },
+                c => s.push(c),
+            }
+        }
+    }
+
+    /// The four hex digits of a `\u` escape.
+    fn hex(&mut self) -> Result<u32, String> {
+        let digits: String = self.chars.by_ref().take(4).collect();
+        u32::from_str_radix(&digits, 16).map_err(|_| format!("bad escape `\\u{}'", digits))
+    }
+}
+
+#[cfg(test)]
+mod json_tests {
+    use super::Json;
+
+    #[test]
+    fn parses_values() {
+        let text = r#" {"a": [1, -2.5e3, true, null], "b": {"c": "d\"\u00e9\ud83d\ude00"}} "#;
+        let json = Json::parse(text).unwrap();
+
+        assert_eq!(Some(&Json::Array(vec![Json::Number(1.0), Json::Number(-2500.0),
+                                          Json::Bool(true), Json::Null])),
+                   json.get("a"));
+        let c = json.get("b").and_then(|b| b.get("c"));
+        assert_eq!(Some("d\"é😀"), c.and_then(Json::as_str));
+        assert_eq!(None, json.get("c"));
+        assert_eq!(Ok(Json::Object(Vec::new())), Json::parse("{}"));
+    }
+
+    #[test]
+    fn reads_lone_surrogates() {
+        let parse = |text| Json::parse(text).unwrap().as_str().map(str::to_owned).unwrap();
+        assert_eq!("\u{fffd}A", parse(r#""\ud800\u0041""#));
+        assert_eq!("\u{fffd} x", parse(r#""\ud800 x""#));
+        assert_eq!("\u{fffd}\n", parse(r#""\ud800\n""#));
+        assert_eq!("\u{fffd}\u{fffd}", parse(r#""\udc00\ud800""#));
+        assert_eq!("\u{fffd}😀", parse(r#""\ud800\ud83d\ude00""#));
+    }
+
+    #[test]
+    fn refuses_bad_json() {
+        for text in &["", "{", "[1,]", "{\"a\" 1}", "\"abc", "nope", "1 2", "{1: 2}"] {
+            assert!(Json::parse(text).is_err(), "{:?}", text);
+        }
+    }
+
+    #[test]
+    fn refuses_deep_nesting() {
+        let nested = |depth| format!("{}0{}", "[{\"a\":".repeat(depth), "}]".repeat(depth));
+        assert!(Json::parse(&nested(64)).is_ok());
+        assert_eq!(Err("nested more than 128 deep".to_owned()), Json::parse(&nested(65)));
+        // Deep enough to run out of stack without the limit.
+        assert!(Json::parse(&"[".repeat(1 << 20)).is_err());
+    }
+
+    #[test]
+    fn writes_json() {
+        let text = r#"{"id":7,"ok":[true,null,"a\"b"],"x":1.5}"#;
+        assert_eq!(text, Json::parse(text).unwrap().to_string());
+    }
+}
@@ -0,0 +1,341 @@
+/*!
+--lsp: a Language Server Protocol server on the standard input and
+output, publishing a diagnostic for each misspelling of an open
+document, and offering its corrections as quick fixes.
+*/
+
+use std::collections::HashMap;
+use std::io::{self, BufRead, Write};
+
+use spellchecker::{Profile, SpellChecker};
+
+use cli::document::line_misspellings;
+use cli::json::{json_string, Json};
+
+/// Speaks the Language Server Protocol on `reader` and `writer`: keeps
+/// the text of each open document, publishes a diagnostic for every word
+/// --document would report in it whenever it changes, and offers the
+/// corrections of those in a range as quick fixes. Returns, at `exit` or
+/// the end of the input, whether the client shut the server down first.
+pub(crate) fn serve_lsp<R: BufRead, W: Write>(checker: &SpellChecker, n: usize, reader: &mut R,
+                                              writer: &mut W) -> io::Result<bool> {
+    let mut documents: HashMap<String, String> = HashMap::new();
+    let mut shut_down = false;
+
+    while let Some(body) = read_lsp_message(reader)? {
+        let message = match Json::parse(&body) {
+            Ok(message) => message,
+            Err(e) => {
+                write_lsp_message(writer, &lsp_error(&Json::Null, -32700, &e))?;
+                continue;
+            }
+        };
+        let params = message.get("params").unwrap_or(&Json::Null);
+        let document = params.get("textDocument").unwrap_or(&Json::Null);
+        let uri = document.get("uri").and_then(Json::as_str);
+
+        let method = message.get("method").and_then(Json::as_str).unwrap_or("");
+        let result = match method {
+            "initialize" => Some("{\"capabilities\":{\"textDocumentSync\":1,\
+                                  \"codeActionProvider\":true},\
+                                  \"serverInfo\":{\"name\":\"spellchecker\"}}".to_owned()),
+            "shutdown" => {
+                shut_down = true;
+                Some("null".to_owned())
+            }
+            "exit" => return Ok(shut_down),
+            "textDocument/didOpen" | "textDocument/didChange" => {
+                let changes = params.get("contentChanges").and_then(Json::as_array);
+                let text = document.get("text")
+                    .or_else(|| changes.and_then(|changes| changes.last()?.get("text")))
+                    .and_then(Json::as_str);
+                if let (Some(uri), Some(text)) = (uri, text) {
+                    let diagnostics: Vec<_> = lsp_misspellings(checker, text, n).into_iter()
+                        .map(|misspelling| misspelling.diagnostic)
+                        .collect();
+                    publish_diagnostics(writer, uri, &diagnostics)?;
+                    documents.insert(uri.to_owned(), text.to_owned());
+                }
+                None
+            }
+            "textDocument/didClose" => {
+                if let Some(uri) = uri {
+                    documents.remove(uri);
+                    publish_diagnostics(writer, uri, &[])?;
+                }
+                None
+            }
+            "textDocument/codeAction" => {
+                let range = params.get("range").and_then(lsp_range);
+                let actions = match (uri, uri.and_then(|uri| documents.get(uri)), range) {
+                    (Some(uri), Some(text), Some(range)) => {
+                        code_actions(checker, uri, text, range, n)
+                    }
+                    _ => Vec::new(),
+                };
+                Some(format!("[{}]", actions.join(",")))
+            }
+            _ => None,
+        };
+
+        if let Some(id) = message.get("id") {
+            let response = match result {
+                Some(result) => {
+                    format!("{{\"jsonrpc\":\"2.0\",\"id\":{},\"result\":{}}}", id, result)
+                }
+                None => lsp_error(id, -32601, &format!("unknown method `{}'", method)),
+            };
+            write_lsp_message(writer, &response)?;
+        }
+    }
+
+    Ok(shut_down)
+}
+
+/// The body of the next message on `reader`, after its headers; `None`
+/// at the end of the input.
+fn read_lsp_message<R: BufRead>(reader: &mut R) -> io::Result<Option<String>> {
+    let mut length = None;
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header)? == 0 {
+            return Ok(None);
+        }
+        if header.trim().is_empty() {
+            if length.is_some() {
+                break;
+            }
+            continue;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.trim().eq_ignore_ascii_case("content-length") {
+                length = value.trim().parse().ok();
+            }
+        }
+    }
+
+    let mut body = vec![0; length.unwrap_or(0)];
+    reader.read_exact(&mut body)?;
+    String::from_utf8(body).map(Some).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+fn write_lsp_message<W: Write>(writer: &mut W, body: &str) -> io::Result<()> {
+    write!(writer, "Content-Length: {}\r\n\r\n{}", body.len(), body)?;
+    writer.flush()
+}
+
+/// A JSON-RPC error response to the request `id`.
+fn lsp_error(id: &Json, code: i32, message: &str) -> String {
+    format!("{{\"jsonrpc\":\"2.0\",\"id\":{},\"error\":{{\"code\":{},\"message\":{}}}}}",
+            id, code, json_string(message))
+}
+
+fn publish_diagnostics<W: Write>(writer: &mut W, uri: &str, diagnostics: &[String])
+                                 -> io::Result<()> {
+    write_lsp_message(writer, &format!("{{\"jsonrpc\":\"2.0\",\
+                                        \"method\":\"textDocument/publishDiagnostics\",\
+                                        \"params\":{{\"uri\":{},\"diagnostics\":[{}]}}}}",
+                                       json_string(uri), diagnostics.join(",")))
+}
+
+/// A word of a document that isn't correct, for the language server.
+struct LspMisspelling {
+    /// Its line, and where it starts and ends on it, in UTF-16 code
+    /// units, as LSP counts them.
+    line:        usize,
+    start:       usize,
+    end:         usize,
+    suggestions: Vec<String>,
+    /// It as an LSP `Diagnostic`, in JSON.
+    diagnostic:  String,
+}
+
+/// The words of `text` that aren't correct, read as running text.
+fn lsp_misspellings(checker: &SpellChecker, text: &str, n: usize) -> Vec<LspMisspelling> {
+    let mut misspellings = Vec::new();
+    let mut prev: Option<String> = None;
+    let mut profile = Profile::default();
+
+    for (number, line) in text.split('\n').enumerate() {
+        for (offset, written, _, suggestions) in line_misspellings(checker, &mut prev, line,
+                                                                    false, n, &mut profile) {
+            let start = line[..offset].encode_utf16().count();
+            let end = start + written.encode_utf16().count();
+            let message = if suggestions.is_empty() {
+                format!("{}: unknown word", written)
+            } else {
+                format!("{}: did you mean {}?", written, suggestions.join(", "))
+            };
+            let diagnostic = format!("{{\"range\":{},\"severity\":3,\"source\":\"spellchecker\",\
+                                      \"message\":{}}}",
+                                     range_json(number, start, end), json_string(&message));
+            misspellings.push(LspMisspelling {
+                line: number,
+                start,
+                end,
+                suggestions,
+                diagnostic,
+            });
+        }
+    }
+
+    misspellings
+}
+
+/// The quick fixes for the misspellings of the document `uri`, with
+/// `text`, that overlap `range`: a `CodeAction` for each correction, in
+/// JSON.
+fn code_actions(checker: &SpellChecker, uri: &str, text: &str,
+                range: ((usize, usize), (usize, usize)), n: usize) -> Vec<String> {
+    let (from, to) = range;
+    let mut actions = Vec::new();
+
+    for misspelling in lsp_misspellings(checker, text, n) {
+        let (line, start, end) = (misspelling.line, misspelling.start, misspelling.end);
+        if (line, end) < from || (line, start) > to {
+            continue;
+        }
+        let range = range_json(line, start, end);
+        for (i, correction) in misspelling.suggestions.iter().enumerate() {
+            let edit = format!("{{\"changes\":{{{}:[{{\"range\":{},\"newText\":{}}}]}}}}",
+                               json_string(uri), range, json_string(correction));
+            actions.push(format!("{{\"title\":{},\"kind\":\"quickfix\",\"isPreferred\":{},\
+                                  \"diagnostics\":[{}],\"edit\":{}}}",
+                                 json_string(&format!("Change to {}", correction)), i == 0,
+                                 misspelling.diagnostic, edit));
+        }
+    }
+
+    actions
+}
+
+/// An LSP `Range` on one line, in JSON.
+fn range_json(line: usize, start: usize, end: usize) -> String {
+    format!("{{\"start\":{{\"line\":{0},\"character\":{1}}},\
+             \"end\":{{\"line\":{0},\"character\":{2}}}}}", line, start, end)
+}
+
+/// The start and end of an LSP `Range`, as (line, character).
+fn lsp_range(range: &Json) -> Option<((usize, usize), (usize, usize))> {
+    let position = |key| {
+        let position = range.get(key)?;
+        match (position.get("line")?, position.get("character")?) {
+            (&Json::Number(line), &Json::Number(character)) => {
+                Some((line as usize, character as usize))
+            }
+            _ => None,
+        }
+    };
+    Some((position("start")?, position("end")?))
+}
+
+#[cfg(test)]
+mod serve_lsp_tests {
+    use super::{serve_lsp, Json};
+    use spellchecker::{SpellChecker, Trie};
+
+    #[test]
+    fn publishes_diagnostics() {
+        let (clean, messages) = session(&[
+            r#"{"jsonrpc":"2.0","id":1,"method":"initialize","params":{}}"#,
+            r#"{"jsonrpc":"2.0","method":"initialized","params":{}}"#,
+            r#"{"jsonrpc":"2.0","method":"textDocument/didOpen","params":{"textDocument":
+               {"uri":"file:///a.txt","languageId":"plaintext","version":1,
+                "text":"Hello wrold\n😀 helo"}}}"#,
+            r#"{"jsonrpc":"2.0","method":"textDocument/didChange","params":{"textDocument":
+               {"uri":"file:///a.txt","version":2},"contentChanges":[{"text":"hello"}]}}"#,
+            r#"{"jsonrpc":"2.0","id":2,"method":"shutdown"}"#,
+            r#"{"jsonrpc":"2.0","method":"exit"}"#,
+        ]);
+
+        assert!(clean);
+        assert_eq!(4, messages.len());
+        assert_eq!(Some(&Json::Number(1.0)), messages[0].get("id"));
+        let capabilities = messages[0].get("result").and_then(|r| r.get("capabilities"));
+        assert_eq!(Some(&Json::Bool(true)),
+                   capabilities.and_then(|c| c.get("codeActionProvider")));
+
+        let diagnostics = |message: &Json| {
+            message.get("params").and_then(|p| p.get("diagnostics")).and_then(Json::as_array)
+                .unwrap().to_vec()
+        };
+        let opened = diagnostics(&messages[1]);
+        // The tokenizer takes the emoji for a word too.
+        assert_eq!(3, opened.len());
+        assert_eq!(Json::parse(r#"{"range":{"start":{"line":0,"character":6},
+                                            "end":{"line":0,"character":11}},
+                                   "severity":3,"source":"spellchecker",
+                                   "message":"wrold: did you mean world?"}"#).unwrap(),
+                   opened[0]);
+        // The emoji takes two UTF-16 code units.
+        let range = opened[2].get("range").unwrap().to_string();
+        assert_eq!(r#"{"start":{"line":1,"character":3},"end":{"line":1,"character":7}}"#,
+                   range);
+        assert!(diagnostics(&messages[2]).is_empty());
+        assert_eq!(Some(&Json::Null), messages[3].get("result"));
+    }
+
+    #[test]
+    fn offers_quick_fixes() {
+        let (_, messages) = session(&[
+            r#"{"jsonrpc":"2.0","method":"textDocument/didOpen","params":{"textDocument":
+               {"uri":"file:///a.txt","text":"Hello Wrold"}}}"#,
+            r#"{"jsonrpc":"2.0","id":"fix","method":"textDocument/codeAction","params":{
+               "textDocument":{"uri":"file:///a.txt"},
+               "range":{"start":{"line":0,"character":8},"end":{"line":0,"character":8}},
+               "context":{"diagnostics":[]}}}"#,
+            r#"{"jsonrpc":"2.0","id":3,"method":"textDocument/codeAction","params":{
+               "textDocument":{"uri":"file:///a.txt"},
+               "range":{"start":{"line":0,"character":0},"end":{"line":0,"character":2}},
+               "context":{"diagnostics":[]}}}"#,
+        ]);
+
+        assert_eq!(Some("fix"), messages[1].get("id").and_then(Json::as_str));
+        let actions = messages[1].get("result").and_then(Json::as_array).unwrap();
+        assert_eq!(1, actions.len());
+        assert_eq!(Some("Change to World"), actions[0].get("title").and_then(Json::as_str));
+        let edits = actions[0].get("edit").and_then(|e| e.get("changes"))
+            .and_then(|c| c.get("file:///a.txt")).and_then(Json::as_array).unwrap();
+        assert_eq!(Some("World"), edits[0].get("newText").and_then(Json::as_str));
+
+        assert_eq!(Some(&Json::Array(Vec::new())), messages[2].get("result"));
+    }
+
+    #[test]
+    fn answers_unknown_requests_with_errors() {
+        let (clean, messages) = session(&[r#"{"jsonrpc":"2.0","id":1,"method":"frobnicate"}"#,
+                                          r#"{"jsonrpc":"2.0","method":"$/frobnicate"}"#,
+                                          "{nope"]);
+
+        assert!(!clean);
+        assert_eq!(2, messages.len());
+        let code = |message: &Json| message.get("error").and_then(|e| e.get("code")).cloned();
+        assert_eq!(Some(Json::Number(-32601.0)), code(&messages[0]));
+        assert_eq!(Some(Json::Number(-32700.0)), code(&messages[1]));
+    }
+
+    /// Runs a session with `messages` from the client, returning whether
+    /// it ended cleanly and the messages from the server.
+    fn session(messages: &[&str]) -> (bool, Vec<Json>) {
+        let mut t = Trie::new();
+        for word in &["hello", "world"] {
+            t.insert(word);
+        }
+        let input: String = messages.iter()
+            .map(|message| format!("Content-Length: {}\r\n\r\n{}", message.len(), message))
+            .collect();
+        let mut output = Vec::new();
+        let clean = serve_lsp(&SpellChecker::new(t), 1, &mut input.as_bytes(), &mut output)
+            .unwrap();
+
+        let output = String::from_utf8(output).unwrap();
+        let replies = output.split("Content-Length: ").skip(1)
+            .map(|message| {
+                let (length, body) = message.split_once("\r\n\r\n").unwrap();
+                assert_eq!(length.parse::<usize>().unwrap(), body.len());
+                Json::parse(body).unwrap()
+            })
+            .collect();
+        (clean, replies)
+    }
+}
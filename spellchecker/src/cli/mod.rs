@@ -0,0 +1,21 @@
+/*!
+What the spellchecker binary does for `main`: `options` reads the
+command line, `setup` makes the checkers it asks for, and each of the
+other modules runs one way of checking words, writing what it finds as
+`format` lays it out, or one of the commands that check nothing, as
+`diff` and `train_errors` do.
+*/
+
+pub(crate) mod diff;
+pub(crate) mod document;
+pub(crate) mod format;
+pub(crate) mod interactive;
+pub(crate) mod json;
+pub(crate) mod lsp;
+pub(crate) mod options;
+pub(crate) mod replace;
+pub(crate) mod serve;
+pub(crate) mod setup;
+pub(crate) mod train_errors;
+pub(crate) mod watch;
+pub(crate) mod words;
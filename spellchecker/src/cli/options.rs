@@ -0,0 +1,1214 @@
+/*!
+The options of the command line, and what `parse_args` makes of them.
+*/
+
+use std::fmt;
+use std::str::FromStr;
+
+use regex::Regex;
+
+use spellchecker::{is_url, CasePolicy, Encoding, Engine, Filter, Hyphens, Mode, Numbers, Ranking};
+
+use cli::format::Format;
+
+/// What the command line asks for, as `parse_args` reads it.
+#[derive(Debug, PartialEq)]
+pub(crate) struct Options {
+    pub(crate) corpora:     Vec<String>,
+    pub(crate) words:       Vec<String>,
+    pub(crate) glob:        String,
+    pub(crate) verbose:     bool,
+    pub(crate) encoding:    Encoding,
+    pub(crate) progress:    bool,
+    pub(crate) timings:     bool,
+    pub(crate) stats:       bool,
+    pub(crate) suggestions: usize,
+    pub(crate) load_model:  Option<String>,
+    pub(crate) dictionary:  Option<String>,
+    pub(crate) from_freq:   Option<String>,
+    pub(crate) hunspell:    Option<String>,
+    pub(crate) lang_models: Vec<(String, String)>,
+    pub(crate) bundles:     Vec<String>,
+    pub(crate) lang:        Option<String>,
+    pub(crate) save_model:  Option<String>,
+    pub(crate) append:      Option<String>,
+    pub(crate) dump_dictionary: Option<String>,
+    pub(crate) stream:      bool,
+    pub(crate) tokenize:    bool,
+    pub(crate) jobs:        usize,
+    pub(crate) beam:        Option<usize>,
+    pub(crate) budget_ms:   Option<u64>,
+    pub(crate) engine:      Engine,
+    pub(crate) minimize:    bool,
+    pub(crate) costs:       Option<String>,
+    pub(crate) mode:        Mode,
+    pub(crate) phonetic:    bool,
+    pub(crate) ranking:     Ranking,
+    pub(crate) smoothing:   f64,
+    pub(crate) context:     bool,
+    pub(crate) real_words:  bool,
+    pub(crate) threshold:   f64,
+    pub(crate) split_compounds: bool,
+    pub(crate) accept_inflections: bool,
+    pub(crate) join_words:  bool,
+    pub(crate) capitalized: CasePolicy,
+    pub(crate) acronyms:    CasePolicy,
+    pub(crate) numbers:     Numbers,
+    pub(crate) min_count:   usize,
+    pub(crate) prune_below: usize,
+    pub(crate) max_vocab:   Option<usize>,
+    pub(crate) max_word_len: Option<usize>,
+    pub(crate) personal:    Option<String>,
+    pub(crate) abbreviations: Option<String>,
+    pub(crate) ignore:      Vec<String>,
+    pub(crate) hyphens:     Hyphens,
+    pub(crate) punctuation: Vec<char>,
+    pub(crate) fold_diacritics: bool,
+    pub(crate) adaptive_edits: bool,
+    pub(crate) format:      Format,
+    pub(crate) list:        bool,
+    pub(crate) explain:     bool,
+    pub(crate) quiet:       bool,
+    pub(crate) document:    bool,
+    pub(crate) annotate:    bool,
+    pub(crate) complete:    bool,
+    pub(crate) filter:      Filter,
+    pub(crate) fix:         bool,
+    pub(crate) dry_run:     bool,
+    pub(crate) interactive: Option<String>,
+    pub(crate) watch:       Option<String>,
+    pub(crate) command:     Command,
+    pub(crate) listen:      String,
+    pub(crate) http:        bool,
+    pub(crate) lsp:         bool,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            corpora:     Vec::new(),
+            words:       Vec::new(),
+            glob:        "*".to_owned(),
+            verbose:     false,
+            encoding:    Encoding::Auto,
+            progress:    false,
+            timings:     false,
+            stats:       false,
+            suggestions: 1,
+            load_model:  None,
+            dictionary:  None,
+            from_freq:   None,
+            hunspell:    None,
+            lang_models: Vec::new(),
+            bundles:     Vec::new(),
+            lang:        None,
+            save_model:  None,
+            append:      None,
+            dump_dictionary: None,
+            stream:      false,
+            tokenize:    false,
+            jobs:        1,
+            beam:        None,
+            budget_ms:   None,
+            engine:      Engine::Trie,
+            minimize:    false,
+            costs:       None,
+            mode:        Mode::Text,
+            phonetic:    false,
+            ranking:     Ranking::Distance,
+            smoothing:   0.0,
+            context:     false,
+            real_words:  false,
+            threshold:   0.1,
+            split_compounds: false,
+            accept_inflections: false,
+            join_words:  false,
+            capitalized: CasePolicy::Check,
+            acronyms:    CasePolicy::Check,
+            numbers:     Numbers::Check,
+            min_count:   1,
+            prune_below: 1,
+            max_vocab:   None,
+            max_word_len: None,
+            personal:    None,
+            abbreviations: None,
+            ignore:      Vec::new(),
+            hyphens:     Hyphens::Keep,
+            punctuation: Vec::new(),
+            fold_diacritics: false,
+            adaptive_edits: false,
+            format:      Format::Text,
+            list:        false,
+            explain:     false,
+            quiet:       false,
+            document:    false,
+            annotate:    false,
+            complete:    false,
+            filter:      Filter::Text,
+            fix:         false,
+            dry_run:     false,
+            interactive: None,
+            watch:       None,
+            command:     Command::Check,
+            listen:      "127.0.0.1:7000".to_owned(),
+            http:        false,
+            lsp:         false,
+        }
+    }
+}
+
+pub(crate) fn parse_args<I: Iterator<Item = String>>(args: I) -> Result<Options, String> {
+    let mut options = Options::default();
+    let mut args = args.peekable();
+
+    if let Some(command) = args.peek().and_then(|arg| arg.parse().ok()) {
+        options.command = command;
+        args.next();
+    }
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--encoding" => {
+                let value = args.next().ok_or("--encoding needs a value")?;
+                options.encoding = value.parse()?;
+            }
+            "--progress" => options.progress = true,
+            "--timings"  => options.timings = true,
+            "--stats"    => options.stats = true,
+            "--verbose"  => options.verbose = true,
+            "--stream"   => options.stream = true,
+            "--tokenize" => options.tokenize = true,
+            "--minimize" => options.minimize = true,
+            "--phonetic" => options.phonetic = true,
+            "--context"  => options.context = true,
+            "--real-words" => options.real_words = true,
+            "--split-compounds" => options.split_compounds = true,
+            "--accept-inflections" => options.accept_inflections = true,
+            "--join-words" => options.join_words = true,
+            "--fold-diacritics" => options.fold_diacritics = true,
+            "--adaptive-edits" => options.adaptive_edits = true,
+            "--list" => options.list = true,
+            "--explain" => options.explain = true,
+            "--quiet" => options.quiet = true,
+            "--document" => options.document = true,
+            "--annotate" => options.annotate = true,
+            "--complete" => options.complete = true,
+            "--fix"      => options.fix = true,
+            "--http"     => options.http = true,
+            "--lsp"      => options.lsp = true,
+            "--dry-run"  => options.dry_run = true,
+            "--threshold" => {
+                let value = args.next().ok_or("--threshold needs a value")?;
+                options.threshold = match value.parse() {
+                    Ok(t) if t > 0.0 && t <= 1.0 => t,
+                    _ => return Err(format!("bad threshold `{}' (expected 0 to 1)", value)),
+                };
+            }
+            "--smoothing" => {
+                let value = args.next().ok_or("--smoothing needs a value")?;
+                options.smoothing = match value.parse::<f64>() {
+                    Ok(k) if k >= 0.0 && k.is_finite() => k,
+                    _ => return Err(format!("bad smoothing `{}' (expected 0 or more)", value)),
+                };
+            }
+            "--format" => {
+                let value = args.next().ok_or("--format needs a value")?;
+                options.format = value.parse()?;
+            }
+            "--min-count" => {
+                let value = args.next().ok_or("--min-count needs a value")?;
+                options.min_count = match value.parse() {
+                    Ok(n) if n > 0 => n,
+                    _ => return Err(format!("bad minimum count `{}'", value)),
+                };
+            }
+            "--prune-below" => {
+                let value = args.next().ok_or("--prune-below needs a value")?;
+                options.prune_below = match value.parse() {
+                    Ok(n) if n > 0 => n,
+                    _ => return Err(format!("bad minimum count `{}'", value)),
+                };
+            }
+            "--max-vocab" => {
+                let value = args.next().ok_or("--max-vocab needs a value")?;
+                options.max_vocab = match value.parse() {
+                    Ok(n) if n > 0 => Some(n),
+                    _ => return Err(format!("bad vocabulary size `{}'", value)),
+                };
+            }
+            "--max-word-len" => {
+                let value = args.next().ok_or("--max-word-len needs a value")?;
+                options.max_word_len = match value.parse() {
+                    Ok(n) if n > 0 => Some(n),
+                    _ => return Err(format!("bad word length `{}'", value)),
+                };
+            }
+            "--suggestions" => {
+                let value = args.next().ok_or("--suggestions needs a value")?;
+                options.suggestions = match value.parse() {
+                    Ok(n) if n > 0 => n,
+                    _ => return Err(format!("bad suggestion count `{}'", value)),
+                };
+            }
+            "--jobs" => {
+                let value = args.next().ok_or("--jobs needs a value")?;
+                options.jobs = match value.parse() {
+                    Ok(n) if n > 0 => n,
+                    _ => return Err(format!("bad job count `{}'", value)),
+                };
+            }
+            "--beam" => {
+                let value = args.next().ok_or("--beam needs a width")?;
+                options.beam = match value.parse() {
+                    Ok(n) if n > 0 => Some(n),
+                    _ => return Err(format!("bad beam width `{}'", value)),
+                };
+            }
+            "--budget-ms" => {
+                let value = args.next().ok_or("--budget-ms needs a time")?;
+                options.budget_ms = match value.parse() {
+                    Ok(ms) if ms > 0 => Some(ms),
+                    _ => return Err(format!("bad time budget `{}'", value)),
+                };
+            }
+            "--engine" => {
+                let value = args.next().ok_or("--engine needs a value")?;
+                options.engine = value.parse()?;
+            }
+            "--load-model" => {
+                options.load_model = Some(args.next().ok_or("--load-model needs a file")?);
+            }
+            "--dictionary" => {
+                options.dictionary = Some(args.next().ok_or("--dictionary needs a file")?);
+            }
+            "--from-freq" => {
+                options.from_freq = Some(args.next().ok_or("--from-freq needs a file")?);
+            }
+            "--hunspell" => {
+                options.hunspell = Some(args.next().ok_or("--hunspell needs a file")?);
+            }
+            "--lang-model" => {
+                let value = args.next().ok_or("--lang-model needs LANG=FILE")?;
+                let (lang, path) = match value.split_once('=') {
+                    Some((lang, path))
+                        if !lang.is_empty() && lang != "auto" && !path.is_empty() => {
+                        (lang.to_owned(), path.to_owned())
+                    }
+                    _ => return Err(format!("bad language model `{}' (expected LANG=FILE)", value)),
+                };
+                if options.lang_models.iter().any(|(known, _)| *known == lang) {
+                    return Err(format!("--lang-model {} given twice", lang));
+                }
+                options.lang_models.push((lang, path));
+            }
+            "--bundle" => options.bundles.push(args.next().ok_or("--bundle needs a directory")?),
+            "--lang" => {
+                let value = args.next().ok_or("--lang needs a language")?;
+                options.lang = if value == "auto" { None } else { Some(value) };
+            }
+            "--save-model" => {
+                options.save_model = Some(args.next().ok_or("--save-model needs a file")?);
+            }
+            "--append" => {
+                options.append = Some(args.next().ok_or("--append needs a file")?);
+            }
+            "--mode" => {
+                let value = args.next().ok_or("--mode needs a value")?;
+                options.mode = value.parse()?;
+            }
+            "--ranking" => {
+                let value = args.next().ok_or("--ranking needs a value")?;
+                options.ranking = value.parse()?;
+            }
+            "--filter" => {
+                let value = args.next().ok_or("--filter needs a value")?;
+                options.filter = value.parse()?;
+            }
+            "--hyphens" => {
+                let value = args.next().ok_or("--hyphens needs a policy")?;
+                options.hyphens = value.parse()?;
+            }
+            "--punctuation" => {
+                let value = args.next().ok_or("--punctuation needs marks")?;
+                options.punctuation = value.chars().collect();
+            }
+            "--capitalized" => {
+                let value = args.next().ok_or("--capitalized needs a policy")?;
+                options.capitalized = value.parse()?;
+            }
+            "--acronyms" => {
+                let value = args.next().ok_or("--acronyms needs a policy")?;
+                options.acronyms = value.parse()?;
+            }
+            "--numbers" => {
+                let value = args.next().ok_or("--numbers needs a policy")?;
+                options.numbers = value.parse()?;
+            }
+            "--listen" => options.listen = args.next().ok_or("--listen needs an address")?,
+            "--interactive" => {
+                options.interactive = Some(args.next().ok_or("--interactive needs a file")?);
+            }
+            "--costs" => options.costs = Some(args.next().ok_or("--costs needs a file")?),
+            "--ignore" => {
+                let pattern = args.next().ok_or("--ignore needs a pattern")?;
+                Regex::new(&pattern).map_err(|e| format!("--ignore: {}", e))?;
+                options.ignore.push(pattern);
+            }
+            "--personal" => {
+                options.personal = Some(args.next().ok_or("--personal needs a file")?);
+            }
+            "--abbreviations" => {
+                options.abbreviations = Some(args.next().ok_or("--abbreviations needs a file")?);
+            }
+            "--glob" => options.glob = args.next().ok_or("--glob needs a pattern")?,
+            "--dump-dictionary" => {
+                options.dump_dictionary =
+                    Some(args.next().ok_or("--dump-dictionary needs a file")?);
+            }
+            "--watch" => options.watch = Some(args.next().ok_or("--watch needs a file")?),
+            "--corpus" => options.corpora.push(args.next().ok_or("--corpus needs a file")?),
+            "--words" => options.words.push(args.next().ok_or("--words needs a file")?),
+            "--" => options.words.extend(args.by_ref()),
+            _ if arg.starts_with("--") => return Err(format!("unknown option `{}'", arg)),
+            _ => options.corpora.push(arg),
+        }
+    }
+
+    if options.costs.is_some() && options.mode != Mode::Text {
+        return Err("give either --costs or --mode, not both".to_owned());
+    }
+    let weighted = options.costs.is_some() || options.mode != Mode::Text;
+    if weighted && options.engine != Engine::Trie {
+        return Err(format!("weighted edits can't be used with --engine {}", options.engine));
+    }
+    if options.phonetic && options.engine == Engine::Fst {
+        return Err("--phonetic can't be used with --engine fst".to_owned());
+    }
+    if (options.beam.is_some() || options.budget_ms.is_some()) && options.engine != Engine::Trie {
+        return Err(format!("--beam and --budget-ms can't be used with --engine {}",
+                           options.engine));
+    }
+    if (options.beam.is_some() || options.budget_ms.is_some() || options.adaptive_edits)
+        && weighted {
+        return Err("--beam, --budget-ms and --adaptive-edits can't be used with weighted edits"
+                       .to_owned());
+    }
+    if options.context && options.corpora.is_empty() {
+        return Err("--context needs a corpus to count word pairs in".to_owned());
+    }
+    match options.corpora.iter().filter(|&corpus| corpus == "-").count() {
+        0 => {}
+        1 if options.context => {
+            return Err("--context can't read a corpus from the standard input".to_owned());
+        }
+        1 => {}
+        _ => return Err("give - as a corpus only once".to_owned()),
+    }
+    if options.context && options.corpora.iter().any(|corpus| is_url(corpus)) {
+        return Err("--context can't read a corpus from a URL".to_owned());
+    }
+    if options.join_words && options.stream {
+        return Err("give either --join-words or --stream, not both".to_owned());
+    }
+    if let Format::Table { .. } = options.format {
+        if options.stream || options.command != Command::Check {
+            return Err("--format table can't be used with --stream or a command".to_owned());
+        }
+    }
+    if options.document && options.format != Format::Text {
+        return Err("give either --document or --format, not both".to_owned());
+    }
+    if options.document && options.join_words {
+        return Err("give either --document or --join-words, not both".to_owned());
+    }
+    if options.fix && (options.document || options.format != Format::Text || options.join_words) {
+        return Err("--fix can't be used with --document, --format or --join-words".to_owned());
+    }
+    if options.interactive.is_some()
+        && (options.document || options.fix || options.stream || options.join_words
+            || options.format != Format::Text) {
+        return Err("--interactive can't be used with --document, --fix, --stream, --join-words \
+                    or --format".to_owned());
+    }
+    if options.command != Command::Check
+        && (options.document || options.fix || options.interactive.is_some() || options.stream
+            || options.join_words) {
+        return Err(format!("{} can't be used with --document, --fix, --interactive, --stream \
+                            or --join-words", options.command));
+    }
+    if options.list
+        && (options.command != Command::Check || options.format != Format::Text || options.fix
+            || options.interactive.is_some() || options.lsp) {
+        return Err("--list can't be used with a command, --format, --fix, --interactive or \
+                    --lsp".to_owned());
+    }
+    if options.explain
+        && (options.command != Command::Check || options.format != Format::Text || options.list
+            || options.document || options.fix || options.interactive.is_some()
+            || options.watch.is_some() || options.lsp) {
+        return Err("--explain can't be used with a command, --format, --list, --document, --fix, \
+                    --interactive, --watch or --lsp".to_owned());
+    }
+    if options.quiet
+        && (options.command != Command::Check || options.format != Format::Text || options.fix
+            || options.interactive.is_some() || options.lsp || options.verbose
+            || options.progress || options.timings || options.stats) {
+        return Err("--quiet can't be used with a command, --format, --fix, --interactive, --lsp, \
+                    --verbose, --progress, --timings or --stats".to_owned());
+    }
+    if !options.words.is_empty()
+        && (options.command != Command::Check || options.fix || options.interactive.is_some()
+            || options.lsp) {
+        return Err("--words can't be used with a command, --fix, --interactive or --lsp"
+                   .to_owned());
+    }
+    if options.watch.is_some()
+        && (options.command != Command::Check || options.document || options.fix
+            || options.interactive.is_some() || options.lsp || options.stream || options.list
+            || options.quiet || options.join_words || !options.words.is_empty()
+            || options.format != Format::Text) {
+        return Err("--watch can't be used with a command, --document, --fix, --interactive, \
+                    --lsp, --stream, --list, --quiet, --join-words, --words or --format"
+                   .to_owned());
+    }
+    if options.command != Command::Serve && options.listen != Options::default().listen {
+        return Err("--listen needs serve".to_owned());
+    }
+    if options.lsp
+        && (options.command != Command::Check || options.document || options.fix
+            || options.interactive.is_some() || options.stream || options.join_words
+            || options.format != Format::Text) {
+        return Err("--lsp can't be used with a command, --document, --fix, --interactive, \
+                    --stream, --join-words or --format".to_owned());
+    }
+    if options.tokenize
+        && (options.command != Command::Check || options.document || options.fix
+            || options.interactive.is_some() || options.watch.is_some() || options.lsp) {
+        return Err("--tokenize can't be used with a command, --document, --fix, --interactive, \
+                    --watch or --lsp".to_owned());
+    }
+    if options.http && options.command != Command::Serve {
+        return Err("--http needs serve".to_owned());
+    }
+    if options.dry_run && !options.fix {
+        return Err("--dry-run needs --fix".to_owned());
+    }
+    if options.annotate
+        && (options.command != Command::Check || options.document || options.fix
+            || options.interactive.is_some() || options.watch.is_some() || options.lsp
+            || options.stream || options.tokenize || options.join_words || options.list
+            || options.explain || options.format != Format::Text) {
+        return Err("--annotate can't be used with a command, --document, --fix, --interactive, \
+                    --watch, --lsp, --stream, --tokenize, --join-words, --list, --explain or \
+                    --format".to_owned());
+    }
+    if options.complete
+        && (options.command != Command::Check || options.document || options.annotate
+            || options.fix || options.interactive.is_some() || options.watch.is_some()
+            || options.lsp || options.stream || options.tokenize || options.join_words
+            || options.list || options.explain || options.format != Format::Text
+            || options.quiet) {
+        return Err("--complete can't be used with a command, --document, --annotate, --fix, \
+                    --interactive, --watch, --lsp, --stream, --tokenize, --join-words, --list, \
+                    --explain, --format or --quiet".to_owned());
+    }
+    if options.filter != Filter::Text && !options.document && !options.annotate
+        && options.watch.is_none() {
+        return Err("--filter needs --document, --annotate or --watch".to_owned());
+    }
+    if options.real_words && !options.context {
+        return Err("--real-words needs --context".to_owned());
+    }
+    // A bundle's language is only known once it is read.
+    if let Some(ref lang) = options.lang {
+        if options.bundles.is_empty() && !options.lang_models.iter().any(|(known, _)| known == lang)
+        {
+            return Err(format!("no --lang-model for --lang {}", lang));
+        }
+    }
+    if (!options.lang_models.is_empty() || !options.bundles.is_empty())
+        && (options.command != Command::Check || options.fix || options.interactive.is_some()
+            || options.watch.is_some() || options.lsp || options.save_model.is_some()
+            || options.dump_dictionary.is_some()) {
+        return Err("--lang-model and --bundle can't be used with a command, --fix, \
+                    --interactive, --watch, --lsp, --save-model or --dump-dictionary".to_owned());
+    }
+    if options.append.is_some() {
+        if options.corpora.is_empty() {
+            return Err("--append needs a corpus to train on".to_owned());
+        }
+        if options.save_model.is_some() {
+            return Err("give either --append or --save-model, not both".to_owned());
+        }
+        // What is pruned from the model written back would be gone for good.
+        if options.prune_below > 1 || options.max_vocab.is_some()
+            || options.max_word_len.is_some() {
+            return Err("--append can't be used with --prune-below, --max-vocab or \
+                        --max-word-len".to_owned());
+        }
+    }
+
+    let sources = [!options.corpora.is_empty(), options.load_model.is_some(),
+                   options.dictionary.is_some(), options.from_freq.is_some(),
+                   options.hunspell.is_some(),
+                   !options.lang_models.is_empty() || !options.bundles.is_empty()];
+    match sources.iter().filter(|&&given| given).count() {
+        0 => Err("no corpus given".to_owned()),
+        1 => Ok(options),
+        _ => Err("give only one of a corpus, --load-model, --dictionary, --from-freq, \
+                  --hunspell, or --lang-model and --bundle".to_owned()),
+    }
+}
+
+#[cfg(test)]
+mod parse_args_tests {
+    use super::{parse_args, Command, Format, Options};
+    use spellchecker::{CasePolicy, Encoding, Engine, Filter, Hyphens, Language, Mode, Numbers,
+                       Ranking};
+
+    fn parse(args: &[&str]) -> Result<Options, String> {
+        parse_args(args.iter().map(|s| s.to_string()))
+    }
+
+    #[test]
+    fn needs_corpus() {
+        assert!(parse(&[]).is_err());
+        assert_eq!(vec!["big.txt"], parse(&["big.txt"]).unwrap().corpora);
+    }
+
+    #[test]
+    fn reads_several_corpora() {
+        let options = parse(&["big.txt", "--glob", "*.txt", "texts/", "--verbose"]).unwrap();
+
+        assert_eq!(vec!["big.txt", "texts/"], options.corpora);
+        assert_eq!("*.txt", options.glob);
+        assert!(options.verbose);
+        assert_eq!("*", parse(&["big.txt"]).unwrap().glob);
+        assert!(parse(&["big.txt", "--glob"]).is_err());
+    }
+
+    #[test]
+    fn reads_options() {
+        let options = parse(&["--progress", "--encoding", "latin1", "big.txt", "--timings"])
+            .unwrap();
+
+        assert_eq!(Options {
+            corpora:     vec!["big.txt".to_owned()],
+            words:       Vec::new(),
+            glob:        "*".to_owned(),
+            verbose:     false,
+            encoding:    Encoding::Latin1,
+            progress:    true,
+            timings:     true,
+            stats:       false,
+            suggestions: 1,
+            load_model:  None,
+            dictionary:  None,
+            from_freq:   None,
+            hunspell:    None,
+            lang_models: Vec::new(),
+            bundles:     Vec::new(),
+            lang:        None,
+            save_model:  None,
+            append:      None,
+            dump_dictionary: None,
+            stream:      false,
+            tokenize:    false,
+            jobs:        1,
+            beam:        None,
+            budget_ms:   None,
+            engine:      Engine::Trie,
+            minimize:    false,
+            costs:       None,
+            mode:        Mode::Text,
+            phonetic:    false,
+            ranking:     Ranking::Distance,
+            smoothing:   0.0,
+            context:     false,
+            real_words:  false,
+            threshold:   0.1,
+            split_compounds: false,
+            accept_inflections: false,
+            join_words:  false,
+            capitalized: CasePolicy::Check,
+            acronyms:    CasePolicy::Check,
+            numbers:     Numbers::Check,
+            min_count:   1,
+            prune_below: 1,
+            max_vocab:   None,
+            max_word_len: None,
+            personal:    None,
+            abbreviations: None,
+            ignore:      Vec::new(),
+            hyphens:     Hyphens::Keep,
+            punctuation: Vec::new(),
+            fold_diacritics: false,
+            adaptive_edits: false,
+            format:      Format::Text,
+            list:        false,
+            explain:     false,
+            quiet:       false,
+            document:    false,
+            annotate:    false,
+            complete:    false,
+            filter:      Filter::Text,
+            fix:         false,
+            dry_run:     false,
+            interactive: None,
+            watch:       None,
+            command:     Command::Check,
+            listen:      "127.0.0.1:7000".to_owned(),
+            http:        false,
+            lsp:         false,
+        }, options);
+    }
+
+    #[test]
+    fn reads_model_files() {
+        let options = parse(&["--save-model", "big.model", "big.txt"]).unwrap();
+        assert_eq!(Some("big.model".to_owned()), options.save_model);
+
+        let options = parse(&["--load-model", "big.model"]).unwrap();
+        assert_eq!(Some("big.model".to_owned()), options.load_model);
+        assert!(options.corpora.is_empty());
+
+        let options = parse(&["--dump-dictionary", "big.dict", "big.txt"]).unwrap();
+        assert_eq!(Some("big.dict".to_owned()), options.dump_dictionary);
+        assert!(parse(&["big.txt", "--dump-dictionary"]).is_err());
+
+        let options = parse(&["--personal", "my.dict", "big.txt"]).unwrap();
+        assert_eq!(Some("my.dict".to_owned()), options.personal);
+        assert!(parse(&["big.txt", "--personal"]).is_err());
+
+        let options = parse(&["--abbreviations", "short.txt", "big.txt"]).unwrap();
+        assert_eq!(Some("short.txt".to_owned()), options.abbreviations);
+        assert!(parse(&["big.txt", "--abbreviations"]).is_err());
+
+        let options = parse(&["--dictionary", "big.dict"]).unwrap();
+        assert_eq!(Some("big.dict".to_owned()), options.dictionary);
+        assert!(options.corpora.is_empty());
+
+        let options = parse(&["--hunspell", "en_US.dic"]).unwrap();
+        assert_eq!(Some("en_US.dic".to_owned()), options.hunspell);
+
+        let options = parse(&["--from-freq", "big.freq"]).unwrap();
+        assert_eq!(Some("big.freq".to_owned()), options.from_freq);
+        assert!(parse(&["--from-freq"]).is_err());
+    }
+
+    #[test]
+    fn reads_costs() {
+        assert_eq!(None, parse(&["big.txt"]).unwrap().costs);
+        assert_eq!(Some("costs.toml".to_owned()),
+                   parse(&["--costs", "costs.toml", "big.txt"]).unwrap().costs);
+        assert!(parse(&["--costs", "costs.toml", "--engine", "symspell", "big.txt"]).is_err());
+    }
+
+    #[test]
+    fn reads_mode() {
+        assert_eq!(Mode::Text, parse(&["big.txt"]).unwrap().mode);
+        assert_eq!(Mode::Ocr, parse(&["--mode", "ocr", "big.txt"]).unwrap().mode);
+        assert!(parse(&["--mode", "scan", "big.txt"]).is_err());
+        assert!(parse(&["--mode", "ocr", "--costs", "costs.toml", "big.txt"]).is_err());
+        assert!(parse(&["--mode", "ocr", "--engine", "bktree", "big.txt"]).is_err());
+    }
+
+    #[test]
+    fn reads_beam() {
+        assert_eq!(None, parse(&["big.txt"]).unwrap().beam);
+        assert_eq!(Some(8), parse(&["--beam", "8", "big.txt"]).unwrap().beam);
+        assert!(parse(&["--beam", "0", "big.txt"]).is_err());
+        assert!(parse(&["--beam", "8", "--engine", "symspell", "big.txt"]).is_err());
+        assert!(parse(&["--beam", "8", "--mode", "ocr", "big.txt"]).is_err());
+    }
+
+    #[test]
+    fn reads_budget() {
+        assert_eq!(None, parse(&["big.txt"]).unwrap().budget_ms);
+        assert_eq!(Some(20), parse(&["--budget-ms", "20", "big.txt"]).unwrap().budget_ms);
+        assert!(parse(&["--budget-ms", "0", "big.txt"]).is_err());
+        assert!(parse(&["--budget-ms", "soon", "big.txt"]).is_err());
+        assert!(parse(&["--budget-ms", "20", "--engine", "bktree", "big.txt"]).is_err());
+        assert!(parse(&["--budget-ms", "20", "--costs", "costs.toml", "big.txt"]).is_err());
+    }
+
+    #[test]
+    fn reads_append() {
+        let options = parse(&["--append", "big.model", "more.txt"]).unwrap();
+        assert_eq!(Some("big.model".to_owned()), options.append);
+        assert_eq!(vec!["more.txt"], options.corpora);
+
+        assert!(parse(&["--append", "big.model"]).is_err());
+        assert!(parse(&["--append", "big.model", "--load-model", "big.model"]).is_err());
+        assert!(parse(&["--append", "big.model", "--save-model", "new.model", "more.txt"])
+            .is_err());
+        for flag in &["--prune-below", "--max-vocab", "--max-word-len"] {
+            assert!(parse(&["--append", "big.model", flag, "2", "more.txt"]).is_err());
+        }
+    }
+
+    #[test]
+    fn wants_one_model_source() {
+        assert!(parse(&["--load-model", "big.model", "big.txt"]).is_err());
+        assert!(parse(&["--save-model", "big.model"]).is_err());
+        assert!(parse(&["--dictionary", "big.dict", "big.txt"]).is_err());
+        assert!(parse(&["--dictionary", "big.dict", "--load-model", "big.model"]).is_err());
+        assert!(parse(&["--from-freq", "big.freq", "--dictionary", "big.dict"]).is_err());
+        assert!(parse(&["--hunspell", "en_US.dic", "big.txt"]).is_err());
+    }
+
+    #[test]
+    fn reads_suggestion_count() {
+        assert_eq!(1, parse(&["big.txt"]).unwrap().suggestions);
+        assert_eq!(3, parse(&["--suggestions", "3", "big.txt"]).unwrap().suggestions);
+        assert!(parse(&["--suggestions", "0", "big.txt"]).is_err());
+        assert!(parse(&["--suggestions", "many", "big.txt"]).is_err());
+    }
+
+    #[test]
+    fn reads_jobs() {
+        assert_eq!(1, parse(&["big.txt"]).unwrap().jobs);
+        assert_eq!(4, parse(&["--jobs", "4", "big.txt"]).unwrap().jobs);
+        assert!(parse(&["--jobs", "0", "big.txt"]).is_err());
+        assert!(parse(&["--jobs"]).is_err());
+    }
+
+    #[test]
+    fn reads_engine() {
+        assert_eq!(Engine::Trie, parse(&["big.txt"]).unwrap().engine);
+        assert_eq!(Engine::SymSpell, parse(&["--engine", "symspell", "big.txt"]).unwrap().engine);
+        assert_eq!(Engine::BkTree, parse(&["--engine", "bktree", "big.txt"]).unwrap().engine);
+        assert_eq!(Engine::Fst, parse(&["--engine", "fst", "big.txt"]).unwrap().engine);
+        assert!(parse(&["--engine", "fast", "big.txt"]).is_err());
+        assert!(parse(&["--engine", "fst", "--phonetic", "big.txt"]).is_err());
+        assert!(parse(&["--engine", "fst", "--beam", "8", "big.txt"]).is_err());
+    }
+
+    #[test]
+    fn reads_stream() {
+        assert!(!parse(&["big.txt"]).unwrap().stream);
+        assert!(parse(&["--stream", "big.txt"]).unwrap().stream);
+    }
+
+    #[test]
+    fn reads_minimize() {
+        assert!(!parse(&["big.txt"]).unwrap().minimize);
+        assert!(parse(&["--minimize", "big.txt"]).unwrap().minimize);
+    }
+
+    #[test]
+    fn reads_ignore_patterns() {
+        let options = parse(&["--ignore", "^https?://", "--ignore", "[0-9a-f]{40}", "big.txt"]);
+        assert_eq!(vec!["^https?://", "[0-9a-f]{40}"], options.unwrap().ignore);
+        assert!(parse(&["--ignore", "(unclosed", "big.txt"]).is_err());
+        assert!(parse(&["big.txt", "--ignore"]).is_err());
+    }
+
+    #[test]
+    fn reads_adaptive_edits() {
+        assert!(!parse(&["big.txt"]).unwrap().adaptive_edits);
+        assert!(parse(&["--adaptive-edits", "--engine", "bktree", "big.txt"]).unwrap()
+                .adaptive_edits);
+        assert!(parse(&["--adaptive-edits", "--mode", "ocr", "big.txt"]).is_err());
+    }
+
+    #[test]
+    fn reads_fold_diacritics() {
+        assert!(!parse(&["big.txt"]).unwrap().fold_diacritics);
+        assert!(parse(&["--fold-diacritics", "big.txt"]).unwrap().fold_diacritics);
+    }
+
+    #[test]
+    fn reads_hyphen_policy() {
+        assert_eq!(Hyphens::Keep, parse(&["big.txt"]).unwrap().hyphens);
+        assert_eq!(Hyphens::Both, parse(&["--hyphens", "both", "big.txt"]).unwrap().hyphens);
+        assert!(parse(&["--hyphens", "join", "big.txt"]).is_err());
+        assert!(parse(&["big.txt", "--hyphens"]).is_err());
+    }
+
+    #[test]
+    fn reads_punctuation() {
+        assert!(parse(&["big.txt"]).unwrap().punctuation.is_empty());
+        assert_eq!(vec!['«', '»', '\u{2014}'],
+                   parse(&["--punctuation", "«»\u{2014}", "big.txt"]).unwrap().punctuation);
+        assert!(parse(&["big.txt", "--punctuation"]).is_err());
+    }
+
+    #[test]
+    fn reads_case_policies() {
+        let options = parse(&["--capitalized", "lenient", "--acronyms", "skip", "big.txt"]);
+        let options = options.unwrap();
+        assert_eq!(CasePolicy::Lenient, options.capitalized);
+        assert_eq!(CasePolicy::Skip, options.acronyms);
+        assert_eq!(CasePolicy::Check, parse(&["big.txt"]).unwrap().acronyms);
+        assert!(parse(&["--capitalized", "ignore", "big.txt"]).is_err());
+        assert!(parse(&["big.txt", "--acronyms"]).is_err());
+    }
+
+    #[test]
+    fn reads_numbers() {
+        assert_eq!(Numbers::Check, parse(&["big.txt"]).unwrap().numbers);
+        assert_eq!(Numbers::Skip, parse(&["--numbers", "skip", "big.txt"]).unwrap().numbers);
+        assert!(parse(&["--numbers", "lenient", "big.txt"]).is_err());
+        assert!(parse(&["big.txt", "--numbers"]).is_err());
+    }
+
+    #[test]
+    fn reads_ranking() {
+        assert_eq!(Ranking::Distance, parse(&["big.txt"]).unwrap().ranking);
+        assert_eq!(Ranking::NoisyChannel,
+                   parse(&["--ranking", "noisy-channel", "big.txt"]).unwrap().ranking);
+        assert!(parse(&["--ranking", "best", "big.txt"]).is_err());
+        assert!(parse(&["big.txt", "--ranking"]).is_err());
+    }
+
+    #[test]
+    fn reads_context() {
+        assert!(!parse(&["big.txt"]).unwrap().context);
+        assert!(parse(&["--context", "big.txt"]).unwrap().context);
+        assert!(parse(&["--context", "--load-model", "big.model"]).is_err());
+    }
+
+    #[test]
+    fn reads_smoothing() {
+        assert_eq!(0.0, parse(&["big.txt"]).unwrap().smoothing);
+        assert_eq!(2.5, parse(&["--smoothing", "2.5", "big.txt"]).unwrap().smoothing);
+        for bad in &["-1", "inf", "NaN", "some"] {
+            assert!(parse(&["--smoothing", bad, "big.txt"]).is_err());
+        }
+    }
+
+    #[test]
+    fn reads_real_words() {
+        let options = parse(&["--context", "--real-words", "big.txt"]).unwrap();
+        assert!(options.real_words);
+        assert_eq!(0.1, options.threshold);
+
+        let options = parse(&["--context", "--real-words", "--threshold", "0.5", "big.txt"])
+            .unwrap();
+        assert_eq!(0.5, options.threshold);
+
+        assert!(parse(&["--real-words", "big.txt"]).is_err());
+        for bad in &["0", "2", "-1", "lots"] {
+            assert!(parse(&["--context", "--real-words", "--threshold", bad, "big.txt"]).is_err());
+        }
+    }
+
+    #[test]
+    fn reads_split_compounds() {
+        assert!(!parse(&["big.txt"]).unwrap().split_compounds);
+        assert!(parse(&["--split-compounds", "big.txt"]).unwrap().split_compounds);
+    }
+
+    #[test]
+    fn reads_accept_inflections() {
+        assert!(!parse(&["big.txt"]).unwrap().accept_inflections);
+        assert!(parse(&["--accept-inflections", "big.txt"]).unwrap().accept_inflections);
+    }
+
+    #[test]
+    fn reads_join_words() {
+        assert!(!parse(&["big.txt"]).unwrap().join_words);
+        assert!(parse(&["--join-words", "big.txt"]).unwrap().join_words);
+        assert!(parse(&["--join-words", "--stream", "big.txt"]).is_err());
+    }
+
+    #[test]
+    fn reads_pruning() {
+        let options = parse(&["big.txt"]).unwrap();
+        assert_eq!((1, None), (options.prune_below, options.max_vocab));
+        let options = parse(&["--prune-below", "2", "--max-vocab", "1000", "big.txt"]).unwrap();
+        assert_eq!((2, Some(1000)), (options.prune_below, options.max_vocab));
+        for bad in &["0", "-1", "many"] {
+            assert!(parse(&["--prune-below", bad, "big.txt"]).is_err());
+            assert!(parse(&["--max-vocab", bad, "big.txt"]).is_err());
+        }
+    }
+
+    #[test]
+    fn reads_max_word_len() {
+        assert_eq!(None, parse(&["big.txt"]).unwrap().max_word_len);
+        assert_eq!(Some(40), parse(&["--max-word-len", "40", "big.txt"]).unwrap().max_word_len);
+        assert!(parse(&["--max-word-len", "0", "big.txt"]).is_err());
+        assert!(parse(&["--max-word-len", "long", "big.txt"]).is_err());
+    }
+
+    #[test]
+    fn reads_min_count() {
+        assert_eq!(1, parse(&["big.txt"]).unwrap().min_count);
+        assert_eq!(3, parse(&["--min-count", "3", "big.txt"]).unwrap().min_count);
+        assert!(parse(&["--min-count", "0", "big.txt"]).is_err());
+        assert!(parse(&["--min-count", "few", "big.txt"]).is_err());
+        assert!(parse(&["big.txt", "--min-count"]).is_err());
+    }
+
+    #[test]
+    fn reads_format() {
+        assert_eq!(Format::Text, parse(&["big.txt"]).unwrap().format);
+        assert_eq!(Format::Scores, parse(&["--format", "scores", "big.txt"]).unwrap().format);
+        assert!(parse(&["--format", "xml", "big.txt"]).is_err());
+        assert!(parse(&["--format", "table", "--stream", "big.txt"]).is_err());
+        assert!(parse(&["repl", "--format", "table", "big.txt"]).is_err());
+        assert!(parse(&["big.txt", "--format"]).is_err());
+    }
+
+    #[test]
+    fn reads_document() {
+        assert!(!parse(&["big.txt"]).unwrap().document);
+        assert!(parse(&["--document", "big.txt"]).unwrap().document);
+        assert!(parse(&["--document", "--format", "json", "big.txt"]).is_err());
+        assert!(parse(&["--document", "--join-words", "big.txt"]).is_err());
+
+        let options = parse(&["--document", "--filter", "markdown", "big.txt"]).unwrap();
+        assert_eq!(Filter::Markdown, options.filter);
+        let options = parse(&["--document", "--filter", "html", "big.txt"]).unwrap();
+        assert_eq!(Filter::Html, options.filter);
+        let options = parse(&["--document", "--filter", "code=rust", "big.txt"]).unwrap();
+        assert_eq!(Filter::Code(Language::Rust), options.filter);
+        assert!(parse(&["--document", "--filter", "code=cobol", "big.txt"]).is_err());
+        assert!(parse(&["--filter", "markdown", "big.txt"]).is_err());
+        assert!(parse(&["--document", "--filter", "rst", "big.txt"]).is_err());
+    }
+
+    #[test]
+    fn reads_list() {
+        assert!(!parse(&["big.txt"]).unwrap().list);
+        assert!(parse(&["--list", "big.txt"]).unwrap().list);
+        assert!(parse(&["--list", "--document", "--filter", "html", "big.txt"]).unwrap().list);
+        assert!(parse(&["--list", "--format", "json", "big.txt"]).is_err());
+        assert!(parse(&["--list", "--fix", "big.txt"]).is_err());
+        assert!(parse(&["repl", "--list", "big.txt"]).is_err());
+    }
+
+    #[test]
+    fn reads_explain() {
+        assert!(!parse(&["big.txt"]).unwrap().explain);
+        assert!(parse(&["--explain", "--stream", "--mode", "ocr", "big.txt"]).unwrap().explain);
+        assert!(parse(&["--explain", "--list", "big.txt"]).is_err());
+        assert!(parse(&["--explain", "--format", "json", "big.txt"]).is_err());
+        assert!(parse(&["--explain", "--document", "big.txt"]).is_err());
+        assert!(parse(&["serve", "--explain", "big.txt"]).is_err());
+    }
+
+    #[test]
+    fn reads_quiet() {
+        assert!(!parse(&["big.txt"]).unwrap().quiet);
+        assert!(parse(&["--quiet", "big.txt"]).unwrap().quiet);
+        assert!(parse(&["--quiet", "--document", "--list", "big.txt"]).unwrap().quiet);
+        assert!(parse(&["--quiet", "--format", "csv", "big.txt"]).is_err());
+        assert!(parse(&["--quiet", "--timings", "big.txt"]).is_err());
+        assert!(parse(&["--quiet", "--stats", "big.txt"]).is_err());
+        assert!(parse(&["serve", "--quiet", "big.txt"]).is_err());
+    }
+
+    #[test]
+    fn reads_annotate() {
+        assert!(parse(&["--annotate", "big.txt"]).unwrap().annotate);
+        assert!(parse(&["--annotate", "--filter", "markdown", "big.txt"]).is_ok());
+        assert!(parse(&["--annotate", "--document", "big.txt"]).is_err());
+        assert!(parse(&["--annotate", "--format", "json", "big.txt"]).is_err());
+        assert!(parse(&["--annotate", "--stream", "big.txt"]).is_err());
+    }
+
+    #[test]
+    fn reads_complete() {
+        assert!(!parse(&["big.txt"]).unwrap().complete);
+        assert!(parse(&["--complete", "--suggestions", "5", "big.txt"]).unwrap().complete);
+        assert!(parse(&["--complete", "--annotate", "big.txt"]).is_err());
+        assert!(parse(&["--complete", "--format", "json", "big.txt"]).is_err());
+        assert!(parse(&["repl", "--complete", "big.txt"]).is_err());
+    }
+
+    #[test]
+    fn reads_tokenize() {
+        assert!(!parse(&["big.txt"]).unwrap().tokenize);
+        assert!(parse(&["--tokenize", "--stream", "big.txt"]).unwrap().tokenize);
+        assert!(parse(&["--tokenize", "--document", "big.txt"]).is_err());
+        assert!(parse(&["repl", "--tokenize", "big.txt"]).is_err());
+    }
+
+    #[test]
+    fn reads_stats() {
+        assert!(!parse(&["big.txt"]).unwrap().stats);
+        assert!(parse(&["--stats", "--engine", "fst", "big.txt"]).unwrap().stats);
+    }
+
+    #[test]
+    fn reads_corpora() {
+        let options = parse(&["--corpus", "big.txt", "more.txt", "--corpus", "-"]).unwrap();
+        assert_eq!(vec!["big.txt", "more.txt", "-"], options.corpora);
+        assert!(parse(&["--words", "a.txt", "-"]).is_ok());
+        assert!(parse(&["--corpus"]).is_err());
+        assert!(parse(&["-", "--corpus", "-"]).is_err());
+        assert!(parse(&["--context", "-"]).is_err());
+        assert!(parse(&["https://norvig.com/big.txt"]).is_ok());
+        assert!(parse(&["--context", "https://norvig.com/big.txt"]).is_err());
+    }
+
+    #[test]
+    fn reads_word_files() {
+        assert!(parse(&["big.txt"]).unwrap().words.is_empty());
+        let options = parse(&["--words", "a.txt", "big.txt", "more.txt", "--words", "b.txt"])
+            .unwrap();
+        assert_eq!(vec!["big.txt", "more.txt"], options.corpora);
+        assert_eq!(vec!["a.txt", "b.txt"], options.words);
+        let options = parse(&["big.txt", "--", "a.txt", "--list"]).unwrap();
+        assert_eq!(vec!["a.txt", "--list"], options.words);
+        assert!(!options.list);
+        assert!(parse(&["big.txt", "--words"]).is_err());
+        assert!(parse(&["--fix", "big.txt", "--", "a.txt"]).is_err());
+        assert!(parse(&["repl", "--words", "a.txt", "big.txt"]).is_err());
+    }
+
+    #[test]
+    fn reads_language_models() {
+        let options = parse(&["--lang-model", "en=en.model", "--lang-model", "de=de.model"])
+            .unwrap();
+        assert_eq!(vec![("en".to_owned(), "en.model".to_owned()),
+                        ("de".to_owned(), "de.model".to_owned())], options.lang_models);
+        assert_eq!(None, options.lang);
+        assert!(options.corpora.is_empty());
+
+        let options = parse(&["--lang", "de", "--lang-model", "de=de.model"]).unwrap();
+        assert_eq!(Some("de".to_owned()), options.lang);
+        assert_eq!(None, parse(&["--lang", "auto", "--lang-model", "de=d"]).unwrap().lang);
+
+        assert!(parse(&["--lang-model", "de.model"]).is_err());
+        assert!(parse(&["--lang-model", "=de.model"]).is_err());
+        assert!(parse(&["--lang-model", "de=d", "--lang-model", "de=e"]).is_err());
+        assert!(parse(&["--lang", "fr", "--lang-model", "de=de.model"]).is_err());
+        assert!(parse(&["--lang", "de", "big.txt"]).is_err());
+        assert!(parse(&["--lang-model", "de=de.model", "big.txt"]).is_err());
+        assert!(parse(&["--lang-model", "de=de.model", "--fix"]).is_err());
+        assert!(parse(&["--lang-model", "de=de.model", "--save-model", "x"]).is_err());
+        assert!(parse(&["repl", "--lang-model", "de=de.model"]).is_err());
+    }
+
+    #[test]
+    fn reads_bundles() {
+        let options = parse(&["--bundle", "bundles/de", "--lang-model", "en=en.model"]).unwrap();
+        assert_eq!(vec!["bundles/de".to_owned()], options.bundles);
+        assert!(options.corpora.is_empty());
+
+        // Which language a bundle is isn't known until it's read.
+        let options = parse(&["--lang", "de", "--bundle", "bundles/german"]).unwrap();
+        assert_eq!(Some("de".to_owned()), options.lang);
+
+        assert!(parse(&["--bundle"]).is_err());
+        assert!(parse(&["--bundle", "de", "big.txt"]).is_err());
+        assert!(parse(&["--bundle", "de", "--load-model", "en.model"]).is_err());
+        assert!(parse(&["--bundle", "de", "--watch", "draft.md"]).is_err());
+        assert!(parse(&["--bundle", "de", "--dump-dictionary", "x"]).is_err());
+    }
+
+    #[test]
+    fn reads_watch() {
+        assert_eq!(None, parse(&["big.txt"]).unwrap().watch);
+        let options = parse(&["--watch", "draft.md", "--filter", "markdown", "big.txt"]).unwrap();
+        assert_eq!(Some("draft.md".to_owned()), options.watch);
+        assert_eq!(Filter::Markdown, options.filter);
+        assert!(parse(&["big.txt", "--watch"]).is_err());
+        assert!(parse(&["--watch", "draft.md", "--fix", "big.txt"]).is_err());
+        assert!(parse(&["--watch", "draft.md", "--format", "json", "big.txt"]).is_err());
+    }
+
+    #[test]
+    fn reads_fix() {
+        let options = parse(&["--fix", "big.txt"]).unwrap();
+        assert!(options.fix && !options.dry_run);
+        assert!(parse(&["--fix", "--dry-run", "big.txt"]).unwrap().dry_run);
+        assert!(parse(&["--dry-run", "big.txt"]).is_err());
+        assert!(parse(&["--fix", "--document", "big.txt"]).is_err());
+        assert!(parse(&["--fix", "--format", "csv", "big.txt"]).is_err());
+    }
+
+    #[test]
+    fn reads_interactive() {
+        assert_eq!(None, parse(&["big.txt"]).unwrap().interactive);
+        assert_eq!(Some("letter.txt".to_owned()),
+                   parse(&["--interactive", "letter.txt", "big.txt"]).unwrap().interactive);
+        assert!(parse(&["big.txt", "--interactive"]).is_err());
+        assert!(parse(&["--interactive", "letter.txt", "--fix", "big.txt"]).is_err());
+        assert!(parse(&["--interactive", "letter.txt", "--stream", "big.txt"]).is_err());
+    }
+
+    #[test]
+    fn reads_commands() {
+        assert_eq!(Command::Check, parse(&["big.txt"]).unwrap().command);
+        let options = parse(&["repl", "--suggestions", "3", "big.txt"]).unwrap();
+        assert_eq!(Command::Repl, options.command);
+        assert_eq!(vec!["big.txt"], options.corpora);
+        assert_eq!(vec!["big.txt", "repl"], parse(&["big.txt", "repl"]).unwrap().corpora);
+        assert!(parse(&["repl"]).is_err());
+        assert!(parse(&["repl", "--stream", "big.txt"]).is_err());
+    }
+
+    #[test]
+    fn reads_listen() {
+        let options = parse(&["serve", "big.txt"]).unwrap();
+        assert_eq!(Command::Serve, options.command);
+        assert_eq!("127.0.0.1:7000", options.listen);
+        assert_eq!("0.0.0.0:8000",
+                   parse(&["serve", "--listen", "0.0.0.0:8000", "big.txt"]).unwrap().listen);
+        assert!(parse(&["serve", "big.txt", "--listen"]).is_err());
+        assert!(parse(&["--listen", "0.0.0.0:8000", "big.txt"]).is_err());
+        assert!(parse(&["serve", "--fix", "big.txt"]).is_err());
+
+        assert!(!options.http);
+        assert!(parse(&["serve", "--http", "big.txt"]).unwrap().http);
+        assert!(parse(&["--http", "big.txt"]).is_err());
+    }
+
+    #[test]
+    fn reads_lsp() {
+        assert!(!parse(&["big.txt"]).unwrap().lsp);
+        assert!(parse(&["--lsp", "--suggestions", "5", "big.txt"]).unwrap().lsp);
+        assert!(parse(&["--lsp", "--document", "big.txt"]).is_err());
+        assert!(parse(&["repl", "--lsp", "big.txt"]).is_err());
+    }
+
+    #[test]
+    fn reads_phonetic() {
+        assert!(!parse(&["big.txt"]).unwrap().phonetic);
+        assert!(parse(&["--phonetic", "big.txt"]).unwrap().phonetic);
+    }
+
+    #[test]
+    fn rejects_junk() {
+        assert!(parse(&["--verbose"]).is_err());
+        assert!(parse(&["--frobnicate", "big.txt"]).is_err());
+    }
+}
+
+/// What to do once the model is ready, picked with the first argument.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Command {
+    /// Check the standard input (without a command).
+    Check,
+    /// Prompt for words to check (`repl`).
+    Repl,
+    /// Answer queries over TCP (`serve`).
+    Serve,
+}
+
+impl FromStr for Command {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, String> {
+        match s {
+            "repl"  => Ok(Command::Repl),
+            "serve" => Ok(Command::Serve),
+            _ => Err(format!("unknown command `{}' (expected repl or serve)", s)),
+        }
+    }
+}
+
+impl fmt::Display for Command {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match *self {
+            Command::Check => "check",
+            Command::Repl  => "repl",
+            Command::Serve => "serve",
+        })
+    }
+}
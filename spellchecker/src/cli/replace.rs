@@ -0,0 +1,94 @@
+/*!
+Writing a file over another: what --interactive and --append save goes
+to a new file beside the old one first, renamed over it only once it is
+all written, so a failure part way leaves the old one as it was.
+*/
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Writes the file at `path` anew with what `write` writes to it, by way
+/// of a file of a name no one else has in the same directory, with the
+/// permissions of the file it replaces, if there is one.
+pub(crate) fn replace_file<F>(path: &Path, write: F) -> io::Result<()>
+    where F: FnOnce(&mut File) -> io::Result<()>
+{
+    let permissions = match fs::metadata(path) {
+        Ok(metadata) => Some(metadata.permissions()),
+        Err(ref e) if e.kind() == io::ErrorKind::NotFound => None,
+        Err(e) => return Err(e),
+    };
+    let (temp, mut file) = create_temp(path)?;
+    let written = permissions.map_or(Ok(()), |permissions| file.set_permissions(permissions))
+        .and_then(|()| write(&mut file))
+        .and_then(|()| file.flush())
+        .and_then(|()| file.sync_all())
+        .and_then(|()| fs::rename(&temp, path));
+    if written.is_err() {
+        let _ = fs::remove_file(&temp);
+    }
+    written
+}
+
+/// A new file beside `path`, and its name.
+fn create_temp(path: &Path) -> io::Result<(PathBuf, File)> {
+    static CREATED: AtomicUsize = AtomicUsize::new(0);
+
+    let name = path.file_name()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "not a file name"))?;
+    loop {
+        let temp = path.with_file_name(format!(".{}.{}-{}.tmp", name.to_string_lossy(),
+                                               process::id(),
+                                               CREATED.fetch_add(1, Ordering::Relaxed)));
+        match OpenOptions::new().write(true).create_new(true).open(&temp) {
+            Ok(file) => return Ok((temp, file)),
+            Err(ref e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod replace_file_tests {
+    use super::replace_file;
+    use std::env;
+    use std::fs;
+    use std::io::{self, Write};
+    use std::process;
+
+    #[test]
+    fn replaces_and_leaves_nothing_behind() {
+        let dir = env::temp_dir().join(format!("spellchecker-replace-{}", process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("words.txt");
+        fs::write(&path, "old").unwrap();
+
+        replace_file(&path, |file| file.write_all(b"new")).unwrap();
+        assert_eq!("new", fs::read_to_string(&path).unwrap());
+
+        let err = replace_file(&path, |_| Err(io::Error::other("full"))).unwrap_err();
+        assert_eq!("full", err.to_string());
+        assert_eq!("new", fs::read_to_string(&path).unwrap());
+        assert_eq!(1, fs::read_dir(&dir).unwrap().count());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn keeps_the_permissions() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = env::temp_dir().join(format!("spellchecker-mode-{}", process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("words.txt");
+        fs::write(&path, "old").unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o640)).unwrap();
+
+        replace_file(&path, |file| file.write_all(b"new")).unwrap();
+        assert_eq!(0o640, fs::metadata(&path).unwrap().permissions().mode() & 0o777);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,382 @@
+/*!
+The serve command: a line of words answered with their results for
+each client, or, with --http, a `POST /check` of a JSON list of words
+answered with theirs.
+*/
+
+use std::collections::HashSet;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::TcpListener;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use spellchecker::{fold_word, Profile, SpellChecker};
+
+use cli::format::{format_header, json_result, Format};
+use cli::interactive::check_line;
+use cli::json::{json_string, Json};
+use cli::words::check_run;
+
+/// Answers the queries of every client that connects to `listener`,
+/// each on its own thread, for as long as the process runs; over HTTP if
+/// `http`, giving up on a client that sends nothing for `HTTP_TIMEOUT`,
+/// or else for `QUERY_TIMEOUT`.
+/// The threads all query the one checker, which needs no lock.
+/// Each keeps a `Profile` of its own queries, and adds it to the totals
+/// when its client is done; with `stats`, the totals so far are then
+/// written to stderr.
+pub(crate) fn serve(checker: &Arc<SpellChecker>, listener: TcpListener, n: usize, format: Format,
+                    http: bool, stats: bool) {
+    let totals = Arc::new(Mutex::new(Profile::default()));
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(e) => {
+                eprintln!("spellchecker: {}", e);
+                continue;
+            }
+        };
+        let checker = Arc::clone(checker);
+        let totals = Arc::clone(&totals);
+        thread::spawn(move || {
+            let peer = stream.peer_addr().map(|addr| addr.to_string()).unwrap_or_default();
+            let mut profile = Profile::default();
+            let answered = if http {
+                stream.set_read_timeout(Some(HTTP_TIMEOUT))
+                    .and_then(|_| answer_http(&checker, n, &stream, &stream, &mut profile))
+            } else {
+                stream.set_read_timeout(Some(QUERY_TIMEOUT))
+                    .and_then(|_| {
+                        answer_queries(&checker, n, format, &stream, &stream, &mut profile)
+                    })
+            };
+            if let Err(e) = answered {
+                eprintln!("spellchecker: {}: {}", peer, e);
+            }
+            let mut totals = totals.lock().unwrap_or_else(|e| e.into_inner());
+            totals.merge(&profile);
+            if stats {
+                eprint!("stats:\n{}", totals);
+            }
+        });
+    }
+}
+
+/// The longest line of words `serve` reads.
+const MAX_LINE: usize = 64 << 10;
+
+/// How long `serve` waits for any read of a line of words.
+const QUERY_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Answers each line of words on `reader` with their results and an
+/// empty line, flushing after each, until the end of the input. A line
+/// longer than `MAX_LINE` is answered with an error, and ends the input.
+fn answer_queries<R: Read, W: Write>(checker: &SpellChecker, n: usize, format: Format, reader: R,
+                                     mut writer: W, profile: &mut Profile) -> io::Result<()> {
+    let none = HashSet::new();
+
+    if let Some(header) = format_header(format) {
+        writer.write_all(header.as_bytes())?;
+    }
+    let mut reader = BufReader::new(reader);
+    loop {
+        let mut line = Vec::new();
+        reader.by_ref().take(MAX_LINE as u64 + 1).read_until(b'\n', &mut line)?;
+        if line.is_empty() {
+            break;
+        }
+        if line.last() == Some(&b'\n') {
+            line.pop();
+            if line.last() == Some(&b'\r') {
+                line.pop();
+            }
+        } else if line.len() > MAX_LINE {
+            writer.write_all(format!("error: line longer than {} bytes\n\n", MAX_LINE)
+                                 .as_bytes())?;
+            writer.flush()?;
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "line too long"));
+        }
+        let line = String::from_utf8(line)
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "line isn't UTF-8"))?;
+        let answer = check_line(checker, &line, n, format, &none, profile);
+        writer.write_all(answer.as_bytes())?;
+        writer.write_all(b"\n")?;
+        writer.flush()?;
+    }
+
+    Ok(())
+}
+
+/// The largest request body `serve --http` reads.
+const MAX_BODY: usize = 1 << 20;
+
+/// The most bytes `serve --http` reads of a request line and headers.
+const MAX_HEAD: usize = 8 << 10;
+
+/// How long `serve --http` waits for any read of a request.
+const HTTP_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Answers the HTTP request on `reader`, reading no more than `MAX_HEAD`
+/// bytes of its request line and headers.
+fn answer_http<R: Read, W: Write>(checker: &SpellChecker, n: usize, reader: R, mut writer: W,
+                                  profile: &mut Profile) -> io::Result<()> {
+    let mut reader = BufReader::new(reader);
+    let mut head = reader.by_ref().take(MAX_HEAD as u64);
+    let mut request = String::new();
+    head.read_line(&mut request)?;
+
+    let mut length: Option<usize> = None;
+    let mut ended = false;
+    loop {
+        let mut header = String::new();
+        if head.read_line(&mut header)? == 0 {
+            break;
+        }
+        if header.trim().is_empty() {
+            ended = true;
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.trim().eq_ignore_ascii_case("content-length") {
+                length = value.trim().parse().ok();
+            }
+        }
+    }
+    let too_large = !ended && head.limit() == 0;
+
+    let mut fields = request.split_whitespace();
+    let (status, json) = match (fields.next(), fields.next(), length) {
+        _ if too_large => {
+            ("431 Request Header Fields Too Large", json_error("headers too large"))
+        }
+        (Some("POST"), Some("/check"), None) => ("411 Length Required", json_error("no length")),
+        (Some("POST"), Some("/check"), Some(length)) if length > MAX_BODY => {
+            ("413 Payload Too Large", json_error("request too large"))
+        }
+        (Some("POST"), Some("/check"), Some(length)) => {
+            let mut body = vec![0; length];
+            reader.read_exact(&mut body)?;
+            let request = String::from_utf8(body)
+                .map_err(|_| "request isn't UTF-8".to_owned())
+                .and_then(|body| read_check_request(&body));
+            match request {
+                Ok((words, suggestions)) => {
+                    let n = suggestions.unwrap_or(n);
+                    ("200 OK", check_response(checker, &words, n, profile))
+                }
+                Err(e) => ("400 Bad Request", json_error(&e)),
+            }
+        }
+        (Some(_), Some("/check"), _) => ("405 Method Not Allowed", json_error("use POST")),
+        _ => ("404 Not Found", json_error("not found")),
+    };
+
+    let allow = if status.starts_with("405") { "Allow: POST\r\n" } else { "" };
+    write!(writer, "HTTP/1.1 {}\r\n{}Content-Type: application/json\r\nContent-Length: {}\r\n\
+                    Connection: close\r\n\r\n{}\n",
+           status, allow, json.len() + 1, json)?;
+    writer.flush()
+}
+
+/// The JSON answer to a `POST /check` of `words`, as running text.
+fn check_response(checker: &SpellChecker, words: &[String], n: usize, profile: &mut Profile)
+                  -> String {
+    let words: Vec<String> = words.iter().map(|word| fold_word(word.trim())).collect();
+    let results: Vec<_> = check_run(checker, None, &words, n, profile).iter().zip(&words)
+        .map(|(result, word)| json_result(word, result))
+        .collect();
+    format!("{{\"results\":[{}]}}", results.join(","))
+}
+
+/// A JSON object with the error `message`.
+fn json_error(message: &str) -> String {
+    format!("{{\"error\":{}}}", json_string(message))
+}
+
+/// Reads the body of a `POST /check`: a JSON object with the words to
+/// check in "words", and optionally how many suggestions to give for
+/// each in "suggestions". Other fields are ignored.
+fn read_check_request(body: &str) -> Result<(Vec<String>, Option<usize>), String> {
+    let request = Json::parse(body)?;
+    if !matches!(request, Json::Object(_)) {
+        return Err("expected an object".to_owned());
+    }
+
+    let words = request.get("words").ok_or("no \"words\" given")?
+        .as_array().ok_or("expected a list of words")?
+        .iter()
+        .map(|word| word.as_str().map(|word| word.to_owned()).ok_or("expected a word"))
+        .collect::<Result<_, _>>()?;
+    let suggestions = match request.get("suggestions") {
+        None => None,
+        Some(&Json::Number(n)) if n >= 1.0 && n.fract() == 0.0 => Some(n as usize),
+        Some(_) => return Err("expected a positive count of suggestions".to_owned()),
+    };
+
+    Ok((words, suggestions))
+}
+
+#[cfg(test)]
+mod answer_http_tests {
+    use super::{answer_http, read_check_request, MAX_BODY, MAX_HEAD};
+    use spellchecker::{Profile, SpellChecker, Trie};
+
+    #[test]
+    fn checks_words() {
+        let body = r#"{"words": ["Wrold", "hello"], "suggestions": 2}"#;
+        let (status, json) = request(&format!("POST /check HTTP/1.1\r\nHost: localhost\r\n\
+                                               content-length: {}\r\n\r\n{}", body.len(), body));
+
+        assert_eq!("HTTP/1.1 200 OK", status);
+        assert_eq!("{\"results\":[{\"word\":\"wrold\",\"status\":\"corrected\",\
+                    \"suggestion\":\"world\",\"distance\":1,\"count\":2,\"confidence\":0.992,\
+                    \"suggestions\":[{\"word\":\"world\",\"distance\":1,\"count\":2,\
+                    \"confidence\":0.992},{\"word\":\"would\",\"distance\":2,\"count\":1,\
+                    \"confidence\":0.008}]},{\"word\":\"hello\",\"status\":\"correct\"}]}\n",
+                   json);
+    }
+
+    #[test]
+    fn refuses_other_requests() {
+        assert_eq!("HTTP/1.1 404 Not Found", request("GET / HTTP/1.1\r\n\r\n").0);
+        assert_eq!("HTTP/1.1 405 Method Not Allowed", request("GET /check HTTP/1.1\r\n\r\n").0);
+        assert_eq!("HTTP/1.1 411 Length Required", request("POST /check HTTP/1.1\r\n\r\n").0);
+        assert_eq!("HTTP/1.1 413 Payload Too Large",
+                   request("POST /check HTTP/1.1\r\nContent-Length: 99999999\r\n\r\n").0);
+        let endless = format!("POST /check HTTP/1.1\r\nX-Junk: {}", "x".repeat(MAX_HEAD));
+        assert_eq!("HTTP/1.1 431 Request Header Fields Too Large", request(&endless).0);
+        let endless = format!("POST /{} HTTP/1.1\r\n\r\n", "x".repeat(MAX_HEAD));
+        assert_eq!("HTTP/1.1 431 Request Header Fields Too Large", request(&endless).0);
+
+        let (status, json) = request("POST /check HTTP/1.1\r\nContent-Length: 2\r\n\r\n[]");
+        assert_eq!("HTTP/1.1 400 Bad Request", status);
+        assert_eq!("{\"error\":\"expected an object\"}\n", json);
+
+        let body = "[".repeat(MAX_BODY);
+        let (status, json) = request(&format!("POST /check HTTP/1.1\r\nContent-Length: {}\r\n\r\n\
+                                               {}", body.len(), body));
+        assert_eq!("HTTP/1.1 400 Bad Request", status);
+        assert_eq!("{\"error\":\"nested more than 128 deep\"}\n", json);
+    }
+
+    #[test]
+    fn reads_requests() {
+        assert_eq!(Ok((vec!["a".to_owned(), "b\"c".to_owned()], None)),
+                   read_check_request(r#" { "words" : [ "a" , "b\"c" ] } "#));
+        assert_eq!(Ok((vec!["é".to_owned()], Some(3))),
+                   read_check_request(r#"{"id": {"x": [1, "}"]}, "words": ["\u00e9"],
+                                          "suggestions": 3}"#));
+        assert_eq!(Ok((Vec::new(), None)), read_check_request(r#"{"words": []}"#));
+
+        assert!(read_check_request("{}").is_err());
+        assert!(read_check_request(r#"{"words": "hello"}"#).is_err());
+        assert!(read_check_request(r#"{"words": ["a"], "suggestions": 0}"#).is_err());
+        assert!(read_check_request(r#"{"words": ["a"]} x"#).is_err());
+        assert!(read_check_request(r#"{"words": ["a"#).is_err());
+    }
+
+    fn request(request: &str) -> (String, String) {
+        let mut t = Trie::new();
+        for word in &["hello", "world", "world", "would"] {
+            t.insert(word);
+        }
+        let mut output = Vec::new();
+        answer_http(&SpellChecker::new(t), 1, request.as_bytes(), &mut output,
+                    &mut Profile::default()).unwrap();
+
+        let output = String::from_utf8(output).unwrap();
+        let (head, body) = output.split_once("\r\n\r\n").unwrap();
+        let status = head.lines().next().unwrap().to_owned();
+        assert!(head.contains(&format!("Content-Length: {}\r\n", body.len())));
+        (status, body.to_owned())
+    }
+}
+
+#[cfg(test)]
+mod serve_tests {
+    use super::{answer_queries, serve, Format, MAX_LINE};
+    use spellchecker::{Profile, SpellChecker, Trie};
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::{TcpListener, TcpStream};
+    use std::sync::Arc;
+    use std::thread;
+
+    #[test]
+    fn answers_each_query() {
+        let mut output = Vec::new();
+        answer_queries(&fixture(), 1, Format::Text, &b"wrold\nHelo, world\n\n"[..], &mut output,
+                       &mut Profile::default()).unwrap();
+
+        assert_eq!("wrold, world\n\nhelo, hello\nworld\n\n\n",
+                   String::from_utf8(output).unwrap());
+    }
+
+    #[test]
+    fn refuses_endless_lines() {
+        let input = format!("helo\n{}\nwrold\n", "x".repeat(MAX_LINE + 1));
+        let mut output = Vec::new();
+        let err = answer_queries(&fixture(), 1, Format::Text, input.as_bytes(), &mut output,
+                                 &mut Profile::default()).unwrap_err();
+
+        assert_eq!("line too long", err.to_string());
+        assert_eq!(format!("helo, hello\n\nerror: line longer than {} bytes\n\n", MAX_LINE),
+                   String::from_utf8(output).unwrap());
+
+        let mut output = Vec::new();
+        let longest = format!("{}\n", "x".repeat(MAX_LINE));
+        answer_queries(&fixture(), 1, Format::Text, longest.as_bytes(), &mut output,
+                       &mut Profile::default()).unwrap();
+    }
+
+    #[test]
+    fn serves_several_clients() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let checker = Arc::new(fixture());
+        thread::spawn(move || serve(&checker, listener, 1, Format::Text, false, false));
+
+        let clients: Vec<_> = (0..4).map(|_| TcpStream::connect(address).unwrap()).collect();
+        for mut client in clients {
+            client.write_all(b"helo\n").unwrap();
+            let mut answer = String::new();
+            BufReader::new(client).read_line(&mut answer).unwrap();
+            assert_eq!("helo, hello\n", answer);
+        }
+    }
+
+    #[test]
+    fn answers_clients_at_once() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let checker = Arc::new(fixture());
+        thread::spawn(move || serve(&checker, listener, 1, Format::Text, false, false));
+
+        let clients: Vec<_> = (0..8)
+            .map(|_| {
+                thread::spawn(move || {
+                    let mut client = TcpStream::connect(address).unwrap();
+                    let mut answers = BufReader::new(client.try_clone().unwrap());
+                    for _ in 0..100 {
+                        client.write_all(b"helo wrold\n").unwrap();
+                        let mut answer = String::new();
+                        for _ in 0..3 {
+                            answers.read_line(&mut answer).unwrap();
+                        }
+                        assert_eq!("helo, hello\nwrold, world\n\n", answer);
+                    }
+                })
+            })
+            .collect();
+        for client in clients {
+            client.join().unwrap();
+        }
+    }
+
+    fn fixture() -> SpellChecker {
+        let mut t = Trie::new();
+        t.insert("hello");
+        t.insert("world");
+        SpellChecker::new(t)
+    }
+}
@@ -0,0 +1,296 @@
+/*!
+Getting the checkers ready: the model trained, loaded or mapped as the
+options say, prepared and saved, and a checker made of it with the
+options it is checked with.
+*/
+
+use std::collections::HashSet;
+use std::env;
+use std::io::{self, stdin, BufWriter};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use regex::Regex;
+
+use spellchecker::{corpus_files, fold_diacritics, is_mappable, is_url, load_abbreviations,
+                   load_costs, load_dictionary, load_freq, load_hunspell, load_model, map_model,
+                   save_model, save_transducer, train_bigrams_from_file, train_from_file,
+                   train_from_reader, train_from_url, Bigrams, Bundle, Engine, Hyphens,
+                   Punctuation, SpellChecker, Transducer, Trie};
+use textkit::progress;
+use textkit::timings::Timings;
+
+use cli::options::Options;
+use cli::replace::replace_file;
+use fail;
+
+/// Where the personal dictionary is kept when --personal doesn't say:
+/// spellchecker/personal.dict in the user's configuration directory.
+pub(crate) fn default_personal() -> Option<PathBuf> {
+    let config = env::var_os("XDG_CONFIG_HOME").filter(|dir| !dir.is_empty()).map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".config")))?;
+    Some(config.join("spellchecker").join("personal.dict"))
+}
+
+/// `model` with its words trimmed of the punctuation of --punctuation
+/// and the `bundle` it came from, if any, its hyphenated words and
+/// diacritics as --hyphens and --fold-diacritics say, and its rare and
+/// long words pruned as --prune-below, --max-vocab and --max-word-len
+/// say.
+pub(crate) fn prepare_model(model: Trie, options: &Options, bundle: Option<&Bundle>,
+                            timings: &mut Timings) -> Trie {
+    let model = punctuation(options, bundle).apply(model);
+    let model = options.hyphens.apply(model);
+    let mut model = if options.fold_diacritics { fold_diacritics(model) } else { model };
+    if let Some(max) = options.max_word_len {
+        let dropped = timings.time("prune", || model.drop_longer_than(max));
+        if dropped > 0 && !options.quiet {
+            eprintln!("spellchecker: dropped {} words longer than --max-word-len", dropped);
+        }
+    }
+    if options.prune_below > 1 || options.max_vocab.is_some() {
+        let words = model.stats().words;
+        let mut dropped = 0;
+        if options.prune_below > 1 {
+            dropped += timings.time("prune", || model.prune(options.prune_below));
+        }
+        if let Some(max) = options.max_vocab {
+            dropped += timings.time("prune", || model.keep_commonest(max));
+        }
+        if !options.quiet {
+            eprintln!("spellchecker: pruned {} of {} words", dropped, words);
+        }
+    }
+    model
+}
+
+/// A checker of `model` as the options say, with `context`, the words of
+/// the personal dictionary and the keyboard and punctuation of the
+/// `bundle` it came from, if there are any.
+pub(crate) fn build_checker(mut model: Trie, context: Option<Bigrams>,
+                            personal: Option<HashSet<String>>, bundle: Option<&Bundle>,
+                            options: &Options, timings: &mut Timings) -> SpellChecker {
+    if options.min_count > 1 {
+        timings.time("prune", || model.prune(options.min_count));
+    }
+    let checker = timings.time("index", || SpellChecker::with_engine(model, options.engine));
+    configure_checker(checker, context, personal, bundle, options, timings)
+}
+
+/// The model saved at `path` mapped into memory, if it is a transducer,
+/// --engine fst is to search it, and the options leave it as it is.
+pub(crate) fn mapped_model(path: &str, options: &Options, timings: &mut Timings)
+                           -> Option<Transducer> {
+    let as_is = options.engine == Engine::Fst && options.hyphens == Hyphens::Keep
+        && options.punctuation.is_empty() && !options.fold_diacritics
+        && options.max_word_len.is_none() && options.prune_below <= 1
+        && options.max_vocab.is_none() && options.min_count <= 1
+        && options.save_model.is_none() && options.dump_dictionary.is_none();
+    if !as_is || !is_mappable(path).unwrap_or_else(|e| fail(format_args!("{}: {}", path, e))) {
+        return None;
+    }
+    Some(timings.time("load", || map_model(path))
+        .unwrap_or_else(|e| fail(format_args!("{}: {}", path, e))))
+}
+
+/// `checker` with everything the options say besides its model and
+/// engine, as `build_checker` has it.
+pub(crate) fn configure_checker(mut checker: SpellChecker, context: Option<Bigrams>,
+                                personal: Option<HashSet<String>>, bundle: Option<&Bundle>,
+                                options: &Options, timings: &mut Timings) -> SpellChecker {
+    let costs = match options.costs {
+        Some(ref path) => {
+            Some(load_costs(path).unwrap_or_else(|e| fail(format_args!("{}: {}", path, e))))
+        }
+        None => options.mode.costs(),
+    };
+    // --costs and --mode say how to weigh edits over the keyboard.
+    let keyboard = bundle.and_then(|bundle| bundle.keyboard.clone()).filter(|_| costs.is_none());
+    if keyboard.is_some()
+        && (options.engine != Engine::Trie || options.beam.is_some()
+            || options.budget_ms.is_some() || options.adaptive_edits) {
+        fail(format_args!("the keyboard of {} weighs edits, which can't be done with --engine \
+                           {}, --beam, --budget-ms or --adaptive-edits",
+                          bundle.map_or("", |bundle| &bundle.language), options.engine));
+    }
+    if let Some(costs) = costs.or(keyboard) {
+        checker = checker.with_costs(costs);
+    }
+    checker = checker.with_ranking(options.ranking).with_smoothing(options.smoothing);
+    checker = checker.with_case_policies(options.capitalized, options.acronyms);
+    checker = checker.with_numbers(options.numbers);
+    checker = checker.with_hyphens(options.hyphens);
+    checker = checker.with_punctuation(punctuation(options, bundle));
+    if options.fold_diacritics {
+        checker = checker.with_diacritic_folding();
+    }
+    if options.adaptive_edits {
+        checker = checker.with_adaptive_edits();
+    }
+    if !options.ignore.is_empty() {
+        let patterns = options.ignore.iter()
+            .map(|pattern| Regex::new(pattern).expect("checked by parse_args"))
+            .collect();
+        checker = checker.with_ignore(patterns);
+    }
+    if let Some(words) = personal {
+        checker = checker.with_personal(words);
+    }
+    if let Some(ref path) = options.abbreviations {
+        let abbreviations = load_abbreviations(path)
+            .unwrap_or_else(|e| fail(format_args!("{}: {}", path, e)));
+        checker = checker.with_abbreviations(abbreviations);
+    }
+    if let Some(bigrams) = context {
+        checker = checker.with_context(bigrams);
+    }
+    if options.real_words {
+        checker = checker.with_real_words(options.threshold);
+    }
+    if options.split_compounds {
+        checker = checker.with_compound_splits();
+    }
+    if options.accept_inflections {
+        checker = checker.with_inflections();
+    }
+    if options.join_words {
+        checker = checker.with_word_joins();
+    }
+    if options.phonetic {
+        checker = timings.time("index", || checker.with_phonetic());
+    }
+    if let Some(width) = options.beam {
+        checker = timings.time("index", || checker.with_beam(width));
+    }
+    if let Some(ms) = options.budget_ms {
+        checker = checker.with_budget(Duration::from_millis(ms));
+    }
+    if let Some(max) = options.max_word_len {
+        checker = checker.with_max_word_len(max);
+    }
+    if options.minimize {
+        let before = checker.stats();
+        checker = timings.time("minimize", || checker.minimize());
+        if !options.quiet {
+            eprintln!("spellchecker: minimized {} nodes to {}", before.nodes,
+                      checker.stats().nodes);
+        }
+    }
+    checker
+}
+
+/// The punctuation of --punctuation and of `bundle`, if there is one.
+fn punctuation(options: &Options, bundle: Option<&Bundle>) -> Punctuation {
+    let bundled = bundle.map_or(&[][..], |bundle| &bundle.punctuation[..]);
+    Punctuation::new(options.punctuation.iter().chain(bundled).cloned().collect())
+}
+
+/// Trains the model, or loads it with --load-model, --dictionary,
+/// --from-freq or --hunspell, exiting on failure.
+pub(crate) fn get_model(options: &Options, timings: &mut Timings) -> Trie {
+    let sources = (&options.load_model, &options.dictionary, &options.from_freq,
+                   &options.hunspell);
+    let (path, model) = match sources {
+        (Some(path), _, _, _) => (path, timings.time("load", || load_model(path))),
+        (_, Some(path), _, _) => {
+            (path, timings.time("load", || load_dictionary(path, options.encoding)))
+        }
+        (_, _, Some(path), _) => {
+            (path, timings.time("load", || load_freq(path, options.encoding)))
+        }
+        (_, _, _, Some(path)) => (path, timings.time("load", || load_hunspell(path))),
+        _ => {
+            let model = train_corpora(options, timings);
+            match options.append {
+                Some(ref path) => (path, timings.time("load", || load_model(path)).map(|mut saved| {
+                    timings.time("merge", || saved.merge(model));
+                    saved
+                })),
+                None => return model,
+            }
+        }
+    };
+
+    model.unwrap_or_else(|e| fail(format_args!("{}: {}", path, e)))
+}
+
+/// Saves `model` to `path`, as the transducer it makes with --engine fst.
+pub(crate) fn save(model: &Trie, path: &str, options: &Options) -> io::Result<()> {
+    if options.engine == Engine::Fst {
+        save_transducer(&Transducer::from_trie(model), path)
+    } else {
+        save_model(model, path)
+    }
+}
+
+/// Saves `model` over the model file at `path`, by way of a temporary file.
+pub(crate) fn replace_model(model: &Trie, path: &str, options: &Options) -> io::Result<()> {
+    replace_file(Path::new(path), |file| {
+        if options.engine == Engine::Fst {
+            Transducer::from_trie(model).save(BufWriter::new(file))
+        } else {
+            model.save(BufWriter::new(file))
+        }
+    })
+}
+
+/// Trains one model on every corpus file, the standard input if a corpus
+/// is -, and the corpora at URLs, exiting on failure.
+fn train_corpora(options: &Options, timings: &mut Timings) -> Trie {
+    let mut files = Vec::new();
+    for corpus in &options.corpora {
+        if corpus == "-" || is_url(corpus) {
+            files.push(PathBuf::from(corpus));
+        } else {
+            files.extend(corpus_files(&[corpus], &options.glob, options.progress)
+                .unwrap_or_else(|e| fail(format_args!("{}", e))));
+        }
+    }
+    if files.is_empty() {
+        fail(format_args!("no corpus files found"));
+    }
+
+    let mut model: Option<Trie> = None;
+    for file in &files {
+        let part = match file.to_str() {
+            Some("-") => {
+                let stdin = stdin();
+                train_from_reader(stdin.lock(), progress::stdin_len(), options.encoding,
+                                  options.progress, options.jobs, timings)
+            }
+            Some(url) if is_url(url) => {
+                train_from_url(url, options.encoding, options.progress, options.jobs, timings)
+            }
+            _ => train_from_file(file, options.encoding, options.progress, options.jobs, timings),
+        };
+        let part = part.unwrap_or_else(|e| fail(format_args!("{}: {}", file.display(), e)));
+        if options.verbose {
+            let words: usize = part.iter().map(|(_, count)| count).sum();
+            eprintln!("spellchecker: {}: {} words", file.display(), words);
+        }
+
+        model = Some(match model {
+            None => part,
+            Some(mut model) => {
+                timings.time("merge", || model.merge(part));
+                model
+            }
+        });
+    }
+
+    model.expect("corpus files")
+}
+
+/// Counts the word pairs of every corpus file, exiting on failure.
+pub(crate) fn train_context(options: &Options, timings: &mut Timings) -> Bigrams {
+    let files = corpus_files(&options.corpora, &options.glob, options.progress)
+        .unwrap_or_else(|e| fail(format_args!("{}", e)));
+
+    let mut bigrams = Bigrams::new();
+    for file in &files {
+        let part = timings.time("context", || train_bigrams_from_file(file, options.encoding))
+            .unwrap_or_else(|e| fail(format_args!("{}: {}", file.display(), e)));
+        timings.time("merge", || bigrams.merge(part));
+    }
+    bigrams
+}
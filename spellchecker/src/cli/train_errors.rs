@@ -0,0 +1,101 @@
+/*!
+spellchecker train-errors: learning the costs of edits from a log of
+corrections, written as a --costs file.
+*/
+
+use std::fs;
+use std::io::{stdin, stdout, Write};
+use std::process::exit;
+
+use spellchecker::{load_errors, read_errors, Confusion, Encoding};
+use textkit::encoding::Decoder;
+
+use {fail, USAGE};
+
+/// Runs `spellchecker train-errors`, with the arguments after it, and
+/// exits.
+pub(crate) fn train_errors<I: Iterator<Item = String>>(args: I) -> ! {
+    let options = parse_train_errors_args(args).unwrap_or_else(|msg| {
+        eprintln!("spellchecker: {}", msg);
+        eprintln!("{}", USAGE);
+        exit(2);
+    });
+
+    let mut confusion = Confusion::default();
+    if options.logs.is_empty() {
+        confusion = read_errors(Decoder::new(stdin(), options.encoding))
+            .unwrap_or_else(|e| fail(format_args!("{}", e)));
+    }
+    for path in &options.logs {
+        let log = load_errors(path, options.encoding)
+            .unwrap_or_else(|e| fail(format_args!("{}: {}", path, e)));
+        confusion.merge(log);
+    }
+
+    let costs = confusion.costs().to_string();
+    match options.output {
+        Some(ref path) => {
+            fs::write(path, costs).unwrap_or_else(|e| fail(format_args!("{}: {}", path, e)));
+        }
+        None => {
+            stdout().write_all(costs.as_bytes()).unwrap_or_else(|e| fail(format_args!("{}", e)));
+        }
+    }
+    let plural = |n| if n == 1 { "" } else { "s" };
+    eprintln!("spellchecker: {} correction{}: {} insertion{}, {} deletion{}, {} replacement{}, \
+               {} transposition{}", confusion.corrections, plural(confusion.corrections),
+              confusion.insert, plural(confusion.insert), confusion.delete,
+              plural(confusion.delete), confusion.replace, plural(confusion.replace),
+              confusion.transpose, plural(confusion.transpose));
+    exit(0);
+}
+
+#[derive(Debug, PartialEq)]
+struct TrainErrorsOptions {
+    logs:     Vec<String>,
+    encoding: Encoding,
+    output:   Option<String>,
+}
+
+fn parse_train_errors_args<I: Iterator<Item = String>>(mut args: I)
+                                                      -> Result<TrainErrorsOptions, String> {
+    let mut options =
+        TrainErrorsOptions { logs: Vec::new(), encoding: Encoding::Auto, output: None };
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--encoding" => {
+                let value = args.next().ok_or("--encoding needs a value")?;
+                options.encoding = value.parse()?;
+            }
+            "--output" => options.output = Some(args.next().ok_or("--output needs a file")?),
+            _ if arg.starts_with("--") => {
+                return Err(format!("unknown option `{}' for train-errors", arg));
+            }
+            _ => options.logs.push(arg),
+        }
+    }
+
+    Ok(options)
+}
+
+#[cfg(test)]
+mod train_errors_tests {
+    use super::parse_train_errors_args;
+    use spellchecker::Encoding;
+
+    #[test]
+    fn reads_options() {
+        let parse = |args: &[&str]| parse_train_errors_args(args.iter().map(|s| s.to_string()));
+
+        let options = parse(&[]).unwrap();
+        assert_eq!((Vec::<String>::new(), Encoding::Auto, None),
+                   (options.logs, options.encoding, options.output));
+        let options = parse(&["--output", "typing.toml", "a.log", "b.log"]).unwrap();
+        assert_eq!(vec!["a.log", "b.log"], options.logs);
+        assert_eq!(Some("typing.toml".to_owned()), options.output);
+        assert_eq!(Encoding::Latin1, parse(&["--encoding", "latin1"]).unwrap().encoding);
+        assert!(parse(&["--output"]).is_err());
+        assert!(parse(&["--costs", "typing.toml"]).is_err());
+    }
+}
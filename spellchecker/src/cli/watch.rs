@@ -0,0 +1,130 @@
+/*!
+--watch: checking a file again each time it changes, reporting what
+changed since the last check.
+*/
+
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{self, Read, Write};
+use std::path::Path;
+use std::sync::mpsc;
+use std::time::Duration;
+
+use notify::event::ModifyKind;
+use notify::{EventKind, RecursiveMode, Watcher};
+
+use spellchecker::{Encoding, Filter, Languages, Profile};
+use textkit::encoding::Decoder;
+use textkit::timings::Timings;
+
+use cli::document::check_document;
+
+/// How long to wait after a change to a watched file for more, so that a
+/// file saved in several writes is checked once, when it is whole.
+const SETTLE: Duration = Duration::from_millis(100);
+
+/// Checks the file at `path` as `check_document` does, and again every
+/// time it changes, writing what `watch_delta` makes of each check, until
+/// the file can no longer be watched.
+pub(crate) fn watch_file<W: Write>(languages: &Languages, path: &str, filter: Filter, n: usize,
+                                   encoding: Encoding, writer: &mut W) -> io::Result<()> {
+    let path = Path::new(path);
+    fs::metadata(path)?;
+    // Editors often save a file by writing another and renaming it over
+    // the first, so its directory is watched rather than the file itself.
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    let (sender, events) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(sender).map_err(io::Error::other)?;
+    watcher.watch(dir, RecursiveMode::NonRecursive).map_err(io::Error::other)?;
+    let mut seen = HashMap::new();
+
+    loop {
+        let mut text = String::new();
+        let read = File::open(path)
+            .and_then(|file| Decoder::new(file, encoding).read_to_string(&mut text));
+        match read {
+            Ok(_) => {
+                let mut report = Vec::new();
+                check_document(languages, text.as_bytes(), filter, false, n, &mut report,
+                               &mut Timings::default(), &mut Profile::default())?;
+                let report = String::from_utf8(report).expect("reports are UTF-8");
+                writer.write_all(watch_delta(&mut seen, &path.display().to_string(), &report)
+                                 .as_bytes())?;
+                writer.flush()?;
+            }
+            // It is gone for a moment while it is being replaced.
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e),
+        }
+
+        loop {
+            let event = match events.recv() {
+                Ok(Ok(event)) => event,
+                Ok(Err(e)) => return Err(io::Error::other(e)),
+                Err(_) => return Ok(()),
+            };
+            let changed = match event.kind {
+                EventKind::Modify(ModifyKind::Metadata(_)) => false,
+                EventKind::Create(_) | EventKind::Modify(_) => true,
+                _ => false,
+            };
+            if changed && event.paths.iter().any(|other| other.file_name() == path.file_name()) {
+                break;
+            }
+        }
+        while events.recv_timeout(SETTLE).is_ok() {}
+    }
+}
+
+/// The line `watch_file` writes for the misspellings of the file `name`
+/// that `report` lists, as `check_document` writes them: how many are
+/// new since those `seen` last time, how many of those are gone, and how
+/// many are left, followed by the new ones. `seen` is then updated.
+/// Misspellings are told apart by what is reported of them other than
+/// where they are, and the occurrences of one beyond as many as were seen
+/// are the new ones.
+fn watch_delta(seen: &mut HashMap<String, usize>, name: &str, report: &str) -> String {
+    let mut now: HashMap<String, usize> = HashMap::new();
+    let mut new = Vec::new();
+    for line in report.lines() {
+        let key = line.split_once(": ").map_or(line, |(_, key)| key);
+        let count = now.entry(key.to_owned()).or_insert(0);
+        *count += 1;
+        if *count > seen.get(key).copied().unwrap_or(0) {
+            new.push(line);
+        }
+    }
+    let fixed: usize = seen.iter()
+        .map(|(key, &count)| count.saturating_sub(now.get(key).copied().unwrap_or(0)))
+        .sum();
+    *seen = now;
+
+    let mut delta = format!("{}: {} new, {} fixed, {} left\n", name, new.len(), fixed,
+                            report.lines().count());
+    for line in new {
+        delta += line;
+        delta += "\n";
+    }
+    delta
+}
+
+#[cfg(test)]
+mod watch_delta_tests {
+    use super::watch_delta;
+    use std::collections::HashMap;
+
+    #[test]
+    fn reports_only_new_misspellings() {
+        let mut seen = HashMap::new();
+        assert_eq!("a.txt: 2 new, 0 fixed, 2 left\n1:1: wrold -> world\n2:3: wor -> word\n",
+                   watch_delta(&mut seen, "a.txt", "1:1: wrold -> world\n2:3: wor -> word\n"));
+        assert_eq!("a.txt: 1 new, 1 fixed, 2 left\n3:1: wrold -> world\n",
+                   watch_delta(&mut seen, "a.txt", "2:1: wrold -> world\n3:1: wrold -> world\n"));
+        assert_eq!("a.txt: 0 new, 0 fixed, 2 left\n",
+                   watch_delta(&mut seen, "a.txt", "1:1: wrold -> world\n1:9: wrold -> world\n"));
+        assert_eq!("a.txt: 0 new, 2 fixed, 0 left\n", watch_delta(&mut seen, "a.txt", ""));
+    }
+}
@@ -0,0 +1,645 @@
+/*!
+Checking lists of words: the words of the input, a line at a time
+with --stream or all at once, split across --jobs threads, and the
+prefixes --complete finds words for.
+*/
+
+use std::collections::HashMap;
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Write};
+use std::iter;
+use std::slice;
+use std::sync::Arc;
+use std::thread;
+
+use spellchecker::{fold_word, tokenize, CheckResult, Languages, Profile, SpellChecker, Suggestion};
+use textkit::timings::Timings;
+
+use cli::format::{format_checked, format_header, format_table, text_rows, Format};
+
+/// The checker for the language called `name`, or for the first of
+/// `languages` if it is none of them.
+pub(crate) fn checker_for<'a>(languages: &'a Languages, name: Option<&str>)
+                              -> &'a Arc<SpellChecker> {
+    name.and_then(|name| languages.get(name))
+        .unwrap_or_else(|| languages.iter().next().expect("a language").1)
+}
+
+/// Reads the words to check, one per line.
+pub(crate) fn read_words<R: Read>(reader: R) -> io::Result<Vec<String>> {
+    let mut words = Vec::new();
+
+    for line in BufReader::new(reader).lines() {
+        if let Some(word) = normalize_word(&line?) {
+            words.push(word);
+        }
+    }
+
+    Ok(words)
+}
+
+/// The word on an input line, trimmed and folded; `None` if blank.
+fn normalize_word(line: &str) -> Option<String> {
+    let word = fold_word(line.trim());
+    if word.is_empty() {None} else {Some(word)}
+}
+
+/// The words of `line`, a line of running text, as `checker` splits it
+/// (see `SpellChecker::word_spans`), folded.
+pub(crate) fn line_words(checker: &SpellChecker, line: &str) -> Vec<String> {
+    checker.word_spans(line).into_iter().map(|(_, word)| fold_word(word)).collect()
+}
+
+/// Which of `languages` the running text in `lines` is in, by the words
+/// the corpus tokenizer finds in them.
+pub(crate) fn detect_lines<'a>(languages: &'a Languages, lines: &[String]) -> Option<&'a str> {
+    let words: Vec<String> = lines.iter().flat_map(|line| tokenize(line)).collect();
+    languages.detect_words(words.iter().map(String::as_str))
+}
+
+#[cfg(test)]
+mod read_words_tests {
+    use super::read_words;
+
+    #[test]
+    fn reads_one_word_per_line() {
+        assert_eq!(vec!["hello", "wor"], read_words(&b"hello\nwor\n"[..]).unwrap());
+    }
+
+    #[test]
+    fn trims_lowercases_and_skips_blanks() {
+        assert_eq!(vec!["hello", "world"], read_words(&b"  Hello \n\n\tWORLD"[..]).unwrap());
+    }
+}
+
+/// Checks every word, each after the one before it, splitting the list
+/// into `jobs` chunks that are checked on their own threads. The results
+/// come back in input order, and what each thread's checks cost is added
+/// to `profile`.
+fn check_words(checker: &Arc<SpellChecker>, words: &[String], n: usize, jobs: usize,
+               profile: &mut Profile) -> Vec<CheckResult> {
+    if jobs <= 1 || words.len() <= 1 {
+        return check_run(checker, None, words, n, profile);
+    }
+
+    let size = words.len().div_ceil(jobs);
+    let workers: Vec<_> = words.chunks(size).enumerate()
+        .map(|(i, chunk)| {
+            let checker = Arc::clone(checker);
+            let prev = words[..i * size].last().cloned();
+            let chunk = chunk.to_vec();
+            thread::spawn(move || {
+                let mut profile = Profile::default();
+                let results = check_run(&checker, prev.as_deref(), &chunk, n, &mut profile);
+                (results, profile)
+            })
+        })
+        .collect();
+
+    let mut results = Vec::with_capacity(words.len());
+    for worker in workers {
+        let (run, cost) = worker.join().expect("checker thread panicked");
+        results.extend(run);
+        profile.merge(&cost);
+    }
+    results
+}
+
+/// Checks a run of consecutive words, the first of which came after
+/// `prev`. Unless the word before a word can change what becomes of it
+/// (see `SpellChecker::is_contextual`), a word that comes again is only
+/// checked the first time, and `profile` counts how many were reused.
+pub(crate) fn check_run(checker: &SpellChecker, prev: Option<&str>, words: &[String], n: usize,
+                        profile: &mut Profile) -> Vec<CheckResult> {
+    let prevs = iter::once(prev).chain(words.iter().map(|word| Some(word.as_str())));
+    if checker.is_contextual() {
+        return prevs.zip(words)
+            .map(|(prev, word)| checker.check_word_profiled(prev, word, n, profile))
+            .collect();
+    }
+
+    let mut checked: HashMap<&str, CheckResult> = HashMap::new();
+    let results = prevs.zip(words)
+        .map(|(prev, word)| {
+            if let Some(result) = checked.get(word.as_str()) {
+                return result.clone();
+            }
+            let result = checker.check_word_profiled(prev, word, n, profile);
+            checked.insert(word, result.clone());
+            result
+        })
+        .collect();
+    profile.record_reuse(words.len(), words.len() - checked.len());
+    results
+}
+
+/// Pairs each word with its result, except that two words in a row the
+/// checker would join (see `SpellChecker::join`) are paired together with
+/// the word they make.
+pub(crate) fn join_words(checker: &SpellChecker, words: &[String], results: Vec<CheckResult>)
+                         -> Vec<(String, CheckResult)> {
+    let mut joined = Vec::with_capacity(words.len());
+    let mut results = words.iter().zip(results).peekable();
+
+    while let Some((word, result)) = results.next() {
+        let join = results.peek().and_then(|&(next, _)| checker.join(word, next));
+        match join {
+            Some(join) => {
+                let (next, _) = results.next().expect("peeked");
+                let suggestion = Suggestion {
+                    count:      checker.count(&join),
+                    word:       join,
+                    distance:   1,
+                    confidence: 1.0,
+                };
+                let pair = format!("{} {}", word, next);
+                joined.push((pair, CheckResult::Corrected(vec![suggestion])));
+            }
+            None => joined.push((word.clone(), result)),
+        }
+    }
+
+    joined
+}
+
+#[cfg(test)]
+mod check_words_tests {
+    use super::{check_words, CheckResult, Format};
+    use cli::format::format_result;
+    use spellchecker::{read_bigrams, Profile, SpellChecker, Trie};
+    use std::sync::Arc;
+
+    #[test]
+    fn keeps_input_order() {
+        let checker = fixture();
+        let words: Vec<String> = ["hello", "wor", "zzzzzz", "helo", "world", "wrld", "x"]
+            .iter().cycle().take(50).map(|w| w.to_string()).collect();
+        let expected: Vec<_> = words.iter().map(|w| checker.check_word(None, w, 2)).collect();
+
+        for jobs in 1..9 {
+            assert_eq!(expected, check_words(&checker, &words, 2, jobs, &mut Profile::default()));
+        }
+    }
+
+    #[test]
+    fn checks_each_word_once() {
+        let words: Vec<String> = ["wor", "hello", "wor", "wor", "helo", "hello"].iter()
+            .map(|w| w.to_string()).collect();
+        let mut profile = Profile::default();
+
+        let results = check_words(&fixture(), &words, 2, 1, &mut profile);
+        assert_eq!(results[0], results[3]);
+        assert_eq!(3, profile.latency().count());
+        assert_eq!((6, 3), (profile.lookups(), profile.reused()));
+
+        // Each thread's checks are counted too.
+        let mut profile = Profile::default();
+        check_words(&fixture(), &words, 2, 2, &mut profile);
+        assert_eq!((5, 6, 1), (profile.latency().count(), profile.lookups(), profile.reused()));
+    }
+
+    #[test]
+    fn reads_words_in_context() {
+        let mut t = Trie::new();
+        for word in &["world", "peace", "a", "piece", "piece"] {
+            t.insert(word);
+        }
+        let bigrams = read_bigrams(&b"world peace, a piece"[..]).unwrap();
+        let checker = Arc::new(SpellChecker::new(t).with_context(bigrams));
+        let words: Vec<String> = ["world", "peice", "a", "peice"].iter()
+            .map(|w| w.to_string()).collect();
+
+        for jobs in 1..5 {
+            let results: Vec<_> = check_words(&checker, &words, 1, jobs, &mut Profile::default())
+                .into_iter()
+                .map(|result| format_result("peice", result, Format::Text))
+                .collect();
+            assert_eq!(vec!["peice, peace\n", "peice, piece\n"], vec![&results[1], &results[3]],
+                       "{}", jobs);
+        }
+    }
+
+    #[test]
+    fn questions_real_words() {
+        let mut t = Trie::new();
+        for word in &["over", "there", "their", "their"] {
+            t.insert(word);
+        }
+        let bigrams = read_bigrams(&b"over there, their"[..]).unwrap();
+        let checker = Arc::new(SpellChecker::new(t).with_context(bigrams).with_real_words(0.5));
+        let words: Vec<String> = ["over", "their", "their"].iter().map(|w| w.to_string()).collect();
+
+        let results = check_words(&checker, &words, 1, 1, &mut Profile::default());
+        assert_eq!(CheckResult::Correct, results[0]);
+        assert_eq!(CheckResult::Correct, results[2]);
+        assert_eq!("their?, there\n", format_result("their", results[1].clone(), Format::Text));
+    }
+
+    #[test]
+    fn handles_more_jobs_than_words() {
+        let words = vec!["wor".to_owned(), "hello".to_owned()];
+
+        assert_eq!(2, check_words(&fixture(), &words, 1, 8, &mut Profile::default()).len());
+        assert!(check_words(&fixture(), &[], 1, 8, &mut Profile::default()).is_empty());
+    }
+
+    fn fixture() -> Arc<SpellChecker> {
+        let mut t = Trie::new();
+        t.insert("hello");
+        t.insert("world");
+        t.insert("word");
+        Arc::new(SpellChecker::new(t))
+    }
+}
+
+/// Checks `words` and writes their results, returning how many of them
+/// weren't correct, or the error writing them failed with. The results
+/// are buffered, and flushed to `writer` once they're all written.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn write_correct_words<W: Write>(checker: &Arc<SpellChecker>, words: &[String], n: usize,
+                                            format: Format, jobs: usize, writer: &mut W,
+                                            timings: &mut Timings, profile: &mut Profile)
+                                            -> io::Result<usize> {
+    let results = timings.time("search", || check_words(checker, words, n, jobs, profile));
+    let results = timings.time("search", || join_words(checker, words, results));
+    let misspelled = results.iter().filter(|&(_, result)| *result != CheckResult::Correct).count();
+    let mut writer = BufWriter::new(writer);
+
+    if let Format::Table { boxed } = format {
+        let rows: Vec<_> = results.iter().flat_map(|(word, result)| text_rows(word, result))
+            .collect();
+        let table = format_table(&rows, boxed);
+        timings.time("write", || writer.write_all(table.as_bytes()).and(writer.flush()))?;
+        return Ok(misspelled);
+    }
+
+    if let Some(header) = format_header(format) {
+        timings.time("write", || writer.write_all(header.as_bytes()))?;
+    }
+
+    for (word, result) in results {
+        let line = format_checked(checker, &word, result, format);
+        timings.time("write", || writer.write_all(line.as_bytes()))?;
+    }
+
+    timings.time("write", || writer.flush())?;
+    Ok(misspelled)
+}
+
+/// Checks words as they arrive on `reader`, writing and flushing each
+/// result before reading the next line. Each is checked in the language
+/// that knows it, if any of `languages` does, or else in that of the
+/// word before. Returns how many weren't correct.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn check_stream<R: Read, W: Write>(languages: &Languages, reader: R, n: usize,
+                                              format: Format, tokenize: bool, writer: &mut W,
+                                              timings: &mut Timings, profile: &mut Profile)
+                                              -> io::Result<usize> {
+    let mut lines = BufReader::new(reader).lines();
+    let mut prev: Option<String> = None;
+    let mut language = None;
+    let mut misspelled = 0;
+
+    if let Some(header) = format_header(format) {
+        timings.time("write", || {
+            writer.write_all(header.as_bytes())?;
+            writer.flush()
+        })?;
+    }
+
+    while let Some(line) = timings.time("read", || lines.next()) {
+        let line = line?;
+        let words = if tokenize {
+            language = detect_lines(languages, slice::from_ref(&line)).or(language);
+            line_words(checker_for(languages, language), &line)
+        } else {
+            let word = match normalize_word(&line) {
+                Some(word) => word,
+                None => continue,
+            };
+            // A word says what language it is in if only one knows it.
+            language = languages.detect_words(iter::once(word.as_str())).or(language);
+            vec![word]
+        };
+        let checker = checker_for(languages, language);
+
+        for word in words {
+            if checker.is_skipped(&word) {
+                continue;
+            }
+            let result = timings.time("search", || {
+                checker.check_word_profiled(prev.as_deref(), &word, n, profile)
+            });
+            if result != CheckResult::Correct {
+                misspelled += 1;
+            }
+            let line = format_checked(checker, &word, result, format);
+            prev = Some(word);
+
+            if !line.is_empty() {
+                timings.time("write", || {
+                    writer.write_all(line.as_bytes())?;
+                    writer.flush()
+                })?;
+            }
+        }
+    }
+
+    Ok(misspelled)
+}
+
+#[cfg(test)]
+mod check_stream_tests {
+    use super::{check_stream, Format};
+    use spellchecker::{Languages, Profile, SpellChecker, Trie};
+    use std::io::{Result, Write};
+    use std::sync::Arc;
+    use textkit::timings::Timings;
+
+    #[test]
+    fn matches_batch_output() {
+        let mut writer = CountingWriter::default();
+        check_stream(&fixture(), &b"Hello\n\nwor\nzzzzzz\n"[..], 1, Format::Text, false,
+                     &mut writer, &mut Timings::default(), &mut Profile::default()).unwrap();
+
+        assert_eq!("hello\nwor, world\nzzzzzz, -\n", String::from_utf8(writer.contents).unwrap());
+    }
+
+    #[test]
+    fn writes_header_first() {
+        let mut writer = CountingWriter::default();
+        check_stream(&fixture(), &b"hello\n"[..], 1, Format::Csv, false, &mut writer,
+                     &mut Timings::default(), &mut Profile::default()).unwrap();
+
+        assert_eq!("word,status,suggestion,distance,count,confidence\nhello,correct,,,,\n",
+                   String::from_utf8(writer.contents).unwrap());
+    }
+
+    #[test]
+    fn flushes_every_line() {
+        let mut writer = CountingWriter::default();
+        check_stream(&fixture(), &b"hello\nwor\n\n"[..], 1, Format::Text, false,
+                     &mut writer, &mut Timings::default(), &mut Profile::default()).unwrap();
+
+        assert_eq!(2, writer.flushes);
+    }
+
+    #[test]
+    fn counts_misspellings() {
+        let mut writer = CountingWriter::default();
+        let misspelled = check_stream(&fixture(), &b"hello
+wor
+zzzzzz
+world
+"[..], 1,
+                                      Format::List, false, &mut writer, &mut Timings::default(),
+                                      &mut Profile::default())
+            .unwrap();
+
+        assert_eq!(2, misspelled);
+        assert_eq!("wor\nzzzzzz\n", String::from_utf8(writer.contents).unwrap());
+        assert_eq!(2, writer.flushes);
+    }
+
+    #[test]
+    fn checks_each_word_in_its_language() {
+        let mut languages = fixture();
+        languages.add("de", Arc::new(checker(&["hallo", "welt"])));
+        let mut writer = CountingWriter::default();
+        check_stream(&languages, &b"hallo\nwlet\nhello\nwrold\nwlet\n"[..], 1, Format::Text,
+                     false, &mut writer, &mut Timings::default(), &mut Profile::default()).unwrap();
+
+        // A word neither knows is checked in the language of the word
+        // before it.
+        assert_eq!("hallo\nwlet, welt\nhello\nwrold, world\nwlet, -\n",
+                   String::from_utf8(writer.contents).unwrap());
+    }
+
+    #[test]
+    fn tokenizes_lines() {
+        let mut languages = fixture();
+        languages.add("de", Arc::new(checker(&["hallo", "welt"])));
+        let mut writer = CountingWriter::default();
+        check_stream(&languages, &b"Hallo, wlet!\n\n\"Hello wrold\" wlet\n"[..], 1,
+                     Format::Text, true, &mut writer, &mut Timings::default(),
+                     &mut Profile::default()).unwrap();
+
+        assert_eq!("hallo\nwlet, welt\nhello\nwrold, world\nwlet, -\n",
+                   String::from_utf8(writer.contents).unwrap());
+        assert_eq!(5, writer.flushes);
+    }
+
+    fn fixture() -> Languages {
+        let mut languages = Languages::new();
+        languages.add("en", Arc::new(checker(&["hello", "world"])));
+        languages
+    }
+
+    fn checker(words: &[&str]) -> SpellChecker {
+        let mut t = Trie::new();
+        for word in words {
+            t.insert(word);
+        }
+        SpellChecker::new(t)
+    }
+
+    #[derive(Default)]
+    struct CountingWriter {
+        contents: Vec<u8>,
+        flushes:  usize,
+    }
+
+    impl Write for CountingWriter {
+        fn write(&mut self, buf: &[u8]) -> Result<usize> {
+            self.contents.extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> Result<()> {
+            self.flushes += 1;
+            Ok(())
+        }
+    }
+}
+
+/// Answers each prefix on `reader`, a line each, with up to `n` of the
+/// words of `checker`'s model that start with it and their counts, a
+/// line each, then an empty line, flushing after each.
+pub(crate) fn complete_prefixes<R: Read, W: Write>(checker: &SpellChecker, reader: R, n: usize,
+                                                   writer: &mut W, timings: &mut Timings)
+                                                   -> io::Result<()> {
+    let mut lines = BufReader::new(reader).lines();
+
+    while let Some(line) = timings.time("read", || lines.next()) {
+        let prefix = fold_word(line?.trim());
+        let completions = timings.time("complete", || checker.complete(&prefix, n));
+        timings.time("write", || {
+            for (word, count) in completions {
+                writeln!(writer, "{}\t{}", word, count)?;
+            }
+            writeln!(writer)?;
+            writer.flush()
+        })?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod complete_prefixes_tests {
+    use super::complete_prefixes;
+    use spellchecker::{SpellChecker, Trie};
+    use textkit::timings::Timings;
+
+    #[test]
+    fn completes_each_prefix() {
+        assert_eq!("world\t2\nword\t1\n\nhello\t1\n\n\n", complete("wor\n  HEL \nxyz\n", 2));
+        assert_eq!("world\t2\n\n", complete("wo", 1));
+        assert_eq!("", complete("", 1));
+    }
+
+    #[test]
+    fn completes_an_empty_prefix_with_anything() {
+        assert_eq!("world\t2\nhello\t1\nword\t1\n\n", complete("\n", 5));
+    }
+
+    fn complete(input: &str, n: usize) -> String {
+        let mut t = Trie::new();
+        for word in &["hello", "world", "world", "word"] {
+            t.insert(word);
+        }
+        let mut output = Vec::new();
+        complete_prefixes(&SpellChecker::new(t), input.as_bytes(), n, &mut output,
+                          &mut Timings::default()).unwrap();
+        String::from_utf8(output).unwrap()
+    }
+}
+
+#[cfg(test)]
+mod write_correct_words_tests {
+    use super::{write_correct_words, Format};
+    use spellchecker::{Profile, SpellChecker, Trie};
+    use std::io::{self, Write};
+    use std::sync::Arc;
+    use textkit::timings::Timings;
+
+    #[test]
+    fn writes_nothing_for_nothing() {
+        let mut buf: Vec<u8> = Vec::new();
+
+        write_correct_words(&fixture(), &[], 1, Format::Text, 1, &mut buf,
+                            &mut Timings::default(), &mut Profile::default()).unwrap();
+        assert_eq!(String::from_utf8(buf).unwrap(), "");
+    }
+
+    #[test]
+    fn writes_one_line_per_word() {
+        let words = vec!["hello".to_owned(), "wor".to_owned(), "zzzzzz".to_owned()];
+        let mut buf: Vec<u8> = Vec::new();
+
+        write_correct_words(&fixture(), &words, 1, Format::Text, 1, &mut buf,
+                            &mut Timings::default(), &mut Profile::default()).unwrap();
+        assert_eq!(String::from_utf8(buf).unwrap(), "hello\nwor, world\nzzzzzz, -\n");
+    }
+
+    #[test]
+    fn lists_misspellings() {
+        let words = vec!["hello".to_owned(), "wor".to_owned(), "zzzzzz".to_owned()];
+        let mut buf: Vec<u8> = Vec::new();
+
+        let misspelled = write_correct_words(&fixture(), &words, 1, Format::List, 2, &mut buf,
+                                             &mut Timings::default(), &mut Profile::default());
+        assert_eq!(2, misspelled.unwrap());
+        assert_eq!(String::from_utf8(buf).unwrap(), "wor\nzzzzzz\n");
+    }
+
+    #[test]
+    fn writes_in_one_go() {
+        // Counts the writes that reach it.
+        struct Writes(usize, Vec<u8>);
+        impl Write for Writes {
+            fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+                self.0 += 1;
+                self.1.write(buf)
+            }
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+        let words = vec!["hello".to_owned(), "wor".to_owned(), "zzzzzz".to_owned()];
+        let mut writes = Writes(0, Vec::new());
+
+        write_correct_words(&fixture(), &words, 1, Format::Text, 1, &mut writes,
+                            &mut Timings::default(), &mut Profile::default()).unwrap();
+        assert_eq!(1, writes.0);
+        assert_eq!("hello\nwor, world\nzzzzzz, -\n", String::from_utf8(writes.1).unwrap());
+    }
+
+    #[test]
+    fn passes_on_write_errors() {
+        struct Closed;
+        impl Write for Closed {
+            fn write(&mut self, _: &[u8]) -> io::Result<usize> {
+                Err(io::ErrorKind::BrokenPipe.into())
+            }
+            fn flush(&mut self) -> io::Result<()> {
+                Err(io::ErrorKind::BrokenPipe.into())
+            }
+        }
+        let words = vec!["hello".to_owned(), "wor".to_owned()];
+
+        for format in [Format::Text, Format::List, Format::Table { boxed: false }] {
+            let err = write_correct_words(&fixture(), &words, 1, format, 1, &mut Closed,
+                                          &mut Timings::default(), &mut Profile::default())
+                .unwrap_err();
+            assert_eq!(io::ErrorKind::BrokenPipe, err.kind());
+        }
+    }
+
+    #[test]
+    fn writes_in_input_order_with_jobs() {
+        let words = vec!["hello".to_owned(), "wor".to_owned(), "zzzzzz".to_owned()];
+        let mut buf: Vec<u8> = Vec::new();
+
+        write_correct_words(&fixture(), &words, 1, Format::Text, 3, &mut buf,
+                            &mut Timings::default(), &mut Profile::default()).unwrap();
+        assert_eq!(String::from_utf8(buf).unwrap(), "hello\nwor, world\nzzzzzz, -\n");
+    }
+
+    #[test]
+    fn joins_words() {
+        let mut t = Trie::new();
+        for word in &["hello", "world", "some", "thing"] {
+            t.insert(word);
+        }
+        let checker = Arc::new(SpellChecker::new(t).with_word_joins());
+        let words: Vec<String> = ["hel", "lo", "some", "thing", "wor", "ld", "wor"].iter()
+            .map(|w| w.to_string()).collect();
+        let mut buf: Vec<u8> = Vec::new();
+
+        write_correct_words(&checker, &words, 1, Format::Text, 2, &mut buf,
+                            &mut Timings::default(), &mut Profile::default()).unwrap();
+        assert_eq!("hel lo, hello\nsome\nthing\nwor ld, world\nwor, world\n",
+                   String::from_utf8(buf).unwrap());
+    }
+
+    #[test]
+    fn writes_several_suggestions() {
+        let mut model = Trie::new();
+        for word in &["hello", "world", "word", "word"] {
+            model.insert(word);
+        }
+        let words = vec!["hello".to_owned(), "wor".to_owned()];
+        let mut buf: Vec<u8> = Vec::new();
+
+        write_correct_words(&Arc::new(SpellChecker::new(model)), &words, 3, Format::Text, 1,
+                            &mut buf, &mut Timings::default(), &mut Profile::default()).unwrap();
+        assert_eq!(String::from_utf8(buf).unwrap(), "hello\nwor, word world\n");
+    }
+
+    fn fixture() -> Arc<SpellChecker> {
+        let mut t = Trie::new();
+        t.insert("hello");
+        t.insert("world");
+        Arc::new(SpellChecker::new(t))
+    }
+}
@@ -0,0 +1,89 @@
+/*!
+Compressed corpora.
+
+Corpora are usually shipped gzipped or zstd-compressed. `decompress`
+looks at the first few bytes of its input for either format's magic
+number and decompresses if it finds one, so a corpus can be trained on
+as it is, whatever its file happens to be called.
+*/
+
+use std::io::{self, BufReader, Cursor, Read};
+
+use flate2::bufread::MultiGzDecoder;
+
+const GZIP_MAGIC: &[u8] = &[0x1F, 0x8B];
+const ZSTD_MAGIC: &[u8] = &[0x28, 0xB5, 0x2F, 0xFD];
+
+/// `reader`, decompressed if it is gzip or zstd data, as it is if not.
+pub fn decompress<'a, R: Read + 'a>(mut reader: R) -> io::Result<Box<dyn Read + 'a>> {
+    // Pipes may hand the magic number over a byte at a time.
+    let mut magic = Vec::with_capacity(ZSTD_MAGIC.len());
+    (&mut reader).take(ZSTD_MAGIC.len() as u64).read_to_end(&mut magic)?;
+    let input = BufReader::new(Cursor::new(magic.clone()).chain(reader));
+
+    Ok(if !is_compressed(&magic) {
+        Box::new(input)
+    } else if magic.starts_with(GZIP_MAGIC) {
+        // Gzip files may be several members one after another, as `cat`
+        // or parallel compressors make them.
+        Box::new(MultiGzDecoder::new(input))
+    } else {
+        Box::new(zstd::stream::read::Decoder::with_buffer(input)?)
+    })
+}
+
+/// Whether `bytes` start as gzip or zstd data does.
+pub fn is_compressed(bytes: &[u8]) -> bool {
+    bytes.starts_with(GZIP_MAGIC) || bytes.starts_with(ZSTD_MAGIC)
+}
+
+#[cfg(test)]
+mod decompress_tests {
+    use super::decompress;
+    use std::io::{Read, Write};
+
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+
+    const TEXT: &str = "Hello world.\nhello, again\n";
+
+    #[test]
+    fn passes_plain_text_through() {
+        assert_eq!(TEXT, read(TEXT.as_bytes()));
+        assert_eq!("", read(b""));
+        assert_eq!("\x1F", read(b"\x1F"));
+    }
+
+    #[test]
+    fn decompresses_gzip() {
+        assert_eq!(TEXT, read(&gzip(TEXT)));
+
+        let mut members = gzip("Hello world.\n");
+        members.extend(gzip("hello, again\n"));
+        assert_eq!(TEXT, read(&members));
+    }
+
+    #[test]
+    fn decompresses_zstd() {
+        assert_eq!(TEXT, read(&zstd::encode_all(TEXT.as_bytes(), 0).unwrap()));
+    }
+
+    #[test]
+    fn reports_corrupt_data() {
+        let mut corrupt = gzip(TEXT);
+        corrupt.truncate(12);
+        assert!(decompress(&corrupt[..]).unwrap().read_to_end(&mut Vec::new()).is_err());
+    }
+
+    fn read(bytes: &[u8]) -> String {
+        let mut text = String::new();
+        decompress(bytes).unwrap().read_to_string(&mut text).unwrap();
+        text
+    }
+
+    fn gzip(text: &str) -> Vec<u8> {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(text.as_bytes()).unwrap();
+        encoder.finish().unwrap()
+    }
+}
@@ -0,0 +1,232 @@
+/*!
+Edit costs learned from real mistakes.
+
+A log of corrections, each a word as it was typed and the word that was
+meant, says which mistakes people actually make. A `Confusion` counts
+the edits each correction took, as `explain` finds them: how often a
+character was left out, typed twice, swapped with the next, and, for
+each pair of characters, how often one was typed for the other. From
+those counts it makes `Costs` that charge less for the likelier
+mistakes, to be saved and given to a checker with `--costs`.
+*/
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read};
+use std::path::Path;
+
+use checker::MAX_EDITS;
+use costs::Costs;
+use explain::{explain, Edit};
+use textkit::encoding::{Decoder, Encoding};
+use textkit::tokenize::fold_word;
+
+/// What the likeliest kind of edit costs; a correction may cost
+/// `MAX_EDITS` of them.
+const BASE_COST: usize = 2;
+
+/// How many times a replacement has to have been seen before its cost
+/// is learned, rather than left at that of replacements in general.
+const MIN_PAIR_COUNT: usize = 2;
+
+/// How often each edit was needed to correct the mistakes in a log.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Confusion {
+    /// The `(typo, correction)` pairs counted, the same word twice
+    /// included.
+    pub corrections: usize,
+    pub insert:      usize,
+    pub delete:      usize,
+    pub replace:     usize,
+    pub transpose:   usize,
+    /// How often the first character was typed for the second.
+    replacements:    HashMap<(char, char), usize>,
+}
+
+impl Confusion {
+    /// Counts the edits turning `typo` into `correction`, the fewest
+    /// there are.
+    pub fn add(&mut self, typo: &str, correction: &str) {
+        self.corrections += 1;
+        for edit in explain(typo, correction, &Costs::default()) {
+            match edit {
+                Edit::Insert(..) => self.insert += 1,
+                Edit::Delete(..) => self.delete += 1,
+                Edit::Replace(_, from, to) => {
+                    self.replace += 1;
+                    *self.replacements.entry((from, to)).or_insert(0) += 1;
+                }
+                Edit::Transpose(..) => self.transpose += 1,
+                Edit::Substitute(..) => unreachable!("default costs have no substitutions"),
+            }
+        }
+    }
+
+    /// Adds the counts of `other`, as if its corrections had been added
+    /// here too.
+    pub fn merge(&mut self, other: Confusion) {
+        self.corrections += other.corrections;
+        self.insert += other.insert;
+        self.delete += other.delete;
+        self.replace += other.replace;
+        self.transpose += other.transpose;
+        for (pair, count) in other.replacements {
+            *self.replacements.entry(pair).or_insert(0) += count;
+        }
+    }
+
+    /// How many edits have been counted.
+    pub fn edits(&self) -> usize {
+        self.insert + self.delete + self.replace + self.transpose
+    }
+
+    /// How often `from` was typed for `to`.
+    pub fn replacements(&self, from: char, to: char) -> usize {
+        self.replacements.get(&(from, to)).cloned().unwrap_or(0)
+    }
+
+    /// Costs that weigh each edit by how likely it is. The likeliest kind
+    /// of edit costs 2, and each other kind 1 more for each halving of
+    /// its likelihood, up to the most a correction may cost, which is
+    /// what `MAX_EDITS` of the likeliest come to. A replacement seen
+    /// often enough costs 1 less than replacements in general for each
+    /// doubling of its likelihood over that of the average replacement
+    /// seen, down to 1. With nothing counted, every edit costs 2.
+    pub fn costs(&self) -> Costs {
+        // Each kind is counted once more than it was seen, so that one
+        // never seen still has some likelihood.
+        let kinds = [self.insert, self.delete, self.replace, self.transpose];
+        let likeliest = *kinds.iter().max().expect("four kinds") + 1;
+        let max_cost = BASE_COST * MAX_EDITS;
+        let cost = |count: usize| {
+            let cost = BASE_COST as f64 + (likeliest as f64 / (count + 1) as f64).log2();
+            (cost.round() as usize).min(max_cost)
+        };
+        let mut costs = Costs::default();
+        costs.insert = cost(self.insert);
+        costs.delete = cost(self.delete);
+        costs.replace = cost(self.replace);
+        costs.transpose = cost(self.transpose);
+        costs.max_cost = max_cost;
+
+        let average = self.replace as f64 / self.replacements.len().max(1) as f64;
+        for (&(from, to), &count) in &self.replacements {
+            if count < MIN_PAIR_COUNT {
+                continue;
+            }
+            let cheaper = (count as f64 / average).log2().round().max(0.0) as usize;
+            let cost = costs.replace.saturating_sub(cheaper).max(1);
+            if cost < costs.replace {
+                costs.set_replace_cost(from, to, cost);
+            }
+        }
+        costs
+    }
+}
+
+/// Reads a log of corrections from `reader`, which must produce UTF-8:
+/// a word as it was typed and the word meant on each line, separated by
+/// spaces or a tab, both lowercased as a dictionary's words are. Blank
+/// lines, and lines starting with `#`, are skipped.
+pub fn read_errors<R: Read>(reader: R) -> io::Result<Confusion> {
+    let mut confusion = Confusion::default();
+
+    for (i, line) in BufReader::new(reader).lines().enumerate() {
+        let line = line?;
+        if line.trim_start().starts_with('#') {
+            continue;
+        }
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        match fields[..] {
+            [] => {}
+            [typo, correction] => confusion.add(&fold_word(typo), &fold_word(correction)),
+            _ => {
+                let msg = format!("line {}: expected a typo and its correction", i + 1);
+                return Err(io::Error::new(io::ErrorKind::InvalidData, msg));
+            }
+        }
+    }
+
+    Ok(confusion)
+}
+
+/// Reads the log of corrections at `path`, decoded according to
+/// `encoding`.
+pub fn load_errors<P: AsRef<Path>>(path: P, encoding: Encoding) -> io::Result<Confusion> {
+    read_errors(Decoder::new(File::open(path)?, encoding))
+}
+
+#[cfg(test)]
+mod confusion_tests {
+    use super::{read_errors, Confusion};
+    use costs::Costs;
+
+    #[test]
+    fn counts_each_edit() {
+        let mut confusion = Confusion::default();
+        confusion.add("teh", "the");
+        confusion.add("wrod", "word");
+        confusion.add("helo", "hello");
+        confusion.add("recieve", "receive");
+        confusion.add("wrld", "world");
+        confusion.add("hello", "hello");
+
+        assert_eq!(6, confusion.corrections);
+        assert_eq!((2, 0, 0, 3), (confusion.insert, confusion.delete, confusion.replace,
+                                  confusion.transpose));
+        assert_eq!(5, confusion.edits());
+        assert_eq!(0, confusion.replacements('e', 'a'));
+
+        confusion.add("wirld", "world");
+        assert_eq!((1, 1), (confusion.replace, confusion.replacements('i', 'o')));
+
+        let mut merged = confusion.clone();
+        merged.merge(confusion);
+        assert_eq!((14, 4, 2, 6), (merged.corrections, merged.insert, merged.replace,
+                                   merged.transpose));
+        assert_eq!(2, merged.replacements('i', 'o'));
+    }
+
+    #[test]
+    fn charges_less_for_likelier_edits() {
+        let mut confusion = Confusion::default();
+        for _ in 0..7 {
+            confusion.add("sare", "same");
+        }
+        confusion.add("cat", "cut");
+        confusion.add("helo", "hello");
+        confusion.add("hte", "the");
+        confusion.add("hte", "the");
+        confusion.add("hte", "the");
+
+        // Replacements are the likeliest, transpositions half as likely,
+        // insertions a quarter, and deletions, never seen, an eighth.
+        let costs = confusion.costs();
+        assert_eq!((2, 3, 4, 4), (costs.replace, costs.transpose, costs.insert, costs.delete));
+        assert_eq!(4, costs.max_cost);
+        // "r" for "m" was seen nearly twice as often as the average
+        // replacement; "a" for "u" too seldom to tell.
+        assert_eq!(1, costs.replace_cost('r', 'm'));
+        assert_eq!(2, costs.replace_cost('a', 'u'));
+        assert_eq!(2, costs.replace_cost('m', 'r'));
+    }
+
+    #[test]
+    fn charges_alike_for_nothing() {
+        let costs = Confusion::default().costs();
+        assert_eq!((2, 2, 2, 2, 4), (costs.insert, costs.delete, costs.replace, costs.transpose,
+                                     costs.max_cost));
+        // Keys next to none at all.
+        assert_eq!(Costs::keyboard(&[]), costs);
+    }
+
+    #[test]
+    fn reads_a_log() {
+        let confusion = read_errors(&b"# typed\tmeant\nTeh\tthe\n\n  wrod word \n"[..]).unwrap();
+        assert_eq!((2, 2), (confusion.corrections, confusion.transpose));
+
+        let err = read_errors(&b"teh the\nwrod\n"[..]).unwrap_err();
+        assert_eq!("line 2: expected a typo and its correction", err.to_string());
+        assert!(read_errors(&b"a b c\n"[..]).is_err());
+    }
+}
@@ -0,0 +1,543 @@
+/*!
+Weighted edits.
+
+By default every edit costs the same, but some mistakes are likelier than
+others: a key next to the right one, a doubled letter dropped. A `Costs`
+says what each kind of edit costs, with overrides for replacing
+particular characters by others, and how much a correction may cost in
+all, and the search looks for the cheapest corrections rather than the
+fewest edits.
+
+Scanned text goes wrong in its own ways: "rn" read as "m", "0" for "o".
+`Costs::ocr` makes those mistakes cheap, and `--mode ocr` picks it.
+*/
+
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::str::FromStr;
+
+use ascii::AsciiTrie;
+use checker::MAX_EDITS;
+use dawg::Dawg;
+use transducer::Transducer;
+use trie::{rank, Graph, Trie};
+
+/// What edits cost. Costs are read from TOML:
+///
+/// ```toml
+/// insert    = 2   # a character the word is missing
+/// delete    = 2   # a character the word has too many of
+/// replace   = 2
+/// transpose = 2   # two neighbouring characters swapped
+/// max_cost  = 4
+///
+/// [pairs]
+/// # Replacing the first character of the word with the second.
+/// as = 1
+/// sa = 1
+/// ```
+///
+/// Costs are whole numbers; to make some edits a fraction of the others,
+/// scale them all up, as above. Anything left out keeps its default,
+/// which is 1 for each edit and `MAX_EDITS` in all, so an empty file
+/// searches exactly as the unweighted engines do.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Costs {
+    pub insert:    usize,
+    pub delete:    usize,
+    pub replace:   usize,
+    pub transpose: usize,
+    /// The most a correction may cost.
+    pub max_cost:  usize,
+    /// Replacement costs for particular `(from, to)` characters.
+    pairs:         HashMap<(char, char), usize>,
+    /// Costs for replacing runs of characters other than one for one,
+    /// as `(from, to, cost)`.
+    substitutions: Vec<(Vec<char>, Vec<char>, usize)>,
+}
+
+impl Default for Costs {
+    fn default() -> Self {
+        Costs {
+            insert:    1,
+            delete:    1,
+            replace:   1,
+            transpose: 1,
+            max_cost:  MAX_EDITS,
+            pairs:     HashMap::new(),
+            substitutions: Vec::new(),
+        }
+    }
+}
+
+impl Costs {
+    /// What replacing `from` in the word with `to` costs.
+    pub fn replace_cost(&self, from: char, to: char) -> usize {
+        if from == to {
+            return 0;
+        }
+        self.pairs.get(&(from, to)).cloned().unwrap_or(self.replace)
+    }
+
+    /// Makes replacing `from` with `to` cost `cost`.
+    pub fn set_replace_cost(&mut self, from: char, to: char, cost: usize) {
+        self.pairs.insert((from, to), cost);
+    }
+
+    /// Makes replacing the characters `from` in the word with `to` cost
+    /// `cost`, as one edit. Neither may be empty.
+    pub fn set_substitution_cost(&mut self, from: &str, to: &str, cost: usize) {
+        assert!(!from.is_empty() && !to.is_empty(), "empty substitution");
+        let from: Vec<char> = from.chars().collect();
+        let to: Vec<char> = to.chars().collect();
+        if from.len() == 1 && to.len() == 1 {
+            return self.set_replace_cost(from[0], to[0], cost);
+        }
+
+        self.substitutions.retain(|s| (&s.0, &s.1) != (&from, &to));
+        self.substitutions.push((from, to, cost));
+    }
+
+    /// Costs for text that came out of OCR: ordinary edits cost 2 and a
+    /// correction at most 4, while the usual misreadings of one shape as
+    /// another cost 1, and of a letter as a digit nothing.
+    pub fn ocr() -> Self {
+        let mut costs = Costs {
+            insert:    2,
+            delete:    2,
+            replace:   2,
+            transpose: 2,
+            max_cost:  4,
+            ..Costs::default()
+        };
+        for &(from, to, cost) in OCR_CONFUSIONS {
+            costs.set_substitution_cost(from, to, cost);
+        }
+        costs
+    }
+
+    /// Costs for typing on a keyboard whose rows of letters, top to bottom,
+    /// are `rows`: ordinary edits cost 2 and a correction at most 4, while
+    /// replacing a letter with one on a key next to it, on its row or the
+    /// rows above and below, costs 1. Each row is taken to sit half a key
+    /// to the right of the one above it, as on most keyboards.
+    pub fn keyboard(rows: &[&str]) -> Self {
+        let mut costs = Costs {
+            insert:    2,
+            delete:    2,
+            replace:   2,
+            transpose: 2,
+            max_cost:  4,
+            ..Costs::default()
+        };
+        let rows: Vec<Vec<char>> = rows.iter().map(|row| row.chars().collect()).collect();
+        for (r, row) in rows.iter().enumerate() {
+            for (i, &key) in row.iter().enumerate() {
+                // The key to the right, and the two below, half a key to
+                // either side; each the other way round too.
+                let below = rows.get(r + 1).map_or(&[][..], |below| &below[..]);
+                let neighbours = row.get(i + 1).into_iter()
+                    .chain(i.checked_sub(1).and_then(|j| below.get(j)))
+                    .chain(below.get(i));
+                for &other in neighbours {
+                    costs.set_replace_cost(key, other, 1);
+                    costs.set_replace_cost(other, key, 1);
+                }
+            }
+        }
+        costs
+    }
+
+    /// The costs of replacing runs of characters, as `(from, to, cost)`.
+    pub(crate) fn substitutions(&self) -> &[(Vec<char>, Vec<char>, usize)] {
+        &self.substitutions
+    }
+
+    /// How many characters of a correction an edit can reach back over.
+    fn reach(&self) -> usize {
+        self.substitutions.iter().map(|s| s.1.len()).max().unwrap_or(0).max(2)
+    }
+}
+
+/// `(read, meant, cost)` for the mistakes `Costs::ocr` makes cheap.
+const OCR_CONFUSIONS: &[(&str, &str, usize)] = &[
+    ("rn", "m", 1), ("m", "rn", 1), ("cl", "d", 1), ("vv", "w", 1), ("ii", "u", 1),
+    ("li", "h", 1), ("l", "i", 1), ("i", "l", 1), ("c", "e", 1), ("e", "c", 1),
+    ("0", "o", 0), ("1", "l", 0), ("1", "i", 0), ("5", "s", 0), ("8", "b", 0),
+];
+
+/// What kind of text is being checked, picked with `--mode text|ocr`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum Mode {
+    /// Typed text, where every edit is as likely as another.
+    #[default]
+    Text,
+    /// Scanned text, weighed with `Costs::ocr`.
+    Ocr,
+}
+
+impl Mode {
+    /// The costs to weigh edits by, if not by counting them.
+    pub fn costs(self) -> Option<Costs> {
+        match self {
+            Mode::Text => None,
+            Mode::Ocr => Some(Costs::ocr()),
+        }
+    }
+}
+
+impl FromStr for Mode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, String> {
+        match s {
+            "text" => Ok(Mode::Text),
+            "ocr"  => Ok(Mode::Ocr),
+            _ => Err(format!("unknown mode `{}' (expected text or ocr)", s)),
+        }
+    }
+}
+
+impl fmt::Display for Mode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match *self {
+            Mode::Text => "text",
+            Mode::Ocr  => "ocr",
+        })
+    }
+}
+
+impl FromStr for Costs {
+    type Err = String;
+
+    /// Reads costs written in TOML, as described above.
+    fn from_str(s: &str) -> Result<Self, String> {
+        let table: toml::Table = s.parse().map_err(|e: toml::de::Error| e.message().to_owned())?;
+        let mut costs = Costs::default();
+
+        for (key, value) in &table {
+            let field = match key.as_str() {
+                "insert"    => &mut costs.insert,
+                "delete"    => &mut costs.delete,
+                "replace"   => &mut costs.replace,
+                "transpose" => &mut costs.transpose,
+                "max_cost"  => &mut costs.max_cost,
+                "pairs" => {
+                    let pairs = value.as_table().ok_or("`pairs' should be a table")?;
+                    for (pair, value) in pairs {
+                        let chars: Vec<char> = pair.chars().collect();
+                        if chars.len() != 2 {
+                            return Err(format!("pair `{}' should be two characters", pair));
+                        }
+                        costs.set_replace_cost(chars[0], chars[1], cost(pair, value)?);
+                    }
+                    continue;
+                }
+                _ => return Err(format!("unknown cost `{}'", key)),
+            };
+            *field = cost(key, value)?;
+        }
+
+        Ok(costs)
+    }
+}
+
+/// Writes the costs as TOML, as `from_str` reads them. Runs of
+/// characters substituted for others have no place in it, and are left
+/// out.
+impl fmt::Display for Costs {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "insert    = {}", self.insert)?;
+        writeln!(f, "delete    = {}", self.delete)?;
+        writeln!(f, "replace   = {}", self.replace)?;
+        writeln!(f, "transpose = {}", self.transpose)?;
+        writeln!(f, "max_cost  = {}", self.max_cost)?;
+        if !self.pairs.is_empty() {
+            let mut pairs: Vec<_> = self.pairs.iter().collect();
+            pairs.sort();
+            writeln!(f, "\n[pairs]")?;
+            for (&(from, to), cost) in pairs {
+                let key = toml::Value::String(format!("{}{}", from, to));
+                writeln!(f, "{} = {}", key, cost)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn cost(key: &str, value: &toml::Value) -> Result<usize, String> {
+    match value.as_integer() {
+        Some(cost) if cost >= 0 => Ok(cost as usize),
+        _ => Err(format!("cost `{}' should be a whole number, at least 0", key)),
+    }
+}
+
+/// Reads the costs in the TOML file at `path`.
+pub fn load_costs<P: AsRef<Path>>(path: P) -> io::Result<Costs> {
+    fs::read_to_string(path)?
+        .parse()
+        .map_err(|msg| io::Error::new(io::ErrorKind::InvalidData, msg))
+}
+
+#[cfg(test)]
+mod costs_tests {
+    use super::{Costs, Mode};
+
+    #[test]
+    fn reads_toml() {
+        let costs: Costs = "insert = 2\nreplace = 3\nmax_cost = 4\n[pairs]\nas = 1\n\"éa\" = 0\n"
+            .parse().unwrap();
+
+        assert_eq!(2, costs.insert);
+        assert_eq!(1, costs.delete);
+        assert_eq!(4, costs.max_cost);
+        assert_eq!(1, costs.replace_cost('a', 's'));
+        assert_eq!(3, costs.replace_cost('s', 'a'));
+        assert_eq!(0, costs.replace_cost('é', 'a'));
+        assert_eq!(0, costs.replace_cost('x', 'x'));
+    }
+
+    #[test]
+    fn writes_toml() {
+        let mut costs = Costs::keyboard(&["as"]);
+        costs.set_replace_cost('"', '\\', 0);
+        let toml = costs.to_string();
+
+        assert!(toml.starts_with("insert    = 2\ndelete    = 2\n"), "{}", toml);
+        assert!(toml.contains("\n[pairs]\n'\"\\' = 0\n\"as\" = 1\n"), "{}", toml);
+        assert_eq!(costs, toml.parse().unwrap());
+        assert_eq!(Costs::default(), Costs::default().to_string().parse().unwrap());
+        assert!(!Costs::default().to_string().contains("pairs"));
+    }
+
+    #[test]
+    fn sets_substitutions() {
+        let mut costs = Costs::default();
+        costs.set_substitution_cost("rn", "m", 3);
+        costs.set_substitution_cost("rn", "m", 1);
+        costs.set_substitution_cost("a", "b", 0);
+
+        assert_eq!(vec![(vec!['r', 'n'], vec!['m'], 1)], costs.substitutions);
+        assert_eq!(0, costs.replace_cost('a', 'b'));
+        assert_eq!(2, Costs::ocr().reach());
+    }
+
+    #[test]
+    fn makes_neighbouring_keys_cheap() {
+        let costs = Costs::keyboard(&["qwer", "asdf", "zxcv"]);
+
+        assert_eq!((2, 4), (costs.insert, costs.max_cost));
+        for &(from, to) in &[('w', 'e'), ('e', 'w'), ('s', 'w'), ('s', 'e'), ('s', 'x'),
+                             ('s', 'z'), ('z', 's'), ('q', 'a')] {
+            assert_eq!(1, costs.replace_cost(from, to), "{} for {}", to, from);
+        }
+        for &(from, to) in &[('w', 'r'), ('q', 's'), ('s', 'c'), ('a', 'x'), ('q', 'z')] {
+            assert_eq!(2, costs.replace_cost(from, to), "{} for {}", to, from);
+        }
+    }
+
+    #[test]
+    fn parses_modes() {
+        assert_eq!(Ok(Mode::Ocr), "ocr".parse());
+        assert_eq!(Ok(Mode::Text), "text".parse());
+        assert!("scan".parse::<Mode>().is_err());
+        assert_eq!("ocr", Mode::Ocr.to_string());
+        assert_eq!(None, Mode::Text.costs());
+        assert_eq!(Some(Costs::ocr()), Mode::Ocr.costs());
+    }
+
+    #[test]
+    fn defaults_to_unit_costs() {
+        assert_eq!(Costs::default(), "".parse().unwrap());
+    }
+
+    #[test]
+    fn rejects_bad_costs() {
+        for toml in &["insert = -1", "insert = 1.5", "insert = \"one\"", "inserts = 1",
+                      "[pairs]\nabc = 1", "pairs = 1", "insert ="] {
+            assert!(toml.parse::<Costs>().is_err(), "{:?}", toml);
+        }
+    }
+}
+
+impl Trie {
+    /// Every word that `word` can be turned into for at most
+    /// `costs.max_cost`, as `(word, count, cost)`, cheapest first, then most
+    /// frequent, then alphabetical. Like `search_with_k_edit`, no character
+    /// is edited twice.
+    pub fn search_with_costs(&self, word: &str, costs: &Costs) -> Vec<(String, usize, usize)> {
+        search_with_costs(self, word, costs)
+    }
+}
+
+impl AsciiTrie {
+    /// See `Trie::search_with_costs`.
+    pub fn search_with_costs(&self, word: &str, costs: &Costs) -> Vec<(String, usize, usize)> {
+        search_with_costs(self, word, costs)
+    }
+}
+
+impl Dawg {
+    /// See `Trie::search_with_costs`.
+    pub fn search_with_costs(&self, word: &str, costs: &Costs) -> Vec<(String, usize, usize)> {
+        search_with_costs(self, word, costs)
+    }
+}
+
+impl Transducer {
+    /// See `Trie::search_with_costs`.
+    pub fn search_with_costs(&self, word: &str, costs: &Costs) -> Vec<(String, usize, usize)> {
+        search_with_costs(self, word, costs)
+    }
+}
+
+/// Walks `graph` keeping a row of the edit table for each letter of the
+/// path, as `edit_distance` does for one word, and leaves any subtrie
+/// whose rows are already over budget.
+fn search_with_costs<G: Graph>(graph: G, word: &str, costs: &Costs)
+                               -> Vec<(String, usize, usize)> {
+    let word: Vec<char> = word.chars().collect();
+    let n = word.len();
+
+    // `rows[d]` is for the first `d` characters of `path`, which is the
+    // path down to the node being visited; deeper rows are left over from
+    // earlier paths, and are overwritten on the way back down.
+    let mut rows: Vec<Vec<usize>> = vec![(0..n + 1).map(|j| j * costs.delete).collect()];
+    let mut path: Vec<char> = Vec::new();
+    let mut found = Vec::new();
+
+    let root = graph.root();
+    if graph.count(root) > 0 && rows[0][n] <= costs.max_cost {
+        found.push((String::new(), graph.count(root), rows[0][n]));
+    }
+
+    let reach = costs.reach();
+    let mut stack = Vec::new();
+    graph.for_each_child(root, |c, child| stack.push((child, c, 1)));
+
+    while let Some((node, c, depth)) = stack.pop() {
+        path.truncate(depth - 1);
+        path.push(c);
+        if rows.len() <= depth {
+            rows.push(vec![0; n + 1]);
+        }
+
+        let (before, row) = rows.split_at_mut(depth);
+        let (last, row) = (&before[depth - 1], &mut row[0]);
+        row[0] = last[0] + costs.insert;
+        for j in 1..n + 1 {
+            row[j] = (last[j - 1] + costs.replace_cost(word[j - 1], c))
+                .min(last[j] + costs.insert)
+                .min(row[j - 1] + costs.delete);
+
+            if depth > 1 && j > 1 && word[j - 1] == path[depth - 2] && word[j - 2] == c {
+                row[j] = row[j].min(before[depth - 2][j - 2] + costs.transpose);
+            }
+
+            for &(ref from, ref to, cost) in &costs.substitutions {
+                if from.len() <= j && to.len() <= depth
+                    && word[j - from.len()..j] == from[..] && path[depth - to.len()..] == to[..] {
+                    row[j] = row[j].min(before[depth - to.len()][j - from.len()] + cost);
+                }
+            }
+        }
+
+        let count = graph.count(node);
+        if count > 0 && row[n] <= costs.max_cost {
+            found.push((path.iter().collect(), count, row[n]));
+        }
+        // A swap or substitution reaches back past this row to earlier
+        // ones, which may be within budget even when this one isn't.
+        let affordable = |row: &[usize]| row.iter().any(|&cost| cost <= costs.max_cost);
+        let earlier = &before[(depth + 1).saturating_sub(reach)..];
+        if affordable(row) || earlier.iter().any(|row| affordable(row)) {
+            graph.for_each_child(node, |c, child| stack.push((child, c, depth + 1)));
+        }
+    }
+
+    rank(&mut found);
+    found
+}
+
+#[cfg(test)]
+mod search_with_costs_tests {
+    use super::Costs;
+    use trie::Trie;
+
+    #[test]
+    fn unit_costs_match_edit_search() {
+        let t = fixture();
+        let costs = Costs::default();
+
+        for word in &["", "h", "helo", "hlelo", "wrold", "ehllo", "xyzzy", "hellp", "ca"] {
+            let expected = t.search_with_k_edit(word, costs.max_cost);
+            assert_eq!(expected, t.search_with_costs(word, &costs), "{:?}", word);
+            assert_eq!(expected, t.minimize().search_with_costs(word, &costs), "{:?}", word);
+        }
+    }
+
+    #[test]
+    fn prefers_cheaper_edits() {
+        let mut costs: Costs = "replace = 2\nmax_cost = 2\n".parse().unwrap();
+
+        // "cas" is a replacement from both "car" and "cat", and "car" is
+        // more frequent, until "s" for "t" is made cheap.
+        let words: Vec<_> = fixture().search_with_costs("cas", &costs).into_iter()
+            .map(|(word, _, cost)| (word, cost)).collect();
+        assert_eq!(vec![("car".to_owned(), 2), ("cat".to_owned(), 2)], words);
+
+        costs.set_replace_cost('s', 't', 1);
+        let words: Vec<_> = fixture().search_with_costs("cas", &costs).into_iter()
+            .map(|(word, _, cost)| (word, cost)).collect();
+        assert_eq!(vec![("cat".to_owned(), 1), ("car".to_owned(), 2)], words);
+    }
+
+    #[test]
+    fn finds_cheap_swaps_behind_dear_edits() {
+        let costs: Costs = "insert = 5\ndelete = 5\nreplace = 5\ntranspose = 1\n".parse().unwrap();
+
+        let words: Vec<_> = fixture().search_with_costs("ehll", &costs).into_iter()
+            .map(|(word, _, cost)| (word, cost)).collect();
+        assert_eq!(vec![("hell".to_owned(), 1)], words);
+    }
+
+    #[test]
+    fn substitutes_runs_of_characters() {
+        let mut t = fixture();
+        for _ in 0..5 {
+            t.insert("tine");
+        }
+        t.insert("time");
+
+        // "tirne" is one edit from "tine" but two from "time"; as OCR
+        // output it's more likely "time".
+        assert_eq!("tine", t.search_with_k_edit("tirne", 2)[0].0);
+        assert_eq!(("time".to_owned(), 1, 1), t.search_with_costs("tirne", &Costs::ocr())[0]);
+
+        assert_eq!(("hello".to_owned(), 1, 0), t.search_with_costs("he11o", &Costs::ocr())[0]);
+        assert_eq!(("word".to_owned(), 1, 1), t.search_with_costs("worcl", &Costs::ocr())[0]);
+    }
+
+    #[test]
+    fn stays_within_budget() {
+        let costs: Costs = "insert = 3\nmax_cost = 2\n".parse().unwrap();
+
+        // "hell" to "hello" is one insertion, now too dear, while "help" is
+        // a replacement away and "he" two deletions.
+        let words: Vec<_> = fixture().search_with_costs("hell", &costs).into_iter()
+            .map(|(word, _, _)| word).collect();
+        assert_eq!(vec!["hell", "help", "he"], words);
+    }
+
+    fn fixture() -> Trie {
+        let mut t = Trie::new();
+        for word in &["hello", "hell", "help", "help", "world", "word", "car", "car", "cat", "",
+                      "abc", "he"] {
+            t.insert(word);
+        }
+        t
+    }
+}
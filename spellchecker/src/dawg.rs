@@ -0,0 +1,222 @@
+/*!
+A minimized trie (a DAWG, directed acyclic word graph).
+
+Most of a big trie is the same few endings over and over: every "-ing"
+and "-tion" spelled out again under each stem. Minimizing keeps one copy
+of each distinct subtrie and points every parent of it at that copy.
+Since the nodes carry counts, two subtries are only the same if their
+counts are too, so frequent words share less than rare ones.
+
+A `Dawg` can't be added to, but answers the same queries as the `Trie`
+it came from.
+*/
+
+use std::collections::HashMap;
+use std::mem;
+
+use trie::{self, rank, Graph, Stats, Trie, ROOT};
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Dawg {
+    /// Children always come before their parents.
+    nodes: Vec<Node>,
+    root:  usize,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+struct Node {
+    count:    usize,
+    /// `(char, node)`, sorted by char.
+    children: Vec<(char, usize)>,
+}
+
+impl Trie {
+    /// A `Dawg` of the same words, with every repeated subtrie shared.
+    pub fn minimize(&self) -> Dawg {
+        let mut nodes: Vec<Node> = Vec::new();
+        let mut ids: HashMap<Node, usize> = HashMap::new();
+
+        // Children are numbered before their parents, so the walk keeps a
+        // stack of nodes still to finish and of the numbers of the children
+        // finished so far.
+        let mut stack = vec![('\0', ROOT, false)];
+        let mut done: Vec<(char, usize)> = Vec::new();
+
+        while let Some((c, id, expanded)) = stack.pop() {
+            let node = self.node(id);
+            if !expanded {
+                stack.push((c, id, true));
+                stack.extend(node.children.iter().map(|&(c, child)| (c, child, false)));
+                continue;
+            }
+
+            let mut children = done.split_off(done.len() - node.children.len());
+            children.reverse();
+            let node = Node { count: node.count, children };
+
+            let id = match ids.get(&node) {
+                Some(&id) => id,
+                None => {
+                    nodes.push(node.clone());
+                    ids.insert(node, nodes.len() - 1);
+                    nodes.len() - 1
+                }
+            };
+            done.push((c, id));
+        }
+
+        Dawg { root: done[0].1, nodes }
+    }
+}
+
+impl Dawg {
+    /// How many times `word` was inserted into the trie this came from.
+    pub fn search(&self, word: &str) -> usize {
+        let mut node = self.root;
+        for c in word.chars() {
+            match self.child(node, c) {
+                Some(child) => node = child,
+                None => return 0,
+            }
+        }
+        self.nodes[node].count
+    }
+
+    /// See `Trie::search_with_k_edit`.
+    pub fn search_with_k_edit(&self, word: &str, k: usize) -> Vec<(String, usize, usize)> {
+        let word: Vec<char> = word.chars().collect();
+        let mut found = trie::search_with_k_edit(self, &word, k);
+        rank(&mut found);
+        found
+    }
+
+    /// See `Trie::complete`.
+    pub fn complete(&self, prefix: &str, n: usize) -> Vec<(String, usize)> {
+        trie::complete(self, prefix, n)
+    }
+
+    /// How big the graph is. `words` and `depth` are the same as for the
+    /// trie this came from, while `nodes` counts each shared node once.
+    pub fn stats(&self) -> Stats {
+        // Children come first, so the words below each node, and the
+        // longest of them, can be worked out in order.
+        let mut words = vec![0; self.nodes.len()];
+        let mut depth = vec![0; self.nodes.len()];
+        for (id, node) in self.nodes.iter().enumerate() {
+            words[id] = (node.count > 0) as usize
+                + node.children.iter().map(|&(_, child)| words[child]).sum::<usize>();
+            depth[id] = node.children.iter().map(|&(_, child)| depth[child] + 1).max().unwrap_or(0);
+        }
+
+        let heap_bytes = self.nodes.capacity() * mem::size_of::<Node>()
+            + self.nodes.iter()
+                .map(|node| node.children.capacity() * mem::size_of::<(char, usize)>())
+                .sum::<usize>();
+
+        Stats {
+            words: words[self.root],
+            nodes: self.nodes.len(),
+            depth: depth[self.root],
+            heap_bytes,
+        }
+    }
+}
+
+impl Graph for &Dawg {
+    type Node = usize;
+
+    fn root(self) -> usize {
+        self.root
+    }
+
+    fn nodes(self) -> usize {
+        self.nodes.len()
+    }
+
+    fn index(self, node: usize) -> usize {
+        node
+    }
+
+    fn count(self, node: usize) -> usize {
+        self.nodes[node].count
+    }
+
+    fn child(self, node: usize, c: char) -> Option<usize> {
+        let children = &self.nodes[node].children;
+        children.binary_search_by_key(&c, |&(c, _)| c).ok().map(|i| children[i].1)
+    }
+
+    fn for_each_child<F: FnMut(char, usize)>(self, node: usize, mut f: F) {
+        for &(c, child) in &self.nodes[node].children {
+            f(c, child);
+        }
+    }
+}
+
+#[cfg(test)]
+mod minimize_tests {
+    use trie::Trie;
+
+    #[test]
+    fn shares_common_endings() {
+        let t = fixture();
+        let dawg = t.minimize();
+
+        assert_eq!((4, 19, 7), (t.stats().words, t.stats().nodes, t.stats().depth));
+        // Everything after the first letter is shared, and the ends of
+        // "-ing" and "-ed" are the same node too.
+        assert_eq!((4, 9, 7), (dawg.stats().words, dawg.stats().nodes, dawg.stats().depth));
+        assert!(dawg.stats().heap_bytes < t.stats().heap_bytes);
+    }
+
+    #[test]
+    fn keeps_counts_apart() {
+        let mut t = Trie::new();
+        for word in &["walked", "talked", "talked"] {
+            t.insert(word);
+        }
+
+        // "-alked" ends in a different count for each, so nothing can be
+        // shared.
+        assert_eq!(t.stats().nodes, t.minimize().stats().nodes);
+    }
+
+    #[test]
+    fn answers_the_same_queries() {
+        let t = fixture();
+        let dawg = t.minimize();
+
+        for word in &["walking", "talked", "walk", "walkin", "", "xyzzy"] {
+            assert_eq!(t.search(word), dawg.search(word), "{:?}", word);
+            assert_eq!(t.complete(word, 3), dawg.complete(word, 3), "{:?}", word);
+            for k in 0..3 {
+                assert_eq!(t.search_with_k_edit(word, k), dawg.search_with_k_edit(word, k),
+                           "{:?} within {}", word, k);
+            }
+        }
+    }
+
+    #[test]
+    fn handles_empty_trie() {
+        let stats = Trie::new().minimize().stats();
+        assert_eq!((0, 1, 0), (stats.words, stats.nodes, stats.depth));
+        assert_eq!(0, Trie::new().minimize().search(""));
+    }
+
+    #[test]
+    fn handles_long_words() {
+        let word: String = "abcdefghij".chars().cycle().take(10_000).collect();
+        let mut t = Trie::new();
+        t.insert(&word);
+
+        assert_eq!(1, t.minimize().search(&word));
+    }
+
+    fn fixture() -> Trie {
+        let mut t = Trie::new();
+        for word in &["walking", "talking", "walked", "talked"] {
+            t.insert(word);
+        }
+        t
+    }
+}
@@ -0,0 +1,198 @@
+/*!
+Dictionaries: plain text lists of words and their counts.
+
+Each line is a word, a tab and the number of times the word was seen.
+Words are written most frequent first, then alphabetically, so a
+dictionary is easy to read, and the same model always makes the same
+file.
+
+Published frequency lists (SymSpell's among them) come in much the same
+format, so reading is a little more forgiving: the word and count may be
+separated by any whitespace, words are lowercased the way the corpus
+tokenizer does it, and blank lines are skipped.
+
+The `freq` tool's frequency tables, a word, a colon and its count on
+each line, can be read as dictionaries too, with `read_freq`.
+*/
+
+use std::cmp::Reverse;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+use textkit::encoding::{Decoder, Encoding};
+use textkit::tokenize::fold_word;
+
+use trie::Trie;
+
+/// Writes every word in `model` to `writer` as a dictionary.
+pub fn write_dictionary<W: Write>(model: &Trie, mut writer: W) -> io::Result<()> {
+    let mut words: Vec<_> = model.iter().collect();
+    words.sort_by(|a, b| (Reverse(a.1), &a.0).cmp(&(Reverse(b.1), &b.0)));
+
+    for (word, count) in words {
+        writeln!(writer, "{}\t{}", word, count)?;
+    }
+    writer.flush()
+}
+
+/// Writes every word in `model` to the file at `path` as a dictionary.
+pub fn dump_dictionary<P: AsRef<Path>>(model: &Trie, path: P) -> io::Result<()> {
+    write_dictionary(model, BufWriter::new(File::create(path)?))
+}
+
+/// Reads a dictionary from `reader`, which must produce UTF-8, into a
+/// model.
+pub fn read_dictionary<R: Read>(reader: R) -> io::Result<Trie> {
+    let mut model = Trie::new();
+
+    for (i, line) in BufReader::new(reader).lines().enumerate() {
+        let line = line?;
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        match fields[..] {
+            [] => {}
+            [word, count] => {
+                let count = count.parse().map_err(|_| bad_line(i, "bad count"))?;
+                model.insert_with_count(&fold_word(word), count);
+            }
+            _ => return Err(bad_line(i, "expected a word and a count")),
+        }
+    }
+
+    Ok(model)
+}
+
+/// Reads the dictionary at `path`, decoded according to `encoding`.
+pub fn load_dictionary<P: AsRef<Path>>(path: P, encoding: Encoding) -> io::Result<Trie> {
+    read_dictionary(Decoder::new(File::open(path)?, encoding))
+}
+
+/// Reads a frequency table as `freq` writes it, `word\t:\tcount` on each
+/// line, from `reader`, which must produce UTF-8, into a model. Words
+/// are lowercased as a dictionary's are, and blank lines are skipped.
+pub fn read_freq<R: Read>(reader: R) -> io::Result<Trie> {
+    let mut model = Trie::new();
+
+    for (i, line) in BufReader::new(reader).lines().enumerate() {
+        let line = line?;
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        match fields[..] {
+            [] => {}
+            [word, ":", count] => {
+                let count = count.parse().map_err(|_| bad_line(i, "bad count"))?;
+                model.insert_with_count(&fold_word(word), count);
+            }
+            _ => return Err(bad_line(i, "expected a word, a colon and a count")),
+        }
+    }
+
+    Ok(model)
+}
+
+/// Reads the frequency table at `path`, decoded according to `encoding`.
+pub fn load_freq<P: AsRef<Path>>(path: P, encoding: Encoding) -> io::Result<Trie> {
+    read_freq(Decoder::new(File::open(path)?, encoding))
+}
+
+fn bad_line(i: usize, msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("line {}: {}", i + 1, msg))
+}
+
+#[cfg(test)]
+mod read_dictionary_tests {
+    use super::{read_dictionary, write_dictionary};
+    use trie::Trie;
+
+    #[test]
+    fn reads_counts() {
+        let model = read_dictionary(&b"hello\t3\nhelp\t2\n\nworld 1\n"[..]).unwrap();
+
+        assert_eq!(3, model.search("hello"));
+        assert_eq!(2, model.search("help"));
+        assert_eq!(1, model.search("world"));
+        assert_eq!(3, model.stats().words);
+    }
+
+    #[test]
+    fn lowercases_and_adds_up_words() {
+        let model = read_dictionary(&b"The\t5\nthe\t2\n"[..]).unwrap();
+        assert_eq!(7, model.search("the"));
+    }
+
+    #[test]
+    fn skips_zero_counts() {
+        let model = read_dictionary(&b"hello\t0\n"[..]).unwrap();
+        assert_eq!(Trie::new(), model);
+    }
+
+    #[test]
+    fn round_trips() {
+        let mut model = Trie::new();
+        for word in &["hello", "help", "hello", "café"] {
+            model.insert(word);
+        }
+
+        let mut buf = Vec::new();
+        write_dictionary(&model, &mut buf).unwrap();
+        assert_eq!(model, read_dictionary(&buf[..]).unwrap());
+    }
+
+    #[test]
+    fn rejects_bad_lines() {
+        let err = read_dictionary(&b"hello\t3\nhelp\n"[..]).unwrap_err();
+        assert_eq!("line 2: expected a word and a count", err.to_string());
+
+        let err = read_dictionary(&b"hello\tmany\n"[..]).unwrap_err();
+        assert_eq!("line 1: bad count", err.to_string());
+
+        assert!(read_dictionary(&b"big bad wolf\n"[..]).is_err());
+        assert!(read_dictionary(&b"hello\t-1\n"[..]).is_err());
+    }
+}
+
+#[cfg(test)]
+mod read_freq_tests {
+    use super::read_freq;
+
+    #[test]
+    fn reads_freq_tables() {
+        let model = read_freq(&b"the\t:\t5\nworld\t:\t2\n\nThe\t:\t1\n"[..]).unwrap();
+
+        assert_eq!((6, 2), (model.search("the"), model.search("world")));
+        assert_eq!(2, model.stats().words);
+    }
+
+    #[test]
+    fn rejects_dictionaries() {
+        let err = read_freq(&b"the\t:\t5\nworld\t2\n"[..]).unwrap_err();
+        assert_eq!("line 2: expected a word, a colon and a count", err.to_string());
+
+        let err = read_freq(&b"the\t:\tmany\n"[..]).unwrap_err();
+        assert_eq!("line 1: bad count", err.to_string());
+    }
+}
+
+#[cfg(test)]
+mod write_dictionary_tests {
+    use super::write_dictionary;
+    use trie::Trie;
+
+    #[test]
+    fn lists_most_frequent_first() {
+        let mut t = Trie::new();
+        for word in &["help", "hello", "world", "hello", "help", "hello", "café"] {
+            t.insert(word);
+        }
+
+        let mut out = Vec::new();
+        write_dictionary(&t, &mut out).unwrap();
+        assert_eq!("hello\t3\nhelp\t2\ncafé\t1\nworld\t1\n", String::from_utf8(out).unwrap());
+    }
+
+    #[test]
+    fn writes_nothing_for_empty_model() {
+        let mut out = Vec::new();
+        write_dictionary(&Trie::new(), &mut out).unwrap();
+        assert!(out.is_empty());
+    }
+}
@@ -0,0 +1,131 @@
+/*!
+Comparing two models.
+
+A model trained again on a new corpus, or pruned harder, knows other
+words than the one it replaces, and knows some of the same words to be
+commoner or rarer. `diff_models` lists what changed, so that a model
+can be looked over before it takes the place of another: the words only
+one of them has, and the words whose counts moved much more than the
+rest did. Counts are compared as shares of all the words each model
+counted, so that a model trained on twice the text doesn't have every
+word changing.
+*/
+
+use std::cmp::Ordering;
+
+use trie::Trie;
+
+/// What is different about one model from another.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ModelDiff {
+    /// The words only the first model has, with their counts, commonest
+    /// first and then alphabetical.
+    pub only_in_a: Vec<(String, usize)>,
+    /// The words only the second model has, the same way.
+    pub only_in_b: Vec<(String, usize)>,
+    /// The words both have whose shares changed by at least the ratio
+    /// asked for, as `(word, count in a, count in b)`, the most changed
+    /// first and then alphabetical.
+    pub changed:   Vec<(String, usize, usize)>,
+}
+
+impl ModelDiff {
+    /// Whether the models differ in nothing the diff reports.
+    pub fn is_empty(&self) -> bool {
+        self.only_in_a.is_empty() && self.only_in_b.is_empty() && self.changed.is_empty()
+    }
+}
+
+/// How `b` differs from `a`: the words only one of them has, and the
+/// words whose shares of all the words counted grew or shrank by a
+/// factor of `ratio` or more. Words neither model has `min_count` times
+/// are left out.
+pub fn diff_models(a: &Trie, b: &Trie, ratio: f64, min_count: usize) -> ModelDiff {
+    let total = |model: &Trie| model.iter().map(|(_, count)| count).sum::<usize>() as f64;
+    let (total_a, total_b) = (total(a), total(b));
+    let mut diff = ModelDiff::default();
+    let mut changed = Vec::new();
+
+    for (word, count_a) in a {
+        let count_b = b.search(&word);
+        if count_a.max(count_b) < min_count {
+            continue;
+        }
+        if count_b == 0 {
+            diff.only_in_a.push((word, count_a));
+            continue;
+        }
+        let share_a = count_a as f64 / total_a;
+        let share_b = count_b as f64 / total_b;
+        let change = (share_a / share_b).max(share_b / share_a);
+        if change >= ratio {
+            changed.push((change, word, count_a, count_b));
+        }
+    }
+    for (word, count_b) in b {
+        if count_b >= min_count && a.search(&word) == 0 {
+            diff.only_in_b.push((word, count_b));
+        }
+    }
+
+    let commonest = |x: &(String, usize), y: &(String, usize)| y.1.cmp(&x.1).then(x.0.cmp(&y.0));
+    diff.only_in_a.sort_by(commonest);
+    diff.only_in_b.sort_by(commonest);
+    changed.sort_by(|x, y| {
+        y.0.partial_cmp(&x.0).unwrap_or(Ordering::Equal).then_with(|| x.1.cmp(&y.1))
+    });
+    diff.changed = changed.into_iter().map(|(_, word, a, b)| (word, a, b)).collect();
+    diff
+}
+
+#[cfg(test)]
+mod diff_models_tests {
+    use super::{diff_models, ModelDiff};
+    use trie::Trie;
+
+    #[test]
+    fn finds_words_on_either_side() {
+        let a = model(&[("colour", 3), ("the", 10), ("teh", 1)]);
+        let b = model(&[("color", 4), ("the", 10), ("flavor", 4)]);
+        let diff = diff_models(&a, &b, 2.0, 1);
+
+        assert_eq!(vec![("colour".to_owned(), 3), ("teh".to_owned(), 1)], diff.only_in_a);
+        assert_eq!(vec![("color".to_owned(), 4), ("flavor".to_owned(), 4)], diff.only_in_b);
+        assert!(!diff.is_empty());
+    }
+
+    #[test]
+    fn compares_shares_of_the_words() {
+        // Twice the text, counted alike, but for one word twenty times as
+        // common.
+        let a = model(&[("the", 100), ("of", 50), ("rain", 2), ("sun", 4)]);
+        let b = model(&[("the", 200), ("of", 100), ("rain", 40), ("sun", 8)]);
+        let diff = diff_models(&a, &b, 2.0, 1);
+
+        assert_eq!(vec![("rain".to_owned(), 2, 40)], diff.changed);
+        // A lower bar catches the others shrinking to make room for it.
+        let changed = diff_models(&a, &b, 1.05, 1).changed;
+        assert_eq!(4, changed.len());
+        assert_eq!("rain", changed[0].0);
+    }
+
+    #[test]
+    fn leaves_out_rare_words() {
+        let a = model(&[("the", 10), ("teh", 1), ("rain", 2)]);
+        let b = model(&[("the", 10), ("hte", 2), ("rain", 8)]);
+
+        let diff = diff_models(&a, &b, 2.0, 3);
+        assert_eq!(ModelDiff { changed: vec![("rain".to_owned(), 2, 8)], ..ModelDiff::default() },
+                   diff);
+        assert!(diff_models(&a, &a, 1.0, 1).only_in_a.is_empty());
+        assert!(diff_models(&a, &b, 2.0, 100).is_empty());
+    }
+
+    fn model(words: &[(&str, usize)]) -> Trie {
+        let mut t = Trie::new();
+        for &(word, count) in words {
+            t.insert_with_count(word, count);
+        }
+        t
+    }
+}
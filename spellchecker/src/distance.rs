@@ -0,0 +1,147 @@
+/*!
+Edit distances between two words.
+*/
+
+use std::collections::HashMap;
+use std::mem;
+
+/// The fewest deletions, insertions, replacements and swaps of adjacent
+/// characters that turn `a` into `b`, never editing any character twice
+/// (the "optimal string alignment" distance). This is the distance
+/// `Trie::search_with_k_edit` counts.
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+
+    // Three rows of the usual table: the one before last, the last one,
+    // and the one being filled in.
+    let mut before: Vec<usize> = vec![0; b.len() + 1];
+    let mut last: Vec<usize> = (0..b.len() + 1).collect();
+    let mut row: Vec<usize> = vec![0; b.len() + 1];
+
+    for i in 1..a.len() + 1 {
+        row[0] = i;
+        for j in 1..b.len() + 1 {
+            let replace = if a[i - 1] == b[j - 1] { 0 } else { 1 };
+            row[j] = (last[j] + 1).min(row[j - 1] + 1).min(last[j - 1] + replace);
+
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                row[j] = row[j].min(before[j - 2] + 1);
+            }
+        }
+        mem::swap(&mut before, &mut last);
+        mem::swap(&mut last, &mut row);
+    }
+
+    last[b.len()]
+}
+
+#[cfg(test)]
+mod edit_distance_tests {
+    use super::edit_distance;
+
+    #[test]
+    fn same_word_is_free() {
+        assert_eq!(0, edit_distance("", ""));
+        assert_eq!(0, edit_distance("hello", "hello"));
+    }
+
+    #[test]
+    fn counts_single_edits() {
+        assert_eq!(1, edit_distance("hello", "helo"));
+        assert_eq!(1, edit_distance("helo", "hello"));
+        assert_eq!(1, edit_distance("hello", "hallo"));
+        assert_eq!(1, edit_distance("hello", "hlelo"));
+    }
+
+    #[test]
+    fn counts_from_nothing() {
+        assert_eq!(5, edit_distance("", "hello"));
+        assert_eq!(5, edit_distance("hello", ""));
+    }
+
+    #[test]
+    fn never_edits_twice() {
+        // "ca" -> "ac" -> "abc" would be two edits, but edits the swapped
+        // characters again.
+        assert_eq!(3, edit_distance("ca", "abc"));
+    }
+
+    #[test]
+    fn handles_non_ascii() {
+        assert_eq!(1, edit_distance("café", "cafe"));
+    }
+}
+
+/// Like `edit_distance`, but a swapped pair may be edited again, so
+/// "ca" is two edits from "abc" (by way of "ac") rather than three. Unlike
+/// `edit_distance` this is a true metric, which is what a `BkTree` needs;
+/// it is never more than `edit_distance`.
+pub fn damerau_levenshtein(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let infinity = a.len() + b.len();
+
+    // The table is offset by one row and column of `infinity`, so that a
+    // swap reaching back to before the start never wins.
+    let mut d = vec![vec![infinity; b.len() + 2]; a.len() + 2];
+    for (i, row) in d.iter_mut().enumerate().skip(1) {
+        row[1] = i - 1;
+    }
+    for (j, cell) in d[1].iter_mut().enumerate().skip(1) {
+        *cell = j - 1;
+    }
+
+    // The last row each character of `a` was seen in.
+    let mut last_row: HashMap<char, usize> = HashMap::new();
+
+    for i in 1..a.len() + 1 {
+        // The last column in this row where the characters matched.
+        let mut last_col = 0;
+
+        for j in 1..b.len() + 1 {
+            let k = last_row.get(&b[j - 1]).cloned().unwrap_or(0);
+            let l = last_col;
+            let replace = if a[i - 1] == b[j - 1] {
+                last_col = j;
+                0
+            } else {
+                1
+            };
+
+            d[i + 1][j + 1] = (d[i][j] + replace)
+                .min(d[i + 1][j] + 1)
+                .min(d[i][j + 1] + 1)
+                .min(d[k][l] + (i - k - 1) + 1 + (j - l - 1));
+        }
+        last_row.insert(a[i - 1], i);
+    }
+
+    d[a.len() + 1][b.len() + 1]
+}
+
+#[cfg(test)]
+mod damerau_levenshtein_tests {
+    use super::{damerau_levenshtein, edit_distance};
+
+    #[test]
+    fn agrees_on_simple_edits() {
+        for &(a, b) in &[("", ""), ("hello", "hello"), ("hello", "helo"), ("helo", "hello"),
+                         ("hello", "hallo"), ("hello", "hlelo"), ("", "hello"), ("abc", "")] {
+            assert_eq!(edit_distance(a, b), damerau_levenshtein(a, b), "{:?} {:?}", a, b);
+        }
+    }
+
+    #[test]
+    fn edits_swapped_pairs_again() {
+        assert_eq!(2, damerau_levenshtein("ca", "abc"));
+        assert_eq!(2, damerau_levenshtein("abc", "ca"));
+    }
+
+    #[test]
+    fn is_symmetric() {
+        for &(a, b) in &[("kitten", "sitting"), ("ca", "abc"), ("abcdef", "badcfe")] {
+            assert_eq!(damerau_levenshtein(a, b), damerau_levenshtein(b, a));
+        }
+    }
+}
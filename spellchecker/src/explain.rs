@@ -0,0 +1,187 @@
+/*!
+Explaining corrections.
+
+A correction's distance says how far it is from the word, not how it
+got there. `explain` finds the edits themselves, walking back through
+the same table of costs the weighted search fills in (see
+`Trie::search_with_costs`), so that what it shows for a `Costs` is the
+cheapest way round by those costs, and, for the default costs, the
+fewest edits `edit_distance` counts.
+*/
+
+use std::fmt;
+
+use costs::Costs;
+
+/// One edit turning a word as typed into its correction, at a character
+/// position in the word as typed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Edit {
+    /// The character the word was missing, inserted before this position.
+    Insert(usize, char),
+    /// A character the word had too many of.
+    Delete(usize, char),
+    /// A character replaced by another.
+    Replace(usize, char, char),
+    /// Two characters swapped, starting at this position.
+    Transpose(usize, char, char),
+    /// A run of characters replaced by another, as one edit (see
+    /// `Costs::set_substitution_cost`).
+    Substitute(usize, String, String),
+}
+
+impl fmt::Display for Edit {
+    /// As `insert(c)@2`, `replace(a,e)@1`, `transpose(r,o)@1` or
+    /// `substitute(rn,m)@0`.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Edit::Insert(at, c) => write!(f, "insert({})@{}", c, at),
+            Edit::Delete(at, c) => write!(f, "delete({})@{}", c, at),
+            Edit::Replace(at, from, to) => write!(f, "replace({},{})@{}", from, to, at),
+            Edit::Transpose(at, a, b) => write!(f, "transpose({},{})@{}", a, b, at),
+            Edit::Substitute(at, ref from, ref to) => {
+                write!(f, "substitute({},{})@{}", from, to, at)
+            }
+        }
+    }
+}
+
+/// How a cell of the table was reached.
+#[derive(Clone, Copy, Debug)]
+enum Step {
+    Start,
+    Insert,
+    Delete,
+    /// Replacing a character, perhaps by itself.
+    Replace,
+    Transpose,
+    /// By the substitution with this index.
+    Substitute(usize),
+}
+
+/// The cheapest edits by `costs` turning `typed` into `word`, in order,
+/// where the usual edits and `costs`' substitutions are the only ones
+/// there are; as `Trie::search_with_costs` finds them, no character is
+/// edited twice. An edit as cheap as any other is taken in that order:
+/// a swap, a replacement, a deletion, an insertion, then a substitution.
+pub fn explain(typed: &str, word: &str, costs: &Costs) -> Vec<Edit> {
+    let typed: Vec<char> = typed.chars().collect();
+    let word: Vec<char> = word.chars().collect();
+    let substitutions = costs.substitutions();
+
+    // `d[i][j]` is what turning the first `i` characters of `typed` into
+    // the first `j` of `word` costs, and `steps[i][j]` the last step.
+    let mut d = vec![vec![0; word.len() + 1]; typed.len() + 1];
+    let mut steps = vec![vec![Step::Start; word.len() + 1]; typed.len() + 1];
+    for i in 0..typed.len() + 1 {
+        for j in 0..word.len() + 1 {
+            if i == 0 && j == 0 {
+                continue;
+            }
+            let mut best = (usize::MAX, Step::Start);
+            let mut consider = |cost: usize, step: Step| {
+                if cost < best.0 {
+                    best = (cost, step);
+                }
+            };
+            if i > 0 && j > 0 && typed[i - 1] == word[j - 1] {
+                consider(d[i - 1][j - 1], Step::Replace);
+            }
+            if i > 1 && j > 1 && typed[i - 1] == word[j - 2] && typed[i - 2] == word[j - 1] {
+                consider(d[i - 2][j - 2] + costs.transpose, Step::Transpose);
+            }
+            if i > 0 && j > 0 {
+                consider(d[i - 1][j - 1] + costs.replace_cost(typed[i - 1], word[j - 1]),
+                         Step::Replace);
+            }
+            if i > 0 {
+                consider(d[i - 1][j] + costs.delete, Step::Delete);
+            }
+            if j > 0 {
+                consider(d[i][j - 1] + costs.insert, Step::Insert);
+            }
+            for (s, &(ref from, ref to, cost)) in substitutions.iter().enumerate() {
+                if from.len() <= i && to.len() <= j
+                    && typed[i - from.len()..i] == from[..] && word[j - to.len()..j] == to[..] {
+                    consider(d[i - from.len()][j - to.len()] + cost, Step::Substitute(s));
+                }
+            }
+            d[i][j] = best.0;
+            steps[i][j] = best.1;
+        }
+    }
+
+    let mut edits = Vec::new();
+    let (mut i, mut j) = (typed.len(), word.len());
+    while i > 0 || j > 0 {
+        match steps[i][j] {
+            Step::Replace => {
+                if typed[i - 1] != word[j - 1] {
+                    edits.push(Edit::Replace(i - 1, typed[i - 1], word[j - 1]));
+                }
+                i -= 1;
+                j -= 1;
+            }
+            Step::Transpose => {
+                edits.push(Edit::Transpose(i - 2, typed[i - 2], typed[i - 1]));
+                i -= 2;
+                j -= 2;
+            }
+            Step::Delete => {
+                edits.push(Edit::Delete(i - 1, typed[i - 1]));
+                i -= 1;
+            }
+            Step::Insert => {
+                edits.push(Edit::Insert(i, word[j - 1]));
+                j -= 1;
+            }
+            Step::Substitute(s) => {
+                let (ref from, ref to, _) = substitutions[s];
+                i -= from.len();
+                j -= to.len();
+                edits.push(Edit::Substitute(i, from.iter().collect(), to.iter().collect()));
+            }
+            Step::Start => unreachable!("only the empty prefixes start"),
+        }
+    }
+    edits.reverse();
+    edits
+}
+
+#[cfg(test)]
+mod explain_tests {
+    use super::{explain, Edit};
+    use costs::Costs;
+    use distance::edit_distance;
+
+    #[test]
+    fn finds_each_edit() {
+        let costs = Costs::default();
+
+        assert_eq!(vec![Edit::Transpose(1, 'r', 'o')], explain("wrod", "word", &costs));
+        assert_eq!(vec![Edit::Insert(2, 'l')], explain("helo", "hello", &costs));
+        assert_eq!(vec![Edit::Delete(0, 'x')], explain("xhello", "hello", &costs));
+        assert_eq!(vec![Edit::Replace(1, 'a', 'e')], explain("hallo", "hello", &costs));
+        assert!(explain("hello", "hello", &costs).is_empty());
+        assert_eq!("transpose(r,o)@1", Edit::Transpose(1, 'r', 'o').to_string());
+    }
+
+    #[test]
+    fn counts_as_edit_distance_does() {
+        for &(typed, word) in &[("ca", "abc"), ("kitten", "sitting"), ("", "abc"),
+                                ("speling", "spelling"), ("acress", "actress")] {
+            assert_eq!(edit_distance(typed, word), explain(typed, word, &Costs::default()).len(),
+                       "{:?} {:?}", typed, word);
+        }
+    }
+
+    #[test]
+    fn follows_the_costs() {
+        assert_eq!(vec![Edit::Substitute(2, "rn".to_owned(), "m".to_owned())],
+                   explain("corn", "com", &Costs::ocr()));
+        assert_eq!(vec![Edit::Replace(2, '0', 'o'), Edit::Insert(4, 'e')],
+                   explain("ph0n", "phone", &Costs::ocr()));
+        assert_eq!("substitute(rn,m)@1",
+                   Edit::Substitute(1, "rn".to_owned(), "m".to_owned()).to_string());
+    }
+}
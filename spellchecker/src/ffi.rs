@@ -0,0 +1,269 @@
+/*!
+A C interface.
+
+Lets C and C++ programs, such as editors, train a model and check words
+against it without reimplementing the search. `include/spellchecker.h`
+declares it:
+
+```c
+Speller *speller = spellchecker_new();
+if (spellchecker_train_file(speller, "big.txt") == 0) {
+    char *correction = spellchecker_check(speller, "speling");
+    if (correction) {
+        puts(correction);
+        spellchecker_free_string(correction);
+    }
+}
+spellchecker_free(speller);
+```
+
+Strings go in and come out as NUL-terminated UTF-8. Every string,
+list of strings and model handed out must be given back to the `free`
+function for it, and to no other.
+*/
+
+use std::ffi::{CStr, CString};
+use std::os::raw::{c_char, c_int};
+use std::ptr;
+
+use textkit::encoding::Encoding;
+use textkit::timings::Timings;
+use textkit::tokenize::tokenize;
+
+use checker::{check_spelling, suggest};
+use train::train_from_file;
+use trie::Trie;
+
+/// A model for C programs to train and check words against.
+pub struct Speller {
+    model: Trie,
+}
+
+/// A new model, knowing no words.
+#[no_mangle]
+pub extern "C" fn spellchecker_new() -> *mut Speller {
+    Box::into_raw(Box::new(Speller { model: Trie::new() }))
+}
+
+/// Counts the words of `text`, running text tokenized as a corpus is.
+/// Returns 0, or -1 if `text` isn't UTF-8.
+///
+/// # Safety
+///
+/// `speller` must come from `spellchecker_new` and not have been freed,
+/// and `text` must be a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn spellchecker_train(speller: *mut Speller, text: *const c_char)
+                                            -> c_int {
+    let (speller, text) = match (speller.as_mut(), from_c(text)) {
+        (Some(speller), Some(text)) => (speller, text),
+        _ => return -1,
+    };
+    for line in text.lines() {
+        for word in tokenize(line) {
+            speller.model.insert(&word);
+        }
+    }
+    0
+}
+
+/// Counts the words of the corpus file at `path`, which may be gzip or
+/// zstd compressed. Returns 0, or -1 if it can't be read.
+///
+/// # Safety
+///
+/// As for `spellchecker_train`, with `path` for `text`.
+#[no_mangle]
+pub unsafe extern "C" fn spellchecker_train_file(speller: *mut Speller, path: *const c_char)
+                                                 -> c_int {
+    let (speller, path) = match (speller.as_mut(), from_c(path)) {
+        (Some(speller), Some(path)) => (speller, path),
+        _ => return -1,
+    };
+    match train_from_file(path, Encoding::Auto, false, 1, &mut Timings::default()) {
+        Ok(model) => {
+            speller.model.merge(model);
+            0
+        }
+        Err(_) => -1,
+    }
+}
+
+/// The best correction for `word`, as `check_spelling` finds it: the
+/// word itself if it is known, and NULL if nothing is close enough.
+/// Free it with `spellchecker_free_string`.
+///
+/// # Safety
+///
+/// `speller` must come from `spellchecker_new` and not have been freed,
+/// and `word` must be a NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn spellchecker_check(speller: *const Speller, word: *const c_char)
+                                            -> *mut c_char {
+    match (speller.as_ref(), from_c(word)) {
+        (Some(speller), Some(word)) => {
+            check_spelling(&speller.model, word).map_or(ptr::null_mut(), to_c)
+        }
+        _ => ptr::null_mut(),
+    }
+}
+
+/// Up to `n` corrections for `word`, best first, as `suggest` finds
+/// them, in an array of `*count` strings; NULL, with `*count` 0, if there
+/// are none. Free it with `spellchecker_free_suggestions`.
+///
+/// # Safety
+///
+/// As for `spellchecker_check`, and `count` must point to a `size_t`.
+#[no_mangle]
+pub unsafe extern "C" fn spellchecker_suggest(speller: *const Speller, word: *const c_char,
+                                              n: usize, count: *mut usize)
+                                              -> *mut *mut c_char {
+    let found = match (speller.as_ref(), from_c(word)) {
+        (Some(speller), Some(word)) => suggest(&speller.model, word, n),
+        _ => Vec::new(),
+    };
+    if let Some(count) = count.as_mut() {
+        *count = found.len();
+    }
+    if found.is_empty() {
+        return ptr::null_mut();
+    }
+
+    let suggestions: Box<[*mut c_char]> = found.into_iter()
+        .map(|(word, _, _)| to_c(word))
+        .collect();
+    Box::into_raw(suggestions) as *mut *mut c_char
+}
+
+/// Frees a string from `spellchecker_check`.
+///
+/// # Safety
+///
+/// `string` must come from `spellchecker_check`, or be NULL, and not
+/// have been freed already.
+#[no_mangle]
+pub unsafe extern "C" fn spellchecker_free_string(string: *mut c_char) {
+    if !string.is_null() {
+        drop(CString::from_raw(string));
+    }
+}
+
+/// Frees the `count` strings from `spellchecker_suggest`.
+///
+/// # Safety
+///
+/// `suggestions` must come from `spellchecker_suggest`, with the count
+/// it gave, or be NULL, and not have been freed already.
+#[no_mangle]
+pub unsafe extern "C" fn spellchecker_free_suggestions(suggestions: *mut *mut c_char,
+                                                       count: usize) {
+    if suggestions.is_null() {
+        return;
+    }
+    let suggestions = Box::from_raw(ptr::slice_from_raw_parts_mut(suggestions, count));
+    for &string in suggestions.iter() {
+        drop(CString::from_raw(string));
+    }
+}
+
+/// Frees a model from `spellchecker_new`.
+///
+/// # Safety
+///
+/// `speller` must come from `spellchecker_new`, or be NULL, and not have
+/// been freed already.
+#[no_mangle]
+pub unsafe extern "C" fn spellchecker_free(speller: *mut Speller) {
+    if !speller.is_null() {
+        drop(Box::from_raw(speller));
+    }
+}
+
+/// The UTF-8 string at `string`, if it isn't NULL.
+unsafe fn from_c<'a>(string: *const c_char) -> Option<&'a str> {
+    if string.is_null() {
+        return None;
+    }
+    CStr::from_ptr(string).to_str().ok()
+}
+
+/// `string` for C. Words have no NULs in them.
+fn to_c(string: String) -> *mut c_char {
+    CString::new(string).expect("no NUL in a word").into_raw()
+}
+
+#[cfg(test)]
+mod ffi_tests {
+    use super::{spellchecker_check, spellchecker_free, spellchecker_free_string,
+                spellchecker_free_suggestions, spellchecker_new, spellchecker_suggest,
+                spellchecker_train, spellchecker_train_file};
+    use std::env;
+    use std::ffi::{CStr, CString};
+    use std::fs;
+    use std::ptr;
+    use std::slice;
+
+    #[test]
+    fn trains_and_checks() {
+        unsafe {
+            let speller = spellchecker_new();
+            assert_eq!(0, spellchecker_train(speller, c("Hello world, the world. Help!").as_ptr()));
+
+            let correction = spellchecker_check(speller, c("wrold").as_ptr());
+            assert_eq!(Ok("world"), CStr::from_ptr(correction).to_str());
+            spellchecker_free_string(correction);
+            assert!(spellchecker_check(speller, c("zzzzzz").as_ptr()).is_null());
+
+            let mut count = 7;
+            let suggestions = spellchecker_suggest(speller, c("helo").as_ptr(), 5, &mut count);
+            assert_eq!(2, count);
+            let words: Vec<_> = slice::from_raw_parts(suggestions, count).iter()
+                .map(|&word| CStr::from_ptr(word).to_str().unwrap())
+                .collect();
+            assert_eq!(vec!["hello", "help"], words);
+            spellchecker_free_suggestions(suggestions, count);
+
+            assert!(spellchecker_suggest(speller, c("zzzzzz").as_ptr(), 5, &mut count).is_null());
+            assert_eq!(0, count);
+            spellchecker_free(speller);
+        }
+    }
+
+    #[test]
+    fn trains_on_files() {
+        let path = env::temp_dir().join("spellchecker-ffi-corpus.txt");
+        fs::write(&path, "spelling\n").unwrap();
+
+        unsafe {
+            let speller = spellchecker_new();
+            assert_eq!(-1, spellchecker_train_file(speller, c("/no/such/corpus").as_ptr()));
+            assert_eq!(0, spellchecker_train_file(speller, c(path.to_str().unwrap()).as_ptr()));
+
+            let correction = spellchecker_check(speller, c("speling").as_ptr());
+            assert_eq!(Ok("spelling"), CStr::from_ptr(correction).to_str());
+            spellchecker_free_string(correction);
+            spellchecker_free(speller);
+        }
+    }
+
+    #[test]
+    fn refuses_bad_arguments() {
+        let latin1 = CString::new(vec![b'c', b'a', b'f', 0xe9]).unwrap();
+
+        unsafe {
+            let speller = spellchecker_new();
+            assert_eq!(-1, spellchecker_train(speller, latin1.as_ptr()));
+            assert_eq!(-1, spellchecker_train(ptr::null_mut(), c("hello").as_ptr()));
+            assert!(spellchecker_check(speller, ptr::null()).is_null());
+            spellchecker_free_string(ptr::null_mut());
+            spellchecker_free_suggestions(ptr::null_mut(), 0);
+            spellchecker_free(speller);
+            spellchecker_free(ptr::null_mut());
+        }
+    }
+
+    fn c(s: &str) -> CString {
+        CString::new(s).unwrap()
+    }
+}
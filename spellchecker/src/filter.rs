@@ -0,0 +1,734 @@
+/*!
+Input filters: which parts of a document are prose.
+
+A document written in a markup language has parts that aren't prose,
+such as code and URLs, which a spell checker would only flag. A
+`Masker` goes through a document a line at a time and blanks those
+parts out, writing a space for each of their bytes, so that the words
+left in a line are at the same byte offsets as in the line itself.
+
+HTML entities are decoded, which makes them shorter: the bytes saved
+are made up with spaces at the next whitespace, so that a word with an
+entity in it, such as "caf&eacute;", stays whole, and only the words
+after it in the same run of text move.
+
+In source code, only comments and string literals are prose. The
+markers that open comments, the quotes around strings and the escapes
+in them are blanked too.
+*/
+
+use std::borrow::Cow;
+use std::fmt;
+use std::str::FromStr;
+
+use unicode_normalization::UnicodeNormalization;
+
+/// The HTML elements whose content is not text.
+const RAW_ELEMENTS: &[&str] = &["script", "style"];
+
+/// What a document is written in, picked with
+/// `--filter text|markdown|html|code[=LANG]`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum Filter {
+    /// Plain text, all of it prose.
+    #[default]
+    Text,
+    /// Markdown, whose fenced code blocks, code spans, link URLs and
+    /// front matter aren't prose.
+    Markdown,
+    /// HTML, whose tags, comments, scripts and styles aren't prose, and
+    /// whose entities are decoded.
+    Html,
+    /// Source code, of which only the comments and string literals are
+    /// prose.
+    Code(Language),
+}
+
+impl FromStr for Filter {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, String> {
+        match s {
+            "text"     => Ok(Filter::Text),
+            "markdown" => Ok(Filter::Markdown),
+            "html"     => Ok(Filter::Html),
+            "code"     => Ok(Filter::Code(Language::C)),
+            _ if s.starts_with("code=") => Ok(Filter::Code(s["code=".len()..].parse()?)),
+            _ => Err(format!("unknown filter `{}' (expected text, markdown, html or code)", s)),
+        }
+    }
+}
+
+impl fmt::Display for Filter {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Filter::Text           => f.write_str("text"),
+            Filter::Markdown       => f.write_str("markdown"),
+            Filter::Html           => f.write_str("html"),
+            Filter::Code(language) => write!(f, "code={}", language),
+        }
+    }
+}
+
+/// A programming language whose comments and string literals
+/// `--filter code` knows, picked with `code=rust|c|py`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Language {
+    /// C, whose comments and strings C++, Java, JavaScript and Go share,
+    /// and which `--filter code` takes by default.
+    C,
+    /// Rust, whose block comments nest and whose raw strings can hold
+    /// quotes.
+    Rust,
+    /// Python, with its `#` comments and triple-quoted strings.
+    Python,
+}
+
+impl FromStr for Language {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, String> {
+        match s {
+            "c"    => Ok(Language::C),
+            "rust" => Ok(Language::Rust),
+            "py"   => Ok(Language::Python),
+            _ => Err(format!("unknown language `{}' (expected rust, c or py)", s)),
+        }
+    }
+}
+
+impl fmt::Display for Language {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match *self {
+            Language::C      => "c",
+            Language::Rust   => "rust",
+            Language::Python => "py",
+        })
+    }
+}
+
+/// Blanks out the parts of a document's lines that its filter says
+/// aren't prose, keeping track of the blocks that span lines.
+#[derive(Clone, Debug)]
+pub struct Masker {
+    filter: Filter,
+    block: Block,
+    /// How many lines have been masked so far.
+    lines: usize,
+}
+
+/// The kind of block a line is in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Block {
+    Prose,
+    /// Front matter, between `---` lines at the very top.
+    FrontMatter,
+    /// A fenced code block, closed by a fence of at least as many of the
+    /// same character.
+    Fence(char, usize),
+    /// An HTML tag, in an attribute value quoted with `quote` if there is
+    /// one, that opens the `raw` element if there is one.
+    Tag { quote: Option<char>, raw: Option<&'static str> },
+    /// An HTML comment.
+    Comment,
+    /// The content of one of the `RAW_ELEMENTS`.
+    Raw(&'static str),
+    /// The rest of a line after a `//` or `#`.
+    LineComment,
+    /// A block comment, nested `depth` deep.
+    CodeComment(usize),
+    /// A string literal, closed by its quote, three of them if it is
+    /// `triple` quoted, followed by its raw string's `hashes`. Escapes
+    /// are only read if it isn't `raw`.
+    Str { quote: u8, triple: bool, hashes: usize, raw: bool },
+}
+
+impl Masker {
+    pub fn new(filter: Filter) -> Self {
+        Masker { filter, block: Block::Prose, lines: 0 }
+    }
+
+    /// `line`, the next line of the document without its line break,
+    /// with whatever isn't prose replaced by spaces.
+    pub fn mask<'a>(&mut self, line: &'a str) -> Cow<'a, str> {
+        let first = self.lines == 0;
+        self.lines += 1;
+        match self.filter {
+            Filter::Text => Cow::Borrowed(line),
+            Filter::Markdown => Cow::Owned(self.markdown(line, first)),
+            Filter::Html => Cow::Owned(self.html(line)),
+            Filter::Code(language) => Cow::Owned(self.code(line, language)),
+        }
+    }
+
+    fn code(&mut self, line: &str, language: Language) -> String {
+        if self.block == Block::LineComment {
+            self.block = Block::Prose;
+        }
+        let bytes = line.as_bytes();
+        let mut masked = bytes.to_vec();
+        let mut i = 0;
+        while i < bytes.len() {
+            let (len, prose) = self.code_step(bytes, i, language);
+            if !prose {
+                for byte in &mut masked[i..i + len] {
+                    *byte = b' ';
+                }
+            }
+            i += len;
+        }
+        String::from_utf8(masked).expect("only whole characters are blanked")
+    }
+
+    /// How long the piece of source `line` has at `i` is, and whether it
+    /// is prose, moving into the block the piece opens or out of the one
+    /// it closes. Outside comments and strings, `Block::Prose` is code.
+    fn code_step(&mut self, line: &[u8], i: usize, language: Language) -> (usize, bool) {
+        let rest = &line[i..];
+        match self.block {
+            Block::CodeComment(depth) if rest.starts_with(b"*/") => {
+                self.block = if depth > 1 { Block::CodeComment(depth - 1) } else { Block::Prose };
+                (2, false)
+            }
+            Block::CodeComment(depth) if language == Language::Rust && rest.starts_with(b"/*") => {
+                self.block = Block::CodeComment(depth + 1);
+                (2, false)
+            }
+            // The stars down the side of a block comment.
+            Block::CodeComment(_) if rest[0] == b'*' && line[..i].iter().all(|b| *b == b' ') => {
+                (1, false)
+            }
+            Block::Str { raw: false, .. } if rest[0] == b'\\' => (escape_len(rest), false),
+            Block::Str { quote, triple, hashes, .. } => {
+                let quotes = if triple { 3 } else { 1 };
+                let closes = rest.len() >= quotes + hashes
+                    && rest[..quotes].iter().all(|&b| b == quote)
+                    && rest[quotes..quotes + hashes].iter().all(|&b| b == b'#');
+                if closes {
+                    self.block = Block::Prose;
+                    (quotes + hashes, false)
+                } else {
+                    (1, true)
+                }
+            }
+            Block::Prose => {
+                let line_comment: &[u8] = if language == Language::Python { b"#" } else { b"//" };
+                if rest.starts_with(line_comment) {
+                    self.block = Block::LineComment;
+                    (marker_len(rest, line_comment.len(), b"/#!"), false)
+                } else if language != Language::Python && rest.starts_with(b"/*") {
+                    self.block = Block::CodeComment(1);
+                    (marker_len(rest, 2, b"*!"), false)
+                } else if let Some((len, block)) = string_start(rest, language) {
+                    self.block = block;
+                    (len, false)
+                } else if rest[0] == b'\'' && language != Language::Python {
+                    (char_literal_len(rest), false)
+                } else if is_identifier(&rest[0]) {
+                    (rest.iter().take_while(|b| is_identifier(b)).count(), false)
+                } else {
+                    (1, false)
+                }
+            }
+            _ => (1, true),
+        }
+    }
+
+    fn html(&mut self, line: &str) -> String {
+        let mut masked = String::with_capacity(line.len());
+        // How many bytes the entities decoded since the last whitespace
+        // saved.
+        let mut owed = 0;
+
+        let mut i = 0;
+        while i < line.len() {
+            let rest = &line[i..];
+            let c = rest.chars().next().expect("i is at a character");
+            let mut len = c.len_utf8();
+            match self.block {
+                Block::Prose if rest.starts_with("<!--") => {
+                    self.block = Block::Comment;
+                    len = 4;
+                }
+                Block::Prose if c == '<' && starts_tag(&rest[1..]) => {
+                    self.block = Block::Tag { quote: None, raw: raw_element(&rest[1..]) };
+                }
+                Block::Prose => match decode_entity(rest) {
+                    // Blanked, like any other whitespace that isn't.
+                    Some((ref decoded, entity)) if decoded.contains(char::is_whitespace) => {
+                        len = entity;
+                    }
+                    Some((decoded, entity)) => {
+                        masked.push_str(&decoded);
+                        owed += entity - decoded.len();
+                        i += entity;
+                        continue;
+                    }
+                    None => {
+                        if c.is_whitespace() {
+                            masked.push_str(&" ".repeat(owed));
+                            owed = 0;
+                        }
+                        masked.push(c);
+                        i += len;
+                        continue;
+                    }
+                },
+                Block::Tag { quote, raw } => {
+                    self.block = match (quote, c) {
+                        (Some(q), c) if c == q => Block::Tag { quote: None, raw },
+                        (None, '"') | (None, '\'') => Block::Tag { quote: Some(c), raw },
+                        (None, '>') => raw.map_or(Block::Prose, Block::Raw),
+                        _ => self.block,
+                    };
+                }
+                Block::Comment if rest.starts_with("-->") => {
+                    self.block = Block::Prose;
+                    len = 3;
+                }
+                Block::Raw(name) if closes(rest, name) => {
+                    self.block = Block::Tag { quote: None, raw: None };
+                }
+                _ => {}
+            }
+            masked.push_str(&" ".repeat(owed + len));
+            owed = 0;
+            i += len;
+        }
+
+        masked.push_str(&" ".repeat(owed));
+        masked
+    }
+
+    fn markdown(&mut self, line: &str, first: bool) -> String {
+        match self.block {
+            Block::FrontMatter => {
+                if line.trim_end() == "---" || line.trim_end() == "..." {
+                    self.block = Block::Prose;
+                }
+                return blank(line);
+            }
+            Block::Fence(c, n) => {
+                if fence(line).is_some_and(|(d, m)| d == c && m >= n)
+                    && line.trim().trim_start_matches(c).is_empty() {
+                    self.block = Block::Prose;
+                }
+                return blank(line);
+            }
+            // Markdown is only ever in prose otherwise.
+            _ => {}
+        }
+
+        if first && line.trim_end() == "---" {
+            self.block = Block::FrontMatter;
+            return blank(line);
+        }
+        if let Some((c, n)) = fence(line) {
+            self.block = Block::Fence(c, n);
+            return blank(line);
+        }
+        mask_inline(line)
+    }
+}
+
+/// As many spaces as `text` has bytes.
+fn blank(text: &str) -> String {
+    " ".repeat(text.len())
+}
+
+/// The character and length of the fence `line` starts with, if it
+/// does: three or more backticks or tildes, indented no more than three
+/// spaces.
+fn fence(line: &str) -> Option<(char, usize)> {
+    let indent = line.len() - line.trim_start_matches(' ').len();
+    let rest = &line[indent..];
+    let c = rest.chars().next().filter(|&c| c == '`' || c == '~')?;
+    let n = rest.len() - rest.trim_start_matches(c).len();
+    // A backtick fence can't have backticks after it, or it would be a
+    // code span.
+    if indent > 3 || n < 3 || (c == '`' && rest[n..].contains('`')) {
+        return None;
+    }
+    Some((c, n))
+}
+
+/// `line` with its code spans, link URLs, autolinks and the URL of a
+/// link reference definition blanked.
+fn mask_inline(line: &str) -> String {
+    let bytes = line.as_bytes();
+    let mut masked = bytes.to_vec();
+    let mut blank = |from: usize, to: usize| {
+        for byte in &mut masked[from..to] {
+            *byte = b' ';
+        }
+    };
+
+    if let Some((from, to)) = reference_url(line) {
+        blank(from, to);
+    }
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'`' => {
+                let n = run(bytes, i);
+                match closing_run(bytes, i + n, n) {
+                    Some(end) => {
+                        blank(i, end);
+                        i = end;
+                    }
+                    None => i += n,
+                }
+            }
+            b']' if bytes.get(i + 1) == Some(&b'(') => match closing_paren(bytes, i + 1) {
+                Some(end) => {
+                    blank(i + 1, end);
+                    i = end;
+                }
+                None => i += 1,
+            },
+            b'<' => match line[i..].find('>') {
+                Some(len) if is_autolink(&line[i + 1..i + len]) => {
+                    blank(i, i + len + 1);
+                    i += len + 1;
+                }
+                _ => i += 1,
+            },
+            _ => i += 1,
+        }
+    }
+
+    String::from_utf8(masked).expect("only whole characters are blanked")
+}
+
+/// How many times the byte at `at` is repeated from there.
+fn run(bytes: &[u8], at: usize) -> usize {
+    bytes[at..].iter().take_while(|&&b| b == bytes[at]).count()
+}
+
+/// Where the first run of exactly `n` backticks from `from` ends.
+fn closing_run(bytes: &[u8], from: usize, n: usize) -> Option<usize> {
+    let mut i = from;
+    while i < bytes.len() {
+        if bytes[i] == b'`' {
+            let m = run(bytes, i);
+            if m == n {
+                return Some(i + m);
+            }
+            i += m;
+        } else {
+            i += 1;
+        }
+    }
+    None
+}
+
+/// Where the parenthesis opened at `open` is closed, just after it.
+fn closing_paren(bytes: &[u8], open: usize) -> Option<usize> {
+    let mut depth = 0;
+    for (i, &b) in bytes.iter().enumerate().skip(open) {
+        match b {
+            b'(' => depth += 1,
+            b')' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i + 1);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// How long the comment marker `text` starts with is: its first `len`
+/// bytes, and the `marks` after them that make it a doc comment, but not
+/// the star that closes an empty block comment, `/**/`.
+fn marker_len(text: &[u8], len: usize, marks: &[u8]) -> usize {
+    let marked = len + text[len..].iter().take_while(|b| marks.contains(b)).count();
+    if marked > len && text.get(marked) == Some(&b'/') { marked - 1 } else { marked }
+}
+
+/// The length of the opening delimiter of the string literal `text`
+/// starts with, prefix and all, and the block it opens, if it does.
+fn string_start(text: &[u8], language: Language) -> Option<(usize, Block)> {
+    let prefix = text.iter().take_while(|b| b.is_ascii_alphanumeric()).count();
+    let allowed: &[&[u8]] = match language {
+        Language::C => &[b"", b"L", b"u", b"U", b"u8"],
+        Language::Rust => &[b"", b"b", b"c", b"r", b"br", b"cr"],
+        Language::Python => &[b"", b"r", b"u", b"b", b"f", b"rb", b"br", b"fr", b"rf"],
+    };
+    let name = text[..prefix].to_ascii_lowercase();
+    if !allowed.contains(&&name[..]) || (language != Language::Python && name != text[..prefix]) {
+        return None;
+    }
+
+    let raw = name.contains(&b'r');
+    let hashes = if raw && language == Language::Rust {
+        text[prefix..].iter().take_while(|&&b| b == b'#').count()
+    } else {
+        0
+    };
+    let at = prefix + hashes;
+    let quote = *text.get(at)
+        .filter(|&&b| b == b'"' || (b == b'\'' && language == Language::Python))?;
+    let triple = language == Language::Python && text[at..].starts_with(&[quote; 3]);
+    let len = at + if triple { 3 } else { 1 };
+    Some((len, Block::Str { quote, triple, hashes, raw }))
+}
+
+/// The length of the character literal `text` starts with, or 1 if its
+/// quote is a Rust lifetime's.
+fn char_literal_len(text: &[u8]) -> usize {
+    let len = match text.get(1) {
+        Some(b'\\') => 1 + escape_len(&text[1..]),
+        Some(&b) if b < 0x80 => 2,
+        Some(&b) if b < 0xe0 => 3,
+        Some(&b) if b < 0xf0 => 4,
+        Some(_) => 5,
+        None => return 1,
+    };
+    if text.get(len) == Some(&b'\'') { len + 1 } else { 1 }
+}
+
+/// The length of the escape sequence `text` starts with, such as `\n`,
+/// `\x41`, `\u00e9` or `\u{e9}`.
+fn escape_len(text: &[u8]) -> usize {
+    let digits = match text.get(1) {
+        Some(b'u') if text.get(2) == Some(&b'{') => {
+            return text.iter().position(|&b| b == b'}').map_or(3, |end| end + 1);
+        }
+        Some(b'x') => 2,
+        Some(b'u') => 4,
+        Some(b'U') => 8,
+        Some(b) if b.is_ascii() => 0,
+        _ => return 1,
+    };
+    2 + text[2..].iter().take(digits).take_while(|b| b.is_ascii_hexdigit()).count()
+}
+
+/// Whether `byte` can be part of an identifier or a number.
+fn is_identifier(byte: &u8) -> bool {
+    byte.is_ascii_alphanumeric() || *byte == b'_'
+}
+
+/// Whether `text`, found after a `<`, is the rest of an HTML tag, a
+/// declaration or a processing instruction, rather than a less-than sign.
+fn starts_tag(text: &str) -> bool {
+    text.starts_with(|c: char| c.is_ascii_alphabetic() || c == '/' || c == '!' || c == '?')
+}
+
+/// Which of the `RAW_ELEMENTS` `text`, found after a `<`, opens, if any.
+fn raw_element(text: &str) -> Option<&'static str> {
+    let name = &text[..text.find(|c: char| !c.is_ascii_alphanumeric()).unwrap_or(text.len())];
+    RAW_ELEMENTS.iter().find(|raw| raw.eq_ignore_ascii_case(name)).cloned()
+}
+
+/// Whether `text` starts with the end tag of the `name` element.
+fn closes(text: &str, name: &str) -> bool {
+    text.starts_with("</")
+        && text.get(2..2 + name.len()).is_some_and(|tag| tag.eq_ignore_ascii_case(name))
+}
+
+/// What the HTML entity `text` starts with stands for, and how long the
+/// entity is: a character reference, such as "&#233;" or "&#xe9;", or
+/// one of the common named ones, such as "&amp;" or "&eacute;".
+fn decode_entity(text: &str) -> Option<(String, usize)> {
+    let end = text.find(';').filter(|&end| end <= 32 && text.starts_with('&'))?;
+    let name = &text[1..end];
+
+    let decoded = if let Some(number) = name.strip_prefix('#') {
+        let code = match number.strip_prefix(['x', 'X']) {
+            Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+            None => number.parse().ok()?,
+        };
+        char::from_u32(code)?.to_string()
+    } else {
+        named_entity(name)?
+    };
+    Some((decoded, end + 1))
+}
+
+/// What the named entity `name` stands for, if it is one of the common
+/// ones: the markup characters, spaces, dashes, quotes, and the letters
+/// with accents, such as "eacute" and "Uuml".
+fn named_entity(name: &str) -> Option<String> {
+    let decoded = match name {
+        "amp" => "&",
+        "lt" => "<",
+        "gt" => ">",
+        "quot" => "\"",
+        "apos" => "'",
+        "nbsp" => "\u{a0}",
+        "ndash" => "\u{2013}",
+        "mdash" => "\u{2014}",
+        "lsquo" => "\u{2018}",
+        "rsquo" => "\u{2019}",
+        "ldquo" => "\u{201c}",
+        "rdquo" => "\u{201d}",
+        "hellip" => "\u{2026}",
+        "copy" => "\u{a9}",
+        "szlig" => "\u{df}",
+        "aelig" => "\u{e6}",
+        "AElig" => "\u{c6}",
+        "oslash" => "\u{f8}",
+        "Oslash" => "\u{d8}",
+        _ => {
+            let letter = name.chars().next().filter(char::is_ascii_alphabetic)?;
+            let mark = match &name[1..] {
+                "acute" => '\u{301}',
+                "grave" => '\u{300}',
+                "circ"  => '\u{302}',
+                "tilde" => '\u{303}',
+                "uml"   => '\u{308}',
+                "ring"  => '\u{30a}',
+                "cedil" => '\u{327}',
+                _ => return None,
+            };
+            return Some([letter, mark].iter().cloned().nfc().collect());
+        }
+    };
+    Some(decoded.to_owned())
+}
+
+/// Whether `text`, found between angle brackets, is a URL or an email
+/// address rather than an HTML tag.
+fn is_autolink(text: &str) -> bool {
+    !text.contains(char::is_whitespace) && (text.contains("://") || text.contains('@'))
+}
+
+/// Where the URL of a link reference definition, `[label]: url`, is in
+/// `line`, if it is one.
+fn reference_url(line: &str) -> Option<(usize, usize)> {
+    let indent = line.len() - line.trim_start_matches(' ').len();
+    if indent > 3 || !line[indent..].starts_with('[') {
+        return None;
+    }
+    let after = indent + line[indent..].find("]:")? + 2;
+    let from = line.len() - line[after..].trim_start().len();
+    let to = line[from..].find(char::is_whitespace).map_or(line.len(), |len| from + len);
+    if to > from { Some((from, to)) } else { None }
+}
+
+#[cfg(test)]
+mod masker_tests {
+    use super::{decode_entity, Filter, Language, Masker};
+
+    #[test]
+    fn parses_filters() {
+        assert_eq!(Ok(Filter::Markdown), "markdown".parse());
+        assert!("md".parse::<Filter>().is_err());
+        assert_eq!("text", Filter::Text.to_string());
+    }
+
+    #[test]
+    fn leaves_text_alone() {
+        assert_eq!(vec!["```", "`x` [a](b)"], mask(Filter::Text, "```\n`x` [a](b)"));
+    }
+
+    #[test]
+    fn blanks_front_matter_and_fences() {
+        let text = "---\ntitle: Hi\n---\nSome text\n````rust\nlet x = 1;\n```\n````\n~~~\nfoo\n";
+        assert_eq!(vec!["   ", "         ", "   ", "Some text", "        ", "          ", "   ",
+                        "    ", "   ", "   "],
+                   mask(Filter::Markdown, text));
+        // Only the first line can open front matter.
+        assert_eq!(vec!["Text", "---"], mask(Filter::Markdown, "Text\n---"));
+    }
+
+    #[test]
+    fn blanks_code_spans_and_links() {
+        assert_eq!(vec!["Run           or            now."],
+                   mask(Filter::Markdown, "Run `cargo b` or ``` `` ``` now."));
+        assert_eq!(vec!["See [the docs]                     and                 ."],
+                   mask(Filter::Markdown,
+                        "See [the docs](https://x.io/a_(b)) and <me@example.com>."));
+        assert_eq!(vec!["[docs]:                   \"Title\"", "a <b>bold</b> `open"],
+                   mask(Filter::Markdown,
+                        "[docs]: https://x.io/docs \"Title\"\na <b>bold</b> `open"));
+    }
+
+    #[test]
+    fn strips_html() {
+        let text = "<!DOCTYPE html>\n<p class=\"a > b\">Caf&eacute; &amp; cr&#xe8;me<br/>\n\
+                    <!-- wrold\nwrold --> Tom&nbsp;&amp;&nbsp;Jerry <script>if (a<b)\n\
+                    wrold()</SCRIPT>. <style>p {}</style>";
+        let expected = vec!["               ",
+                            "                 Café       &     crème         ",
+                            "          ",
+                            "          Tom      &          Jerry                 ",
+                            "                .                    "];
+        let masked = mask(Filter::Html, text);
+        assert_eq!(expected, masked);
+        for (line, masked) in text.lines().zip(masked) {
+            assert_eq!(line.len(), masked.len());
+        }
+    }
+
+    #[test]
+    fn decodes_entities() {
+        assert_eq!(Some(("é".to_owned(), 8)), decode_entity("&eacute;s"));
+        assert_eq!(Some(("Ü".to_owned(), 6)), decode_entity("&Uuml;"));
+        assert_eq!(Some(("é".to_owned(), 6)), decode_entity("&#233;"));
+        assert_eq!(Some(("'".to_owned(), 6)), decode_entity("&#x27;"));
+        assert_eq!(None, decode_entity("&nosuch;"));
+        assert_eq!(None, decode_entity("& so on;"));
+        assert_eq!(None, decode_entity("&amp"));
+    }
+
+    #[test]
+    fn parses_code_filters() {
+        assert_eq!(Ok(Filter::Code(Language::C)), "code".parse());
+        assert_eq!(Ok(Filter::Code(Language::Python)), "code=py".parse());
+        assert!("code=cobol".parse::<Filter>().is_err());
+        assert_eq!("code=rust", Filter::Code(Language::Rust).to_string());
+    }
+
+    #[test]
+    fn keeps_rust_comments_and_strings() {
+        let text = "//! The crate.\n\
+                    /* a /* nested */ comment */ let s = r#\"a \"raw\" one\"#; // end\n\
+                    fn f<'a>(c: char) -> &'a str { if c == '\\'' { \"it\\'s\\n\" }\n\
+                    else { b\"x\" } }\n\
+                    /**/ let t = \"two\n lines\";";
+        let expected = vec!["    The crate.",
+                            "   a    nested    comment               a \"raw\" one       end",
+                            "                                               it  s     ",
+                            "         x     ",
+                            "              two",
+                            " lines  "];
+        assert_eq!(expected, mask(Filter::Code(Language::Rust), text));
+    }
+
+    #[test]
+    fn keeps_c_comments_and_strings() {
+        let text = "/**\n * The docs.\n */\nputs(\"Caf\\u00e9 /* open\"); /* c */ char q = '\"';";
+        let expected = vec!["   ", "   The docs.", "   ",
+                            "      Caf       /* open       c                 "];
+        assert_eq!(expected, mask(Filter::Code(Language::C), text));
+    }
+
+    #[test]
+    fn keeps_python_comments_and_strings() {
+        let text = "## Notes\ndef f(x):\n    '''Doc\n    string.'''\n\
+                    return rb'\\d' + f\"{x}'s\" # x";
+        let expected = vec!["   Notes",
+                            "         ",
+                            "       Doc",
+                            "    string.   ",
+                            "          \\d      {x}'s    x"];
+        assert_eq!(expected, mask(Filter::Code(Language::Python), text));
+    }
+
+    #[test]
+    fn keeps_offsets() {
+        let line = "Café `naïve` résumé";
+        let masked = Masker::new(Filter::Markdown).mask(line).into_owned();
+        assert_eq!(line.len(), masked.len());
+        assert_eq!(Some(line.find("résumé").unwrap()), masked.find("résumé"));
+    }
+
+    fn mask(filter: Filter, text: &str) -> Vec<String> {
+        let mut masker = Masker::new(filter);
+        text.lines().map(|line| masker.mask(line).into_owned()).collect()
+    }
+}
@@ -0,0 +1,392 @@
+/*!
+Hunspell dictionaries as models.
+
+A Hunspell dictionary is a pair of files: `en_US.dic` lists the stems,
+each with the affix flags it takes, and `en_US.aff` says what each flag
+does. Every stem is expanded into the words its affixes make, and each
+word is counted once, since a dictionary has no frequencies.
+
+Only the basics are understood: `SET`, `FLAG` and the `PFX` and `SFX`
+rules, with their stripping, conditions and cross products. Affixes on
+affixes (continuation classes), compounding and the rest are ignored.
+Words are lowercased, like the corpus tokenizer does.
+*/
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::Path;
+
+use textkit::encoding::{decode, Encoding};
+
+use trie::Trie;
+
+/// Reads the dictionary whose stems are in the file at `dic`, and whose
+/// affixes are in the `.aff` file next to it.
+pub fn load_hunspell<P: AsRef<Path>>(dic: P) -> io::Result<Trie> {
+    let dic = dic.as_ref();
+    let aff = read_file(&dic.with_extension("aff"))?;
+
+    // The `SET` line, if there is one, is ASCII whatever the encoding is.
+    let encoding = set_encoding(&decode(&aff, Encoding::Latin1)?)?;
+    let aff = decode(&aff, encoding)?;
+    let dic = decode(&read_file(dic)?, encoding)?;
+
+    read_hunspell(&aff, &dic)
+}
+
+/// Builds a model from the text of a `.aff` and a `.dic` file.
+pub fn read_hunspell(aff: &str, dic: &str) -> io::Result<Trie> {
+    let affixes = Affixes::parse(aff)?;
+    let mut model = Trie::new();
+
+    // The first line is the number of stems.
+    for line in dic.lines().skip(1) {
+        let entry = match line.split_whitespace().next() {
+            Some(entry) => entry,
+            None => continue,
+        };
+        let (stem, flags) = match entry.find('/') {
+            Some(i) => (&entry[..i], affixes.flags(&entry[i + 1..])),
+            None => (entry, Vec::new()),
+        };
+
+        for word in affixes.expand(stem, &flags) {
+            let word = word.to_lowercase();
+            if model.search(&word) == 0 {
+                model.insert(&word);
+            }
+        }
+    }
+
+    Ok(model)
+}
+
+fn read_file(path: &Path) -> io::Result<Vec<u8>> {
+    let mut bytes = Vec::new();
+    File::open(path)?.read_to_end(&mut bytes)?;
+    Ok(bytes)
+}
+
+/// The encoding named by the `SET` line of a `.aff` file. Hunspell's own
+/// default is ISO8859-1.
+fn set_encoding(aff: &str) -> io::Result<Encoding> {
+    let set = aff.lines()
+        .map(|line| line.split_whitespace().collect::<Vec<_>>())
+        .find(|fields| fields.first() == Some(&"SET"))
+        .and_then(|fields| fields.get(1).map(|name| name.to_uppercase()));
+
+    match set.as_ref().map(|name| &name[..]) {
+        None | Some("ISO8859-1") => Ok(Encoding::Latin1),
+        Some("UTF-8") => Ok(Encoding::Utf8),
+        Some(name) => Err(invalid(&format!("unsupported encoding {}", name))),
+    }
+}
+
+#[cfg(test)]
+mod set_encoding_tests {
+    use super::set_encoding;
+    use textkit::encoding::Encoding;
+
+    #[test]
+    fn reads_set() {
+        assert_eq!(Encoding::Utf8, set_encoding("# comment\nSET UTF-8\nTRY abc\n").unwrap());
+        assert_eq!(Encoding::Latin1, set_encoding("SET ISO8859-1\n").unwrap());
+        assert_eq!(Encoding::Latin1, set_encoding("TRY abc\n").unwrap());
+        assert!(set_encoding("SET KOI8-R\n").is_err());
+    }
+}
+
+/// How the flags after a stem are written, set with `FLAG`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum FlagType {
+    /// One character each, the default (`FLAG UTF-8` is the same once
+    /// decoded).
+    Char,
+    /// Two characters each (`FLAG long`).
+    Long,
+    /// Numbers separated by commas (`FLAG num`).
+    Num,
+}
+
+/// The affix classes of a `.aff` file, by flag.
+#[derive(Debug)]
+struct Affixes {
+    flag_type: FlagType,
+    classes:   HashMap<String, Class>,
+}
+
+/// A `PFX` or `SFX` class: the rules one flag stands for.
+#[derive(Debug)]
+struct Class {
+    prefix: bool,
+    /// Whether it can combine with an affix of the other kind.
+    cross:  bool,
+    rules:  Vec<Rule>,
+}
+
+#[derive(Debug)]
+struct Rule {
+    /// Taken off the stem before `add` goes on.
+    strip:     String,
+    add:       String,
+    /// Must match the start (for a prefix) or end (for a suffix) of the
+    /// stem.
+    condition: Vec<CharClass>,
+}
+
+/// One character of a rule's condition.
+#[derive(Debug)]
+enum CharClass {
+    Any,
+    OneOf(Vec<char>),
+    NoneOf(Vec<char>),
+}
+
+impl Affixes {
+    fn parse(aff: &str) -> io::Result<Affixes> {
+        let mut affixes = Affixes { flag_type: FlagType::Char, classes: HashMap::new() };
+        let mut lines = aff.lines().enumerate();
+
+        while let Some((i, line)) = lines.next() {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            match fields.first() {
+                Some(&"FLAG") => {
+                    affixes.flag_type = match fields.get(1) {
+                        Some(&"long") => FlagType::Long,
+                        Some(&"num") => FlagType::Num,
+                        _ => FlagType::Char,
+                    };
+                }
+                Some(&kind) if kind == "PFX" || kind == "SFX" => {
+                    // The header is the flag, the cross product and the
+                    // number of rules that follow.
+                    if fields.len() < 4 {
+                        return Err(bad_line(i, "bad affix header"));
+                    }
+                    let flag = fields[1];
+                    let count: usize =
+                        fields[3].parse().map_err(|_| bad_line(i, "bad rule count"))?;
+                    let mut class = Class { prefix: kind == "PFX", cross: fields[2] == "Y",
+                                            rules: Vec::new() };
+
+                    for _ in 0..count {
+                        let (i, line) =
+                            lines.next().ok_or_else(|| invalid("truncated affix rules"))?;
+                        let fields: Vec<&str> = line.split_whitespace().collect();
+                        if fields.len() < 4 || fields[0] != kind || fields[1] != flag {
+                            return Err(bad_line(i, "bad affix rule"));
+                        }
+                        class.rules.push(Rule::parse(&fields, i)?);
+                    }
+
+                    affixes.classes.insert(flag.to_owned(), class);
+                }
+                _ => {}
+            }
+        }
+
+        Ok(affixes)
+    }
+
+    /// Splits the flags after a stem's `/`.
+    fn flags(&self, flags: &str) -> Vec<String> {
+        match self.flag_type {
+            FlagType::Char => flags.chars().map(|c| c.to_string()).collect(),
+            FlagType::Long => {
+                let chars: Vec<char> = flags.chars().collect();
+                chars.chunks(2).map(|pair| pair.iter().collect()).collect()
+            }
+            FlagType::Num => flags.split(',').map(|flag| flag.to_owned()).collect(),
+        }
+    }
+
+    /// The stem and every word its affixes make of it.
+    fn expand(&self, stem: &str, flags: &[String]) -> Vec<String> {
+        let classes: Vec<&Class> = flags.iter().filter_map(|flag| self.classes.get(flag)).collect();
+        let mut words = vec![stem.to_owned()];
+
+        let mut suffixed = Vec::new();
+        for class in classes.iter().filter(|class| !class.prefix) {
+            for rule in &class.rules {
+                if let Some(word) = rule.apply(stem, false) {
+                    suffixed.push((word, class.cross));
+                }
+            }
+        }
+
+        for class in classes.iter().filter(|class| class.prefix) {
+            for rule in &class.rules {
+                words.extend(rule.apply(stem, true));
+
+                if class.cross {
+                    for &(ref word, cross) in &suffixed {
+                        if cross {
+                            words.extend(rule.apply(word, true));
+                        }
+                    }
+                }
+            }
+        }
+
+        words.extend(suffixed.into_iter().map(|(word, _)| word));
+        words
+    }
+}
+
+impl Rule {
+    /// Reads a rule line: kind, flag, strip, add and condition.
+    fn parse(fields: &[&str], i: usize) -> io::Result<Rule> {
+        let strip = if fields[2] == "0" { "" } else { fields[2] };
+        // Continuation flags after the `/` aren't supported.
+        let add = fields[3].split('/').next().unwrap_or("");
+        let add = if add == "0" { "" } else { add };
+        let condition = match fields.get(4) {
+            Some(condition) => {
+                parse_condition(condition).ok_or_else(|| bad_line(i, "bad condition"))?
+            }
+            None => Vec::new(),
+        };
+
+        Ok(Rule { strip: strip.to_owned(), add: add.to_owned(), condition })
+    }
+
+    /// `stem` with this affix, if it applies.
+    fn apply(&self, stem: &str, prefix: bool) -> Option<String> {
+        let chars: Vec<char> = stem.chars().collect();
+        if self.condition.len() > chars.len() {
+            return None;
+        }
+
+        let tested = if prefix {
+            &chars[..self.condition.len()]
+        } else {
+            &chars[chars.len() - self.condition.len()..]
+        };
+        if !self.condition.iter().zip(tested).all(|(class, &c)| class.matches(c)) {
+            return None;
+        }
+
+        if prefix {
+            stem.strip_prefix(&self.strip[..]).map(|rest| format!("{}{}", self.add, rest))
+        } else {
+            stem.strip_suffix(&self.strip[..]).map(|rest| format!("{}{}", rest, self.add))
+        }
+    }
+}
+
+/// Reads a condition such as `[^aeiou]y`; `.` alone means any stem.
+fn parse_condition(condition: &str) -> Option<Vec<CharClass>> {
+    if condition == "." {
+        return Some(Vec::new());
+    }
+
+    let mut classes = Vec::new();
+    let mut chars = condition.chars();
+    while let Some(c) = chars.next() {
+        classes.push(match c {
+            '.' => CharClass::Any,
+            '[' => {
+                let mut set: Vec<char> = Vec::new();
+                loop {
+                    match chars.next() {
+                        Some(']') => break,
+                        Some(c) => set.push(c),
+                        None => return None,
+                    }
+                }
+                if set.first() == Some(&'^') {
+                    CharClass::NoneOf(set.split_off(1))
+                } else {
+                    CharClass::OneOf(set)
+                }
+            }
+            c => CharClass::OneOf(vec![c]),
+        });
+    }
+
+    Some(classes)
+}
+
+impl CharClass {
+    fn matches(&self, c: char) -> bool {
+        match *self {
+            CharClass::Any => true,
+            CharClass::OneOf(ref set) => set.contains(&c),
+            CharClass::NoneOf(ref set) => !set.contains(&c),
+        }
+    }
+}
+
+fn bad_line(i: usize, msg: &str) -> io::Error {
+    invalid(&format!("line {}: {}", i + 1, msg))
+}
+
+fn invalid(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+#[cfg(test)]
+mod read_hunspell_tests {
+    use super::read_hunspell;
+
+    const AFF: &str = "\
+SET UTF-8
+TRY esianrtolcdugmphbyfvkwz
+
+PFX A Y 1
+PFX A   0     re         .
+
+SFX D Y 4
+SFX D   0     d          e
+SFX D   y     ied        [^aeiou]y
+SFX D   0     ed         [^ey]
+SFX D   0     ed         [aeiou]y
+
+SFX S Y 2
+SFX S   y     ies        [^aeiou]y
+SFX S   0     s          [aeiou]y
+";
+
+    #[test]
+    fn expands_affixes() {
+        let model = read_hunspell(AFF, "3\ncreate/AD\ntry/DS\nplay/DS\n").unwrap();
+
+        let mut words: Vec<_> = model.iter().map(|(word, _)| word).collect();
+        words.sort();
+        assert_eq!(vec!["create", "created", "play", "played", "plays", "recreate", "recreated",
+                        "tried", "tries", "try"], words);
+    }
+
+    #[test]
+    fn counts_each_word_once() {
+        let model = read_hunspell(AFF, "2\nplay/S\nplays\n").unwrap();
+        assert_eq!(1, model.search("plays"));
+    }
+
+    #[test]
+    fn lowercases_and_skips_morphology() {
+        let model = read_hunspell(AFF, "2\nParis\tpo:noun\nCafé/S\n").unwrap();
+
+        assert_eq!(1, model.search("paris"));
+        assert_eq!(1, model.search("café"));
+        assert_eq!(0, model.search("cafés"));
+    }
+
+    #[test]
+    fn reads_long_and_numeric_flags() {
+        let aff = "FLAG long\nSFX Ab N 1\nSFX Ab 0 s .\n";
+        assert_eq!(1, read_hunspell(aff, "1\ncat/Ab\n").unwrap().search("cats"));
+
+        let aff = "FLAG num\nSFX 12 N 1\nSFX 12 0 s .\n";
+        assert_eq!(1, read_hunspell(aff, "1\ncat/3,12\n").unwrap().search("cats"));
+    }
+
+    #[test]
+    fn rejects_bad_rules() {
+        assert!(read_hunspell("SFX S Y 2\nSFX S 0 s .\n", "0\n").is_err());
+        assert!(read_hunspell("SFX S Y 1\nSFX S 0 s [ab\n", "0\n").is_err());
+        assert!(read_hunspell("SFX S Y many\n", "0\n").is_err());
+        assert!(read_hunspell("SFX S Y 1\nSFX T 0 s .\n", "0\n").is_err());
+    }
+}
@@ -0,0 +1,153 @@
+/*!
+Checking text in several languages.
+
+`Languages` keeps a `SpellChecker` for each of several languages, by
+name, and guesses which language a text is in by which of them knows
+the most of its words. That takes nothing but the models a spell checker
+has anyway, and is right often enough for a whole document; a short line
+is in any of the languages that know its words, and ties go to the
+language added first.
+*/
+
+use std::sync::Arc;
+
+use textkit::tokenize::{fold_word, word_spans};
+
+use checker::SpellChecker;
+
+#[derive(Clone, Debug, Default)]
+pub struct Languages {
+    /// In the order they were added.
+    checkers: Vec<(String, Arc<SpellChecker>)>,
+}
+
+impl Languages {
+    pub fn new() -> Self {
+        Languages::default()
+    }
+
+    /// Checks the language called `name` with `checker`, in place of any
+    /// checker it had.
+    pub fn add(&mut self, name: &str, checker: Arc<SpellChecker>) {
+        match self.checkers.iter_mut().find(|(known, _)| known == name) {
+            Some(entry) => entry.1 = checker,
+            None => self.checkers.push((name.to_owned(), checker)),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.checkers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.checkers.is_empty()
+    }
+
+    /// The checker for the language called `name`.
+    pub fn get(&self, name: &str) -> Option<&Arc<SpellChecker>> {
+        self.checkers.iter().find(|(known, _)| known == name).map(|(_, checker)| checker)
+    }
+
+    /// Each language's name and checker, in the order they were added.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &Arc<SpellChecker>)> {
+        self.checkers.iter().map(|(name, checker)| (name.as_str(), checker))
+    }
+
+    /// The language of `words`, given lowercased as `fold_word` leaves
+    /// them: the one whose checker knows the most of them, or the first of
+    /// those that know as many. `None` if none knows any of them, unless
+    /// there is only the one language, which is always the answer.
+    pub fn detect_words<'a, I: IntoIterator<Item = &'a str>>(&self, words: I) -> Option<&str> {
+        if self.checkers.len() == 1 {
+            return Some(&self.checkers[0].0);
+        }
+
+        let words: Vec<&str> = words.into_iter().collect();
+        let mut best = None;
+        let mut most = 0;
+        for (name, checker) in &self.checkers {
+            let known = words.iter().filter(|word| checker.is_known(&checker.folded(word))).count();
+            if known > most {
+                best = Some(name.as_str());
+                most = known;
+            }
+        }
+        best
+    }
+
+    /// The language of `text`, running text tokenized as a corpus is (see
+    /// `word_spans`), as `detect_words` has it.
+    pub fn detect(&self, text: &str) -> Option<&str> {
+        if self.checkers.len() == 1 {
+            return Some(&self.checkers[0].0);
+        }
+        let words: Vec<String> = word_spans(text).into_iter()
+            .map(|(_, word)| fold_word(word))
+            .collect();
+        self.detect_words(words.iter().map(String::as_str))
+    }
+}
+
+#[cfg(test)]
+mod detect_tests {
+    use super::Languages;
+    use checker::SpellChecker;
+    use std::sync::Arc;
+    use trie::Trie;
+
+    #[test]
+    fn picks_the_language_knowing_most() {
+        let languages = fixture();
+
+        assert_eq!(Some("en"), languages.detect("The house is on the hill."));
+        assert_eq!(Some("de"), languages.detect("Das Haus ist auf dem Hügel."));
+        assert_eq!(Some("de"), languages.detect_words(vec!["das", "haus", "the"]));
+        assert_eq!(None, languages.detect("xyzzy plugh"));
+        assert_eq!(None, languages.detect(""));
+    }
+
+    #[test]
+    fn breaks_ties_in_order() {
+        let languages = fixture();
+
+        // "hand" is both.
+        assert_eq!(Some("en"), languages.detect("Hand"));
+        assert_eq!(Some("en"), languages.detect("the Haus"));
+    }
+
+    #[test]
+    fn answers_for_a_single_language() {
+        let mut languages = Languages::new();
+        languages.add("en", Arc::new(checker(&["the"])));
+
+        assert_eq!(Some("en"), languages.detect("xyzzy"));
+        assert_eq!(Some("en"), languages.detect_words(vec![]));
+    }
+
+    #[test]
+    fn replaces_checkers() {
+        let mut languages = fixture();
+        languages.add("de", Arc::new(checker(&["xyzzy"])));
+
+        assert_eq!(2, languages.len());
+        assert_eq!(vec!["en", "de"], languages.iter().map(|(name, _)| name).collect::<Vec<_>>());
+        assert_eq!(Some("de"), languages.detect("xyzzy"));
+        assert!(languages.get("fr").is_none());
+    }
+
+    fn fixture() -> Languages {
+        let mut languages = Languages::new();
+        languages.add("en", Arc::new(checker(&["the", "house", "is", "on", "hill", "hand"])));
+        languages.add("de", Arc::new(checker(&["das", "haus", "ist", "auf", "dem", "hügel",
+                                               "hand"])));
+        languages
+    }
+
+    fn checker(words: &[&str]) -> SpellChecker {
+        let mut t = Trie::new();
+        for word in words {
+            t.insert(word);
+        }
+        SpellChecker::new(t)
+    }
+}
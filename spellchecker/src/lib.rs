@@ -0,0 +1,139 @@
+/*!
+spellchecker
+
+A Norvig-style spelling corrector. A `Trie` is trained on a corpus of
+running text, counting how often each word occurs; a misspelled word is
+then corrected to the most frequent known word within `MAX_EDITS` edits
+(deletions, insertions, replacements and adjacent transpositions), with
+closer words always beating more frequent ones, unless
+`Ranking::NoisyChannel` weighs the two against each other, or a `Ranker`
+of the caller's own orders the candidates instead.
+
+Candidates are found by walking the trie, or, with `Engine::SymSpell`, by
+looking them up in a precomputed `SymSpell` deletion index, which is
+faster to query but slow to build and memory hungry. A `BkTree` of the
+known words (`Engine::BkTree`) can also be searched, and keeps large
+edit distances affordable, and a `Transducer` (`Engine::Fst`) keeps the
+words and their counts in a fraction of the trie's memory. A model of
+nothing but ASCII is walked as an `AsciiTrie`, keyed by bytes, which is
+smaller and quicker to search.
+Edits can also be weighted, some costing more than others, with `Costs`,
+which a `Confusion` learns from a log of real corrections, and a
+`PhoneticIndex` finds words that sound like a misspelling no edits
+reach. `explain` shows the edits a correction took.
+
+Corpora may be gzip or zstd compressed. Training a big corpus takes a
+while, so it can be spread over several threads, and a trained `Trie`
+can be saved with `save_model` and loaded again with `load_model`, or
+made a `Transducer`, saved with `save_transducer`, and mapped into
+memory with `map_model`, to be searched without being loaded at all.
+`train_from_url` downloads a corpus as it trains on it, if the crate is
+built with the `http` feature. `dump_dictionary` writes the words it
+learned out as a plain list of words and counts, which
+`load_dictionary` reads back, as it does published frequency lists, and
+`load_freq` reads the frequency tables of the `freq` tool.
+`diff_models` compares two models, for a new one to be looked over.
+`load_hunspell` makes a model of a Hunspell dictionary, and a personal
+dictionary of words to accept (see `load_personal`) can be added to any
+model with `SpellChecker::with_personal`, as can abbreviations to expand
+(see `load_abbreviations`), and `stems` lets a checker take the
+inflections of its words as known too. `Languages` keeps a checker for
+each of several languages, and guesses which one a text is in, and a
+`Bundle` describes a language as data: its words, its keyboard and its
+punctuation.
+`SpellChecker::check_all` checks a batch of words, making a `Correction`
+of each, `SpellChecker::check_word_profiled` adds what checking a word
+cost to a `Profile` the caller keeps, and `SpellChecker::complete` finds
+the most frequent words starting with a prefix, for autocompletion. C
+and C++ programs can train and query a model through `ffi`, declared
+in `include/spellchecker.h`.
+
+```no_run
+# extern crate spellchecker;
+# extern crate textkit;
+# use spellchecker::{train_from_file, Encoding, SpellChecker};
+# use textkit::timings::Timings;
+# fn main() -> std::io::Result<()> {
+let model = train_from_file("big.txt", Encoding::Auto, false, 1, &mut Timings::default())?;
+let checker = SpellChecker::new(model);
+assert_eq!(Some("spelling".to_owned()), checker.check("speling"));
+# Ok(())
+# }
+```
+*/
+
+extern crate flate2;
+extern crate fst;
+extern crate memmap2;
+extern crate regex;
+extern crate textkit;
+extern crate toml;
+extern crate unicode_normalization;
+#[cfg(feature = "http")]
+extern crate ureq;
+extern crate zstd;
+
+mod abbreviations;
+mod ascii;
+mod bigrams;
+mod bktree;
+mod bundle;
+mod channel;
+mod checker;
+mod compress;
+mod confusion;
+mod costs;
+mod dawg;
+mod dictionary;
+mod diff;
+mod distance;
+mod explain;
+pub mod ffi;
+mod filter;
+mod hunspell;
+mod languages;
+mod model;
+mod personal;
+mod phonetic;
+mod profile;
+mod stem;
+mod symspell;
+mod train;
+mod transducer;
+mod trie;
+
+pub use abbreviations::{load_abbreviations, read_abbreviations};
+pub use ascii::AsciiTrie;
+pub use bigrams::{read_bigrams, train_bigrams_from_file, Bigrams};
+pub use bktree::BkTree;
+pub use bundle::{load_bundle, read_bundle, Bundle, BUNDLE_FILE};
+pub use channel::{channel_score, error_probability, RankContext, Ranker, Ranking};
+pub use checker::{adaptive_edits, check_spelling, suggest, CasePolicy, CheckResult, Correction,
+                  Engine, Numbers, SpellChecker, Suggestion, MAX_EDITS};
+pub use compress::decompress;
+pub use confusion::{load_errors, read_errors, Confusion};
+pub use costs::{load_costs, Costs, Mode};
+pub use dawg::Dawg;
+pub use dictionary::{dump_dictionary, load_dictionary, load_freq, read_dictionary, read_freq,
+                     write_dictionary};
+pub use diff::{diff_models, ModelDiff};
+pub use distance::{damerau_levenshtein, edit_distance};
+pub use explain::{explain, Edit};
+pub use filter::{Filter, Language, Masker};
+pub use hunspell::{load_hunspell, read_hunspell};
+pub use languages::Languages;
+pub use model::{is_mappable, load_model, map_model, save_model, save_transducer};
+pub use personal::{append_personal, load_personal, read_personal};
+pub use phonetic::{metaphone, PhoneticIndex};
+pub use profile::{Histogram, Profile};
+pub use stem::stems;
+pub use train::{corpus_files, identifier_parts, is_url, read_n_train_model,
+                read_n_train_model_parallel, read_n_train_model_timed, fold_diacritics,
+                strip_diacritics, train_from_file, train_from_reader, train_from_url, Hyphens,
+                Punctuation};
+pub use symspell::SymSpell;
+pub use transducer::Transducer;
+pub use trie::{Stats, SubTries, Trie, Words};
+
+pub use textkit::encoding::Encoding;
+pub use textkit::tokenize::{fold_word, tokenize, word_spans, MARKS};
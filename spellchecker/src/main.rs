@@ -1,64 +1,1108 @@
-#[doc="
-Counts the frequencies of words read from the standard input, and print
-a sorted frequency table.
+/*!
+spellchecker
+
+Trains a spelling corrector on a corpus, then reads words from the
+standard input and prints the correction for each.
+
+USAGE:
+
+    spellchecker [--encoding auto|utf8|latin1|utf16] [--progress] [--timings] [--words FILE]...
+                 [--suggestions N] [--save-model FILE] [--stream] [--jobs N] [--adaptive-edits]
+                 [--engine trie|symspell|bktree|fst] [--beam N] [--budget-ms MS] [--minimize]
+                 [--costs FILE | --mode text|ocr] [--phonetic] [--hyphens keep|split|both]
+                 [--punctuation MARKS] [--tokenize] [--complete]
+                 [--ranking distance|noisy-channel] [--context] [--fold-diacritics]
+                 [--real-words] [--threshold T] [--split-compounds] [--ignore REGEX]...
+                 [--smoothing K] [--accept-inflections] [--stats]
+                 [--join-words] [--min-count N] [--prune-below N] [--max-vocab M]
+                 [--max-word-len N] [--corpus FILE]...
+                 [--list | --explain | --format text|scores|scored|json|csv|tsv|table[=box]]
+                 [--dump-dictionary FILE] [--glob PATTERN] [--verbose] [--personal FILE]
+                 [--capitalized check|skip|lenient] [--acronyms check|skip|lenient]
+                 [--numbers check|accept|skip] [--abbreviations FILE] [--quiet]
+                 [(--document | --annotate) [--filter text|markdown|html|code[=LANG]] |
+                  --fix [--dry-run] | --interactive FILE | --watch FILE | --lsp]
+                 CORPUS... < words
+    spellchecker [OPTIONS] CORPUS... -- WORDFILE...
+    spellchecker [OPTIONS] --words FILE - < corpus
+    spellchecker [OPTIONS] --load-model FILE < words
+    spellchecker [OPTIONS] --append FILE CORPUS... < words
+    spellchecker [OPTIONS] --dictionary FILE < words
+    spellchecker [OPTIONS] --from-freq FILE < words
+    spellchecker [OPTIONS] --hunspell FILE.dic < words
+    spellchecker [OPTIONS] --lang-model LANG=FILE... [--lang auto|LANG] < words
+    spellchecker [OPTIONS] --bundle DIR... [--lang auto|LANG] < words
+    spellchecker repl [OPTIONS] CORPUS...
+    spellchecker serve [--listen ADDRESS] [--http] [OPTIONS] CORPUS...
+    spellchecker train-errors [--encoding auto|utf8|latin1|utf16] [--output FILE] [LOG...]
+    spellchecker diff [--ratio R] [--min-count N] MODEL_A MODEL_B
+
+INPUT:
+
+CORPUS is a file of running text, tokenized the same way freq does it.
+It may be gzip or zstd compressed. Several corpora may be given, and any
+of them may be a directory, in which every file whose name matches
+--glob PATTERN (default `*', any file) is read, however deeply it is
+nested. One model is trained on all of them; --verbose reports on
+stderr how many words each file had. --corpus FILE gives a corpus too,
+as an argument would.
+
+A corpus of - is read from the standard input, for a corpus that is
+more easily piped in than the words. The words then have to come from
+files, with --words: otherwise nothing is left of the input to check,
+which is enough to train a model and --save-model it. Only one corpus
+can be read that way, and not with --context, which reads the corpora
+twice.
+
+A corpus may also be an http:// or https:// URL, which is downloaded
+as it is trained on, and not kept, if spellchecker was built with the
+http feature (cargo build --features http). --context can't be used
+with it either.
+
+    spellchecker https://norvig.com/big.txt < words
+
+The standard input has one word per line:
+
+    hello
+    wor
+    zzzzzz
+
+The input terminates with EOF.
+
+The words can be read from files instead: --words FILE reads FILE, and
+may be given more than once, and every argument after a -- is a file
+of words too, so that the corpora and the files can be told apart:
+
+    spellchecker big.txt -- chapter1.txt chapter2.txt
+
+The files are checked in turn, each as the standard input would be
+(with --document, --stream or --list alike), and the output for each
+follows a header with its name, as head writes them:
+
+    ==> chapter1.txt <==
+    hello
+    wor, world
+
+They can't be used with a command, --fix, --interactive or --lsp.
+
+Instead of a corpus, --load-model FILE reads a model saved earlier with
+--save-model FILE, which writes the trained model out before checking.
+To train and save without checking anything, give an empty input:
+
+    spellchecker --save-model big.model big.txt < /dev/null
+    spellchecker --load-model big.model < words
+
+With --engine fst, --save-model saves the finite-state transducer the
+engine searches instead, which --load-model with --engine fst maps into
+memory and searches where it is, so that even a big model is ready at
+once, as a server wants it to be. A model that has anything to be done
+to it first, by --hyphens, --punctuation, --fold-diacritics or pruning,
+is read in whole, and either kind of model file can be read by any
+engine.
+
+`spellchecker diff MODEL_A MODEL_B` compares two saved models, so that
+a new one can be looked over before it takes the place of the old: it
+lists the words only MODEL_A has, each after a -, then those only
+MODEL_B has, after a +, commonest first, each with a tab and its
+count, and then, after a ~, the words whose counts changed by a factor
+of --ratio R (default 2) or more, the most changed first:
+
+    $ spellchecker diff old.model new.model
+    - colour    12
+    + color     40
+    ~ rain      2 -> 40
+
+The counts are compared as shares of all the words each model counted,
+so that a model trained on twice the text isn't different in every
+word. Words neither model has --min-count N times (default 1) are left
+out. How many words there were of each is said on stderr, and the exit
+status is 0 if the models are the same in all this, 1 if they aren't,
+and 2 if a model couldn't be read, as diff's is.
+
+--append FILE grows a saved model instead of starting afresh: the model
+in FILE is trained further on the corpora and written back to FILE. The
+new model is written alongside first and then moved into place, so
+FILE is never left half written. It can't be given with --prune-below,
+--max-vocab or --max-word-len, since what they drop would be lost from
+FILE for good.
+
+    spellchecker --append big.model more.txt < /dev/null
+
+--dump-dictionary FILE writes every word the model knows to FILE, one
+per line followed by a tab and its count, most frequent first.
+--dictionary FILE builds the model from such a file instead of a
+corpus, which also reads most published frequency lists.
+--from-freq FILE builds it from the frequency table freq writes, a
+word, a tab, a colon, a tab and its count on each line, so that the
+two tools make a pipeline:
+
+    freq < big.txt > big.freq
+    spellchecker --from-freq big.freq < words
+
+--hunspell FILE.dic builds it from a Hunspell dictionary (with its
+FILE.aff alongside), expanding each stem's prefixes and suffixes and
+counting every word once.
+
+--lang-model LANG=FILE reads a model saved with --save-model as the
+model for the language LANG, and is given once for each language, so
+that text in any of them can be checked:
+
+    spellchecker --lang-model en=en.model --lang-model de=de.model < words
+
+Each input is checked in the language whose model knows the most of its
+words, or the first given of those that know as many. A list of words
+is read whole first, so it is all checked in one language; --document
+and --stream check as they read, so each line is checked in the
+language of its own words, or, if they don't tell, in that of the line
+before. --lang LANG checks in LANG whatever the input, and reads no
+other model; --lang auto, the default, guesses. Every model is checked
+with the same options. They can't be used with a command, --fix,
+--interactive, --watch, --lsp, --save-model or --dump-dictionary.
+
+--bundle DIR reads a language bundle: a directory whose bundle.toml
+names the language (the directory's name if not), the word list, model
+or Hunspell dictionary of its words, and, optionally, the rows of its
+keyboard and the punctuation to trim from its words:
+
+    language = "de"
+    dictionary = "de.dict"
+    [keyboard]
+    rows = ["qwertzuiopü", "asdfghjklöä", "yxcvbnm"]
+    [punctuation]
+    trim = "«»„"
+
+An edit between neighbouring keys costs half as much as any other,
+unless --costs or --mode say otherwise, and a keyboard can only be
+searched with the trie engine. Bundles are languages as --lang-model's
+models are, and may be given alongside them.
+
+OUTPUT:
+
+One line per input word: the word alone if the corpus knows it, the word
+and its correction if there is one within two edits, and the word and a
+dash if there isn't:
+
+    hello
+    wor, world
+    zzzzzz, -
+
+With --suggestions N, up to N candidates are listed instead of one,
+closest first, then most frequent:
+
+    wor, word work world
+
+--format scores writes a line for every suggestion instead, with how
+many edits it is from the word and how confident the checker is in it,
+from 0 to 1 (its share of the likelihood of all the candidates it
+found; see spellchecker::Suggestion), so that other tools can decide
+for themselves which corrections to take. A known word is its own
+suggestion:
+
+    hello, hello, 0, 1.000
+    wor, world, 1, 0.625
+    wor, word, 1, 0.375
+    zzzzzz, -
+
+--format scored keeps to a line per word, with each of its suggestions,
+best first, followed by the same figures and its count, as
+distance:count:confidence, to see at a glance why the first one won:
+
+    hello
+    wor, world 1:5:0.625, word 1:3:0.375
+    zzzzzz, -
+
+--format json writes a JSON object per word instead: the word, its
+status (correct, corrected, suspect or unknown), and the best
+suggestion with its distance, count and confidence, followed, when
+there are several suggestions, by all of them:
+
+    {"word":"hello","status":"correct"}
+    {"word":"wo","status":"corrected","suggestion":"who","distance":1,"count":2,"confidence":0.912}
+    {"word":"zzzzzz","status":"unknown","suggestion":null}
+
+--format csv and --format tsv write a table, for spreadsheets: a
+header row, then a row per suggestion (or a row with only the word and
+its status when there are none), with the columns always in this
+order:
+
+    word,status,suggestion,distance,count,confidence
+    hello,correct,,,,
+    wo,corrected,who,1,2,0.912
+    zzzzzz,unknown,,,,
+
+Fields with commas, quotes or line breaks in them are quoted in CSV;
+tabs, line breaks and backslashes are escaped as \t, \n and \\ in TSV.
+
+--format table writes the word, suggestion, distance and count columns
+of that table for people to read instead, lined up, with a suspect
+word marked with a question mark and an unknown one's suggestion as a
+dash. --format table=box draws lines around the cells too:
+
+    ┌────────┬────────────┬──────────┬───────┐
+    │ word   │ suggestion │ distance │ count │
+    ├────────┼────────────┼──────────┼───────┤
+    │ hello  │            │          │       │
+    │ wo     │ who        │        1 │     2 │
+    │ zzzzzz │ -          │          │       │
+    └────────┴────────────┴──────────┴───────┘
+
+As the columns can only be lined up once every word is checked, it
+can't be used with --stream or a command.
+
+--list writes only the words that aren't correct, a line each, without
+their corrections, and exits with status 1 if there were any, so that
+a CI job or a git hook can fail on a misspelling, as with ispell -l.
+An error, such as a file that can't be read, exits with status 2, with
+or without --list.
+With --document, the words are listed as they are written in the text.
+
+--explain writes each suggestion with the edits that make it of the
+word instead, each with where it starts in the word, counting from 0:
+
+    wrod -> word: transpose(r,o)@1
+    helo -> hello: insert(l)@2
+    hallo -> hello: replace(a,e)@1
+
+The edits are the cheapest by --costs or --mode, if either is given, as
+substitute(rn,m)@2 for a run of characters, so that a cost model can be
+checked. It can't be used with a command, --format, --list, --document,
+--fix, --interactive, --watch or --lsp.
+
+--quiet writes nothing at all, to the standard output or to stderr,
+and leaves the exit status to say how it went: 0 if every word was
+correct, 1 if any weren't, and 2 if the options were wrong or a file
+couldn't be read or written, for shell scripts. It can't be used with a
+command, --format, --fix, --interactive, --lsp, --verbose, --progress,
+--timings or --stats.
+
+Normally all the input is read before anything is checked. With
+--stream each word is checked and its line written (and flushed) as
+soon as it is read, so the checker can sit at the end of an unbounded
+pipe or be typed at directly.
+
+--tokenize reads running text rather than a word to a line: each line
+is split into words as the corpus is, trimmed of punctuation (that of
+--punctuation too) and split at hyphens as --hyphens says, and each
+word is checked and written as if it had a line of its own, so that a
+pasted sentence is checked a word at a time rather than as one long
+word:
+
+    $ echo 'Teh quick brown fox.' | spellchecker --tokenize big.txt
+    teh, the
+    quick
+    brown
+    fox
+
+With --stream each line is checked as soon as it is read, in the
+language of its own words. It can't be used with a command, --document,
+--fix, --interactive, --watch or --lsp, which read running text anyway.
+
+--document reads running text instead of a word per line, tokenized
+as the corpus is, and reports only the words it would correct, each
+with the line and column (counting characters from 1) where it starts,
+as compilers report errors, for editors to pick up:
+
+    3:14: wrold -> world
+    7:1: Zzzzzz -> -
+
+The words are written as they appear in the text, and the corrections
+capitalized to match: "Wrold" becomes "World" and "WROLD" "WORLD". With
+--suggestions N up to N corrections follow the arrow, separated by
+spaces. Each line of the text is reported, and the output flushed, as
+soon as it is read.
+
+--filter markdown, with --document, reads the text as Markdown, and
+leaves out what isn't prose: front matter, fenced code blocks, code
+spans, and the URLs of links, autolinks and link definitions, so that
+a README's identifiers and paths aren't reported. Lines and columns
+are still those of the file. --filter text, the default, checks it all.
+
+--filter html reads it as HTML instead, leaving out tags, comments,
+scripts and styles, and decoding entities such as &eacute; and &#233;,
+so that a page saved from the web can be checked as it is. A word
+after an entity in the same run of text, as in "AT&amp;T's", is
+reported at a column that counts the entity as one character.
+
+--filter code checks source code, of which only the comments and
+string literals are prose: the code itself, the markers that open
+comments, and the quotes and escapes of strings are left out. LANG is
+c, the default, whose comments and strings C++, Java, JavaScript and
+Go share; rust, whose block comments nest and whose raw strings can
+hold quotes; or py, with its # comments and triple-quoted strings.
+Identifiers written there are checked a word at a time, split at
+underscores, digits and changes of case, and a misspelled part is
+reported with the identifier it is in, at its own column:
+
+    4:12: parseConfg (Confg) -> Config
+
+--fix writes the text back out instead, as it was but for each word
+the checker is confident of a correction for: one with more than half
+the likelihood of all the candidates (see spellchecker::Suggestion),
+for a word the corpus doesn't know. Punctuation and spacing are kept,
+and the correction takes the word's capitalization, so "Teh" becomes
+"The" and "WROLD" "WORLD". With --dry-run the text isn't written, only
+the lines that would change, before and after, as a diff would show
+them:
+
+    @@ -3 +3 @@
+    -Teh wrold is round.
+    +The world is round.
+
+--annotate writes the text back out as it was, but with the
+corrections of each word --document would report in brackets right
+after it, for a quick look over a document:
+
+    $ echo 'Teh wrold is round.' | spellchecker --annotate big.txt
+    Teh[->The] wrold[->world] is round.
+
+With --suggestions N up to N corrections are given, separated by
+spaces, and a word with none is followed by [->-]. --filter picks out
+the prose as it does for --document, and the rest is written out
+unchecked. It can't be used with a command, --document, --fix,
+--interactive, --watch, --lsp, --stream, --tokenize, --join-words,
+--list, --explain or --format.
+
+--complete reads a prefix to a line instead of a word, and answers
+each, as soon as it is read, with the words the model knows that start
+with it, most frequent first, a line each with a tab and its count as
+--dump-dictionary writes them, and then an empty line, so that a
+corpus can drive the completions of a shell or an editor:
+
+    $ printf 'wor\nspel\n' | spellchecker --complete --suggestions 2 big.txt
+    world       347
+    words       338
+
+    spell       12
+    spelling    5
+
+Up to --suggestions N words are given for each. A prefix is trimmed
+and folded as a word would be; an empty one is answered with the most
+frequent words of all, and one that no word starts with by the empty
+line alone. With several languages, the words are those of the first.
+It can't be used with a command, --document, --annotate, --fix,
+--interactive, --watch, --lsp, --stream, --tokenize, --join-words,
+--list, --explain, --format or --quiet.
+
+--interactive FILE goes through FILE instead of the standard input,
+stopping at each word --document would report to show it, its line,
+and its corrections, numbered, and to ask what to do:
+
+    1:5: wrold
+        The wrold is round.
+        1) world  2) word
+    [1-2, r)eplace, i)gnore, a)dd, x) save and exit, q)uit]?
+
+A number takes that correction; r asks for a replacement to type in; i,
+or an empty answer, leaves the word as it is; a adds it to the personal
+dictionary (creating it if need be), and so to the words taken as
+correct from then on. x stops asking and q
+stops without saving. Unless the session was quit, FILE is then written
+back with the corrections made, by way of a temporary file, so it is
+never left half written. Give --suggestions N for more than one
+correction to choose from.
+
+--watch FILE checks FILE as --document would, and then again every
+time it changes, for as long as the checker runs, so that a text can be
+written with the checker watching in another window. After each check
+comes a line with how many misspellings are new, how many of the last
+check's were fixed, and how many are left, followed by only the new
+ones. --filter says what in it is prose, as with --document:
+
+    draft.md: 1 new, 2 fixed, 3 left
+    12:7: wrold -> world
+
+A misspelling is known by how it is written, not where it is, as the
+text around it moves when it is edited. The standard input isn't read.
+
+`spellchecker repl` loads or trains the model once and then prompts
+for words to check, for as long as the session lasts, answering each
+as the standard input would be. A line may have several words, which
+are read as running text. Lines starting with a colon are commands:
+
+    > wrold
+    wrold, world
+    > :suggest 3 wrod
+    wrod, word world rod
+    > :add wrod
+    > wrod
+    wrod
+    > :quit
+
+:add WORD takes WORD as correct for the rest of the session, :suggest
+N WORD lists up to N corrections for WORD, and :quit (or the end of the
+input) ends the session.
+
+`spellchecker serve` loads or trains the model once too, and answers
+queries over TCP on --listen ADDRESS (default 127.0.0.1:7000), so that
+many clients can share one model in memory. Each connection is served
+on its own thread. A query is a line of words, read as running text
+like a line of the repl, and is answered with a line for each of its
+words, as the standard input would be, then an empty line:
+
+    $ printf 'wrold\nhelo there\n' | nc 127.0.0.1 7000
+    wrold, world
+
+    helo, hello
+    there
+
+A line longer than 64 KiB is answered with an error line and closes the
+connection, and so does a client that sends nothing for five minutes.
+
+`spellcheck-client` sends a list of words to a server this way, a word
+to a query, and prints the answers as `spellchecker` would have printed
+them for the same list, so a script can check against a server instead:
+
+    $ spellcheck-client --connect 127.0.0.1:7000 < words
+
+With --http it speaks HTTP instead, for web pages and programs with an
+HTTP client at hand: POST /check takes a JSON object with the words to
+check, and optionally how many suggestions to give for each, and
+answers with a result for each word, as --format json writes them:
+
+    $ curl -d '{"words":["wrold","hello"],"suggestions":2}' 127.0.0.1:7000/check
+    {"results":[{"word":"wrold","status":"corrected","suggestion":"world",...},
+                {"word":"hello","status":"correct"}]}
+
+A request that isn't understood is answered with a 4xx status and a
+JSON object with an "error" message. Each connection takes a single
+request.
+
+
+--lsp makes the checker a language server instead, speaking the
+Language Server Protocol on the standard input and output, so that
+editors can check documents as they are typed: every word --document
+would report is published as a diagnostic, and its corrections offered
+as quick fixes. Give --suggestions N for more than one fix to choose
+from.
+
+Names and acronyms are seldom in a corpus, and would mostly be
+reported as misspellings. Wherever words are read as running text,
+--capitalized POLICY says what to do with words starting with a
+capital, and --acronyms POLICY with words of two or more letters that
+are all capitals: check them like any other word (check, the default),
+skip them, or check them leniently (lenient), reporting them only when
+there is a correction to offer, so "Wrold" is still corrected but
+"Zanzibar" let be.
+
+--numbers POLICY says what to do with words with a digit in them, all
+digits or mixed, such as "42", "3rd" or "x86_64": check them like any
+other word (check, the default), accept them as correct (accept), or
+skip them (skip), leaving them out of the output as if they weren't
+words at all.
+
+--ignore REGEX takes every word the regular expression matches, anywhere
+in it, as correct without looking it up, so that URLs, email addresses,
+hashes and ticket numbers aren't "corrected" into nonsense. It may be
+given any number of times:
+
+    spellchecker --document --ignore '^https?://' --ignore '^[A-Z]+-[0-9]+$' big.txt
+
+In running text the word is matched as it is written (with the
+punctuation around it trimmed), otherwise as it is lowercased.
+
+--hyphens POLICY says what a hyphenated word such as "well-known" is,
+in the corpora and in running text alike: one word (keep, the default),
+a word for each part (split, so "well" and "known" are trained and
+checked instead), or both: the whole word and its parts are trained,
+and a hyphenated word is checked whole if the corpus has it, otherwise
+part by part, so a novel compound of known words passes while
+"well-knwon" is still corrected.
+
+--punctuation MARKS trims each of the characters of MARKS from either
+end of the words of the corpora and of running text alike, as well as
+the usual marks (the ASCII ones and curly quotes), for text written
+with others, such as em-dashes or guillemets:
+
+    spellchecker --punctuation '«»—' --document french.txt < letter.txt
+
+A bundle's punctuation is trimmed alongside.
+
+--fold-diacritics strips accents and other diacritics from the words of
+the corpora and the input alike, so "café" and "cafe" are the same
+word, which helps with corpora of mixed origins and with input typed
+without accents. Corrections come without them too.
+
+--jobs N trains on N threads, and checks the words on N threads sharing
+one model; the output is in input order all the same. --stream checks
+on a single thread regardless.
+
+--engine picks how candidate corrections are found: trie (the default)
+walks the trained trie, while symspell first builds an index of every
+known word's deletions, which takes a while and a lot of memory but
+makes each lookup much cheaper, and bktree searches a BK-tree of the
+known words. fst makes the trie a finite-state transducer instead,
+which shares the endings of words as well as their beginnings, and
+takes a fraction of the memory, at some cost to each lookup; it can't
+be used with --phonetic, which needs the trie. All give the same
+answers.
+
+--beam N narrows the trie engine's search to the N branches at each
+letter with the most words below them in the corpus, wherever it would
+otherwise try every one. That is much faster on a huge dictionary, but
+can miss a correction among rarer words, so how many words' searches
+were narrowed is reported on stderr. It can't be used with weighted
+edits.
+
+--budget-ms MS gives the trie engine's search for each word no more
+than MS milliseconds, after which it settles for the best correction
+found so far, if any, for editors that would rather have an answer
+soon than the best one. How many words ran out of time is reported on
+stderr. Like --beam, it can't be used with weighted edits.
+
+--adaptive-edits lets a correction be as many edits from a word as its
+length allows, rather than always 2: 1 edit for words of up to 4
+letters, 2 for up to 8 and 3 for longer ones, so that short words aren't
+corrected to something quite unlike them and long ones can be further
+off. The symspell engine still stops at 2. It can't be used with
+weighted edits.
+
+--costs FILE weighs edits by the costs in the TOML file FILE, rather
+than counting them, and picks the cheapest correction within its
+max_cost (see spellchecker::Costs for the format):
+
+    insert  = 2
+    replace = 2
+    max_cost = 4
+    [pairs]
+    as = 1
+
+--mode ocr weighs edits for text that came out of OCR instead: the
+usual misreadings, such as "rn" for "m" or "0" for "o", cost less than
+other edits or nothing. Both work with the trie engine only.
+
+`spellchecker train-errors` learns the costs from the mistakes people
+make instead. It reads logs of corrections, each line a word as it was
+typed and the word meant, separated by spaces or a tab (lines starting
+with # are comments), from the files LOG, or the standard input if
+there are none, and counts the edits each correction took: the
+characters left out, typed twice, swapped, and typed for one another.
+The likeliest kind of edit costs 2, and the others more as they are
+less likely; a replacement of one character by another seen often
+enough costs less than others. It writes the costs, as --costs reads
+them, to --output FILE, or the standard output, and says on stderr how
+many edits of each kind it counted:
+
+    $ spellchecker train-errors --output typing.toml corrections.log
+    spellchecker: 120 corrections: 31 insertions, 24 deletions, 53 replacements, 17 transpositions
+    $ spellchecker --costs typing.toml big.txt < words
+
+--split-compounds falls back to splitting a word into two known words
+when nothing is within reach by editing:
+
+    helloworld, hello world
+
+--accept-inflections takes a word as known when it looks like an
+inflection of a known word, with one of the usual English suffixes
+undone, so that a small corpus with "run" and "try" in it knows
+"running" and "tries" too:
+
+    spellchecker --accept-inflections --dictionary small.dict < words
+
+--join-words reads the input words as running text, and looks for
+words split in two: two words in a row that make a known word together
+are reported on one line, with the word they make, when either of them
+isn't known, or (with --context) when the word they make is commoner in
+the corpora than the two of them in a row. It can't be used with
+--stream.
+
+    some thing, something
+
+--phonetic falls back to words that sound alike (by their Metaphone
+codes) when nothing is within reach by editing, so "fotograf" is
+still corrected to "photograph".
+
+--ranking noisy-channel orders the candidates by how likely each is to
+have been meant, rather than by distance and then count: a word's count
+in the corpus times the chance of typing the input when meaning it,
+which falls with every edit (see spellchecker::error_probability). A
+very common word two edits away can then beat a rare one a single edit
+away.
+
+--smoothing K adds K to every candidate's count before weighing it
+against its edits, so that between rare words the edits decide: with
+raw counts a word seen twice is twice as likely to be meant as one seen
+once, however unlikely its edits, while with --smoothing 10 it is 12 to
+11. Common words hardly notice. It changes the order of the candidates
+only with --ranking noisy-channel, and otherwise only how confident the
+checker is in each (see --format scores). The default is 0.
+
+--context also counts which words follow which in the corpora, and
+reads the input words as running text: of the candidates for a word,
+the ones that often followed the word before it are preferred to the
+ones that are merely common, so "peice" after "world" becomes "peace",
+and after "a", "piece". It needs a corpus, and takes a second pass over
+it.
+
+--real-words, with --context, questions known words as well: a word is
+marked with a question mark, and its likelier alternatives listed, when
+a word close to it often followed the word before it in the corpora,
+and the word itself scores less than --threshold T (default 0.1) times
+as much there:
+
+    over
+    their?, there
+
+--min-count N ignores every word the corpus has fewer than N times, so
+that a typo the corpus happens to contain is neither accepted nor
+offered as a correction. A saved or dumped model still has them all.
+
+--prune-below N drops them from the model as soon as it is trained or
+read instead, so that a saved or dumped model doesn't have them
+either, and --max-vocab M keeps only its M commonest words (the
+first alphabetically of those as common), to bound the memory the model
+takes on a small machine. Either says on stderr how many words it
+dropped, unless --quiet.
+
+    spellchecker --prune-below 2 --max-vocab 50000 --save-model small.model big.txt
+
+--max-word-len N drops the words of more than N characters from the
+model, and takes any such word of the input as correct without looking
+for corrections, which no base64 blob or line of minified code has, and
+which the search takes the longest over. How many it skipped, of each,
+goes on stderr, unless --quiet.
+
+--personal FILE reads a personal dictionary, a file with a word on each
+line (blank lines and lines starting with # are skipped), and takes its
+words as correct, however seldom the corpus has them. They are
+suggested too, ahead of the other corrections as close. Without
+--personal, $XDG_CONFIG_HOME/spellchecker/personal.dict (or
+~/.config/spellchecker/personal.dict) is read if it is there. The
+dictionary is not part of the model, so a saved model doesn't have its
+words.
+
+--abbreviations FILE reads abbreviations to expand, a file with an
+abbreviation on each line, then what it stands for, which may be several
+words (blank lines and lines starting with # are skipped):
+
+    govt   government
+    asap   as soon as possible
+
+An unknown word the file has is corrected to what it stands for, rather
+than to the closest word the corpus has, whether the corpus has it or
+not.
+
+--minimize shares the repeated parts of the trained trie (making it a
+DAWG) before checking, which saves memory on a big corpus, and reports
+how many nodes it saved on stderr. A model of nothing but ASCII is
+searched as a trie keyed by bytes, which is smaller still, and isn't
+minimized. A saved model is always the full trie.
 
 Assumptions:
-"]
+
+ - Input words are trimmed of surrounding whitespace and lowercased;
+   blank lines are skipped. Typographic apostrophes are read as
+   straight ones, in the input and the corpus alike, so "don’t" is
+   "don't"; apostrophes and quotes around a word in running text are
+   trimmed, but not those inside it.
+
+ - Words are brought to Unicode normal form C, in the corpus and the
+   input alike, so an accented letter is the same however it was
+   composed.
+
+ - Both the corpus and the input are decoded according to --encoding
+   (default auto, see textkit::encoding).
+
+ - --progress shows training progress on stderr: how much of the corpus
+   is read, and how many words, and different words, are found so far.
+   With --jobs, each thread only knows the words it found, so the count
+   of different words is that of the thread that found most, and falls
+   short.
+
+ - --timings prints the time spent reading, tokenizing, training (or
+   loading), merging, indexing, minimizing, searching for corrections
+   and writing to stderr at exit. With --jobs tokenizing happens on the
+   training threads and is charged to training. serve, --lsp and
+   --watch, which run until they are stopped, print the time it took to
+   get the model ready instead, before they start. spellcheck-client
+   and serve-grpc take --timings too.
+
+ - --stats prints what checking cost to stderr at exit: how long the
+   model took to get ready (trained, loaded or mapped) and how much
+   memory it takes, how long each word took to check (on average, for
+   the median and the 99th percentile, and at most), how many nodes each
+   search visited, with the trie and fst engines, and how often a word
+   of a list had been checked already. A list's words are only checked
+   once each, however often they come, unless --context or a ranker
+   makes the word before them matter. Percentiles are to within a power
+   of two. serve prints the totals so far each time a client is done.
+*/
+
+extern crate notify;
+extern crate regex;
+extern crate spellchecker;
+extern crate textkit;
+
+mod cli;
+
+use std::env;
+use std::fmt;
+use std::fs::File;
+use std::io::{self,BufRead,BufReader,Read,stdin};
+use std::io::{Write, stdout};
+use std::net::TcpListener;
+use std::path::PathBuf;
+use std::process::exit;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use spellchecker::{dump_dictionary, load_bundle, load_model, load_personal, Languages, Profile,
+                   SpellChecker};
+use textkit::encoding::Decoder;
+use textkit::timings::Timings;
+
+use cli::diff::diff;
+use cli::document::{annotate_document, check_document, fix_document};
+use cli::format::Format;
+use cli::interactive::{correct_file, repl};
+use cli::lsp::serve_lsp;
+use cli::options::{parse_args, Command, Options};
+use cli::serve::serve;
+use cli::setup::{build_checker, configure_checker, default_personal, get_model, mapped_model,
+                 prepare_model, replace_model, save, train_context};
+use cli::train_errors::train_errors;
+use cli::watch::watch_file;
+use cli::words::{check_stream, checker_for, complete_prefixes, detect_lines, line_words,
+                 read_words, write_correct_words};
+
+const USAGE: &str = "\
+usage: spellchecker [--encoding auto|utf8|latin1|utf16] [--progress] [--timings] [--words FILE]...
+                    [--suggestions N] [--save-model FILE | --append FILE] [--stream]
+                    [--jobs N] [--engine trie|symspell|bktree|fst] [--beam N] [--budget-ms MS]
+                    [--costs FILE | --mode text|ocr] [--phonetic] [--adaptive-edits]
+                    [--hyphens keep|split|both] [--punctuation MARKS] [--tokenize] [--complete]
+                    [--ranking distance|noisy-channel] [--context] [--fold-diacritics] [--minimize]
+                    [--real-words] [--threshold T] [--split-compounds] [--ignore REGEX]...
+                    [--smoothing K] [--accept-inflections] [--stats]
+                    [--join-words] [--min-count N] [--prune-below N] [--max-vocab M]
+                    [--max-word-len N] [--corpus FILE]...
+                    [--list | --explain | --format text|scores|scored|json|csv|tsv|table[=box]]
+                    [--dump-dictionary FILE] [--glob PATTERN] [--verbose] [--personal FILE]
+                    [--capitalized check|skip|lenient] [--acronyms check|skip|lenient]
+                    [--numbers check|accept|skip] [--abbreviations FILE] [--quiet]
+                    [(--document | --annotate) [--filter text|markdown|html|code[=LANG]] |
+                     --fix [--dry-run] | --interactive FILE | --watch FILE | --lsp]
+                    (CORPUS... | --load-model FILE | --dictionary FILE | --from-freq FILE |
+                     --hunspell FILE | (--lang-model LANG=FILE | --bundle DIR)...
+                     [--lang auto|LANG])
+                    [-- WORDFILE...]
+       spellchecker repl [OPTIONS] (CORPUS... | --load-model FILE | ...)
+       spellchecker serve [--listen ADDRESS] [--http] [OPTIONS]
+                          (CORPUS... | --load-model FILE | ...)
+       spellchecker train-errors [--encoding auto|utf8|latin1|utf16] [--output FILE] [LOG...]
+       spellchecker diff [--ratio R] [--min-count N] MODEL_A MODEL_B";
+
+/// Set by --quiet, which leaves the exit status to say how it went.
+static QUIET: AtomicBool = AtomicBool::new(false);
+
 fn main() {
-}
+    let mut args = env::args().skip(1).peekable();
+    match args.peek().map(String::as_str) {
+        Some("train-errors") => {
+            args.next();
+            train_errors(args);
+        }
+        Some("diff") => {
+            args.next();
+            diff(args);
+        }
+        _ => {}
+    }
 
-type CountTable = std::collections::HashMap<String, usize>;
+    let options = parse_args(args).unwrap_or_else(|msg| {
+        if !env::args().any(|arg| arg == "--quiet") {
+            eprintln!("spellchecker: {}", msg);
+            eprintln!("{}", USAGE);
+        }
+        exit(2);
+    });
+    QUIET.store(options.quiet, Ordering::Relaxed);
 
-#[allow(dead_code)]
-fn increment_word(map: &mut CountTable, word: String) {
-    *map.entry(word).or_insert(0) += 1;
-}
+    let mut timings = Timings::new(options.timings);
+    let started = Instant::now();
 
-#[cfg(test)]
-mod increment_word_tests {
-    use super::{increment_word, CountTable};
+    let personal = options.personal.as_ref().map(PathBuf::from).or_else(default_personal);
+    let personal_words = personal.as_ref().and_then(|path| match load_personal(path) {
+        Ok(words) => Some(words),
+        Err(ref e) if e.kind() == io::ErrorKind::NotFound => None,
+        Err(e) => fail(format_args!("{}: {}", path.display(), e)),
+    });
 
-    #[test]
-    fn inserts_if_empty() {
-        let mut h = CountTable::new();
-        increment_word(&mut h, "one".to_owned());
+    let mut languages = Languages::new();
+    let mapped = match options.load_model {
+        Some(ref path) => mapped_model(path, &options, &mut timings),
+        None => None,
+    };
+    if let Some(model) = mapped {
+        let checker = configure_checker(SpellChecker::from_transducer(model), None,
+                                        personal_words, None, &options, &mut timings);
+        languages.add("", Arc::new(checker));
+    } else if options.lang_models.is_empty() && options.bundles.is_empty() {
+        let model = get_model(&options, &mut timings);
+        let model = prepare_model(model, &options, None, &mut timings);
+        let context =
+            if options.context { Some(train_context(&options, &mut timings)) } else { None };
+        if let Some(ref path) = options.save_model {
+            save(&model, path, &options)
+                .unwrap_or_else(|e| fail(format_args!("{}: {}", path, e)));
+        }
+        if let Some(ref path) = options.append {
+            replace_model(&model, path, &options)
+                .unwrap_or_else(|e| fail(format_args!("{}: {}", path, e)));
+        }
+        if let Some(ref path) = options.dump_dictionary {
+            dump_dictionary(&model, path)
+                .unwrap_or_else(|e| fail(format_args!("{}: {}", path, e)));
+        }
+        let checker = build_checker(model, context, personal_words, None, &options, &mut timings);
+        languages.add("", Arc::new(checker));
+    } else {
+        // With --lang, only its model is needed.
+        for (name, path) in &options.lang_models {
+            if options.lang.as_ref().is_some_and(|lang| lang != name) {
+                continue;
+            }
+            let checker = match mapped_model(path, &options, &mut timings) {
+                Some(model) => configure_checker(SpellChecker::from_transducer(model), None,
+                                                 personal_words.clone(), None, &options,
+                                                 &mut timings),
+                None => {
+                    let model = timings.time("load", || load_model(path))
+                        .unwrap_or_else(|e| fail(format_args!("{}: {}", path, e)));
+                    let model = prepare_model(model, &options, None, &mut timings);
+                    build_checker(model, None, personal_words.clone(), None, &options,
+                                  &mut timings)
+                }
+            };
+            languages.add(name, Arc::new(checker));
+        }
+        for dir in &options.bundles {
+            let bundle = load_bundle(dir).unwrap_or_else(|e| fail(format_args!("{}: {}", dir, e)));
+            if options.lang.as_ref().is_some_and(|lang| *lang != bundle.language) {
+                continue;
+            }
+            if languages.get(&bundle.language).is_some() {
+                fail(format_args!("{}: a second model for {}", dir, bundle.language));
+            }
+            let model = timings.time("load", || bundle.load_model(options.encoding))
+                .unwrap_or_else(|e| fail(format_args!("{}: {}", dir, e)));
+            let model = prepare_model(model, &options, Some(&bundle), &mut timings);
+            let checker = build_checker(model, None, personal_words.clone(), Some(&bundle),
+                                        &options, &mut timings);
+            languages.add(&bundle.language, Arc::new(checker));
+        }
+        if let (Some(lang), true) = (&options.lang, languages.is_empty()) {
+            fail(format_args!("no --lang-model or --bundle for --lang {}", lang));
+        }
+    }
+    let ready = started.elapsed();
+    let checker = Arc::clone(languages.iter().next().expect("a language").1);
+    let mut profile = Profile::default();
 
-        assert_eq!(Some(&1), h.get("one"));
-        assert_eq!(1, h.len());
+    match options.command {
+        Command::Check => {}
+        Command::Repl => {
+            let stdin = stdin();
+            repl(&checker, options.suggestions, options.format, &mut stdin.lock(), &mut stdout(),
+                 &mut profile)
+                .unwrap_or_else(|e| fail(format_args!("{}", e)));
+            report_cuts(&profile);
+            timings.report();
+            if options.stats {
+                report_stats(&languages, ready, &profile);
+            }
+            return;
+        }
+        Command::Serve => {
+            let listener = TcpListener::bind(&options.listen)
+                .unwrap_or_else(|e| fail(format_args!("{}: {}", options.listen, e)));
+            eprintln!("spellchecker: listening on {}", options.listen);
+            timings.report();
+            serve(&checker, listener, options.suggestions, options.format, options.http,
+                  options.stats);
+            return;
+        }
+    }
+    if options.lsp {
+        timings.report();
+        let stdin = stdin();
+        let shut_down = serve_lsp(&checker, options.suggestions, &mut stdin.lock(), &mut stdout())
+            .unwrap_or_else(|e| fail(format_args!("{}", e)));
+        exit(if shut_down { 0 } else { 1 });
+    }
+    if let Some(ref path) = options.interactive {
+        correct_file(&checker, path, options.suggestions, options.encoding, personal.as_deref(),
+                     &mut profile)
+            .unwrap_or_else(|e| fail(format_args!("{}: {}", path, e)));
+        report_cuts(&profile);
+        timings.report();
+        if options.stats {
+            report_stats(&languages, ready, &profile);
+        }
+        return;
     }
 
-    #[test]
-    fn increments_if_present() {
-        let mut under_test = fixture();
-        let mut expected   = fixture();
+    if let Some(ref path) = options.watch {
+        timings.report();
+        watch_file(&languages, path, options.filter, options.suggestions, options.encoding,
+                   &mut stdout())
+            .unwrap_or_else(|e| fail(format_args!("{}: {}", path, e)));
+        return;
+    }
 
-        increment_word(&mut under_test, "three".to_owned());
-        expected.insert("three".to_owned(), 4);
+    let mut output: Box<dyn Write> =
+        if options.quiet { Box::new(io::sink()) } else { Box::new(stdout()) };
+    let mut misspelled = 0;
+    if options.words.is_empty() {
+        let input = Decoder::new(stdin(), options.encoding);
+        misspelled = check_input(&languages, &options, input, &mut output, &mut timings,
+                                 &mut profile)
+            .unwrap_or_else(|e| fail_output(&e, format_args!("{}", e)));
+    }
+    for (i, path) in options.words.iter().enumerate() {
+        let header = format!("{}==> {} <==\n", if i == 0 { "" } else { "\n" }, path);
+        misspelled += File::open(path)
+            .and_then(|file| {
+                timings.time("write", || output.write_all(header.as_bytes()))?;
+                let input = Decoder::new(file, options.encoding);
+                check_input(&languages, &options, input, &mut output, &mut timings, &mut profile)
+            })
+            .unwrap_or_else(|e| fail_output(&e, format_args!("{}: {}", path, e)));
+    }
+    if !options.quiet {
+        report_cuts(&profile);
+    }
+    timings.report();
+    if options.stats {
+        report_stats(&languages, ready, &profile);
+    }
+    if (options.list || options.quiet) && misspelled > 0 {
+        exit(1);
+    }
+}
 
-        assert_eq!(expected, under_test);
+/// Checks the words on `input` as the options say, in whichever of
+/// `languages` they are in, writing the results to `output`, and returns
+/// how many weren't correct, adding what checking them cost to `profile`.
+fn check_input<R: Read, W: Write>(languages: &Languages, options: &Options, input: R,
+                                  output: &mut W, timings: &mut Timings, profile: &mut Profile)
+                                  -> io::Result<usize> {
+    let format = match options.format {
+        _ if options.list => Format::List,
+        _ if options.explain => Format::Explain,
+        format => format,
+    };
+    if options.fix {
+        let (_, checker) = languages.iter().next().expect("a language");
+        fix_document(checker, input, options.dry_run, output, timings, profile)?;
+        Ok(0)
+    } else if options.document {
+        check_document(languages, input, options.filter, options.list, options.suggestions,
+                       output, timings, profile)
+    } else if options.annotate {
+        annotate_document(languages, input, options.filter, options.suggestions, output, timings,
+                          profile)
+    } else if options.complete {
+        let (_, checker) = languages.iter().next().expect("a language");
+        complete_prefixes(checker, input, options.suggestions, output, timings)?;
+        Ok(0)
+    } else if options.stream {
+        check_stream(languages, input, options.suggestions, format, options.tokenize, output,
+                     timings, profile)
+    } else {
+        let (checker, mut words) = if options.tokenize {
+            let lines = timings.time("read", || {
+                BufReader::new(input).lines().collect::<io::Result<Vec<_>>>()
+            })?;
+            let checker = checker_for(languages, detect_lines(languages, &lines));
+            let words = timings.time("tokenize", || {
+                lines.iter().flat_map(|line| line_words(checker, line)).collect()
+            });
+            (checker, words)
+        } else {
+            let words = timings.time("read", || read_words(input))?;
+            let language = languages.detect_words(words.iter().map(String::as_str));
+            (checker_for(languages, language), words)
+        };
+        words.retain(|word| !checker.is_skipped(word));
+        write_correct_words(checker, &words, options.suggestions, format, options.jobs, output,
+                            timings, profile)
     }
+}
 
-    #[test]
-    fn insert_if_absent() {
-        let mut under_test = fixture();
-        let mut expected   = fixture();
+/// Says on stderr what went wrong, unless --quiet, and exits with status
+/// 2, as 1 means that words weren't correct.
+fn fail(message: fmt::Arguments) -> ! {
+    exit(failure(message, QUIET.load(Ordering::Relaxed), &mut io::stderr()));
+}
+
+/// Writes `message` to `stderr` unless `quiet`, and returns the status to
+/// exit with.
+fn failure<W: Write>(message: fmt::Arguments, quiet: bool, stderr: &mut W) -> i32 {
+    if !quiet {
+        let _ = writeln!(stderr, "spellchecker: {}", message);
+    }
+    2
+}
 
-        increment_word(&mut under_test, "one".to_owned());
-        expected.insert("one".to_owned(), 1);
+/// As `fail`, but with no more said when `e` is that the output was
+/// closed, as by `head` once it has the lines it wants.
+fn fail_output(e: &io::Error, message: fmt::Arguments) -> ! {
+    if e.kind() == io::ErrorKind::BrokenPipe {
+        exit(0);
+    }
+    fail(message)
+}
 
-        assert_eq!(expected, under_test);
+/// Says on stderr how many words --beam narrowed the search for, how many
+/// ran out of the time --budget-ms gives, and how many were longer than
+/// --max-word-len, if any, as `profile` counted them.
+fn report_cuts(profile: &Profile) {
+    let plural = |n| if n == 1 { "" } else { "s" };
+    let pruned = profile.pruned();
+    if pruned > 0 {
+        eprintln!("spellchecker: --beam narrowed the search for {} word{}", pruned,
+                  plural(pruned));
     }
+    let timed_out = profile.timed_out();
+    if timed_out > 0 {
+        eprintln!("spellchecker: {} word{} ran out of --budget-ms", timed_out,
+                  plural(timed_out));
+    }
+    let skipped = profile.skipped();
+    if skipped > 0 {
+        eprintln!("spellchecker: skipped {} word{} longer than --max-word-len", skipped,
+                  plural(skipped));
+    }
+}
 
-    fn fixture() -> CountTable {
-        let mut h = CountTable::new();
-        h.insert("two".to_owned(), 2);
-        h.insert("three".to_owned(), 3);
+/// Says on stderr, for --stats, how long the models took to get `ready`,
+/// the engine and size of each, and what checking with them cost, as
+/// `profile` has it.
+fn report_stats(languages: &Languages, ready: Duration, profile: &Profile) {
+    eprintln!("stats:");
+    eprintln!("  {:<10}{:.3?}", "ready", ready);
+    for (name, checker) in languages.iter() {
+        let label = if name.is_empty() { "model".to_owned() } else { format!("model {}", name) };
+        eprintln!("  {:<10}{}, {}", label, checker.engine(), checker.stats());
+    }
+    eprint!("{}", profile);
+}
 
-        assert_eq!(None, h.get("one"));
-        assert_eq!(Some(&2), h.get("two"));
-        assert_eq!(Some(&3), h.get("three"));
-        assert_eq!(2, h.len());
+#[cfg(test)]
+mod failure_tests {
+    use super::failure;
+
+    #[test]
+    fn exits_2_quiet_or_not() {
+        // 1 is for words that weren't correct, under --list as well.
+        let mut stderr = Vec::new();
+        assert_eq!(2, failure(format_args!("/nonexistent: No such file"), false, &mut stderr));
+        assert_eq!("spellchecker: /nonexistent: No such file\n",
+                   String::from_utf8(stderr).unwrap());
 
-        h
+        let mut stderr = Vec::new();
+        assert_eq!(2, failure(format_args!("/nonexistent: No such file"), true, &mut stderr));
+        assert!(stderr.is_empty());
     }
 }
@@ -0,0 +1,341 @@
+/*!
+Saving and loading trained models.
+
+A model file is the magic bytes `SPCK`, a format version byte, and then
+the trie in preorder. Each node is its word count, its number of
+children, and then for each child the child's character followed by the
+child node. All numbers, characters included, are unsigned LEB128
+varints, so a typical node costs three or four bytes. Children are
+written in character order, so the same model always makes the same
+file.
+
+A `Transducer` is saved as version 2 instead: the magic bytes and the
+version byte, the number of its states and the most characters in a
+word, each as eight bytes, little-endian, and then the transducer's own
+bytes. Its states point at each other by offsets into those bytes, so
+`map_model` maps the file into memory and searches it where it is, with
+nothing to read or build first, and the operating system reads in only
+the parts searched. `load_model` reads either version into a trie.
+*/
+
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+use std::sync::Arc;
+
+use memmap2::Mmap;
+
+use transducer::{Bytes, Transducer};
+use trie::{NodeId, Trie, ROOT};
+
+const MAGIC:   &[u8; 4] = b"SPCK";
+const VERSION: u8       = 1;
+/// The version of a saved `Transducer`.
+const MAPPED_VERSION: u8 = 2;
+/// How many bytes of a saved `Transducer` come before the transducer.
+const MAPPED_HEADER: usize = 21;
+
+impl Trie {
+    /// Writes the model to `writer` in the format described above.
+    pub fn save<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writer.write_all(MAGIC)?;
+        writer.write_all(&[VERSION])?;
+        write_node(self, &mut writer)?;
+        writer.flush()
+    }
+
+    /// Reads a model written by `save`, or by `Transducer::save`.
+    pub fn load<R: Read>(mut reader: R) -> io::Result<Trie> {
+        let mut header = [0; 5];
+        reader.read_exact(&mut header).map_err(|_| invalid("not a model file"))?;
+
+        if &header[..4] != MAGIC {
+            return Err(invalid("not a model file"));
+        }
+        match header[4] {
+            VERSION => read_node(&mut reader),
+            MAPPED_VERSION => {
+                let mut bytes = header.to_vec();
+                reader.read_to_end(&mut bytes)?;
+                let mut model = Trie::new();
+                for (word, count) in read_transducer(Bytes::Owned(bytes))?.words()? {
+                    model.insert_with_count(&word, count);
+                }
+                Ok(model)
+            }
+            version => Err(invalid(&format!("unsupported model version {}", version))),
+        }
+    }
+}
+
+impl Transducer {
+    /// Writes the transducer to `writer` in the format described above.
+    pub fn save<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        let stats = self.stats();
+        writer.write_all(MAGIC)?;
+        writer.write_all(&[MAPPED_VERSION])?;
+        writer.write_all(&(stats.nodes as u64).to_le_bytes())?;
+        writer.write_all(&(stats.depth as u64).to_le_bytes())?;
+        writer.write_all(self.as_bytes())?;
+        writer.flush()
+    }
+}
+
+/// Saves `model` to the file at `path`.
+pub fn save_model<P: AsRef<Path>>(model: &Trie, path: P) -> io::Result<()> {
+    model.save(BufWriter::new(File::create(path)?))
+}
+
+/// Saves `model` to the file at `path`, to be mapped by `map_model`.
+pub fn save_transducer<P: AsRef<Path>>(model: &Transducer, path: P) -> io::Result<()> {
+    model.save(BufWriter::new(File::create(path)?))
+}
+
+/// Loads a model from the file at `path`.
+pub fn load_model<P: AsRef<Path>>(path: P) -> io::Result<Trie> {
+    Trie::load(BufReader::new(File::open(path)?))
+}
+
+/// Maps the transducer saved in the file at `path` into memory, to be
+/// searched where it is. Only the header is read.
+pub fn map_model<P: AsRef<Path>>(path: P) -> io::Result<Transducer> {
+    let file = File::open(path)?;
+    // SAFETY: the map is only read, and like any reader of the file,
+    // counts on nothing else changing it meanwhile.
+    let map = unsafe { Mmap::map(&file)? };
+    read_transducer(Bytes::Mapped(Arc::new(map), 0))
+}
+
+/// Whether the file at `path` is a saved `Transducer`, which `map_model`
+/// can map.
+pub fn is_mappable<P: AsRef<Path>>(path: P) -> io::Result<bool> {
+    let mut header = [0; 5];
+    match File::open(path)?.read_exact(&mut header) {
+        Ok(()) => Ok(&header[..4] == MAGIC && header[4] == MAPPED_VERSION),
+        Err(ref e) if e.kind() == io::ErrorKind::UnexpectedEof => Ok(false),
+        Err(e) => Err(e),
+    }
+}
+
+/// The transducer saved in `bytes`, header and all.
+fn read_transducer(bytes: Bytes) -> io::Result<Transducer> {
+    let (nodes, depth, start) = {
+        let header = bytes.as_ref();
+        if header.len() < MAPPED_HEADER || &header[..4] != MAGIC {
+            return Err(invalid("not a model file"));
+        }
+        if header[4] != MAPPED_VERSION {
+            return Err(invalid(&format!("model version {} can't be mapped", header[4])));
+        }
+        let number = |at: usize| {
+            let mut n = [0; 8];
+            n.copy_from_slice(&header[at..at + 8]);
+            u64::from_le_bytes(n) as usize
+        };
+        (number(5), number(13), MAPPED_HEADER)
+    };
+    let bytes = match bytes {
+        Bytes::Owned(mut bytes) => Bytes::Owned(bytes.split_off(start)),
+        Bytes::Mapped(map, at) => Bytes::Mapped(map, at + start),
+    };
+    Transducer::from_bytes(bytes, nodes, depth).map_err(|e| invalid(&format!("bad model: {}", e)))
+}
+
+// Both directions keep an explicit stack, so a model with very long words
+// can't overflow the call stack.
+
+fn write_node<W: Write>(model: &Trie, writer: &mut W) -> io::Result<()> {
+    let mut stack = vec![(None, ROOT)];
+
+    while let Some((c, id)) = stack.pop() {
+        let node = model.node(id);
+        if let Some(c) = c {
+            write_varint(writer, c as u64)?;
+        }
+        write_varint(writer, node.count as u64)?;
+        write_varint(writer, node.children.len() as u64)?;
+
+        stack.extend(node.children.iter().rev().map(|&(c, child)| (Some(c), child)));
+    }
+
+    Ok(())
+}
+
+fn read_node<R: Read>(reader: &mut R) -> io::Result<Trie> {
+    let mut model = Trie::new();
+
+    // Each entry is a node still being read, and how many of its children
+    // are still to come.
+    let children = read_counts(reader, &mut model, ROOT)?;
+    let mut stack: Vec<(NodeId, u64)> = vec![(ROOT, children)];
+
+    while let Some(top) = stack.last_mut() {
+        if top.1 == 0 {
+            stack.pop();
+            continue;
+        }
+
+        top.1 -= 1;
+        let parent = top.0;
+        let c = read_char(reader)?;
+        if model.node(parent).child(c).is_some() {
+            return Err(invalid("repeated character in model"));
+        }
+
+        let node = model.child_or_insert(parent, c);
+        let children = read_counts(reader, &mut model, node)?;
+        stack.push((node, children));
+    }
+
+    Ok(model)
+}
+
+/// Reads `node`'s count into `model`, and returns how many children it
+/// has.
+fn read_counts<R: Read>(reader: &mut R, model: &mut Trie, node: NodeId) -> io::Result<u64> {
+    let count = read_varint(reader)? as usize;
+    model.add_count(node, count);
+    read_varint(reader)
+}
+
+fn read_char<R: Read>(reader: &mut R) -> io::Result<char> {
+    let code = read_varint(reader)?;
+    if code > u64::from(u32::MAX) {
+        return Err(invalid("bad character in model"));
+    }
+    std::char::from_u32(code as u32).ok_or_else(|| invalid("bad character in model"))
+}
+
+fn write_varint<W: Write>(writer: &mut W, mut n: u64) -> io::Result<()> {
+    loop {
+        let byte = (n & 0x7F) as u8;
+        n >>= 7;
+        if n == 0 {
+            return writer.write_all(&[byte]);
+        }
+        writer.write_all(&[byte | 0x80])?;
+    }
+}
+
+fn read_varint<R: Read>(reader: &mut R) -> io::Result<u64> {
+    let mut n = 0;
+
+    for shift in (0..64).step_by(7) {
+        let mut byte = [0];
+        reader.read_exact(&mut byte).map_err(|_| invalid("truncated model"))?;
+
+        n |= u64::from(byte[0] & 0x7F) << shift;
+        if byte[0] & 0x80 == 0 {
+            return Ok(n);
+        }
+    }
+
+    Err(invalid("bad number in model"))
+}
+
+fn invalid(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+#[cfg(test)]
+mod model_tests {
+    use std::env;
+    use std::fs;
+
+    use super::{is_mappable, load_model, map_model, read_varint, save_model, save_transducer,
+                write_varint};
+    use transducer::Transducer;
+    use trie::Trie;
+
+    #[test]
+    fn varints_round_trip() {
+        for &n in &[0, 1, 127, 128, 300, 1 << 40, u64::MAX] {
+            let mut buf = Vec::new();
+            write_varint(&mut buf, n).unwrap();
+            assert_eq!(n, read_varint(&mut &buf[..]).unwrap());
+        }
+    }
+
+    #[test]
+    fn round_trips() {
+        let model = fixture();
+        let mut buf = Vec::new();
+        model.save(&mut buf).unwrap();
+
+        assert_eq!(model, Trie::load(&buf[..]).unwrap());
+    }
+
+    #[test]
+    fn output_is_deterministic() {
+        let mut a = Vec::new();
+        let mut b = Vec::new();
+        fixture().save(&mut a).unwrap();
+        fixture().save(&mut b).unwrap();
+
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn round_trips_long_words() {
+        let word: String = "abcdefghij".chars().cycle().take(10_000).collect();
+        let mut model = Trie::new();
+        model.insert(&word);
+        model.insert("abc");
+
+        let mut buf = Vec::new();
+        model.save(&mut buf).unwrap();
+        let loaded = Trie::load(&buf[..]).unwrap();
+
+        assert_eq!(1, loaded.search(&word));
+        assert_eq!(1, loaded.search("abc"));
+        assert_eq!(0, loaded.search("ab"));
+    }
+
+    #[test]
+    fn rejects_garbage() {
+        assert!(Trie::load(&b""[..]).is_err());
+        assert!(Trie::load(&b"hello world"[..]).is_err());
+        assert!(Trie::load(&b"SPCK\x09\x00\x00"[..]).is_err());
+    }
+
+    #[test]
+    fn rejects_truncated_model() {
+        let mut buf = Vec::new();
+        fixture().save(&mut buf).unwrap();
+        buf.pop();
+
+        assert!(Trie::load(&buf[..]).is_err());
+    }
+
+    #[test]
+    fn maps_transducers() {
+        let path = env::temp_dir().join(format!("spellchecker-model-{}", std::process::id()));
+        let fst = Transducer::from_trie(&fixture());
+        save_transducer(&fst, &path).unwrap();
+
+        assert!(is_mappable(&path).unwrap());
+        let mapped = map_model(&path).unwrap();
+        assert_eq!(fst.stats(), mapped.stats());
+        assert_eq!((2, 1, 0), (mapped.search("hello"), mapped.search("café"),
+                               mapped.search("hel")));
+        assert_eq!(fst.search_with_k_edit("wrld", 2), mapped.clone().search_with_k_edit("wrld", 2));
+        assert_eq!(fixture(), load_model(&path).unwrap());
+
+        save_model(&fixture(), &path).unwrap();
+        assert!(!is_mappable(&path).unwrap());
+        assert!(map_model(&path).is_err());
+        fs::write(&path, b"SPCK\x02short").unwrap();
+        assert!(map_model(&path).is_err());
+        fs::write(&path, b"").unwrap();
+        assert!(!is_mappable(&path).unwrap());
+        fs::remove_file(&path).unwrap();
+    }
+
+    fn fixture() -> Trie {
+        let mut t = Trie::new();
+        for word in &["hello", "help", "hello", "world", "café", ""] {
+            t.insert(word);
+        }
+        t
+    }
+}
@@ -0,0 +1,75 @@
+/*!
+Personal dictionaries: words a user wants taken as correct.
+
+A personal dictionary is a plain text file with a word on each line.
+Words are lowercased the way the corpus tokenizer does it, and blank
+lines and lines starting with `#` are skipped, so the file can be kept
+by hand as well as grown by `append_personal`.
+*/
+
+use std::collections::HashSet;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::path::Path;
+
+use textkit::tokenize::fold_word;
+
+/// Reads a personal dictionary from `reader`, which must produce UTF-8.
+pub fn read_personal<R: Read>(reader: R) -> io::Result<HashSet<String>> {
+    let mut words = HashSet::new();
+
+    for line in BufReader::new(reader).lines() {
+        let line = line?;
+        let word = line.trim();
+        if !word.is_empty() && !word.starts_with('#') {
+            words.insert(fold_word(word));
+        }
+    }
+
+    Ok(words)
+}
+
+/// Reads the personal dictionary at `path`.
+pub fn load_personal<P: AsRef<Path>>(path: P) -> io::Result<HashSet<String>> {
+    read_personal(File::open(path)?)
+}
+
+/// Adds `word` to the end of the personal dictionary at `path`, creating
+/// the file, and the directories it is in, if there is none yet.
+pub fn append_personal<P: AsRef<Path>>(path: P, word: &str) -> io::Result<()> {
+    let path = path.as_ref();
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{}", word)
+}
+
+#[cfg(test)]
+mod read_personal_tests {
+    use std::env;
+    use std::fs;
+
+    use super::{append_personal, load_personal, read_personal};
+
+    #[test]
+    fn reads_words() {
+        let words = read_personal(&b"# names\nNathan\n\n  rustacean \nnathan\n"[..]).unwrap();
+        let mut words: Vec<_> = words.into_iter().collect();
+        words.sort();
+        assert_eq!(vec!["nathan", "rustacean"], words);
+    }
+
+    #[test]
+    fn appends_words() {
+        let dir = env::temp_dir().join(format!("spellchecker-personal-{}", std::process::id()));
+        let path = dir.join("config").join("personal.dict");
+
+        append_personal(&path, "rustacean").unwrap();
+        append_personal(&path, "nathan").unwrap();
+        assert_eq!("rustacean\nnathan\n", fs::read_to_string(&path).unwrap());
+        assert_eq!(2, load_personal(&path).unwrap().len());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
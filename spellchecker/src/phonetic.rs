@@ -0,0 +1,230 @@
+/*!
+Words that sound alike.
+
+Some misspellings are too far from the word to be found by editing it
+("fotograf" is four edits from "photograph"), but sound the same. `metaphone`
+reduces a word to a rough code for how it sounds, and a `PhoneticIndex`
+lists the known words by their codes, so a word no edit search can
+correct can still be matched with words that sound like it.
+*/
+
+use std::collections::HashMap;
+
+use distance::edit_distance;
+use trie::rank;
+
+/// The Metaphone code of `word`: its consonant sounds, roughly, with
+/// vowels dropped except at the start. Only the letters a to z count;
+/// anything else is skipped.
+pub fn metaphone(word: &str) -> String {
+    let letters: Vec<char> = word.chars()
+        .filter_map(|c| {
+            let c = c.to_ascii_lowercase();
+            if c.is_ascii_lowercase() { Some(c) } else { None }
+        })
+        .collect();
+    let at = |i: usize| letters.get(i).cloned().unwrap_or('\0');
+    let is_vowel = |c: char| "aeiou".contains(c) && c != '\0';
+    let front_vowel = |c: char| "eiy".contains(c) && c != '\0';
+
+    let mut code = String::new();
+    let mut start = 0;
+
+    // Some beginnings aren't pronounced as they are spelled.
+    match (at(0), at(1)) {
+        ('a', 'e') | ('g', 'n') | ('k', 'n') | ('p', 'n') | ('w', 'r') => start = 1,
+        ('x', _) => {
+            code.push('S');
+            start = 1;
+        }
+        ('w', 'h') => {
+            code.push('W');
+            start = 2;
+        }
+        _ => {}
+    }
+
+    let mut i = start;
+    while i < letters.len() {
+        let c = letters[i];
+        let (prev, next, after) = (if i > 0 { at(i - 1) } else { '\0' }, at(i + 1), at(i + 2));
+        i += 1;
+
+        // Doubled letters sound once, except for "cc", as in "accept".
+        if c == prev && c != 'c' {
+            continue;
+        }
+
+        match c {
+            'a' | 'e' | 'i' | 'o' | 'u' => {
+                if i - 1 == start && code.is_empty() {
+                    code.push(c.to_ascii_uppercase());
+                }
+            }
+            'b' => {
+                // Silent in a final "mb", as in "dumb".
+                if !(prev == 'm' && i == letters.len()) {
+                    code.push('B');
+                }
+            }
+            'c' => {
+                if next == 'i' && after == 'a' || next == 'h' && prev != 's' {
+                    code.push('X');
+                } else if front_vowel(next) {
+                    if prev != 's' {
+                        code.push('S');
+                    }
+                } else {
+                    code.push('K');
+                }
+            }
+            'd' => {
+                if next == 'g' && front_vowel(after) {
+                    code.push('J');
+                    i += 1;
+                } else {
+                    code.push('T');
+                }
+            }
+            'g' => {
+                if next == 'h' && !(i + 1 == letters.len() || is_vowel(after)) {
+                    // Silent, as in "night".
+                } else if next == 'n' && (i + 1 == letters.len()
+                                          || after == 'e' && at(i + 2) == 'd'
+                                             && i + 3 == letters.len()) {
+                    // Silent, as in "sign" and "signed".
+                } else if front_vowel(next) && prev != 'g' {
+                    code.push('J');
+                } else {
+                    code.push('K');
+                }
+            }
+            'h' => {
+                // Part of the sound before it after "c", "g", "p", "s" and
+                // "t", and silent between a vowel and a consonant.
+                if !"cgpst".contains(prev) && (!is_vowel(prev) || is_vowel(next)) {
+                    code.push('H');
+                }
+            }
+            'k' => {
+                if prev != 'c' {
+                    code.push('K');
+                }
+            }
+            'p' => code.push(if next == 'h' { 'F' } else { 'P' }),
+            'q' => code.push('K'),
+            's' => {
+                if next == 'h' || next == 'i' && (after == 'o' || after == 'a') {
+                    code.push('X');
+                } else {
+                    code.push('S');
+                }
+            }
+            't' => {
+                if next == 'i' && (after == 'o' || after == 'a') {
+                    code.push('X');
+                } else if next == 'h' {
+                    code.push('0');
+                } else if !(next == 'c' && after == 'h') {
+                    code.push('T');
+                }
+            }
+            'v' => code.push('F'),
+            'w' | 'y' => {
+                if is_vowel(next) {
+                    code.push(c.to_ascii_uppercase());
+                }
+            }
+            'x' => code.push_str("KS"),
+            'z' => code.push('S'),
+            _ => code.push(c.to_ascii_uppercase()),
+        }
+    }
+
+    code
+}
+
+#[cfg(test)]
+mod metaphone_tests {
+    use super::metaphone;
+
+    #[test]
+    fn codes_sounds() {
+        for &(word, code) in &[("phone", "FN"), ("fone", "FN"), ("knight", "NT"), ("night", "NT"),
+                               ("school", "SKL"), ("thumb", "0M"), ("judge", "JJ"),
+                               ("science", "SNS"), ("xylophone", "SLFN"), ("which", "WX"),
+                               ("wright", "RT"), ("apple", "APL"), ("nation", "NXN"),
+                               ("cherry", "XR"), ("quick", "KK"), ("", "")] {
+            assert_eq!(code, metaphone(word), "{:?}", word);
+        }
+    }
+
+    #[test]
+    fn matches_misspellings() {
+        for &(typo, word) in &[("fone", "phone"), ("nite", "night"), ("nolij", "knowledge"),
+                               ("rite", "write"), ("fysiks", "physics")] {
+            assert_eq!(metaphone(word), metaphone(typo), "{:?} {:?}", typo, word);
+        }
+    }
+
+    #[test]
+    fn skips_other_characters() {
+        assert_eq!(metaphone("dont"), metaphone("don't"));
+        assert_eq!(metaphone("cafe"), metaphone("café"));
+    }
+}
+
+/// The known words, by their `metaphone` codes.
+#[derive(Clone, Debug, Default)]
+pub struct PhoneticIndex {
+    words: HashMap<String, Vec<(String, usize)>>,
+}
+
+impl PhoneticIndex {
+    /// An index of the words of `model`, each with its count, as a `Trie`
+    /// lists them.
+    pub fn new<I: IntoIterator<Item = (String, usize)>>(model: I) -> Self {
+        let mut words: HashMap<String, Vec<(String, usize)>> = HashMap::new();
+        for (word, count) in model {
+            let code = metaphone(&word);
+            if !code.is_empty() {
+                words.entry(code).or_default().push((word, count));
+            }
+        }
+        PhoneticIndex { words }
+    }
+
+    /// The known words that sound like `word`, as `(word, count,
+    /// distance)`, closest first, then most frequent, then alphabetical.
+    pub fn search(&self, word: &str) -> Vec<(String, usize, usize)> {
+        let mut found: Vec<_> = self.words.get(&metaphone(word)).into_iter()
+            .flat_map(|words| words.iter())
+            .map(|&(ref candidate, count)| {
+                (candidate.clone(), count, edit_distance(word, candidate))
+            })
+            .collect();
+        rank(&mut found);
+        found
+    }
+}
+
+#[cfg(test)]
+mod phonetic_index_tests {
+    use super::PhoneticIndex;
+    use trie::Trie;
+
+    #[test]
+    fn finds_words_that_sound_alike() {
+        let mut t = Trie::new();
+        for word in &["phone", "phone", "fan", "fun", "fin", "phony", "bone", "'"] {
+            t.insert(word);
+        }
+        let index = PhoneticIndex::new(&t);
+
+        assert_eq!(("phone".to_owned(), 2, 2), index.search("fone")[0]);
+        let words: Vec<_> = index.search("fone").into_iter().map(|c| c.0).collect();
+        assert_eq!(vec!["phone", "fan", "fin", "fun", "phony"], words);
+        assert!(index.search("xyzzy").is_empty());
+        assert!(index.search("").is_empty());
+    }
+}
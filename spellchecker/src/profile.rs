@@ -0,0 +1,275 @@
+/*!
+What a checker's queries cost, kept by whoever asks them.
+
+`SpellChecker::check_word_profiled` adds what checking a word cost to a
+`Profile` the caller owns: how long the word took, how many nodes each
+walk of the model visited, and how many searches the checker's beam and
+budget cut short and words it skipped for their length. A server keeps
+a profile for each thread, and adds them up with `merge` when it wants
+the total. The times and walks go in a `Histogram` by powers of two,
+which takes the same few bytes however many queries there are, and is
+close enough to say where the slowest of them lie.
+*/
+
+use std::fmt;
+use std::time::Duration;
+
+use trie::Outcome;
+
+/// How many values of each order of magnitude, in powers of two, have
+/// been recorded, with their sum and the greatest of them.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Histogram {
+    /// How many values had each number of significant bits, less one;
+    /// 0 counts with 1.
+    buckets: [usize; u64::BITS as usize],
+    sum:     u64,
+    max:     u64,
+}
+
+impl Default for Histogram {
+    fn default() -> Self {
+        Histogram { buckets: [0; u64::BITS as usize], sum: 0, max: 0 }
+    }
+}
+
+impl Histogram {
+    pub fn record(&mut self, value: u64) {
+        let bucket = (u64::BITS - 1 - value.max(1).leading_zeros()) as usize;
+        self.buckets[bucket] += 1;
+        self.sum = self.sum.saturating_add(value);
+        self.max = self.max.max(value);
+    }
+
+    /// Adds the values recorded in `other`, as if they had been recorded
+    /// here.
+    pub fn merge(&mut self, other: &Histogram) {
+        for (bucket, count) in self.buckets.iter_mut().zip(&other.buckets) {
+            *bucket += count;
+        }
+        self.sum = self.sum.saturating_add(other.sum);
+        self.max = self.max.max(other.max);
+    }
+
+    /// How many values have been recorded.
+    pub fn count(&self) -> usize {
+        self.buckets.iter().sum()
+    }
+
+    pub fn sum(&self) -> u64 {
+        self.sum
+    }
+
+    pub fn max(&self) -> u64 {
+        self.max
+    }
+
+    /// The mean of the values, or 0 if there are none.
+    pub fn mean(&self) -> f64 {
+        match self.count() {
+            0 => 0.0,
+            count => self.sum() as f64 / count as f64,
+        }
+    }
+
+    /// A value that at least the fraction `p` of the values are no more
+    /// than: the top of the power of two they reach `p` within, or the
+    /// greatest value if that is less. 0 if there are none.
+    pub fn percentile(&self, p: f64) -> u64 {
+        let total = self.count();
+        if total == 0 {
+            return 0;
+        }
+        let wanted = ((p * total as f64).ceil() as usize).clamp(1, total);
+        let mut seen = 0;
+        for (bucket, &count) in self.buckets.iter().enumerate() {
+            seen += count;
+            if seen >= wanted {
+                let top = u64::MAX >> (u64::BITS as usize - 1 - bucket);
+                return top.min(self.max());
+            }
+        }
+        self.max()
+    }
+}
+
+/// The cost of the queries a checker has answered someone; see
+/// `SpellChecker::check_word_profiled`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Profile {
+    /// Nanoseconds taken by each word checked.
+    latency:   Histogram,
+    /// Nodes visited by each walk of the model for candidates.
+    visited:   Histogram,
+    pruned:    usize,
+    timed_out: usize,
+    skipped:   usize,
+    lookups:   usize,
+    reused:    usize,
+}
+
+impl Profile {
+    /// How long each word took to check, in nanoseconds.
+    pub fn latency(&self) -> &Histogram {
+        &self.latency
+    }
+
+    /// How many nodes each search for candidates visited. Only engines
+    /// that walk the model, the trie and fst engines without weighted
+    /// edits, count them.
+    pub fn visited(&self) -> &Histogram {
+        &self.visited
+    }
+
+    /// How many searches the beam of `SpellChecker::with_beam` left some
+    /// children out of.
+    pub fn pruned(&self) -> usize {
+        self.pruned
+    }
+
+    /// How many searches ran out of the time `SpellChecker::with_budget`
+    /// gives them.
+    pub fn timed_out(&self) -> usize {
+        self.timed_out
+    }
+
+    /// How many words were longer than `SpellChecker::with_max_word_len`
+    /// lets a word be, and taken as correct.
+    pub fn skipped(&self) -> usize {
+        self.skipped
+    }
+
+    /// How many words a caller looked up among the words it had checked
+    /// already, as `record_reuse` has them.
+    pub fn lookups(&self) -> usize {
+        self.lookups
+    }
+
+    /// How many of the words looked up had been checked already.
+    pub fn reused(&self) -> usize {
+        self.reused
+    }
+
+    /// Records that `lookups` words were looked up among the words
+    /// checked already, rather than checked again, and `reused` of them
+    /// found there.
+    pub fn record_reuse(&mut self, lookups: usize, reused: usize) {
+        self.lookups += lookups;
+        self.reused += reused;
+    }
+
+    /// Adds everything recorded in `other`, as if it had been recorded
+    /// here.
+    pub fn merge(&mut self, other: &Profile) {
+        self.latency.merge(&other.latency);
+        self.visited.merge(&other.visited);
+        self.pruned += other.pruned;
+        self.timed_out += other.timed_out;
+        self.skipped += other.skipped;
+        self.lookups += other.lookups;
+        self.reused += other.reused;
+    }
+
+    pub(crate) fn record_check(&mut self, elapsed: Duration) {
+        self.latency.record(elapsed.as_nanos().min(u64::MAX as u128) as u64);
+    }
+
+    pub(crate) fn record_walk(&mut self, outcome: &Outcome) {
+        self.visited.record(outcome.visited as u64);
+        self.pruned += outcome.pruned as usize;
+        self.timed_out += outcome.timed_out as usize;
+    }
+
+    pub(crate) fn record_skip(&mut self) {
+        self.skipped += 1;
+    }
+}
+
+/// A line each for the words checked, the searches counted and the
+/// words reused, as `Timings` lays out its phases.
+impl fmt::Display for Profile {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let latency = &self.latency;
+        let time = Duration::from_nanos;
+        writeln!(f, "  {:<10}{} in {:.3?}: {:.3?} avg, p50 <= {:.3?}, p99 <= {:.3?}, max {:.3?}",
+                 "checks", latency.count(), time(latency.sum()), time(latency.mean() as u64),
+                 time(latency.percentile(0.5)), time(latency.percentile(0.99)),
+                 time(latency.max()))?;
+        let visited = &self.visited;
+        if visited.count() > 0 {
+            writeln!(f, "  {:<10}{}, visiting {:.0} nodes avg, p99 <= {}, max {}", "searches",
+                     visited.count(), visited.mean(), visited.percentile(0.99), visited.max())?;
+        }
+        if self.lookups > 0 {
+            writeln!(f, "  {:<10}{} of {} words checked already ({:.1}%)", "cache", self.reused,
+                     self.lookups, 100.0 * self.reused as f64 / self.lookups as f64)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod profile_tests {
+    use super::{Histogram, Profile};
+    use std::time::Duration;
+    use trie::Outcome;
+
+    #[test]
+    fn counts_by_powers_of_two() {
+        let mut histogram = Histogram::default();
+        assert_eq!((0, 0.0, 0), (histogram.count(), histogram.mean(), histogram.percentile(0.99)));
+
+        for value in 1..=100 {
+            histogram.record(value);
+        }
+        assert_eq!((100, 5050, 100), (histogram.count(), histogram.sum(), histogram.max()));
+        assert_eq!(50.5, histogram.mean());
+        // 50 is among 32 to 63; 99 among 64 to 127, of which 100 is the
+        // most.
+        assert_eq!(63, histogram.percentile(0.5));
+        assert_eq!(100, histogram.percentile(0.99));
+        assert_eq!(1, histogram.percentile(0.0));
+    }
+
+    #[test]
+    fn keeps_the_extremes() {
+        let mut histogram = Histogram::default();
+        histogram.record(0);
+        histogram.record(u64::MAX);
+        assert_eq!((1, u64::MAX), (histogram.percentile(0.5), histogram.percentile(1.0)));
+    }
+
+    #[test]
+    fn reports_checks_and_searches() {
+        let mut profile = Profile::default();
+        profile.record_check(Duration::from_micros(3));
+        let report = profile.to_string();
+        assert!(report.starts_with("  checks    1 in 3.000µs"), "{}", report);
+        assert!(!report.contains("searches") && !report.contains("cache"));
+
+        profile.record_walk(&Outcome { visited: 12, pruned: true, timed_out: false });
+        profile.record_reuse(4, 3);
+        let report = profile.to_string();
+        assert!(report.contains("  searches  1, visiting 12 nodes avg"), "{}", report);
+        assert!(report.contains("  cache     3 of 4 words checked already (75.0%)"), "{}", report);
+    }
+
+    #[test]
+    fn merges_as_if_recorded_once() {
+        let (mut a, mut b, mut both) = (Profile::default(), Profile::default(), Profile::default());
+        for (profile, visited) in [(&mut a, 3), (&mut b, 300)] {
+            let outcome = Outcome { visited, pruned: visited > 100, timed_out: visited > 100 };
+            profile.record_check(Duration::from_nanos(visited as u64));
+            profile.record_walk(&outcome);
+            profile.record_skip();
+            both.record_check(Duration::from_nanos(visited as u64));
+            both.record_walk(&outcome);
+            both.record_skip();
+        }
+
+        a.merge(&b);
+        assert_eq!(both, a);
+        assert_eq!((2, 300, 1, 1, 2), (a.visited().count(), a.visited().max(), a.pruned(),
+                                       a.timed_out(), a.skipped()));
+    }
+}
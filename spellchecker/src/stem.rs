@@ -0,0 +1,95 @@
+/*!
+Stemming English inflections.
+
+A small corpus has "run" but not "running", "ran" but not "runs", and
+every inflection it lacks is a word the checker would otherwise
+question. `stems` undoes the common English suffixes the simple way:
+cutting each off, with the spelling changes they make ("tries" for
+"try", "stopped" for "stop", "making" for "make"), and leaving it to the
+model to say which of the stems, if any, is a word. That is no match for
+a real morphology, and over-accepts now and then ("singer" for "sing"),
+but it needs nothing but the model.
+*/
+
+/// How many characters a stem has at the least.
+const MIN_STEM: usize = 2;
+
+/// Each suffix, what may have been in its place before it was added, and
+/// whether adding it may have doubled the consonant before it.
+const SUFFIXES: &[(&str, &[&str], bool)] = &[
+    ("ies", &["y"], false),
+    ("ied", &["y"], false),
+    ("ier", &["y"], false),
+    ("iest", &["y"], false),
+    ("ily", &["y"], false),
+    ("'s", &[""], false),
+    ("es", &[""], false),
+    ("s", &[""], false),
+    ("ed", &["", "e"], true),
+    ("ing", &["", "e"], true),
+    ("er", &["", "e"], true),
+    ("est", &["", "e"], true),
+    ("ly", &["", "le"], false),
+];
+
+/// The words `word`, a lowercase word, may be an inflection of, in no
+/// particular order and many of them not words at all.
+pub fn stems(word: &str) -> Vec<String> {
+    let mut stems: Vec<String> = Vec::new();
+    let mut add = |stem: String| {
+        if stem.chars().count() >= MIN_STEM && !stems.contains(&stem) {
+            stems.push(stem);
+        }
+    };
+
+    for &(suffix, endings, doubles) in SUFFIXES {
+        let base = match word.strip_suffix(suffix) {
+            // "class" is no plural of "clas".
+            Some(base) if !(suffix == "s" && base.ends_with('s')) => base,
+            _ => continue,
+        };
+        for ending in endings {
+            add(format!("{}{}", base, ending));
+        }
+        if doubles && ends_doubled(base) {
+            add(base[..base.len() - 1].to_owned());
+        }
+    }
+    stems
+}
+
+/// Whether `base` ends in a doubled consonant, as "stopp" does.
+fn ends_doubled(base: &str) -> bool {
+    let mut chars = base.chars().rev();
+    match (chars.next(), chars.next()) {
+        (Some(last), Some(before)) => last == before && last.is_alphabetic()
+            && !"aeiou".contains(last),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod stems_tests {
+    use super::stems;
+
+    #[test]
+    fn undoes_suffixes() {
+        for &(word, stem) in &[("walks", "walk"), ("boxes", "box"), ("flies", "fly"),
+                               ("tried", "try"), ("walked", "walk"), ("baked", "bake"),
+                               ("stopped", "stop"), ("running", "run"), ("making", "make"),
+                               ("bigger", "big"), ("happiest", "happy"), ("quickly", "quick"),
+                               ("gently", "gentle"), ("world's", "world")] {
+            assert!(stems(word).contains(&stem.to_owned()), "{} from {:?}", stem, stems(word));
+        }
+    }
+
+    #[test]
+    fn leaves_stems_whole() {
+        assert!(stems("run").is_empty());
+        assert!(!stems("class").contains(&"clas".to_owned()));
+        // Too little is left of "is" to be a stem.
+        assert!(stems("is").is_empty());
+        assert_eq!(vec!["fall".to_owned(), "falle".to_owned(), "fal".to_owned()],
+                   stems("falling"));
+    }
+}
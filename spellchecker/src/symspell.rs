@@ -0,0 +1,209 @@
+/*!
+A SymSpell index: the "symmetric delete" alternative to walking the trie.
+
+Every known word is stored under each string it can be shortened to by
+deleting up to `max_edits` characters. Two words within `k` edits of each
+other can always both be shortened to some common string with at most `k`
+deletions each, so the candidates for a misspelling are found by looking
+up its own deletions, and only those candidates have their distance
+worked out.
+
+Building the index takes time and memory that grow quickly with word
+length and `max_edits`, so it suits dictionaries of ordinary words.
+*/
+
+use std::collections::{HashMap, HashSet};
+
+use distance::edit_distance;
+use trie::{rank, Trie};
+
+#[derive(Clone, Debug)]
+pub struct SymSpell {
+    max_edits: usize,
+    words:     Vec<(String, usize)>,
+    deletes:   HashMap<String, Vec<usize>>,
+    longest:   usize,
+}
+
+impl SymSpell {
+    /// Indexes every word of `model` for searches of up to `max_edits`.
+    pub fn new(model: &Trie, max_edits: usize) -> Self {
+        let words = model.words();
+        let mut deletes: HashMap<String, Vec<usize>> = HashMap::new();
+        let mut longest = 0;
+
+        for (i, (word, _)) in words.iter().enumerate() {
+            longest = longest.max(word.chars().count());
+            for delete in deletions(word, max_edits) {
+                deletes.entry(delete).or_default().push(i);
+            }
+        }
+
+        SymSpell { max_edits, words, deletes, longest }
+    }
+
+    pub fn max_edits(&self) -> usize {
+        self.max_edits
+    }
+
+    /// Exactly what `Trie::search_with_k_edit` would find: every word
+    /// within `k` edits of `word`, as `(word, count, distance)`, best
+    /// first.
+    ///
+    /// Panics if `k` is more than the index was built for.
+    pub fn search(&self, word: &str, k: usize) -> Vec<(String, usize, usize)> {
+        assert!(k <= self.max_edits, "SymSpell index built for {} edits, asked for {}",
+                self.max_edits, k);
+
+        if word.chars().count() > self.longest + k {
+            return Vec::new();
+        }
+
+        let mut checked = HashSet::new();
+        let mut found = Vec::new();
+        for delete in deletions(word, k) {
+            for &i in self.deletes.get(&delete).into_iter().flatten() {
+                if !checked.insert(i) {
+                    continue;
+                }
+
+                let (ref candidate, count) = self.words[i];
+                let distance = edit_distance(word, candidate);
+                if distance <= k {
+                    found.push((candidate.clone(), count, distance));
+                }
+            }
+        }
+
+        rank(&mut found);
+        found
+    }
+}
+
+/// `word` and every string it can be shortened to by deleting up to `k`
+/// of its characters.
+fn deletions(word: &str, k: usize) -> HashSet<String> {
+    let mut all = HashSet::new();
+    let mut level = vec![word.chars().collect::<Vec<char>>()];
+    all.insert(word.to_owned());
+
+    for _ in 0..k {
+        let mut next = Vec::new();
+        for chars in &level {
+            for i in 0..chars.len() {
+                let mut shorter = chars.clone();
+                shorter.remove(i);
+                if all.insert(shorter.iter().collect()) {
+                    next.push(shorter);
+                }
+            }
+        }
+        level = next;
+    }
+
+    all
+}
+
+#[cfg(test)]
+mod deletions_tests {
+    use super::deletions;
+
+    #[test]
+    fn lists_each_deletion_once() {
+        let mut found: Vec<_> = deletions("abb", 1).into_iter().collect();
+        found.sort();
+        assert_eq!(vec!["ab", "abb", "bb"], found);
+    }
+
+    #[test]
+    fn goes_down_to_nothing() {
+        assert!(deletions("ab", 2).contains(""));
+        assert_eq!(1, deletions("ab", 0).len());
+    }
+}
+
+#[cfg(test)]
+mod search_tests {
+    use super::SymSpell;
+    use trie::Trie;
+
+    #[test]
+    fn finds_corrections() {
+        let index = SymSpell::new(&fixture(), 2);
+
+        assert_eq!(vec![("hello".to_owned(), 2, 0)], index.search("hello", 0));
+        assert_eq!(vec![("hello".to_owned(), 2, 1), ("hell".to_owned(), 1, 1),
+                        ("help".to_owned(), 1, 2)],
+                   index.search("hellx", 2));
+        assert!(index.search("xyzzy", 2).is_empty());
+    }
+
+    #[test]
+    fn matches_the_trie() {
+        let model = fixture();
+        let index = SymSpell::new(&model, 2);
+        let queries = ["", "h", "he", "hel", "hello", "helo", "hlelo", "hellp", "hellooo",
+                       "wrold", "world", "wordl", "owrld", "wo", "sword", "swrod", "xyzzy",
+                       "ehllo", "lleh", "helpp", "hepl", "worldd", "a", "ab", "ba"];
+
+        for query in &queries {
+            for k in 0..3 {
+                assert_eq!(model.search_with_k_edit(query, k), index.search(query, k),
+                           "{:?} within {}", query, k);
+            }
+        }
+    }
+
+    #[test]
+    fn matches_the_trie_on_generated_typos() {
+        let model = fixture();
+        let index = SymSpell::new(&model, 2);
+        let alphabet = ['a', 'd', 'e', 'h', 'l', 'o', 'p', 'r', 'w'];
+
+        // Every word with one or two characters replaced, dropped or
+        // added, in every combination the alphabet allows.
+        let mut queries = vec!["hello".to_owned(), "world".to_owned()];
+        for _ in 0..2 {
+            let mut next = Vec::new();
+            for query in &queries {
+                let chars: Vec<char> = query.chars().collect();
+                for i in 0..chars.len() + 1 {
+                    for &c in &alphabet {
+                        let mut added = chars.clone();
+                        added.insert(i, c);
+                        next.push(added.iter().collect());
+                        if i < chars.len() {
+                            let mut replaced = chars.clone();
+                            replaced[i] = c;
+                            next.push(replaced.iter().collect());
+                        }
+                    }
+                    if i < chars.len() {
+                        let mut dropped = chars.clone();
+                        dropped.remove(i);
+                        next.push(dropped.iter().collect());
+                    }
+                }
+            }
+            queries.extend(next);
+        }
+
+        for query in &queries {
+            assert_eq!(model.search_with_k_edit(query, 2), index.search(query, 2), "{:?}", query);
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn refuses_more_edits_than_indexed() {
+        SymSpell::new(&fixture(), 1).search("hello", 2);
+    }
+
+    fn fixture() -> Trie {
+        let mut t = Trie::new();
+        for word in &["hello", "hello", "help", "hell", "world", "word", "sword", "he", "a"] {
+            t.insert(word);
+        }
+        t
+    }
+}
@@ -0,0 +1,926 @@
+/*!
+Building a `Trie` from a corpus of running text.
+*/
+
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, Read};
+use std::mem;
+use std::path::{Path, PathBuf};
+use std::str::{self, FromStr};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{self, Receiver};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use textkit::encoding::{Decoder, Encoding};
+use textkit::progress::{self, HumanCount, ProgressBar};
+use textkit::timings::Timings;
+use textkit::tokenize::{fold_word, spans_trimming, tokenize, word_spans, MARKS};
+
+use unicode_normalization::char::is_combining_mark;
+use unicode_normalization::UnicodeNormalization;
+
+use compress::{decompress, is_compressed};
+use trie::Trie;
+
+/// How many lines of corpus a training thread takes at a time.
+const CHUNK_LINES: usize = 4096;
+
+const UTF8_BOM: &[u8] = &[0xEF, 0xBB, 0xBF];
+
+/// About how many bytes of a mapped corpus a training thread takes at a
+/// time.
+const CHUNK_BYTES: usize = 1 << 20;
+
+/// `word` without its diacritics: "café" is "cafe" and "Ångström"
+/// "Angstrom". Letters that are not a base letter and an accent, such as
+/// "ø" and "ß", are kept.
+pub fn strip_diacritics(word: &str) -> String {
+    word.nfd().filter(|&c| !is_combining_mark(c)).nfc().collect()
+}
+
+/// `model` with the diacritics of its words stripped, adding up the
+/// counts of words that are then the same.
+pub fn fold_diacritics(model: Trie) -> Trie {
+    if model.iter().all(|(word, _)| word.is_ascii()) {
+        return model;
+    }
+
+    let mut folded = Trie::new();
+    for (word, count) in model.iter() {
+        folded.insert_with_count(&strip_diacritics(&word), count);
+    }
+    folded
+}
+
+/// The parts of `word`, a word `word_spans` found at byte offset `start`,
+/// between its hyphens, each with the offset it starts at.
+pub fn hyphen_parts(start: usize, word: &str) -> Vec<(usize, &str)> {
+    word.split('-')
+        .scan(start, |at, part| {
+            let offset = *at;
+            *at += part.len() + 1;
+            Some((offset, part))
+        })
+        .filter_map(|(offset, part)| {
+            let trimmed = part.trim_matches(MARKS);
+            if trimmed.is_empty() {
+                return None;
+            }
+            let lead = part.len() - part.trim_start_matches(MARKS).len();
+            Some((offset + lead, trimmed))
+        })
+        .collect()
+}
+
+/// The words the identifier `word`, found at byte offset `start`, is
+/// made of, each with the offset it starts at: it is split at
+/// underscores, digits and where its case changes, as in `snake_case`,
+/// `camelCase`, `PascalCase` and `HTTPServer`. A word without any of
+/// those is its only part.
+pub fn identifier_parts(start: usize, word: &str) -> Vec<(usize, &str)> {
+    let mut parts = Vec::new();
+    let mut from: Option<usize> = None;
+    let mut prev: Option<char> = None;
+
+    let mut chars = word.char_indices().peekable();
+    while let Some((at, c)) = chars.next() {
+        let next = chars.peek().map(|&(_, c)| c);
+        if !c.is_alphabetic() {
+            if let Some(from) = from.take() {
+                parts.push((start + from, &word[from..at]));
+            }
+        } else if let Some(first) = from {
+            // "aB" starts a word at the "B", and so does "ABc".
+            let hump = c.is_uppercase() && prev.is_some_and(|p| {
+                p.is_lowercase() || (p.is_uppercase() && next.is_some_and(char::is_lowercase))
+            });
+            if hump {
+                parts.push((start + first, &word[first..at]));
+                from = Some(at);
+            }
+        } else {
+            from = Some(at);
+        }
+        prev = Some(c);
+    }
+    if let Some(from) = from {
+        parts.push((start + from, &word[from..]));
+    }
+
+    parts
+}
+
+#[cfg(test)]
+mod tokenize_tests {
+    use super::{fold_diacritics, hyphen_parts, identifier_parts, read_n_train_model,
+                strip_diacritics};
+
+    #[test]
+    fn strips_diacritics() {
+        assert_eq!("cafe", strip_diacritics("café"));
+        assert_eq!("Angstrom", strip_diacritics("A\u{30a}ngstro\u{308}m"));
+        assert_eq!("søß", strip_diacritics("søß"));
+    }
+
+    #[test]
+    fn folds_diacritics_of_models() {
+        let model = read_n_train_model(&b"caf\xc3\xa9 cafe na\xc3\xafve"[..]).unwrap();
+        let model = fold_diacritics(model);
+        assert_eq!((2, 0, 1), (model.search("cafe"), model.search("café"), model.search("naive")));
+    }
+
+    #[test]
+    fn splits_at_hyphens() {
+        assert_eq!(vec![(3, "well"), (8, "known")], hyphen_parts(3, "well-known"));
+        assert_eq!(vec![(0, "rock"), (6, "n"), (9, "roll")], hyphen_parts(0, "rock-'n'-roll"));
+        assert_eq!(vec![(0, "a"), (3, "b")], hyphen_parts(0, "a--b"));
+    }
+
+    #[test]
+    fn splits_identifiers() {
+        assert_eq!(vec![(2, "parse"), (7, "Config")], identifier_parts(2, "parseConfig"));
+        assert_eq!(vec![(0, "HTTP"), (4, "Server"), (11, "v")],
+                   identifier_parts(0, "HTTPServer_v2"));
+        assert_eq!(vec![(1, "word"), (6, "spans")], identifier_parts(0, "_word_spans"));
+        assert_eq!(vec![(0, "Straße")], identifier_parts(0, "Straße"));
+    }
+}
+
+/// The punctuation trimmed from either end of a word, in training and
+/// checking alike: `MARKS`, and any more a text is written with, such as
+/// guillemets or em-dashes, picked with `--punctuation`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Punctuation {
+    /// Trimmed besides `MARKS`.
+    more: Vec<char>,
+}
+
+impl Punctuation {
+    /// `MARKS`, and `more`.
+    pub fn new(more: Vec<char>) -> Self {
+        let mut more: Vec<char> = more.into_iter().filter(|c| !MARKS.contains(c)).collect();
+        more.sort_unstable();
+        more.dedup();
+        Punctuation { more }
+    }
+
+    /// Every mark trimmed, `MARKS` first.
+    pub fn marks(&self) -> Vec<char> {
+        MARKS.iter().chain(&self.more).cloned().collect()
+    }
+
+    pub fn is_mark(&self, c: char) -> bool {
+        MARKS.contains(&c) || self.more.contains(&c)
+    }
+
+    /// `word_spans`, trimming these marks.
+    pub fn word_spans<'a>(&self, line: &'a str) -> Vec<(usize, &'a str)> {
+        if self.more.is_empty() {
+            word_spans(line)
+        } else {
+            spans_trimming(line, |c| self.is_mark(c))
+        }
+    }
+
+    /// `tokenize`, trimming these marks.
+    pub fn tokenize(&self, line: &str) -> Vec<String> {
+        self.word_spans(line).into_iter().map(|(_, word)| fold_word(word)).collect()
+    }
+
+    /// `model`, trained trimming `MARKS`, as trained trimming these marks:
+    /// each word with more of them at either end counted as the word
+    /// without, and a word of nothing but marks dropped.
+    pub fn apply(&self, model: Trie) -> Trie {
+        let trimmed = |word: &str| word.trim_matches(|c| self.is_mark(c)).len() != word.len();
+        if self.more.is_empty() || !model.iter().any(|(word, _)| trimmed(&word)) {
+            return model;
+        }
+
+        let mut applied = Trie::new();
+        for (word, count) in model.iter() {
+            let word = word.trim_matches(|c| self.is_mark(c));
+            if !word.is_empty() {
+                applied.insert_with_count(word, count);
+            }
+        }
+        applied
+    }
+}
+
+#[cfg(test)]
+mod punctuation_tests {
+    use super::{read_n_train_model, tokenize, Punctuation, MARKS};
+
+    #[test]
+    fn trims_more_marks() {
+        let punctuation = Punctuation::new(vec!['«', '»', '\u{2014}', ',']);
+        let line = "«Bonjour», dit-il\u{2014} \u{2014}";
+
+        assert_eq!(vec![(2, "Bonjour"), (13, "dit-il")], punctuation.word_spans(line));
+        assert_eq!(vec!["bonjour", "dit-il"], punctuation.tokenize(line));
+        assert_eq!(vec!["«bonjour»", "dit-il\u{2014}", "\u{2014}"], tokenize(line));
+        assert_eq!(MARKS.len() + 3, punctuation.marks().len());
+        assert!(punctuation.is_mark('»') && punctuation.is_mark('!') && !punctuation.is_mark('x'));
+        assert_eq!(tokenize(line), Punctuation::default().tokenize(line));
+    }
+
+    #[test]
+    fn applies_to_models() {
+        let corpus = "«Bonjour», bonjour! «\u{2014}» dit-il\u{2014}";
+        let punctuation = Punctuation::new(vec!['«', '»', '\u{2014}']);
+        let model = punctuation.apply(read_n_train_model(corpus.as_bytes()).unwrap());
+
+        assert_eq!(read_n_train_model(punctuation.tokenize(corpus).join(" ").as_bytes()).unwrap(),
+                   model);
+        assert_eq!((2, 1), (model.search("bonjour"), model.search("dit-il")));
+        assert_eq!(2, model.stats().words);
+        let model = read_n_train_model(corpus.as_bytes()).unwrap();
+        assert_eq!(model, Punctuation::default().apply(model.clone()));
+    }
+}
+
+/// What to make of a hyphenated word such as "well-known", in training
+/// and checking alike, picked with `--hyphens keep|split|both`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum Hyphens {
+    /// One word, hyphens and all.
+    #[default]
+    Keep,
+    /// A word for each part.
+    Split,
+    /// Both the whole word and its parts are trained; a text's word is
+    /// checked whole if the model knows it that way, otherwise by parts.
+    Both,
+}
+
+impl Hyphens {
+    /// `model`, trained keeping hyphenated words whole, as trained with
+    /// this policy: each hyphenated word's count goes to its parts too,
+    /// or only to them.
+    pub fn apply(self, model: Trie) -> Trie {
+        if self == Hyphens::Keep || !model.iter().any(|(word, _)| word.contains('-')) {
+            return model;
+        }
+
+        let mut applied = Trie::new();
+        for (word, count) in model.iter() {
+            if !word.contains('-') {
+                applied.insert_with_count(&word, count);
+                continue;
+            }
+            if self == Hyphens::Both {
+                applied.insert_with_count(&word, count);
+            }
+            for (_, part) in hyphen_parts(0, &word) {
+                applied.insert_with_count(part, count);
+            }
+        }
+        applied
+    }
+}
+
+impl FromStr for Hyphens {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, String> {
+        match s {
+            "keep"  => Ok(Hyphens::Keep),
+            "split" => Ok(Hyphens::Split),
+            "both"  => Ok(Hyphens::Both),
+            _ => Err(format!("unknown hyphen policy `{}' (expected keep, split or both)", s)),
+        }
+    }
+}
+
+impl fmt::Display for Hyphens {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match *self {
+            Hyphens::Keep  => "keep",
+            Hyphens::Split => "split",
+            Hyphens::Both  => "both",
+        })
+    }
+}
+
+#[cfg(test)]
+mod hyphens_tests {
+    use super::{read_n_train_model, Hyphens};
+
+    #[test]
+    fn applies_to_models() {
+        let model = || read_n_train_model(&b"a well-known well, known-ish"[..]).unwrap();
+
+        assert_eq!(model(), Hyphens::Keep.apply(model()));
+        let split = Hyphens::Split.apply(model());
+        assert_eq!((0, 2, 2, 1), (split.search("well-known"), split.search("well"),
+                                  split.search("known"), split.search("ish")));
+        let both = Hyphens::Both.apply(model());
+        assert_eq!((1, 2, 2, 1), (both.search("well-known"), both.search("well"),
+                                  both.search("known"), both.search("known-ish")));
+    }
+
+    #[test]
+    fn parses_policies() {
+        assert_eq!(Ok(Hyphens::Both), "both".parse());
+        assert!("join".parse::<Hyphens>().is_err());
+        assert_eq!("split", Hyphens::Split.to_string());
+    }
+}
+
+/// Trains a model on the words of `reader`, which must produce UTF-8,
+/// either as it is or gzip or zstd compressed.
+pub fn read_n_train_model<R: Read>(reader: R) -> io::Result<Trie> {
+    read_n_train_model_timed(reader, &mut Timings::default())
+}
+
+/// `read_n_train_model`, charging the time spent to the "read",
+/// "tokenize" and "train" phases of `timings`.
+pub fn read_n_train_model_timed<R: Read>(reader: R, timings: &mut Timings) -> io::Result<Trie> {
+    train_lines(reader, &Tally::new(ProgressBar::hidden()), timings)
+}
+
+/// `read_n_train_model_timed`, adding up what it gets through in `tally`
+/// every `CHUNK_LINES` lines.
+fn train_lines<R: Read>(reader: R, tally: &Tally, timings: &mut Timings) -> io::Result<Trie> {
+    let mut model = Trie::new();
+    let mut lines = BufReader::new(decompress(reader)?).lines();
+    let (mut read, mut words, mut unique) = (0, 0, 0);
+
+    while let Some(line) = timings.time("read", || lines.next()) {
+        let line = line?;
+        let tokens = timings.time("tokenize", || tokenize(&line));
+
+        timings.time("train", || {
+            for word in &tokens {
+                unique += model.insert_is_new(word) as usize;
+            }
+        });
+        words += tokens.len();
+        read += 1;
+        if read % CHUNK_LINES == 0 {
+            tally.add(mem::take(&mut words), unique);
+        }
+    }
+    tally.add(words, unique);
+
+    Ok(model)
+}
+
+/// How many words training has read so far, and how many different
+/// ones, shown as the message of its progress bar.
+///
+/// Each thread trains a trie of its own, and none knows which words the
+/// others have seen, so with several threads the different words are
+/// those of the thread that has seen the most: too few, but never too
+/// many, and the count from one thread is exact.
+struct Tally {
+    bar:    ProgressBar,
+    words:  AtomicUsize,
+    unique: AtomicUsize,
+}
+
+impl Tally {
+    fn new(bar: ProgressBar) -> Self {
+        Tally { bar, words: AtomicUsize::new(0), unique: AtomicUsize::new(0) }
+    }
+
+    /// Counts `words` more words read by one thread, which has now seen
+    /// `unique` different words.
+    fn add(&self, words: usize, unique: usize) {
+        let words = self.words.fetch_add(words, Ordering::Relaxed) + words;
+        let unique = self.unique.fetch_max(unique, Ordering::Relaxed).max(unique);
+        self.bar.set_message(format!("training ({} words, {} unique)",
+                                     HumanCount(words as u64), HumanCount(unique as u64)));
+    }
+}
+
+#[cfg(test)]
+mod read_n_train_model_tests {
+    use super::{read_n_train_model, read_n_train_model_timed};
+    use textkit::timings::Timings;
+
+    #[test]
+    fn counts_every_word() {
+        let model = read_n_train_model(&b"Hello world.\nhello, (again)\n"[..]).unwrap();
+
+        assert_eq!(2, model.search("hello"));
+        assert_eq!(1, model.search("world"));
+        assert_eq!(1, model.search("again"));
+        assert_eq!(0, model.search("hello,"));
+    }
+
+    #[test]
+    fn charges_phases() {
+        let mut timings = Timings::new(true);
+        read_n_train_model_timed(&b"one two\n"[..], &mut timings).unwrap();
+
+        assert!(timings.get("read").is_some());
+        assert!(timings.get("tokenize").is_some());
+        assert!(timings.get("train").is_some());
+    }
+
+    #[test]
+    fn reads_compressed_corpus() {
+        let corpus = zstd::encode_all(&b"Hello world.\nhello, (again)\n"[..], 0).unwrap();
+        let model = read_n_train_model(&corpus[..]).unwrap();
+
+        assert_eq!(2, model.search("hello"));
+        assert_eq!(1, model.search("again"));
+    }
+
+    #[test]
+    fn reports_bad_input() {
+        assert!(read_n_train_model(&b"ok \xFF"[..]).is_err());
+    }
+}
+
+/// `read_n_train_model_timed` on `jobs` threads.
+///
+/// The corpus is read on the calling thread and handed out in chunks of
+/// lines; each thread tokenizes its chunks into a trie of its own, and the
+/// tries are merged at the end. Reading is charged to "read", waiting for
+/// the threads to finish to "train", and merging to "merge".
+pub fn read_n_train_model_parallel<R: Read>(reader: R, jobs: usize, timings: &mut Timings)
+                                            -> io::Result<Trie> {
+    train_lines_parallel(reader, jobs, Arc::new(Tally::new(ProgressBar::hidden())), timings)
+}
+
+/// `read_n_train_model_parallel`, adding up what each thread gets through
+/// in `tally`.
+fn train_lines_parallel<R: Read>(reader: R, jobs: usize, tally: Arc<Tally>,
+                                 timings: &mut Timings) -> io::Result<Trie> {
+    if jobs <= 1 {
+        return train_lines(reader, &tally, timings);
+    }
+
+    let (sender, receiver) = mpsc::sync_channel::<Vec<String>>(jobs);
+    let receiver = Arc::new(Mutex::new(receiver));
+    let workers: Vec<_> = (0..jobs)
+        .map(|_| {
+            let (receiver, tally) = (Arc::clone(&receiver), Arc::clone(&tally));
+            thread::spawn(move || train_chunks(&receiver, &tally))
+        })
+        .collect();
+
+    let read: io::Result<()> = timings.time("read", || {
+        let mut chunk = Vec::with_capacity(CHUNK_LINES);
+        for line in BufReader::new(decompress(reader)?).lines() {
+            chunk.push(line?);
+            if chunk.len() == CHUNK_LINES {
+                let full = mem::replace(&mut chunk, Vec::with_capacity(CHUNK_LINES));
+                if sender.send(full).is_err() {
+                    break;
+                }
+            }
+        }
+        let _ = sender.send(chunk);
+        Ok(())
+    });
+    drop(sender);
+
+    let models: Vec<Trie> = timings.time("train", || {
+        workers.into_iter()
+            .map(|worker| worker.join().expect("training thread panicked"))
+            .collect()
+    });
+    read?;
+
+    Ok(timings.time("merge", || {
+        let mut model = Trie::new();
+        for partial in models {
+            model.merge(partial);
+        }
+        model
+    }))
+}
+
+/// Trains a model on chunks of lines from `receiver` until it hangs up,
+/// adding up each chunk in `tally`.
+fn train_chunks(receiver: &Mutex<Receiver<Vec<String>>>, tally: &Tally) -> Trie {
+    let mut model = Trie::new();
+    let mut unique = 0;
+
+    loop {
+        let chunk = receiver.lock().expect("training thread panicked").recv();
+        let chunk = match chunk {
+            Ok(chunk) => chunk,
+            Err(_) => return model,
+        };
+
+        let mut words = 0;
+        for line in &chunk {
+            for word in tokenize(line) {
+                unique += model.insert_is_new(&word) as usize;
+                words += 1;
+            }
+        }
+        tally.add(words, unique);
+    }
+}
+
+#[cfg(test)]
+mod read_n_train_model_parallel_tests {
+    use super::{read_n_train_model, read_n_train_model_parallel, train_lines_parallel, Tally,
+                CHUNK_LINES};
+    use std::sync::atomic::Ordering;
+    use std::sync::Arc;
+    use textkit::progress::ProgressBar;
+    use textkit::timings::Timings;
+
+    #[test]
+    fn matches_single_threaded() {
+        let corpus: String = (0..3 * CHUNK_LINES + 7)
+            .map(|i| format!("Line {}: the quick brown fox, number {}.\n", i % 13, i % 101))
+            .collect();
+        let expected = read_n_train_model(corpus.as_bytes()).unwrap();
+
+        for jobs in 1..5 {
+            let model = read_n_train_model_parallel(corpus.as_bytes(), jobs,
+                                                    &mut Timings::default()).unwrap();
+            assert_eq!(expected, model);
+        }
+    }
+
+    #[test]
+    fn tallies_words() {
+        let corpus: String = (0..2 * CHUNK_LINES + 7)
+            .map(|i| format!("Line {} of {}.\n", i, i % 3))
+            .collect();
+
+        for jobs in 1..4 {
+            let tally = Arc::new(Tally::new(ProgressBar::hidden()));
+            let model = train_lines_parallel(corpus.as_bytes(), jobs, Arc::clone(&tally),
+                                             &mut Timings::default()).unwrap();
+            let unique = tally.unique.load(Ordering::Relaxed);
+
+            assert_eq!(4 * (2 * CHUNK_LINES + 7), tally.words.load(Ordering::Relaxed));
+            assert!(unique <= model.stats().words);
+            if jobs == 1 {
+                assert_eq!(model.stats().words, unique);
+                assert_eq!("training (32,796 words, 8,201 unique)", tally.bar.message());
+            }
+        }
+    }
+
+    #[test]
+    fn handles_empty_corpus() {
+        let model = read_n_train_model_parallel(&b""[..], 4, &mut Timings::default()).unwrap();
+        assert_eq!(0, model.search(""));
+        assert_eq!(read_n_train_model(&b""[..]).unwrap(), model);
+    }
+
+    #[test]
+    fn charges_phases() {
+        let mut timings = Timings::new(true);
+        read_n_train_model_parallel(&b"one two\n"[..], 2, &mut timings).unwrap();
+
+        assert!(timings.get("read").is_some());
+        assert!(timings.get("train").is_some());
+        assert!(timings.get("merge").is_some());
+    }
+
+    #[test]
+    fn reports_bad_input() {
+        assert!(read_n_train_model_parallel(&b"ok \xFF"[..], 2, &mut Timings::default()).is_err());
+    }
+}
+
+/// Trains a model on the corpus file at `path` on `jobs` threads, decoding
+/// it as `encoding` and showing a progress bar if `progress` is set, with
+/// how many words it has read so far and how many different ones.
+pub fn train_from_file<P: AsRef<Path>>(path: P, encoding: Encoding, progress: bool, jobs: usize,
+                                       timings: &mut Timings) -> io::Result<Trie> {
+    let file = File::open(path)?;
+    let len = file.metadata()?.len();
+
+    // A plain UTF-8 corpus is mapped into memory and tokenized where it
+    // is, rather than copied out a line at a time.
+    if len > 0 && (encoding == Encoding::Auto || encoding == Encoding::Utf8) {
+        // SAFETY: the map is only read, and like any reader of the file,
+        // counts on nothing else truncating it meanwhile.
+        if let Ok(map) = unsafe { memmap2::Mmap::map(&file) } {
+            if let Some(text) = mapped_text(&map) {
+                let tally = Tally::new(progress::bytes(progress, Some(len), "training"));
+                let model = train_text(text, jobs, &tally, timings);
+                tally.bar.finish_and_clear();
+                return Ok(model);
+            }
+        }
+    }
+
+    train_from_reader(file, Some(len), encoding, progress, jobs, timings)
+}
+
+/// The text of a mapped corpus, if it is plain UTF-8 (without its BOM,
+/// if it has one); `None` if it is compressed or in any other encoding.
+fn mapped_text(bytes: &[u8]) -> Option<&str> {
+    if is_compressed(bytes) {
+        return None;
+    }
+    str::from_utf8(bytes.strip_prefix(UTF8_BOM).unwrap_or(bytes)).ok()
+}
+
+/// Trains a model on `text`, a whole corpus, on `jobs` threads, each
+/// taking the next chunk of lines as soon as it is done with the last,
+/// and tokenizing the lines where they are. `tally`'s bar counts the
+/// bytes.
+fn train_text(text: &str, jobs: usize, tally: &Tally, timings: &mut Timings) -> Trie {
+    let chunks = line_chunks(text, CHUNK_BYTES);
+    let next = AtomicUsize::new(0);
+    let train = || {
+        let mut model = Trie::new();
+        let mut unique = 0;
+        while let Some(chunk) = chunks.get(next.fetch_add(1, Ordering::Relaxed)) {
+            let mut words = 0;
+            for line in chunk.lines() {
+                for (_, word) in word_spans(line) {
+                    unique += model.insert_is_new(&fold_word(word)) as usize;
+                    words += 1;
+                }
+            }
+            tally.add(words, unique);
+            tally.bar.inc(chunk.len() as u64);
+        }
+        model
+    };
+
+    let models: Vec<Trie> = timings.time("train", || {
+        thread::scope(|scope| {
+            let workers: Vec<_> = (0..jobs.max(1)).map(|_| scope.spawn(train)).collect();
+            workers.into_iter()
+                .map(|worker| worker.join().expect("training thread panicked"))
+                .collect()
+        })
+    });
+
+    timings.time("merge", || {
+        let mut model = Trie::new();
+        for partial in models {
+            model.merge(partial);
+        }
+        model
+    })
+}
+
+/// `text` cut into chunks of whole lines, each about `size` bytes long or
+/// as long as its longest line.
+fn line_chunks(text: &str, size: usize) -> Vec<&str> {
+    let mut chunks = Vec::new();
+    let mut rest = text;
+
+    while !rest.is_empty() {
+        let end = match rest.as_bytes().get(size..).and_then(|tail| memchr(b'\n', tail)) {
+            Some(newline) => size + newline + 1,
+            None => rest.len(),
+        };
+        let (chunk, tail) = rest.split_at(end);
+        chunks.push(chunk);
+        rest = tail;
+    }
+
+    chunks
+}
+
+/// Where the first `byte` in `bytes` is, if anywhere.
+fn memchr(byte: u8, bytes: &[u8]) -> Option<usize> {
+    bytes.iter().position(|&b| b == byte)
+}
+
+#[cfg(test)]
+mod train_from_file_tests {
+    use super::{line_chunks, read_n_train_model, train_from_file, CHUNK_BYTES};
+    use std::env;
+    use std::fs;
+    use textkit::encoding::Encoding;
+    use textkit::timings::Timings;
+
+    #[test]
+    fn cuts_chunks_at_line_ends() {
+        assert_eq!(vec!["one two\n", "three\n", "four"], line_chunks("one two\nthree\nfour", 3));
+        assert_eq!(vec!["one\ntwo\n"], line_chunks("one\ntwo\n", 5));
+        assert!(line_chunks("", 3).is_empty());
+    }
+
+    #[test]
+    fn maps_plain_corpora() {
+        let corpus: String = (0..CHUNK_BYTES / 8)
+            .map(|i| format!("Line {}: the café, number {}.\r\n", i % 13, i % 101))
+            .collect();
+        let expected = read_n_train_model(corpus.as_bytes()).unwrap();
+        let path = env::temp_dir().join(format!("spellchecker-mapped-{}", std::process::id()));
+
+        let files = [(&b""[..], Encoding::Auto), (b"\xEF\xBB\xBFthe", Encoding::Utf8),
+                     (b"caf\xE9", Encoding::Auto), (corpus.as_bytes(), Encoding::Auto)];
+        for &(bytes, encoding) in &files {
+            fs::write(&path, bytes).unwrap();
+            let expected = if bytes.len() == corpus.len() {
+                expected.clone()
+            } else {
+                read_n_train_model(textkit::encoding::decode(bytes, encoding).unwrap().as_bytes())
+                    .unwrap()
+            };
+            for jobs in 1..4 {
+                assert_eq!(expected, train_from_file(&path, encoding, false, jobs,
+                                                     &mut Timings::default()).unwrap());
+            }
+        }
+        fs::remove_file(&path).unwrap();
+    }
+}
+
+/// Trains a model on the corpus read from `reader` as `train_from_file`
+/// does, for a corpus that isn't a file, such as the standard input. The
+/// progress bar shows how much of `len` bytes is read, if that is known.
+pub fn train_from_reader<R: Read>(reader: R, len: Option<u64>, encoding: Encoding,
+                                  progress: bool, jobs: usize, timings: &mut Timings)
+                                  -> io::Result<Trie> {
+    let bar = progress::bytes(progress, len, "training");
+    let input = Decoder::new(decompress(bar.wrap_read(reader))?, encoding);
+    let tally = Arc::new(Tally::new(bar));
+    let model = train_lines_parallel(input, jobs, Arc::clone(&tally), timings)?;
+    tally.bar.finish_and_clear();
+
+    Ok(model)
+}
+
+/// Whether the corpus `corpus` names is an http:// or https:// URL
+/// rather than a path.
+pub fn is_url(corpus: &str) -> bool {
+    corpus.starts_with("http://") || corpus.starts_with("https://")
+}
+
+/// Trains a model on the corpus at `url` as `train_from_reader` does,
+/// downloading it as it goes. Only with the `http` feature; otherwise
+/// this always fails.
+#[cfg(feature = "http")]
+pub fn train_from_url(url: &str, encoding: Encoding, progress: bool, jobs: usize,
+                      timings: &mut Timings) -> io::Result<Trie> {
+    let response = ureq::get(url).call().map_err(io::Error::other)?;
+    let len = response.headers().get("content-length")
+        .and_then(|len| len.to_str().ok()?.parse().ok());
+
+    train_from_reader(response.into_body().into_reader(), len, encoding, progress, jobs, timings)
+}
+
+#[cfg(not(feature = "http"))]
+pub fn train_from_url(_url: &str, _encoding: Encoding, _progress: bool, _jobs: usize,
+                      _timings: &mut Timings) -> io::Result<Trie> {
+    Err(io::Error::new(io::ErrorKind::Unsupported,
+                       "training on a URL needs spellchecker built with the http feature"))
+}
+
+#[cfg(test)]
+mod train_from_reader_tests {
+    use super::{is_url, train_from_reader};
+    use textkit::encoding::Encoding;
+    use textkit::timings::Timings;
+
+    #[test]
+    fn decodes_what_it_reads() {
+        let model = train_from_reader(&b"caf\xE9 the caf\xE9\n"[..], None, Encoding::Latin1,
+                                      false, 2, &mut Timings::default()).unwrap();
+
+        assert_eq!(2, model.search("café"));
+        assert_eq!(1, model.search("the"));
+    }
+
+    #[test]
+    fn tells_urls_from_paths() {
+        assert!(is_url("https://norvig.com/big.txt"));
+        assert!(is_url("http://localhost:8000/big.txt.gz"));
+        assert!(!is_url("big.txt"));
+        assert!(!is_url("http/big.txt"));
+    }
+}
+
+/// The corpus files among `paths`: each file as given, and every file
+/// anywhere under each directory whose name matches `glob`, in which `*`
+/// stands for any run of characters and `?` for any one. A directory's
+/// files come in order of their paths, and symbolic links to directories
+/// aren't followed. With `progress`, a spinner counts the files found.
+pub fn corpus_files<P: AsRef<Path>>(paths: &[P], glob: &str, progress: bool)
+                                    -> io::Result<Vec<PathBuf>> {
+    let glob: Vec<char> = glob.chars().collect();
+    let bar = progress::items(progress, None, "finding corpus files");
+    let mut files = Vec::new();
+
+    for path in paths {
+        let path = path.as_ref();
+        if fs::metadata(path).map_err(|e| in_path(path, e))?.is_dir() {
+            walk(path, &glob, &bar, &mut files)?;
+        } else {
+            files.push(path.to_owned());
+            bar.inc(1);
+        }
+    }
+
+    bar.finish_and_clear();
+    Ok(files)
+}
+
+fn walk(dir: &Path, glob: &[char], bar: &ProgressBar, files: &mut Vec<PathBuf>)
+        -> io::Result<()> {
+    let mut entries = fs::read_dir(dir).map_err(|e| in_path(dir, e))?
+        .collect::<io::Result<Vec<_>>>()
+        .map_err(|e| in_path(dir, e))?;
+    entries.sort_by_key(|entry| entry.path());
+
+    for entry in entries {
+        let path = entry.path();
+        if entry.file_type().map_err(|e| in_path(&path, e))?.is_dir() {
+            walk(&path, glob, bar, files)?;
+        } else if path.is_file() {
+            let name: Vec<char> = entry.file_name().to_string_lossy().chars().collect();
+            if glob_matches(glob, &name) {
+                files.push(path);
+                bar.inc(1);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Whether `name` matches all of `glob`.
+fn glob_matches(glob: &[char], name: &[char]) -> bool {
+    // Where to go back to when a match fails after a `*`: just past the
+    // star, and the next character of the name for it to take.
+    let mut star: Option<(usize, usize)> = None;
+    let (mut g, mut n) = (0, 0);
+
+    while n < name.len() {
+        match glob.get(g) {
+            Some(&'*') => {
+                star = Some((g + 1, n));
+                g += 1;
+            }
+            Some(&c) if c == '?' || c == name[n] => {
+                g += 1;
+                n += 1;
+            }
+            _ => match star {
+                Some((after, taken)) => {
+                    star = Some((after, taken + 1));
+                    g = after;
+                    n = taken + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+
+    glob[g..].iter().all(|&c| c == '*')
+}
+
+/// `error`, saying which path it was about.
+fn in_path(path: &Path, error: io::Error) -> io::Error {
+    io::Error::new(error.kind(), format!("{}: {}", path.display(), error))
+}
+
+#[cfg(test)]
+mod corpus_files_tests {
+    use super::{corpus_files, glob_matches};
+    use std::env;
+    use std::fs;
+    use std::path::PathBuf;
+
+    #[test]
+    fn matches_globs() {
+        for &(glob, name) in &[("*", "big.txt"), ("*.txt", "big.txt"), ("big.*", "big.txt"),
+                               ("b?g.txt", "big.txt"), ("*i*t", "big.txt"), ("*", ""),
+                               ("**.txt", "a.txt"), ("*.txt", ".txt")] {
+            assert!(glob_matches(&chars(glob), &chars(name)), "{} {}", glob, name);
+        }
+        for &(glob, name) in &[("*.txt", "big.gz"), ("big", "big.txt"), ("?", ""),
+                               ("b?g", "bg"), ("*.txt", "big.txt.gz")] {
+            assert!(!glob_matches(&chars(glob), &chars(name)), "{} {}", glob, name);
+        }
+    }
+
+    #[test]
+    fn walks_directories() {
+        let dir = env::temp_dir().join(format!("spellchecker-corpus-{}", std::process::id()));
+        fs::create_dir_all(dir.join("b/c")).unwrap();
+        for name in &["a.txt", "b/c/d.txt", "b/e.txt", "b/f.gz", "g.txt"] {
+            fs::write(dir.join(name), "hello\n").unwrap();
+        }
+
+        let files = corpus_files(&[dir.join("b"), dir.join("a.txt")], "*.txt", false).unwrap();
+        let expected: Vec<PathBuf> =
+            ["b/c/d.txt", "b/e.txt", "a.txt"].iter().map(|name| dir.join(name)).collect();
+        assert_eq!(expected, files);
+
+        // Files given by name are read whatever they're called.
+        assert_eq!(vec![dir.join("b/f.gz")],
+                   corpus_files(&[dir.join("b/f.gz")], "*.txt", false).unwrap());
+        assert!(corpus_files(&[dir.join("missing")], "*", false).is_err());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    fn chars(s: &str) -> Vec<char> {
+        s.chars().collect()
+    }
+}
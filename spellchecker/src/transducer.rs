@@ -0,0 +1,293 @@
+/*!
+A dictionary kept as a finite-state transducer (see the `fst` crate).
+
+Like a `Dawg`, a transducer shares the endings its words have in common
+as well as their beginnings, but it keeps their counts on the way
+rather than in the nodes: each transition adds something to the count
+of the words through it, and a word's count is what its path adds up
+to. Words of different counts can then share their endings too, and the
+whole thing is a single run of bytes, a fraction of the size of the
+trie it is built from.
+
+The transducer is keyed by the words' UTF-8 bytes, so a search walks it
+a byte at a time, putting the bytes of each character back together
+before stepping the same automaton a trie search steps.
+
+Its states point at each other by where they are in its bytes rather
+than by address, so a transducer saved with `save_transducer` can be
+mapped into memory and searched where it is (see `map_model`), however
+big it is.
+*/
+
+use std::collections::HashSet;
+use std::fmt;
+use std::io;
+use std::str;
+use std::sync::Arc;
+
+use fst::raw::{Fst, Node};
+use fst::Streamer;
+use memmap2::Mmap;
+
+use trie::{self, rank, Graph, Stats, Trie};
+
+#[derive(Clone)]
+pub struct Transducer {
+    fst:   Fst<Bytes>,
+    /// How many states `fst` has.
+    nodes: usize,
+    /// The most characters in a word.
+    depth: usize,
+}
+
+impl Transducer {
+    /// A transducer of the words of `model` and their counts.
+    pub fn from_trie(model: &Trie) -> Self {
+        let mut depth = 0;
+        let words = model.iter().map(|(word, count)| {
+            depth = depth.max(word.chars().count());
+            (word, count as u64)
+        });
+        // The trie lists its words in order of their characters, which is
+        // the order of their UTF-8 bytes too.
+        let fst = Fst::from_iter_map(words).expect("the trie's words in order");
+        let fst = fst.map_data(Bytes::Owned).expect("a transducer");
+
+        let mut seen = HashSet::new();
+        let mut stack = vec![fst.root().addr()];
+        while let Some(addr) = stack.pop() {
+            if seen.insert(addr) {
+                stack.extend(fst.node(addr).transitions().map(|t| t.addr));
+            }
+        }
+
+        Transducer { nodes: seen.len(), depth, fst }
+    }
+
+    /// The transducer in `bytes`, as `as_bytes` gives them, of `nodes`
+    /// states and words of up to `depth` characters, or an error if they
+    /// aren't one.
+    pub(crate) fn from_bytes(bytes: Bytes, nodes: usize, depth: usize) -> Result<Self, String> {
+        let fst = Fst::new(bytes).map_err(|e| e.to_string())?;
+        Ok(Transducer { fst, nodes, depth })
+    }
+
+    /// The bytes the transducer is made of.
+    pub fn as_bytes(&self) -> &[u8] {
+        self.fst.as_bytes()
+    }
+
+    /// Every word in the transducer with its count, in alphabetical
+    /// order, or an error if one of them isn't UTF-8, as in a corrupt
+    /// model.
+    pub fn words(&self) -> io::Result<Vec<(String, usize)>> {
+        let mut words = Vec::with_capacity(self.fst.len());
+        let mut stream = self.fst.stream();
+        while let Some((word, count)) = stream.next() {
+            let word = str::from_utf8(word).map_err(|_| {
+                io::Error::new(io::ErrorKind::InvalidData, "a word in the model isn't UTF-8")
+            })?;
+            words.push((word.to_owned(), count.value() as usize));
+        }
+        Ok(words)
+    }
+
+    /// How many times `word` was inserted into the trie this came from.
+    pub fn search(&self, word: &str) -> usize {
+        self.fst.get(word).map_or(0, |count| count.value() as usize)
+    }
+
+    /// See `Trie::search_with_k_edit`.
+    pub fn search_with_k_edit(&self, word: &str, k: usize) -> Vec<(String, usize, usize)> {
+        let word: Vec<char> = word.chars().collect();
+        let mut found = trie::search_with_k_edit(self, &word, k);
+        rank(&mut found);
+        found
+    }
+
+    /// See `Trie::complete`.
+    pub fn complete(&self, prefix: &str, n: usize) -> Vec<(String, usize)> {
+        trie::complete(self, prefix, n)
+    }
+
+    /// How big the transducer is. `words` and `depth` are the same as for
+    /// the trie this came from, `nodes` counts its states, and
+    /// `heap_bytes` is the size of the bytes they are encoded in, even if
+    /// they are mapped from a file rather than on the heap.
+    pub fn stats(&self) -> Stats {
+        Stats {
+            words:      self.fst.len(),
+            nodes:      self.nodes,
+            depth:      self.depth,
+            heap_bytes: self.fst.as_bytes().len(),
+        }
+    }
+
+    /// Calls `f` on each character leading on from `node`, `bytes` of
+    /// which have been read so far, and the state after it. Bytes that
+    /// can't be part of a character, as in a corrupt model, lead nowhere.
+    fn each_char<F: FnMut(char, State)>(&self, node: State, bytes: &mut Vec<u8>, f: &mut F) {
+        let depth = bytes.len();
+        for t in self.fst.node(node.addr).transitions() {
+            bytes.truncate(depth);
+            bytes.push(t.inp);
+            let next = State { addr: t.addr, count: node.count + t.out.value() };
+            match str::from_utf8(bytes) {
+                Ok(c) => f(c.chars().next().expect("a character"), next),
+                // Part of a character: read on.
+                Err(ref e) if e.error_len().is_none() => self.each_char(next, bytes, f),
+                Err(_) => {}
+            }
+        }
+        bytes.truncate(depth);
+    }
+
+    fn node(&self, state: State) -> Node<'_> {
+        self.fst.node(state.addr)
+    }
+}
+
+/// What a `Transducer` is read from: bytes of its own, or a part of a
+/// file mapped into memory, shared by its clones.
+#[derive(Clone)]
+pub(crate) enum Bytes {
+    Owned(Vec<u8>),
+    /// The map, and where in it the transducer starts.
+    Mapped(Arc<Mmap>, usize),
+}
+
+impl AsRef<[u8]> for Bytes {
+    fn as_ref(&self) -> &[u8] {
+        match *self {
+            Bytes::Owned(ref bytes) => bytes,
+            Bytes::Mapped(ref map, start) => &map[start..],
+        }
+    }
+}
+
+impl fmt::Debug for Transducer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Transducer").field("stats", &self.stats()).finish()
+    }
+}
+
+/// Where a walk of a `Transducer` has got to: a state, and the count the
+/// transitions to it have added up to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct State {
+    addr:  usize,
+    count: u64,
+}
+
+/// A state is numbered by where it is in the transducer's bytes, so states
+/// reached by words of different counts share a number: `subtree_counts`
+/// can't tell them apart, and the beam doesn't search a `Transducer`.
+impl Graph for &Transducer {
+    type Node = State;
+
+    fn root(self) -> State {
+        State { addr: self.fst.root().addr(), count: 0 }
+    }
+
+    fn nodes(self) -> usize {
+        self.fst.as_bytes().len()
+    }
+
+    fn index(self, node: State) -> usize {
+        node.addr
+    }
+
+    fn count(self, node: State) -> usize {
+        let state = self.node(node);
+        if state.is_final() { (node.count + state.final_output().value()) as usize } else { 0 }
+    }
+
+    fn child(self, node: State, c: char) -> Option<State> {
+        let mut node = node;
+        for &b in c.encode_utf8(&mut [0; 4]).as_bytes() {
+            let state = self.node(node);
+            let t = state.transition(state.find_input(b)?);
+            node = State { addr: t.addr, count: node.count + t.out.value() };
+        }
+        Some(node)
+    }
+
+    fn for_each_child<F: FnMut(char, State)>(self, node: State, mut f: F) {
+        self.each_char(node, &mut Vec::with_capacity(4), &mut f);
+    }
+}
+
+#[cfg(test)]
+mod transducer_tests {
+    use super::{Bytes, Transducer};
+    use trie::Trie;
+
+    #[test]
+    fn answers_the_same_queries() {
+        let t = fixture();
+        let fst = Transducer::from_trie(&t);
+
+        assert_eq!((t.stats().words, t.stats().depth), (fst.stats().words, fst.stats().depth));
+        for word in &["walking", "talked", "walk", "walkin", "café", "cafe", "naïve", "", "xyzzy"] {
+            assert_eq!(t.search(word), fst.search(word), "{:?}", word);
+            assert_eq!(t.complete(word, 3), fst.complete(word, 3), "{:?}", word);
+            for k in 0..3 {
+                assert_eq!(t.search_with_k_edit(word, k), fst.search_with_k_edit(word, k),
+                           "{:?} within {}", word, k);
+            }
+        }
+    }
+
+    #[test]
+    fn shares_endings_of_any_count() {
+        let mut t = Trie::new();
+        for word in &["walked", "talked", "talked"] {
+            t.insert(word);
+        }
+
+        // A `Dawg` can share nothing here, as the counts differ.
+        let fst = Transducer::from_trie(&t);
+        assert!(fst.stats().nodes < t.minimize().stats().nodes);
+        assert_eq!((1, 2), (fst.search("walked"), fst.search("talked")));
+        assert!(fst.stats().heap_bytes < t.stats().heap_bytes);
+    }
+
+    #[test]
+    fn reads_its_bytes_back() {
+        let fst = Transducer::from_trie(&fixture());
+        let stats = fst.stats();
+        let read = Transducer::from_bytes(Bytes::Owned(fst.as_bytes().to_vec()), stats.nodes,
+                                          stats.depth).unwrap();
+
+        assert_eq!(stats, read.stats());
+        assert_eq!(fixture().iter().collect::<Vec<_>>(), read.words().unwrap());
+        assert!(Transducer::from_bytes(Bytes::Owned(b"hello".to_vec()), 1, 1).is_err());
+    }
+
+    #[test]
+    fn survives_keys_that_arent_utf8() {
+        let keys: Vec<(&[u8], u64)> = vec![(b"cat", 2), (b"ca\xff", 1), (b"c\xc3\xa9", 3)];
+        let bytes = fst::raw::Fst::from_iter_map(keys).unwrap().into_inner();
+        let fst = Transducer::from_bytes(Bytes::Owned(bytes), 5, 3).unwrap();
+
+        assert_eq!("a word in the model isn't UTF-8", fst.words().unwrap_err().to_string());
+        assert_eq!(vec![("cat".to_owned(), 2, 0)], fst.search_with_k_edit("cat", 0));
+        assert_eq!(vec![("cé".to_owned(), 3), ("cat".to_owned(), 2)], fst.complete("c", 5));
+    }
+
+    #[test]
+    fn handles_empty_trie() {
+        let fst = Transducer::from_trie(&Trie::new());
+        assert_eq!((0, 0), (fst.stats().words, fst.stats().depth));
+        assert_eq!(0, fst.search(""));
+        assert!(fst.search_with_k_edit("a", 2).is_empty());
+    }
+
+    fn fixture() -> Trie {
+        let mut t = Trie::new();
+        for word in &["walking", "talking", "walked", "talked", "talked", "café", "naïve", "a"] {
+            t.insert(word);
+        }
+        t
+    }
+}
@@ -0,0 +1,1354 @@
+/*!
+The dictionary: a trie of characters whose nodes count how many times the
+word ending there was seen in the corpus.
+
+Approximate searches run a Levenshtein automaton for the misspelled word
+over the trie, entering only the subtries that can still lead to a word
+within the allowed distance.
+*/
+
+use std::cmp::Reverse;
+use std::fmt;
+use std::mem;
+use std::time::Instant;
+
+/// A node of a `Trie`: its index in the trie's arena.
+pub type NodeId = u32;
+
+/// A node's children, as the character leading to each and the child,
+/// sorted by character. Most nodes have only a child or two, for which a
+/// short vector is both smaller and quicker to search than a hash map,
+/// and it always lists the children in the same order.
+pub type SubTries = Vec<(char, NodeId)>;
+
+/// The nodes all live in one arena, children referring to each other by
+/// index, rather than each owning its children. That's one allocation
+/// for the lot instead of one per node, the nodes of a word sit near each
+/// other in memory, and nothing recurses on drop. Nodes pruned by
+/// `remove` are kept on a free list for reuse.
+///
+/// Only inserting and removing words change a trie; searching it takes
+/// `&self` and changes nothing, so a trie is `Send + Sync`, and any
+/// number of threads can search one at once.
+#[derive(Clone, Debug)]
+pub struct Trie {
+    nodes: Vec<Node>,
+    free:  Vec<NodeId>,
+}
+
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Node {
+    pub(crate) count:    usize,
+    pub(crate) children: SubTries,
+}
+
+impl Node {
+    pub(crate) fn child(&self, c: char) -> Option<NodeId> {
+        self.find(c).ok().map(|i| self.children[i].1)
+    }
+
+    /// Where the child along `c` is in `children`, or where it would go.
+    fn find(&self, c: char) -> Result<usize, usize> {
+        self.children.binary_search_by_key(&c, |&(c, _)| c)
+    }
+}
+
+/// The root of every trie.
+pub(crate) const ROOT: NodeId = 0;
+
+impl Default for Trie {
+    fn default() -> Self {
+        Trie { nodes: vec![Node::default()], free: Vec::new() }
+    }
+}
+
+impl Trie {
+    pub fn new() -> Self {
+        Trie::default()
+    }
+
+    /// Records one more occurrence of `word`.
+    pub fn insert(&mut self, word: &str) {
+        self.insert_with_count(word, 1);
+    }
+
+    /// Records one more occurrence of `word`, and says whether it's the
+    /// first.
+    pub(crate) fn insert_is_new(&mut self, word: &str) -> bool {
+        let mut node = ROOT;
+        for c in word.chars() {
+            node = self.child_or_insert(node, c);
+        }
+        self.add_count(node, 1);
+        self.nodes[node as usize].count == 1
+    }
+
+    /// Records `count` more occurrences of `word` at once.
+    pub fn insert_with_count(&mut self, word: &str, count: usize) {
+        if count == 0 {
+            return;
+        }
+
+        let mut node = ROOT;
+        for c in word.chars() {
+            node = self.child_or_insert(node, c);
+        }
+        self.add_count(node, count);
+    }
+
+    /// How many times `word` was inserted.
+    pub fn search(&self, word: &str) -> usize {
+        let mut node = ROOT;
+        for c in word.chars() {
+            match self.node(node).child(c) {
+                Some(child) => node = child,
+                None => return 0,
+            }
+        }
+        self.node(node).count
+    }
+
+    /// Every word within `k` edits of `word`, as `(word, count, distance)`
+    /// triples. An edit is deleting, inserting or replacing one character,
+    /// or swapping two adjacent ones; the distance is `edit_distance`.
+    ///
+    /// The result is ordered best first: closest, then most frequent, then
+    /// alphabetical, so equally good candidates always come out the same
+    /// way round.
+    pub fn search_with_k_edit(&self, word: &str, k: usize) -> Vec<(String, usize, usize)> {
+        let mut found = self.within_edits(word, k);
+        rank(&mut found);
+        found
+    }
+
+    /// Every word within `k` edits of `word`, as `search_with_k_edit`
+    /// finds them, but in no particular order, for a caller that ranks the
+    /// candidates its own way and needn't pay for sorting them first.
+    pub fn within_edits(&self, word: &str, k: usize) -> Vec<(String, usize, usize)> {
+        let word: Vec<char> = word.chars().collect();
+        search_with_k_edit(self, &word, k)
+    }
+
+    /// How big the trie is.
+    pub fn stats(&self) -> Stats {
+        let mut depth = 0;
+        let mut stack = vec![(ROOT, 0)];
+        while let Some((node, d)) = stack.pop() {
+            depth = depth.max(d);
+            stack.extend(self.node(node).children.iter().map(|&(_, child)| (child, d + 1)));
+        }
+
+        let heap_bytes = self.nodes.capacity() * mem::size_of::<Node>()
+            + self.free.capacity() * mem::size_of::<NodeId>()
+            + self.nodes.iter()
+                .map(|node| node.children.capacity() * mem::size_of::<(char, NodeId)>())
+                .sum::<usize>();
+
+        // Free nodes are left with no count, so they don't count as words.
+        Stats {
+            words: self.nodes.iter().filter(|node| node.count > 0).count(),
+            nodes: self.nodes.len() - self.free.len(),
+            depth,
+            heap_bytes,
+        }
+    }
+
+    /// Every word in the trie with its count, in alphabetical order.
+    pub fn iter(&self) -> Words<'_> {
+        Words::new(self, ROOT, "")
+    }
+
+    /// Takes back one occurrence of `word`, returning false if it wasn't
+    /// there. Once its count is down to nothing, the nodes that led only
+    /// to it are pruned.
+    pub fn remove(&mut self, word: &str) -> bool {
+        // The path to the word, as each node and the character leading on
+        // from it.
+        let mut path: Vec<(NodeId, char)> = Vec::new();
+        let mut node = ROOT;
+        for c in word.chars() {
+            match self.node(node).child(c) {
+                Some(child) => {
+                    path.push((node, c));
+                    node = child;
+                }
+                None => return false,
+            }
+        }
+
+        if self.node(node).count == 0 {
+            return false;
+        }
+        self.nodes[node as usize].count -= 1;
+
+        while let Some((parent, c)) = path.pop() {
+            let child = &self.nodes[node as usize];
+            if child.count > 0 || !child.children.is_empty() {
+                break;
+            }
+
+            self.nodes[node as usize] = Node::default();
+            self.free.push(node);
+            let parent_node = &mut self.nodes[parent as usize];
+            let i = parent_node.find(c).expect("child on the path");
+            parent_node.children.remove(i);
+            node = parent;
+        }
+
+        true
+    }
+
+    /// Drops every word seen fewer than `min_count` times, returning how
+    /// many words were dropped. The trie is rebuilt without them, so it
+    /// takes no more memory than if they had never been inserted.
+    pub fn prune(&mut self, min_count: usize) -> usize {
+        let mut pruned = Trie::new();
+        let mut dropped = 0;
+        for (word, count) in self.iter() {
+            if count >= min_count {
+                pruned.insert_with_count(&word, count);
+            } else {
+                dropped += 1;
+            }
+        }
+        *self = pruned;
+        dropped
+    }
+
+    /// Drops every word longer than `max_chars` characters, returning how
+    /// many words were dropped. As with `prune`, the trie is rebuilt
+    /// without them.
+    pub fn drop_longer_than(&mut self, max_chars: usize) -> usize {
+        let mut kept = Trie::new();
+        let mut dropped = 0;
+        for (word, count) in self.iter() {
+            if word.chars().count() <= max_chars {
+                kept.insert_with_count(&word, count);
+            } else {
+                dropped += 1;
+            }
+        }
+        *self = kept;
+        dropped
+    }
+
+    /// Keeps only the `max_words` commonest words, returning how many
+    /// were dropped; of words seen as often, the first alphabetically are
+    /// kept. As with `prune`, the trie is rebuilt without the others.
+    pub fn keep_commonest(&mut self, max_words: usize) -> usize {
+        let mut words = self.words();
+        if words.len() <= max_words {
+            return 0;
+        }
+        words.sort_by(|a, b| (Reverse(a.1), &a.0).cmp(&(Reverse(b.1), &b.0)));
+        let dropped = words.len() - max_words;
+        let mut kept = Trie::new();
+        for (word, count) in words.into_iter().take(max_words) {
+            kept.insert_with_count(&word, count);
+        }
+        *self = kept;
+        dropped
+    }
+
+    /// Every word in the trie with its count, in alphabetical order, as
+    /// `iter` gives them.
+    pub(crate) fn words(&self) -> Vec<(String, usize)> {
+        self.iter().collect()
+    }
+
+    /// Up to `n` of the words starting with `prefix`, as `(word, count)`,
+    /// most frequent first and then alphabetical. `prefix` itself is
+    /// included if it is a word.
+    pub fn complete(&self, prefix: &str, n: usize) -> Vec<(String, usize)> {
+        complete(self, prefix, n)
+    }
+
+    /// Adds every count in `other` to this trie, as if its words had been
+    /// inserted here too.
+    pub fn merge(&mut self, other: Trie) {
+        let mut stack = vec![(ROOT, ROOT)];
+
+        while let Some((into, from)) = stack.pop() {
+            let from = other.node(from);
+            self.add_count(into, from.count);
+
+            for &(c, from_child) in &from.children {
+                let into_child = self.child_or_insert(into, c);
+                stack.push((into_child, from_child));
+            }
+        }
+    }
+
+    pub(crate) fn node(&self, id: NodeId) -> &Node {
+        &self.nodes[id as usize]
+    }
+
+    pub(crate) fn add_count(&mut self, node: NodeId, count: usize) {
+        self.nodes[node as usize].count += count;
+    }
+
+    /// The child of `node` along `c`, added if it isn't there yet.
+    pub(crate) fn child_or_insert(&mut self, node: NodeId, c: char) -> NodeId {
+        let at = match self.nodes[node as usize].find(c) {
+            Ok(i) => return self.nodes[node as usize].children[i].1,
+            Err(at) => at,
+        };
+
+        let child = match self.free.pop() {
+            Some(child) => child,
+            None => {
+                self.nodes.push(Node::default());
+                (self.nodes.len() - 1) as NodeId
+            }
+        };
+        self.nodes[node as usize].children.insert(at, (c, child));
+        child
+    }
+}
+
+/// Two tries are equal if they hold the same words with the same counts,
+/// however their nodes happen to be laid out.
+impl PartialEq for Trie {
+    fn eq(&self, other: &Trie) -> bool {
+        let mut stack = vec![(ROOT, ROOT)];
+
+        while let Some((a, b)) = stack.pop() {
+            let (a, b) = (self.node(a), other.node(b));
+            if a.count != b.count || a.children.len() != b.children.len() {
+                return false;
+            }
+
+            for (&(a_c, a_child), &(b_c, b_child)) in a.children.iter().zip(&b.children) {
+                if a_c != b_c {
+                    return false;
+                }
+                stack.push((a_child, b_child));
+            }
+        }
+
+        true
+    }
+}
+
+#[cfg(test)]
+mod insert_search_tests {
+    use super::{Trie, ROOT};
+
+    #[test]
+    fn empty_trie_knows_nothing() {
+        assert_eq!(0, Trie::new().search("hello"));
+        assert_eq!(0, Trie::new().search(""));
+    }
+
+    #[test]
+    fn counts_insertions() {
+        let mut t = Trie::new();
+        t.insert("hello");
+        t.insert("help");
+        t.insert("hello");
+
+        assert_eq!(2, t.search("hello"));
+        assert_eq!(1, t.search("help"));
+        assert_eq!(0, t.search("hel"));
+        assert_eq!(0, t.search("helping"));
+    }
+
+    #[test]
+    fn handles_long_words() {
+        let word = long_word(10_000);
+        let mut t = Trie::new();
+        t.insert(&word);
+        t.insert(&word);
+
+        assert_eq!(2, t.search(&word));
+        assert_eq!(0, t.search(&word[1..]));
+    }
+
+    #[test]
+    fn keeps_children_in_order() {
+        let mut t = Trie::new();
+        for word in &["cat", "apple", "bee", "ant", "café"] {
+            t.insert(word);
+        }
+
+        let chars: Vec<char> = t.node(ROOT).children.iter().map(|&(c, _)| c).collect();
+        assert_eq!(vec!['a', 'b', 'c'], chars);
+    }
+
+    #[test]
+    fn equality_ignores_layout() {
+        let mut a = Trie::new();
+        a.insert("hello");
+        a.insert("world");
+        let mut b = Trie::new();
+        b.insert("world");
+        b.insert("hello");
+
+        assert_eq!(a, b);
+        b.insert("hello");
+        assert!(a != b);
+        a.insert("help");
+        assert!(a != b);
+    }
+
+    /// A word of `len` letters with no two neighbours alike.
+    pub fn long_word(len: usize) -> String {
+        "abcdefghij".chars().cycle().take(len).collect()
+    }
+
+    #[test]
+    fn handles_non_ascii() {
+        let mut t = Trie::new();
+        t.insert("café");
+
+        assert_eq!(1, t.search("café"));
+        assert_eq!(0, t.search("cafe"));
+    }
+}
+
+#[cfg(test)]
+mod remove_tests {
+    use super::Trie;
+    use super::insert_search_tests::long_word;
+
+    #[test]
+    fn decrements_count() {
+        let mut t = trie(&["hello", "hello", "help"]);
+
+        assert!(t.remove("hello"));
+        assert_eq!(1, t.search("hello"));
+        assert_eq!(trie(&["hello", "help"]), t);
+    }
+
+    #[test]
+    fn prunes_dead_branches() {
+        let mut t = trie(&["hello", "help", "he"]);
+
+        assert!(t.remove("hello"));
+        assert_eq!(trie(&["help", "he"]), t);
+        assert_eq!((2, 5), (t.stats().words, t.stats().nodes));
+
+        // "he" is still a word, so removing "help" stops there.
+        assert!(t.remove("help"));
+        assert_eq!((1, 3), (t.stats().words, t.stats().nodes));
+
+        assert!(t.remove("he"));
+        assert_eq!(Trie::new(), t);
+        assert_eq!((0, 1), (t.stats().words, t.stats().nodes));
+    }
+
+    #[test]
+    fn ignores_missing_words() {
+        let mut t = trie(&["hello"]);
+
+        assert!(!t.remove("hell"));
+        assert!(!t.remove("help"));
+        assert!(!t.remove(""));
+        assert_eq!(trie(&["hello"]), t);
+    }
+
+    #[test]
+    fn reuses_pruned_nodes() {
+        let mut t = trie(&["hello"]);
+        t.remove("hello");
+        t.insert("world");
+
+        assert_eq!(trie(&["world"]), t);
+        assert_eq!((1, 6), (t.stats().words, t.stats().nodes));
+        assert_eq!(6, t.nodes.len());
+    }
+
+    #[test]
+    fn handles_long_words() {
+        let word = long_word(10_000);
+        let mut t = trie(&[&word, "ab"]);
+
+        assert!(t.remove(&word));
+        assert_eq!(trie(&["ab"]), t);
+    }
+
+    fn trie(words: &[&str]) -> Trie {
+        let mut t = Trie::new();
+        for word in words {
+            t.insert(word);
+        }
+        t
+    }
+}
+
+impl<'a> IntoIterator for &'a Trie {
+    type Item = (String, usize);
+    type IntoIter = Words<'a>;
+
+    fn into_iter(self) -> Words<'a> {
+        self.iter()
+    }
+}
+
+/// The words of a `Trie` and their counts, from `Trie::iter`.
+pub struct Words<'a> {
+    trie:  &'a Trie,
+    path:  Vec<char>,
+    /// Nodes still to visit, with the character leading to each and how
+    /// much of `path` comes before it.
+    stack: Vec<(NodeId, Option<char>, usize)>,
+}
+
+impl<'a> Words<'a> {
+    /// The words in the subtrie at `node`, which `prefix` leads to.
+    fn new(trie: &'a Trie, node: NodeId, prefix: &str) -> Self {
+        let path: Vec<char> = prefix.chars().collect();
+        let stack = vec![(node, None, path.len())];
+        Words { trie, path, stack }
+    }
+}
+
+impl<'a> Iterator for Words<'a> {
+    type Item = (String, usize);
+
+    fn next(&mut self) -> Option<(String, usize)> {
+        while let Some((node, c, depth)) = self.stack.pop() {
+            self.path.truncate(depth);
+            self.path.extend(c);
+
+            let node = self.trie.node(node);
+            let depth = self.path.len();
+            let children = node.children.iter().rev();
+            self.stack.extend(children.map(|&(c, child)| (child, Some(c), depth)));
+
+            if node.count > 0 {
+                return Some((self.path.iter().collect(), node.count));
+            }
+        }
+        None
+    }
+}
+
+/// The size of a `Trie` or `Dawg`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Stats {
+    /// Distinct words.
+    pub words:      usize,
+    /// Nodes, counting the root.
+    pub nodes:      usize,
+    /// The most characters in a word.
+    pub depth:      usize,
+    /// Roughly how much memory the nodes take up.
+    pub heap_bytes: usize,
+}
+
+impl fmt::Display for Stats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} words in {} nodes, up to {} deep, {} KiB",
+               self.words, self.nodes, self.depth, self.heap_bytes.div_ceil(1024))
+    }
+}
+
+#[cfg(test)]
+mod stats_tests {
+    use super::{Stats, Trie};
+
+    #[test]
+    fn counts_words_and_nodes() {
+        let stats = fixture().stats();
+        assert_eq!((3, 7, 5), (stats.words, stats.nodes, stats.depth));
+
+        let stats = Trie::new().stats();
+        assert_eq!((0, 1, 0), (stats.words, stats.nodes, stats.depth));
+    }
+
+    #[test]
+    fn estimates_heap_bytes() {
+        let small = Trie::new().stats().heap_bytes;
+        let big = fixture().stats().heap_bytes;
+
+        assert!(small > 0);
+        assert!(big > small);
+    }
+
+    #[test]
+    fn displays_summary() {
+        let stats = Stats { words: 3, nodes: 7, depth: 5, heap_bytes: 2000 };
+        assert_eq!("3 words in 7 nodes, up to 5 deep, 2 KiB", stats.to_string());
+    }
+
+    fn fixture() -> Trie {
+        let mut t = Trie::new();
+        for word in &["hello", "help", "hello", "he"] {
+            t.insert(word);
+        }
+        t
+    }
+}
+
+/// Puts `(word, count, distance)` candidates best first: closest, then
+/// most frequent, then alphabetical.
+pub(crate) fn rank(found: &mut [(String, usize, usize)]) {
+    found.sort_by(|a, b| (a.2, Reverse(a.1), &a.0).cmp(&(b.2, Reverse(b.1), &b.0)));
+}
+
+#[cfg(test)]
+mod words_tests {
+    use super::Trie;
+
+    #[test]
+    fn lists_every_word_in_order() {
+        let mut t = Trie::new();
+        for word in &["help", "hello", "he", "hello", ""] {
+            t.insert(word);
+        }
+
+        assert_eq!(vec![("".to_owned(), 1), ("he".to_owned(), 1), ("hello".to_owned(), 2),
+                         ("help".to_owned(), 1)], t.words());
+        assert!(Trie::new().words().is_empty());
+    }
+
+    #[test]
+    fn iterates_lazily() {
+        let mut t = Trie::new();
+        for word in &["b", "a", "c"] {
+            t.insert(word);
+        }
+
+        assert_eq!(Some(("a".to_owned(), 1)), t.iter().next());
+        assert_eq!(3, (&t).into_iter().count());
+    }
+}
+
+#[cfg(test)]
+mod prune_tests {
+    use super::Trie;
+
+    #[test]
+    fn drops_rare_words() {
+        let mut t = Trie::new();
+        for word in &["hello", "hello", "help", "he", "he", "he"] {
+            t.insert(word);
+        }
+
+        assert_eq!(0, t.clone().prune(1));
+        assert_eq!(1, t.prune(2));
+        assert_eq!(vec![("he".to_owned(), 3), ("hello".to_owned(), 2)], t.words());
+        assert_eq!(6, t.stats().nodes);
+
+        assert_eq!(2, t.prune(10));
+        assert_eq!(Trie::new(), t);
+    }
+
+    #[test]
+    fn drops_long_words() {
+        let mut t = Trie::new();
+        for word in &["hello", "hello", "help", "aGVsbG8gd29ybGQ", "ünïcödé"] {
+            t.insert(word);
+        }
+
+        assert_eq!(0, t.clone().drop_longer_than(15));
+        assert_eq!(1, t.drop_longer_than(7));
+        assert_eq!(vec![("hello".to_owned(), 2), ("help".to_owned(), 1),
+                        ("ünïcödé".to_owned(), 1)], t.words());
+    }
+
+    #[test]
+    fn keeps_commonest_words() {
+        let mut t = Trie::new();
+        for word in &["hello", "hello", "help", "hell", "he", "he", "he"] {
+            t.insert(word);
+        }
+
+        assert_eq!(0, t.clone().keep_commonest(4));
+        // "hell" and "help" are as common; "hell" comes first.
+        assert_eq!(1, t.keep_commonest(3));
+        assert_eq!(vec![("he".to_owned(), 3), ("hell".to_owned(), 1), ("hello".to_owned(), 2)],
+                   t.words());
+        assert_eq!(2, t.keep_commonest(1));
+        assert_eq!(vec![("he".to_owned(), 3)], t.words());
+        assert_eq!(1, t.keep_commonest(0));
+        assert_eq!(Trie::new(), t);
+    }
+}
+
+#[cfg(test)]
+mod complete_tests {
+    use super::Trie;
+
+    #[test]
+    fn orders_by_count_then_word() {
+        assert_eq!(vec![("help".to_owned(), 3), ("hell".to_owned(), 2), ("hello".to_owned(), 2),
+                        ("he".to_owned(), 1)],
+                   fixture().complete("he", 10));
+    }
+
+    #[test]
+    fn limits_to_n() {
+        assert_eq!(vec![("help".to_owned(), 3)], fixture().complete("he", 1));
+        assert!(fixture().complete("he", 0).is_empty());
+    }
+
+    #[test]
+    fn includes_prefix_itself() {
+        assert_eq!(vec![("hell".to_owned(), 2), ("hello".to_owned(), 2)],
+                   fixture().complete("hell", 10));
+    }
+
+    #[test]
+    fn empty_prefix_completes_anything() {
+        assert_eq!(vec![("help".to_owned(), 3), ("hell".to_owned(), 2)],
+                   fixture().complete("", 2));
+    }
+
+    #[test]
+    fn unknown_prefix_completes_nothing() {
+        assert!(fixture().complete("wor", 10).is_empty());
+        assert!(fixture().complete("helpx", 10).is_empty());
+    }
+
+    fn fixture() -> Trie {
+        let mut t = Trie::new();
+        for word in &["he", "hell", "hell", "hello", "hello", "help", "help", "help", "car"] {
+            t.insert(word);
+        }
+        t
+    }
+}
+
+#[cfg(test)]
+mod merge_tests {
+    use super::Trie;
+    use super::insert_search_tests::long_word;
+
+    #[test]
+    fn adds_counts() {
+        let mut t = trie(&["hello", "help", "hello"]);
+        t.merge(trie(&["hello", "world", "he"]));
+
+        assert_eq!(trie(&["hello", "help", "hello", "hello", "world", "he"]), t);
+        assert_eq!(3, t.search("hello"));
+        assert_eq!(1, t.search("he"));
+    }
+
+    #[test]
+    fn merges_empty_tries() {
+        let mut t = trie(&["hello"]);
+        t.merge(Trie::new());
+        assert_eq!(trie(&["hello"]), t);
+
+        let mut t = Trie::new();
+        t.merge(trie(&["hello"]));
+        assert_eq!(trie(&["hello"]), t);
+    }
+
+    #[test]
+    fn handles_long_words() {
+        let word = long_word(10_000);
+        let mut t = trie(&[&word]);
+        t.merge(trie(&[&word]));
+
+        assert_eq!(2, t.search(&word));
+    }
+
+    fn trie(words: &[&str]) -> Trie {
+        let mut t = Trie::new();
+        for word in words {
+            t.insert(word);
+        }
+        t
+    }
+}
+
+/// A Levenshtein automaton for `word`, in its Damerau form: it reads a
+/// candidate one character at a time, and its state says how many edits
+/// the candidate so far is from each prefix of `word`.
+///
+/// Only distances up to `k` matter, so a state keeps just the band of
+/// `2k + 1` prefixes within `k` characters of the candidate's length (the
+/// others are necessarily further away), and anything beyond `k` is held
+/// as `k + 1`. A state with nothing left within `k` is dead: no
+/// continuation of the candidate can match.
+///
+/// The bands of all the states live end to end in `cells`, so stepping
+/// the automaton doesn't allocate.
+struct Automaton<'a> {
+    word:  &'a [char],
+    k:     usize,
+    cells: Vec<usize>,
+}
+
+/// Where an `Automaton` is after reading `depth` characters: `cells[at]`
+/// onwards holds the distances to the prefixes of the word of length
+/// `depth - k` to `depth + k`.
+#[derive(Clone, Copy, Debug)]
+struct State {
+    depth: usize,
+    at:    usize,
+}
+
+impl<'a> Automaton<'a> {
+    fn new(word: &'a [char], k: usize) -> Self {
+        Automaton { word, k, cells: Vec::new() }
+    }
+
+    /// The state before reading anything.
+    fn start(&mut self) -> State {
+        let at = self.cells.len();
+        for t in 0..2 * self.k + 1 {
+            let distance = match t.checked_sub(self.k) {
+                Some(j) if j <= self.word.len() => j,
+                _ => self.k + 1,
+            };
+            self.cells.push(distance);
+        }
+        State { depth: 0, at }
+    }
+
+    /// The state after reading `c` in `state`, or `None` if it's dead. A
+    /// swap needs to look back one more character, to `last` and the
+    /// state `before` it was read.
+    fn step(&mut self, before: Option<State>, state: State, last: Option<char>, c: char)
+            -> Option<State> {
+        let depth = state.depth + 1;
+        let at = self.cells.len();
+        let mut alive = false;
+
+        for t in 0..2 * self.k + 1 {
+            let j = match (depth + t).checked_sub(self.k) {
+                Some(j) if j <= self.word.len() => j,
+                _ => {
+                    self.cells.push(self.k + 1);
+                    continue;
+                }
+            };
+
+            // Taking `c` where the word has nothing.
+            let mut distance = self.get(state, j) + 1;
+
+            if j == 0 {
+                distance = distance.min(depth);
+            } else {
+                // Matching or replacing the word's jth character, or
+                // leaving it out.
+                let replace = if self.word[j - 1] == c { 0 } else { 1 };
+                let left = if t > 0 { self.cells[at + t - 1] } else { self.k + 1 };
+                distance = distance.min(self.get(state, j - 1) + replace).min(left + 1);
+            }
+
+            if j > 1 && c == self.word[j - 2] && last == Some(self.word[j - 1]) {
+                if let Some(before) = before {
+                    distance = distance.min(self.get(before, j - 2) + 1);
+                }
+            }
+
+            let distance = distance.min(self.k + 1);
+            alive |= distance <= self.k;
+            self.cells.push(distance);
+        }
+
+        if alive {
+            Some(State { depth, at })
+        } else {
+            self.cells.truncate(at);
+            None
+        }
+    }
+
+    fn get(&self, state: State, j: usize) -> usize {
+        match (j + self.k).checked_sub(state.depth) {
+            Some(t) if t < 2 * self.k + 1 => self.cells[state.at + t],
+            _ => self.k + 1,
+        }
+    }
+
+    /// How far the candidate read so far is from the whole word, if it is
+    /// within `k`.
+    fn distance(&self, state: State) -> Option<usize> {
+        Some(self.get(state, self.word.len())).filter(|&distance| distance <= self.k)
+    }
+
+    /// Puts in `next` the only characters that can keep `state` alive, if
+    /// it has no edits to spare: those that match the word where it's
+    /// exactly `k` away, or complete a swap begun from `before`. Returns
+    /// false, leaving `next` empty, if it can still afford to replace or
+    /// insert anything.
+    fn only_next(&self, before: Option<State>, state: State, last: Option<char>,
+                 next: &mut Vec<char>) -> bool {
+        next.clear();
+        let band = state.depth.saturating_sub(self.k)..state.depth + self.k + 1;
+
+        for j in band.clone() {
+            match self.get(state, j) {
+                distance if distance < self.k => {
+                    next.clear();
+                    return false;
+                }
+                distance if distance == self.k && j < self.word.len() => next.push(self.word[j]),
+                _ => {}
+            }
+        }
+
+        if let Some(before) = before {
+            for j in band {
+                if j + 1 < self.word.len() && last == Some(self.word[j + 1])
+                    && self.get(before, j) < self.k {
+                    next.push(self.word[j]);
+                }
+            }
+        }
+
+        true
+    }
+}
+
+#[cfg(test)]
+mod automaton_tests {
+    use super::{Automaton, State};
+    use distance::edit_distance;
+
+    #[test]
+    fn measures_edit_distance() {
+        let word: Vec<char> = "hello".chars().collect();
+        for candidate in &["hello", "hlelo", "helo", "hellop", "jello", "ehllo", "hel", "lehlo"] {
+            for k in 0..4 {
+                let expected = Some(edit_distance("hello", candidate)).filter(|&d| d <= k);
+                assert_eq!(expected, read(&word, k, candidate).and_then(|(a, s)| a.distance(s)),
+                           "{:?} within {}", candidate, k);
+            }
+        }
+    }
+
+    #[test]
+    fn dies_once_out_of_reach() {
+        let word: Vec<char> = "hello".chars().collect();
+        assert!(read(&word, 1, "xx").is_none());
+        assert!(read(&word, 2, "xx").is_some());
+        assert!(read(&word, 2, "xxx").is_none());
+    }
+
+    fn read<'a>(word: &'a [char], k: usize, candidate: &str) -> Option<(Automaton<'a>, State)> {
+        let mut automaton = Automaton::new(word, k);
+        let (mut before, mut state, mut last) = (None, automaton.start(), None);
+
+        for c in candidate.chars() {
+            let next = automaton.step(before, state, last, c)?;
+            before = Some(state);
+            state = next;
+            last = Some(c);
+        }
+        Some((automaton, state))
+    }
+}
+
+/// What a search needs from a dictionary: a `Trie`, or anything else
+/// shaped like one.
+pub(crate) trait Graph: Copy {
+    type Node: Copy;
+
+    fn root(self) -> Self::Node;
+
+    /// How many nodes there are.
+    fn nodes(self) -> usize;
+
+    /// Where `node` is among them, from 0 up to `nodes`.
+    fn index(self, node: Self::Node) -> usize;
+
+    /// How many times the word ending at `node` was seen.
+    fn count(self, node: Self::Node) -> usize;
+
+    fn child(self, node: Self::Node, c: char) -> Option<Self::Node>;
+
+    /// Calls `f` on each child of `node` and the character leading to it.
+    fn for_each_child<F: FnMut(char, Self::Node)>(self, node: Self::Node, f: F);
+}
+
+impl Graph for &Trie {
+    type Node = NodeId;
+
+    fn root(self) -> NodeId {
+        ROOT
+    }
+
+    fn nodes(self) -> usize {
+        self.nodes.len()
+    }
+
+    fn index(self, node: NodeId) -> usize {
+        node as usize
+    }
+
+    fn count(self, node: NodeId) -> usize {
+        self.node(node).count
+    }
+
+    fn child(self, node: NodeId, c: char) -> Option<NodeId> {
+        self.node(node).child(c)
+    }
+
+    fn for_each_child<F: FnMut(char, NodeId)>(self, node: NodeId, mut f: F) {
+        for &(c, child) in &self.node(node).children {
+            f(c, child);
+        }
+    }
+}
+
+/// One node still to visit, with the automaton's state on reaching it
+/// and on reaching its parent, the character between the two, and the
+/// path taken, as an index into the search's path links.
+struct Frame<N> {
+    node:   N,
+    before: Option<State>,
+    state:  State,
+    last:   Option<char>,
+    path:   usize,
+}
+
+/// Runs the automaton for `word` over every path of `graph`, and returns
+/// the words at which it accepts, as `(word, count, distance)`.
+///
+/// A subtrie is only entered while the automaton is alive, so the walk
+/// covers just the prefixes within `k` edits of some prefix of `word`.
+/// It keeps its own stack rather than recursing, so the length of `word`
+/// is limited by memory rather than by the call stack, and paths are kept
+/// as `(parent, char)` links, only spelled out for the words found.
+pub(crate) fn search_with_k_edit<G: Graph>(graph: G, word: &[char], k: usize)
+                                          -> Vec<(String, usize, usize)> {
+    search_within(graph, word, k, Limits::default()).0
+}
+
+/// Up to `n` of the words of `graph` starting with `prefix`, as
+/// `Trie::complete` finds them.
+pub(crate) fn complete<G: Graph>(graph: G, prefix: &str, n: usize) -> Vec<(String, usize)> {
+    let mut node = graph.root();
+    for c in prefix.chars() {
+        match graph.child(node, c) {
+            Some(child) => node = child,
+            None => return Vec::new(),
+        }
+    }
+
+    let mut words = Vec::new();
+    let mut stack = vec![(node, prefix.to_owned())];
+    while let Some((node, word)) = stack.pop() {
+        graph.for_each_child(node, |c, child| {
+            let mut longer = word.clone();
+            longer.push(c);
+            stack.push((child, longer));
+        });
+        let count = graph.count(node);
+        if count > 0 {
+            words.push((word, count));
+        }
+    }
+    words.sort_by(|a, b| (Reverse(a.1), &a.0).cmp(&(Reverse(b.1), &b.0)));
+    words.truncate(n);
+    words
+}
+
+/// What a search may leave out to go faster.
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct Limits<'a> {
+    /// `(width, totals)`: go no further than the `width` children of a
+    /// node with the most words below them, as `totals` (see
+    /// `subtree_counts`) has it, wherever every child could still lead to
+    /// a word.
+    pub(crate) beam:     Option<(usize, &'a [usize])>,
+    /// Stop when this time comes, with whatever has been found by then.
+    pub(crate) deadline: Option<Instant>,
+}
+
+/// How a search went: how far it got, and which of its `Limits` cut it
+/// short.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) struct Outcome {
+    /// How many nodes the search visited.
+    pub(crate) visited:   usize,
+    /// The beam left some children out.
+    pub(crate) pruned:    bool,
+    /// The deadline came before the search was done.
+    pub(crate) timed_out: bool,
+}
+
+/// How many nodes a search visits between looks at the clock.
+const CLOCK_EVERY: usize = 256;
+
+/// `search_with_k_edit`, within `limits`, and how it went.
+pub(crate) fn search_within<G: Graph>(graph: G, word: &[char], k: usize, limits: Limits)
+                                      -> (Vec<(String, usize, usize)>, Outcome) {
+    let mut automaton = Automaton::new(word, k);
+    let mut paths: Vec<(usize, char)> = vec![(0, '\0')];
+    let mut found = Vec::new();
+    let start = automaton.start();
+    let mut stack = vec![Frame { node: graph.root(), before: None, state: start, last: None,
+                                 path: 0 }];
+    let mut next = Vec::new();
+    let mut children = Vec::new();
+    let mut spelling = Vec::new();
+    let mut outcome = Outcome::default();
+
+    while let Some(frame) = stack.pop() {
+        if let Some(deadline) = limits.deadline {
+            if outcome.visited % CLOCK_EVERY == 0 && Instant::now() >= deadline {
+                outcome.timed_out = true;
+                break;
+            }
+        }
+        outcome.visited += 1;
+
+        let count = graph.count(frame.node);
+        if count > 0 {
+            if let Some(distance) = automaton.distance(frame.state) {
+                found.push((spell(&paths, frame.path, &mut spelling), count, distance));
+            }
+        }
+
+        // With no edits to spare, look up the few children that could
+        // still match rather than trying every one.
+        if automaton.only_next(frame.before, frame.state, frame.last, &mut next) {
+            for (i, &c) in next.iter().enumerate() {
+                if next[..i].contains(&c) {
+                    continue;
+                }
+                if let Some(child) = graph.child(frame.node, c) {
+                    visit(&mut automaton, &mut paths, &mut stack, &frame, c, child);
+                }
+            }
+        } else if let Some((width, totals)) = limits.beam {
+            children.clear();
+            graph.for_each_child(frame.node, |c, child| children.push((c, child)));
+            if children.len() > width {
+                children.sort_by_key(|&(_, child)| Reverse(totals[graph.index(child)]));
+                children.truncate(width);
+                outcome.pruned = true;
+            }
+            for &(c, child) in &children {
+                visit(&mut automaton, &mut paths, &mut stack, &frame, c, child);
+            }
+        } else {
+            graph.for_each_child(frame.node, |c, child| {
+                visit(&mut automaton, &mut paths, &mut stack, &frame, c, child);
+            });
+        }
+    }
+
+    (found, outcome)
+}
+
+/// How many times the words at or below each node of `graph` were seen
+/// between them, by the node's `Graph::index`.
+pub(crate) fn subtree_counts<G: Graph>(graph: G) -> Vec<usize> {
+    let mut totals: Vec<Option<usize>> = vec![None; graph.nodes()];
+    // Each node comes off the stack once to queue its children, and again
+    // once they're all counted.
+    let mut stack = vec![(graph.root(), false)];
+
+    while let Some((node, counted)) = stack.pop() {
+        let id = graph.index(node);
+        if totals[id].is_some() {
+            continue;
+        }
+        if counted {
+            let mut total = graph.count(node);
+            graph.for_each_child(node, |_, child| total += totals[graph.index(child)].unwrap_or(0));
+            totals[id] = Some(total);
+        } else {
+            stack.push((node, true));
+            graph.for_each_child(node, |_, child| {
+                if totals[graph.index(child)].is_none() {
+                    stack.push((child, false));
+                }
+            });
+        }
+    }
+
+    totals.into_iter().map(|total| total.unwrap_or(0)).collect()
+}
+
+/// Steps the automaton from `frame` along `c`, and queues up `child` if
+/// it's still alive.
+fn visit<N>(automaton: &mut Automaton, paths: &mut Vec<(usize, char)>,
+            stack: &mut Vec<Frame<N>>, frame: &Frame<N>, c: char, child: N) {
+    if let Some(state) = automaton.step(frame.before, frame.state, frame.last, c) {
+        let path = extend(paths, frame.path, c);
+        stack.push(Frame { node: child, before: Some(frame.state), state, last: Some(c), path });
+    }
+}
+
+fn extend(paths: &mut Vec<(usize, char)>, parent: usize, c: char) -> usize {
+    paths.push((parent, c));
+    paths.len() - 1
+}
+
+/// The word along `path`, spelled out backwards in `chars` first, which
+/// is kept from one word to the next so only the word itself is new.
+fn spell(paths: &[(usize, char)], mut path: usize, chars: &mut Vec<char>) -> String {
+    chars.clear();
+    while path != 0 {
+        let (parent, c) = paths[path];
+        chars.push(c);
+        path = parent;
+    }
+    chars.iter().rev().collect()
+}
+
+#[cfg(test)]
+mod search_with_k_edit_tests {
+    use std::time::{Duration, Instant};
+
+    use super::{rank, search_within, subtree_counts, Limits, Trie, ROOT};
+    use distance::edit_distance;
+    use super::insert_search_tests::long_word;
+
+    #[test]
+    fn exact_match_costs_nothing() {
+        assert_eq!(vec![("hello".to_owned(), 2, 0)], search("hello", 0));
+    }
+
+    #[test]
+    fn finds_deletion() {
+        assert_eq!(vec![("hello".to_owned(), 2, 1)], search("helllo", 1));
+    }
+
+    #[test]
+    fn finds_insertion() {
+        assert_eq!(vec![("hello".to_owned(), 2, 1)], search("hllo", 1));
+    }
+
+    #[test]
+    fn finds_replacement() {
+        assert_eq!(vec![("hello".to_owned(), 2, 1)], search("hallo", 1));
+    }
+
+    #[test]
+    fn finds_transposition() {
+        assert_eq!(vec![("hello".to_owned(), 2, 1)], search("hlelo", 1));
+    }
+
+    #[test]
+    fn finds_everything_in_range() {
+        assert_eq!(vec![("hello".to_owned(), 2, 1), ("help".to_owned(), 1, 2)],
+                   search("hellx", 2));
+    }
+
+    #[test]
+    fn finds_the_same_unranked() {
+        let t = fixture();
+        for k in 0..4 {
+            let mut found = t.within_edits("hellx", k);
+            rank(&mut found);
+            assert_eq!(t.search_with_k_edit("hellx", k), found, "within {}", k);
+        }
+    }
+
+    #[test]
+    fn reports_shortest_distance() {
+        assert_eq!(vec![("world".to_owned(), 1, 1)], search("wrld", 2));
+    }
+
+    #[test]
+    fn respects_the_budget() {
+        assert!(search("hlp", 0).is_empty());
+        assert!(search("xyzzy", 2).is_empty());
+    }
+
+    #[test]
+    fn handles_long_words() {
+        let word = long_word(10_000);
+        let mut t = Trie::new();
+        t.insert(&word);
+
+        let mut typo = word.clone();
+        typo.replace_range(5_000..5_001, "x");
+
+        assert_eq!(vec![(word.clone(), 1, 1)], t.search_with_k_edit(&typo, 2));
+        assert_eq!(vec![(word.clone(), 1, 0)], t.search_with_k_edit(&word, 2));
+    }
+
+    #[test]
+    fn orders_ties_alphabetically() {
+        let mut t = Trie::new();
+        for word in &["hat", "cat", "bat", "at"] {
+            t.insert(word);
+        }
+        t.insert("at");
+
+        let words: Vec<_> = t.search_with_k_edit("xat", 1).into_iter().map(|c| c.0).collect();
+        assert_eq!(vec!["at", "bat", "cat", "hat"], words);
+    }
+
+    #[test]
+    fn matches_edit_distance() {
+        let t = fixture();
+        let words = t.words();
+
+        for query in &["", "h", "hlelo", "ehllo", "wrold", "dlrow", "pleh", "xyzzy", "helloworld"] {
+            for k in 0..6 {
+                let mut expected: Vec<_> = words.iter()
+                    .map(|&(ref word, count)| (word.clone(), count, edit_distance(query, word)))
+                    .filter(|&(_, _, distance)| distance <= k)
+                    .collect();
+                rank(&mut expected);
+
+                assert_eq!(expected, t.search_with_k_edit(query, k), "{:?} within {}", query, k);
+            }
+        }
+    }
+
+    #[test]
+    fn counts_subtrees() {
+        let t = fixture();
+        let totals = subtree_counts(&t);
+        assert_eq!(4, totals[ROOT as usize]);
+        let hel = ['h', 'e', 'l'].iter().fold(ROOT, |node, &c| t.node(node).child(c).unwrap());
+        assert_eq!(3, totals[hel as usize]);
+    }
+
+    #[test]
+    fn keeps_to_the_beam() {
+        let mut t = fixture();
+        for word in &["hello", "hello", "jello", "cello"] {
+            t.insert(word);
+        }
+        let totals = subtree_counts(&t);
+        let word: Vec<char> = "xello".chars().collect();
+
+        let limits = |width| Limits { beam: Some((width, &totals[..])), deadline: None };
+        let (mut found, cut) = search_within(&t, &word, 1, limits(1));
+        rank(&mut found);
+        assert_eq!(vec![("hello".to_owned(), 4, 1)], found);
+        assert!(cut.pruned && !cut.timed_out);
+
+        let (found, wide) = search_within(&t, &word, 1, limits(10));
+        assert_eq!(3, found.len());
+        assert!(!wide.pruned);
+        assert!(cut.visited < wide.visited);
+    }
+
+    #[test]
+    fn stops_at_the_deadline() {
+        let t = fixture();
+        let word: Vec<char> = "wrld".chars().collect();
+
+        let limits = Limits { beam: None, deadline: Some(Instant::now()) };
+        let (found, cut) = search_within(&t, &word, 2, limits);
+        assert!(found.is_empty());
+        assert!(cut.timed_out && !cut.pruned);
+        assert_eq!(0, cut.visited);
+
+        let deadline = Instant::now() + Duration::from_secs(60);
+        let limits = Limits { beam: None, deadline: Some(deadline) };
+        let (mut found, cut) = search_within(&t, &word, 2, limits);
+        rank(&mut found);
+        assert_eq!(t.search_with_k_edit("wrld", 2), found);
+        assert!(!cut.timed_out);
+    }
+
+    fn search(word: &str, k: usize) -> Vec<(String, usize, usize)> {
+        fixture().search_with_k_edit(word, k)
+    }
+
+    fn fixture() -> Trie {
+        let mut t = Trie::new();
+        t.insert("hello");
+        t.insert("hello");
+        t.insert("help");
+        t.insert("world");
+        t
+    }
+}
@@ -0,0 +1,527 @@
+/*!
+Input encodings.
+
+A `Decoder` wraps any reader and hands out UTF-8, whatever the input was
+written in. The encoding is picked with `--encoding auto|utf8|latin1|utf16`:
+
+ - `auto` honours a byte-order mark if there is one; otherwise the input
+   is UTF-8 if the first chunk read from it is, and Latin-1 if not.
+
+ - `utf8` requires valid UTF-8; a leading BOM is dropped.
+
+ - `latin1` maps every byte to the code point of the same value, so it
+   never fails.
+
+ - `utf16` uses the BOM to pick the byte order, and little-endian when
+   there is none.
+
+Once `auto` settles on UTF-8, invalid bytes later in the input are an
+error rather than a silent switch to Latin-1, since the text already
+handed out can't be taken back.
+
+The decision is made as soon as the input can't be the start of a BOM
+any more (nor ends part way through a UTF-8 sequence), so interactive
+input isn't held back waiting for more. `Decoder::detected` says what it
+was, so that text can be written back the way it was read.
+*/
+
+use std::convert::TryFrom;
+use std::fmt;
+use std::io::{self, Read};
+use std::str::FromStr;
+
+const CHUNK_LEN: usize = 8192;
+
+const UTF8_BOM:    &[u8] = &[0xEF, 0xBB, 0xBF];
+const UTF16LE_BOM: &[u8] = &[0xFF, 0xFE];
+const UTF16BE_BOM: &[u8] = &[0xFE, 0xFF];
+
+/// The encoding requested on the command line.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum Encoding {
+    #[default]
+    Auto,
+    Utf8,
+    Latin1,
+    Utf16,
+}
+
+impl FromStr for Encoding {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, String> {
+        match s {
+            "auto"   => Ok(Encoding::Auto),
+            "utf8"   => Ok(Encoding::Utf8),
+            "latin1" => Ok(Encoding::Latin1),
+            "utf16"  => Ok(Encoding::Utf16),
+            _ => Err(format!("unknown encoding `{}' (expected auto, utf8, latin1 or utf16)", s)),
+        }
+    }
+}
+
+impl fmt::Display for Encoding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match *self {
+            Encoding::Auto   => "auto",
+            Encoding::Utf8   => "utf8",
+            Encoding::Latin1 => "latin1",
+            Encoding::Utf16  => "utf16",
+        })
+    }
+}
+
+/// The encoding actually in use once the BOM has been looked at.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Source {
+    Utf8,
+    Latin1,
+    Utf16Le,
+    Utf16Be,
+}
+
+/// How a `Decoder`'s input turned out to be written: its encoding, and
+/// whether it began with a byte-order mark.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Detected {
+    source: Source,
+    bom:    bool,
+}
+
+impl Detected {
+    /// `text` written the way the input was, BOM and all, or an error if
+    /// it has a character the encoding can't hold, as Latin-1 can't.
+    pub fn encode(&self, text: &str) -> io::Result<Vec<u8>> {
+        let mut bytes = Vec::with_capacity(text.len() + 3);
+        match self.source {
+            Source::Utf8 => {
+                if self.bom {
+                    bytes.extend_from_slice(UTF8_BOM);
+                }
+                bytes.extend_from_slice(text.as_bytes());
+            }
+            Source::Latin1 => {
+                for c in text.chars() {
+                    let b = u8::try_from(c).map_err(|_| {
+                        io::Error::new(io::ErrorKind::InvalidData,
+                                       format!("`{}' can't be written in Latin-1", c))
+                    })?;
+                    bytes.push(b);
+                }
+            }
+            Source::Utf16Le => {
+                if self.bom {
+                    bytes.extend_from_slice(UTF16LE_BOM);
+                }
+                bytes.extend(text.encode_utf16().flat_map(u16::to_le_bytes));
+            }
+            Source::Utf16Be => {
+                if self.bom {
+                    bytes.extend_from_slice(UTF16BE_BOM);
+                }
+                bytes.extend(text.encode_utf16().flat_map(u16::to_be_bytes));
+            }
+        }
+        Ok(bytes)
+    }
+}
+
+/// Picks the source encoding for `bytes`, returning it together with the
+/// length of the BOM to skip. `eof` says whether `bytes` is all there is.
+fn sniff(bytes: &[u8], requested: Encoding, eof: bool) -> (Source, usize) {
+    match requested {
+        Encoding::Latin1 => (Source::Latin1, 0),
+
+        Encoding::Utf8 => {
+            let bom = if bytes.starts_with(UTF8_BOM) {UTF8_BOM.len()} else {0};
+            (Source::Utf8, bom)
+        }
+
+        Encoding::Utf16 => {
+            if bytes.starts_with(UTF16BE_BOM) {
+                (Source::Utf16Be, UTF16BE_BOM.len())
+            } else if bytes.starts_with(UTF16LE_BOM) {
+                (Source::Utf16Le, UTF16LE_BOM.len())
+            } else {
+                (Source::Utf16Le, 0)
+            }
+        }
+
+        Encoding::Auto => {
+            if bytes.starts_with(UTF8_BOM) {
+                (Source::Utf8, UTF8_BOM.len())
+            } else if bytes.starts_with(UTF16LE_BOM) {
+                (Source::Utf16Le, UTF16LE_BOM.len())
+            } else if bytes.starts_with(UTF16BE_BOM) {
+                (Source::Utf16Be, UTF16BE_BOM.len())
+            } else if looks_like_utf8(bytes, eof) {
+                (Source::Utf8, 0)
+            } else {
+                (Source::Latin1, 0)
+            }
+        }
+    }
+}
+
+/// Whether more input could still turn `bytes` into a BOM.
+fn could_become_bom(bytes: &[u8]) -> bool {
+    [UTF8_BOM, UTF16LE_BOM, UTF16BE_BOM].iter()
+        .any(|bom| bytes.len() < bom.len() && bom.starts_with(bytes))
+}
+
+/// Whether `bytes` is valid UTF-8 except for a sequence cut off at the end.
+fn ends_mid_utf8(bytes: &[u8]) -> bool {
+    match std::str::from_utf8(bytes) {
+        Ok(_)  => false,
+        Err(e) => e.error_len().is_none(),
+    }
+}
+
+fn looks_like_utf8(bytes: &[u8], eof: bool) -> bool {
+    match std::str::from_utf8(bytes) {
+        Ok(_)  => true,
+        // A sequence cut off by the end of the sample is fine, unless the
+        // sample is the whole input.
+        Err(e) => !eof && e.error_len().is_none(),
+    }
+}
+
+#[cfg(test)]
+mod sniff_tests {
+    use super::{could_become_bom, sniff, Encoding, Source};
+
+    #[test]
+    fn auto_honours_boms() {
+        assert_eq!((Source::Utf8, 3),    sniff(b"\xEF\xBB\xBFhi", Encoding::Auto, true));
+        assert_eq!((Source::Utf16Le, 2), sniff(b"\xFF\xFEh\0", Encoding::Auto, true));
+        assert_eq!((Source::Utf16Be, 2), sniff(b"\xFE\xFF\0h", Encoding::Auto, true));
+    }
+
+    #[test]
+    fn auto_prefers_utf8() {
+        assert_eq!((Source::Utf8, 0), sniff("café".as_bytes(), Encoding::Auto, true));
+    }
+
+    #[test]
+    fn auto_falls_back_to_latin1() {
+        assert_eq!((Source::Latin1, 0), sniff(b"caf\xE9", Encoding::Auto, true));
+    }
+
+    #[test]
+    fn auto_allows_truncated_sample() {
+        assert_eq!((Source::Utf8, 0), sniff(b"caf\xC3", Encoding::Auto, false));
+        assert_eq!((Source::Latin1, 0), sniff(b"caf\xC3", Encoding::Auto, true));
+    }
+
+    #[test]
+    fn waits_only_for_a_possible_bom() {
+        assert!(could_become_bom(b""));
+        assert!(could_become_bom(b"\xEF\xBB"));
+        assert!(could_become_bom(b"\xFE"));
+        assert!(!could_become_bom(b"a"));
+        assert!(!could_become_bom(b"\xEF\xBB\xBF"));
+    }
+
+    #[test]
+    fn explicit_encoding_wins() {
+        assert_eq!((Source::Latin1, 0), sniff(b"\xEF\xBB\xBFhi", Encoding::Latin1, true));
+        assert_eq!((Source::Utf16Le, 0), sniff(b"h\0i\0", Encoding::Utf16, true));
+    }
+}
+
+/// A reader that transcodes its input to UTF-8.
+pub struct Decoder<R> {
+    inner:     R,
+    requested: Encoding,
+    source:    Option<Source>,
+    bom:       bool,
+    raw:       Vec<u8>,
+    decoded:   Vec<u8>,
+    offset:    usize,
+    position:  usize,
+    eof:       bool,
+}
+
+impl<R: Read> Decoder<R> {
+    pub fn new(inner: R, encoding: Encoding) -> Self {
+        Decoder {
+            inner,
+            requested: encoding,
+            source:    None,
+            bom:       false,
+            raw:       Vec::new(),
+            decoded:   Vec::new(),
+            offset:    0,
+            position:  0,
+            eof:       false,
+        }
+    }
+
+    /// How the input is written, once enough of it has been read to tell,
+    /// as it always has by the end.
+    pub fn detected(&self) -> Option<Detected> {
+        self.source.map(|source| Detected { source, bom: self.bom })
+    }
+
+    /// Reads another chunk of input and decodes as much of it as possible.
+    fn fill(&mut self) -> io::Result<()> {
+        self.decoded.clear();
+        self.offset = 0;
+
+        let mut chunk = [0; CHUNK_LEN];
+        let n = loop {
+            match self.inner.read(&mut chunk) {
+                Err(ref e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                result => break result?,
+            }
+        };
+
+        if n == 0 {
+            self.eof = true;
+        } else {
+            self.raw.extend_from_slice(&chunk[..n]);
+        }
+
+        let source = match self.source {
+            Some(source) => source,
+            None => {
+                if !self.eof && (could_become_bom(&self.raw) ||
+                                 self.requested == Encoding::Auto && ends_mid_utf8(&self.raw)) {
+                    return Ok(());
+                }
+                let (source, bom) = sniff(&self.raw, self.requested, self.eof);
+                self.raw.drain(..bom);
+                self.position += bom;
+                self.source = Some(source);
+                self.bom = bom > 0;
+                source
+            }
+        };
+
+        let consumed = match source {
+            Source::Utf8    => self.decode_utf8()?,
+            Source::Latin1  => self.decode_latin1(),
+            Source::Utf16Le => self.decode_utf16(u16::from_le_bytes)?,
+            Source::Utf16Be => self.decode_utf16(u16::from_be_bytes)?,
+        };
+
+        self.raw.drain(..consumed);
+        self.position += consumed;
+        Ok(())
+    }
+
+    fn decode_utf8(&mut self) -> io::Result<usize> {
+        match std::str::from_utf8(&self.raw) {
+            Ok(s) => {
+                self.decoded.extend_from_slice(s.as_bytes());
+                Ok(self.raw.len())
+            }
+            Err(e) => {
+                let valid = e.valid_up_to();
+                if e.error_len().is_some() || self.eof {
+                    return Err(self.invalid(valid, "invalid UTF-8"));
+                }
+                self.decoded.extend_from_slice(&self.raw[..valid]);
+                Ok(valid)
+            }
+        }
+    }
+
+    fn decode_latin1(&mut self) -> usize {
+        let s: String = self.raw.iter().map(|&b| char::from(b)).collect();
+        self.decoded.extend_from_slice(s.as_bytes());
+        self.raw.len()
+    }
+
+    fn decode_utf16(&mut self, unit: fn([u8; 2]) -> u16) -> io::Result<usize> {
+        let mut units: Vec<u16> = self.raw.chunks_exact(2)
+            .map(|pair| unit([pair[0], pair[1]]))
+            .collect();
+
+        if self.eof && !self.raw.len().is_multiple_of(2) {
+            return Err(self.invalid(self.raw.len() - 1, "odd number of bytes in UTF-16"));
+        }
+
+        // Hold back a high surrogate whose partner is still to come.
+        if !self.eof {
+            if let Some(&last) = units.last() {
+                if (0xD800..0xDC00).contains(&last) {
+                    units.pop();
+                }
+            }
+        }
+
+        // Counted in units, not chars, as a surrogate pair is two.
+        let mut s = String::with_capacity(units.len());
+        let mut at = 0;
+        for c in char::decode_utf16(units.iter().cloned()) {
+            match c {
+                Ok(c) => {
+                    s.push(c);
+                    at += c.len_utf16();
+                }
+                Err(_) => return Err(self.invalid(2 * at, "unpaired surrogate in UTF-16")),
+            }
+        }
+
+        self.decoded.extend_from_slice(s.as_bytes());
+        Ok(2 * units.len())
+    }
+
+    fn invalid(&self, offset: usize, what: &str) -> io::Error {
+        io::Error::new(io::ErrorKind::InvalidData,
+                       format!("{} at byte {}", what, self.position + offset))
+    }
+}
+
+impl<R: Read> Read for Decoder<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.offset == self.decoded.len() {
+            if self.eof && self.raw.is_empty() {
+                return Ok(0);
+            }
+            self.fill()?;
+        }
+
+        let available = &self.decoded[self.offset..];
+        let count = available.len().min(buf.len());
+        buf[..count].copy_from_slice(&available[..count]);
+        self.offset += count;
+
+        Ok(count)
+    }
+}
+
+/// Decodes a whole byte string.
+pub fn decode(bytes: &[u8], encoding: Encoding) -> io::Result<String> {
+    let mut result = String::new();
+    Decoder::new(bytes, encoding).read_to_string(&mut result)?;
+    Ok(result)
+}
+
+#[cfg(test)]
+mod decoder_tests {
+    use super::{decode, Decoder, Encoding};
+    use std::io::{self, Read};
+
+    #[test]
+    fn passes_utf8_through() {
+        assert_eq!("héllo wörld", decode("héllo wörld".as_bytes(), Encoding::Auto).unwrap());
+    }
+
+    #[test]
+    fn strips_utf8_bom() {
+        assert_eq!("hi", decode(b"\xEF\xBB\xBFhi", Encoding::Utf8).unwrap());
+    }
+
+    #[test]
+    fn rejects_invalid_utf8() {
+        let err = decode(b"ok\xFFno", Encoding::Utf8).unwrap_err();
+        assert_eq!(io::ErrorKind::InvalidData, err.kind());
+        assert!(err.to_string().contains("byte 2"));
+    }
+
+    #[test]
+    fn transcodes_latin1() {
+        assert_eq!("café", decode(b"caf\xE9", Encoding::Latin1).unwrap());
+        assert_eq!("café", decode(b"caf\xE9", Encoding::Auto).unwrap());
+    }
+
+    #[test]
+    fn transcodes_utf16() {
+        assert_eq!("hé", decode(b"\xFF\xFEh\0\xE9\0", Encoding::Auto).unwrap());
+        assert_eq!("hé", decode(b"\xFE\xFF\0h\0\xE9", Encoding::Utf16).unwrap());
+        assert_eq!("hé", decode(b"h\0\xE9\0", Encoding::Utf16).unwrap());
+    }
+
+    #[test]
+    fn transcodes_utf16_surrogate_pairs() {
+        assert_eq!("\u{1F600}", decode(b"\x3D\xD8\x00\xDE", Encoding::Utf16).unwrap());
+    }
+
+    #[test]
+    fn rejects_unpaired_surrogate() {
+        assert!(decode(b"\x3D\xD8", Encoding::Utf16).is_err());
+
+        let err = decode(b"\x3D\xD8\x00\xDEa\0\x00\xDC", Encoding::Utf16).unwrap_err();
+        assert!(err.to_string().ends_with("at byte 6"), "{}", err);
+    }
+
+    #[test]
+    fn survives_chunk_boundaries() {
+        let mut input = vec![0xFF, 0xFE];
+        for unit in "a\u{1F600}é".encode_utf16() {
+            input.push(unit as u8);
+            input.push((unit >> 8) as u8);
+        }
+
+        let mut result = String::new();
+        Decoder::new(OneByteReader(&input), Encoding::Auto)
+            .read_to_string(&mut result).unwrap();
+        assert_eq!("a\u{1F600}é", result);
+    }
+
+    #[test]
+    fn does_not_wait_for_more_input() {
+        let mut decoder = Decoder::new(Reluctant(b"hi\n", false), Encoding::Auto);
+        let mut buf = [0; 16];
+
+        assert_eq!(3, decoder.read(&mut buf).unwrap());
+        assert_eq!(b"hi\n", &buf[..3]);
+    }
+
+    #[test]
+    fn writes_back_as_read() {
+        for (bytes, encoding) in [(&b"caf\xE9"[..], Encoding::Auto),
+                                  (b"\xEF\xBB\xBFcaf\xC3\xA9", Encoding::Auto),
+                                  (b"\xFE\xFF\0c\0a\0f\0\xE9", Encoding::Auto),
+                                  (b"c\0a\0f\0\xE9\0", Encoding::Utf16)] {
+            let mut decoder = Decoder::new(bytes, encoding);
+            let mut text = String::new();
+            decoder.read_to_string(&mut text).unwrap();
+            assert_eq!("café", text);
+            assert_eq!(bytes, &decoder.detected().unwrap().encode(&text).unwrap()[..]);
+        }
+    }
+
+    #[test]
+    fn refuses_what_latin1_cant_hold() {
+        let mut decoder = Decoder::new(&b"caf\xE9"[..], Encoding::Latin1);
+        decoder.read_to_string(&mut String::new()).unwrap();
+        let err = decoder.detected().unwrap().encode("ca\u{2019}").unwrap_err();
+        assert_eq!(io::ErrorKind::InvalidData, err.kind());
+    }
+
+    #[test]
+    fn parses_encoding_names() {
+        assert_eq!(Ok(Encoding::Latin1), "latin1".parse());
+        assert!("ebcdic".parse::<Encoding>().is_err());
+    }
+
+    /// Hands out its contents once, then fails as if reading were to
+    /// block.
+    struct Reluctant<'a>(&'a [u8], bool);
+
+    impl<'a> Read for Reluctant<'a> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            assert!(!self.1, "read again before the first line was handed out");
+            self.1 = true;
+            buf[..self.0.len()].copy_from_slice(self.0);
+            Ok(self.0.len())
+        }
+    }
+
+    /// Hands out its contents a byte at a time, to exercise carry-over.
+    struct OneByteReader<'a>(&'a [u8]);
+
+    impl<'a> Read for OneByteReader<'a> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            if self.0.is_empty() || buf.is_empty() {
+                return Ok(0);
+            }
+            buf[0] = self.0[0];
+            self.0 = &self.0[1..];
+            Ok(1)
+        }
+    }
+}
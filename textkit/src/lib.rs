@@ -0,0 +1,26 @@
+/*!
+textkit
+
+Input/output plumbing shared by the programs in this repository, so that
+freq, the spellchecker and friends agree on how text gets in and out.
+
+ - `encoding`: detects byte-order marks, validates UTF-8, and transcodes
+   Latin-1 and UTF-16 input to UTF-8 behind an ordinary `Read`.
+
+ - `progress`: `--progress` bars on stderr, hidden when stderr isn't a
+   terminal.
+
+ - `timings`: `--timings` reports of wall-clock time per phase.
+
+ - `tokenize`: the words of a line of running text, trimmed of
+   punctuation and folded, as freq counts them and the spellchecker
+   trains and checks them.
+*/
+
+extern crate indicatif;
+extern crate unicode_normalization;
+
+pub mod encoding;
+pub mod progress;
+pub mod timings;
+pub mod tokenize;
@@ -0,0 +1,107 @@
+/*!
+Progress reporting.
+
+Long-running phases (training, counting, walking directories, copying
+files) report through an `indicatif::ProgressBar` built here, so every
+program draws the same bars on stderr. A program asks for progress when
+the user passes `--progress`; the bar is still hidden when stderr isn't a
+terminal, so redirected logs don't fill up with control sequences.
+
+Bars are cheap to drive when hidden, so callers update them
+unconditionally instead of checking whether progress was requested.
+*/
+
+use std::io::{self, IsTerminal};
+
+use indicatif::{ProgressDrawTarget, ProgressStyle};
+
+pub use indicatif::{HumanCount, ProgressBar};
+
+const BYTES_TEMPLATE: &str =
+    "{msg} [{bar:40}] {bytes}/{total_bytes} {bytes_per_sec} ETA {eta}";
+const BYTES_SPINNER:  &str =
+    "{spinner} {msg} {bytes} {bytes_per_sec}";
+const ITEMS_TEMPLATE: &str =
+    "{msg} [{bar:40}] {human_pos}/{human_len} {per_sec} ETA {eta}";
+const ITEMS_SPINNER:  &str =
+    "{spinner} {msg} {human_pos} {per_sec}";
+
+/// Whether a bar should be drawn: the user asked for one and stderr is a
+/// terminal.
+pub fn enabled(requested: bool) -> bool {
+    requested && io::stderr().is_terminal()
+}
+
+/// A bar counting bytes, with an ETA when `total` is known.
+pub fn bytes(requested: bool, total: Option<u64>, message: &str) -> ProgressBar {
+    build(requested, total, message, BYTES_TEMPLATE, BYTES_SPINNER)
+}
+
+/// A bar counting items (words, files, ...), with an ETA when `total` is
+/// known.
+pub fn items(requested: bool, total: Option<u64>, message: &str) -> ProgressBar {
+    build(requested, total, message, ITEMS_TEMPLATE, ITEMS_SPINNER)
+}
+
+/// The size of standard input when it is redirected from a regular file,
+/// for use as a bar's total.
+#[cfg(unix)]
+pub fn stdin_len() -> Option<u64> {
+    use std::fs::File;
+    use std::os::fd::AsFd;
+
+    let fd = io::stdin().as_fd().try_clone_to_owned().ok()?;
+    let metadata = File::from(fd).metadata().ok()?;
+
+    if metadata.is_file() {Some(metadata.len())} else {None}
+}
+
+#[cfg(not(unix))]
+pub fn stdin_len() -> Option<u64> {
+    None
+}
+
+fn build(requested: bool, total: Option<u64>, message: &str,
+         bar: &str, spinner: &str) -> ProgressBar {
+    if !enabled(requested) {
+        return ProgressBar::hidden();
+    }
+
+    let (progress, template) = match total {
+        Some(len) => (ProgressBar::new(len), bar),
+        None      => (ProgressBar::new_spinner(), spinner),
+    };
+
+    let style = ProgressStyle::with_template(template)
+        .expect("progress template")
+        .progress_chars("=> ");
+
+    progress.set_draw_target(ProgressDrawTarget::stderr());
+    progress.set_style(style);
+    progress.set_message(message.to_owned());
+    progress
+}
+
+#[cfg(test)]
+mod progress_tests {
+    use super::{bytes, enabled, items};
+    use std::io::Read;
+
+    #[test]
+    fn disabled_unless_requested() {
+        assert!(!enabled(false));
+        assert!(bytes(false, Some(10), "reading").is_hidden());
+        assert!(items(false, None, "counting").is_hidden());
+    }
+
+    #[test]
+    fn hidden_bar_still_counts() {
+        let bar = bytes(false, None, "reading");
+        let mut reader = bar.wrap_read(&b"hello world"[..]);
+        let mut contents = String::new();
+        reader.read_to_string(&mut contents).unwrap();
+
+        assert_eq!("hello world", contents);
+        assert_eq!(11, bar.position());
+    }
+}
@@ -0,0 +1,128 @@
+/*!
+Phase timings.
+
+`--timings` makes a program report, on exit, how much wall-clock time it
+spent in each phase of its work (read, tokenize, train or count, search,
+sort, write). Phases that interleave, like reading and tokenizing a line
+at a time, are timed piecewise and the pieces summed.
+
+A disabled `Timings` doesn't look at the clock at all, so instrumented
+code costs nothing extra when the flag is off.
+*/
+
+use std::fmt;
+use std::time::{Duration, Instant};
+
+#[derive(Clone, Debug)]
+pub struct Timings {
+    enabled: bool,
+    start:   Instant,
+    phases:  Vec<(&'static str, Duration)>,
+}
+
+impl Default for Timings {
+    fn default() -> Self {
+        Timings::new(false)
+    }
+}
+
+impl Timings {
+    pub fn new(enabled: bool) -> Self {
+        Timings {
+            enabled,
+            start:  Instant::now(),
+            phases: Vec::new(),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+
+    /// Runs `f`, charging the time it takes to `phase`.
+    pub fn time<T, F: FnOnce() -> T>(&mut self, phase: &'static str, f: F) -> T {
+        if !self.enabled {
+            return f();
+        }
+
+        let start = Instant::now();
+        let result = f();
+        self.add(phase, start.elapsed());
+        result
+    }
+
+    /// Charges `elapsed` to `phase`. Phases are reported in the order
+    /// they were first charged.
+    pub fn add(&mut self, phase: &'static str, elapsed: Duration) {
+        if !self.enabled {
+            return;
+        }
+
+        match self.phases.iter_mut().find(|p| p.0 == phase) {
+            Some(p) => p.1 += elapsed,
+            None    => self.phases.push((phase, elapsed)),
+        }
+    }
+
+    pub fn get(&self, phase: &str) -> Option<Duration> {
+        self.phases.iter().find(|p| p.0 == phase).map(|p| p.1)
+    }
+
+    /// Prints the report to stderr if timings were requested.
+    pub fn report(&self) {
+        if self.enabled {
+            eprint!("{}", self);
+        }
+    }
+}
+
+impl fmt::Display for Timings {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "timings:")?;
+        for &(phase, elapsed) in &self.phases {
+            writeln!(f, "  {:<10}{:>12.3?}", phase, elapsed)?;
+        }
+        writeln!(f, "  {:<10}{:>12.3?}", "total", self.start.elapsed())
+    }
+}
+
+#[cfg(test)]
+mod timings_tests {
+    use super::Timings;
+    use std::time::Duration;
+
+    #[test]
+    fn disabled_records_nothing() {
+        let mut t = Timings::new(false);
+
+        assert_eq!(5, t.time("read", || 5));
+        t.add("sort", Duration::from_millis(3));
+        assert_eq!(None, t.get("read"));
+        assert_eq!(None, t.get("sort"));
+    }
+
+    #[test]
+    fn accumulates_phases() {
+        let mut t = Timings::new(true);
+        t.add("read", Duration::from_millis(3));
+        t.add("write", Duration::from_millis(1));
+        t.add("read", Duration::from_millis(4));
+
+        assert_eq!(Some(Duration::from_millis(7)), t.get("read"));
+        assert_eq!(Some(Duration::from_millis(1)), t.get("write"));
+    }
+
+    #[test]
+    fn reports_in_first_seen_order() {
+        let mut t = Timings::new(true);
+        t.add("write", Duration::from_millis(1));
+        t.add("read", Duration::from_millis(2));
+        let report = t.to_string();
+
+        let lines: Vec<&str> = report.lines().collect();
+        assert_eq!("timings:", lines[0]);
+        assert!(lines[1].trim_start().starts_with("write"));
+        assert!(lines[2].trim_start().starts_with("read"));
+        assert!(lines[3].trim_start().starts_with("total"));
+    }
+}
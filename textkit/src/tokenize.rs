@@ -0,0 +1,93 @@
+/*!
+Splitting running text into words.
+
+A word is a run of characters between whitespace, with the punctuation
+of `MARKS` trimmed from either end of it; an apostrophe inside a word,
+as in "don't", is kept. `tokenize` gives the words folded as `fold_word`
+folds them, in Unicode normal form C and lowercase, with typographic
+apostrophes made straight, so that freq's counts, the spellchecker's
+models and the words checked against them all agree.
+*/
+
+use unicode_normalization::UnicodeNormalization;
+
+/// Punctuation trimmed from either end of a word. Apostrophes inside a
+/// word, as in "don't", are kept.
+pub const MARKS: &[char] = &[',','.','!','?',':',';','(',')','\'','"','[',']','-',
+                              '\u{2018}','\u{2019}','\u{201c}','\u{201d}'];
+
+/// The typographic apostrophe, which is also a closing single quote.
+const APOSTROPHE: char = '\u{2019}';
+
+/// Splits `line` into lowercase words, trimming surrounding punctuation.
+pub fn tokenize(line: &str) -> Vec<String> {
+    word_spans(line).into_iter().map(|(_, word)| fold_word(word)).collect()
+}
+
+/// `word` as a model has it: in Unicode normal form C, so that an "é"
+/// typed as "e" and a combining accent is the same as a single "é",
+/// lowercased, and with typographic apostrophes made straight, so that
+/// "Don’t" is "don't".
+pub fn fold_word(word: &str) -> String {
+    word.nfc().collect::<String>().to_lowercase().replace(APOSTROPHE, "'")
+}
+
+/// The words `tokenize` finds in `line`, as they are written there, each
+/// with the byte offset it starts at.
+pub fn word_spans(line: &str) -> Vec<(usize, &str)> {
+    spans_trimming(line, |c| MARKS.contains(&c))
+}
+
+/// `word_spans`, trimming the characters `is_mark` says are punctuation
+/// rather than `MARKS`.
+pub fn spans_trimming<F: Fn(char) -> bool>(line: &str, is_mark: F) -> Vec<(usize, &str)> {
+    line.split_whitespace()
+        .filter_map(|word| {
+            let trimmed = word.trim_matches(&is_mark);
+            if trimmed.is_empty() {
+                return None;
+            }
+            let start = word.as_ptr() as usize - line.as_ptr() as usize;
+            let lead = word.len() - word.trim_start_matches(&is_mark).len();
+            Some((start + lead, trimmed))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tokenize_tests {
+    use super::{fold_word, spans_trimming, tokenize, word_spans};
+
+    #[test]
+    fn splits_on_whitespace() {
+        assert_eq!(vec!["hello", "big", "world"], tokenize("hello  big\tworld"));
+    }
+
+    #[test]
+    fn trims_marks_and_lowercases() {
+        assert_eq!(vec!["hello", "world", "don't"], tokenize("(Hello), \"WORLD!\" -- don't"));
+    }
+
+    #[test]
+    fn keeps_contractions_whole() {
+        assert_eq!(vec!["don't", "it's", "o'clock", "quoted", "students"],
+                   tokenize("Don\u{2019}t 'it's' o'clock \u{2018}quoted\u{2019} students'"));
+        assert_eq!(vec!["said", "won't"],
+                   tokenize("\u{201c}Said\u{201d} \u{201c}won\u{2019}t\u{201d}"));
+        assert_eq!("don't", fold_word("DON\u{2019}T"));
+    }
+
+    #[test]
+    fn finds_where_words_start() {
+        assert_eq!(vec![(1, "Hello"), (10, "WORLD"), (21, "don't")],
+                   word_spans("(Hello), \"WORLD!\" -- don't"));
+        assert_eq!(vec![(1, "café"), (9, "ok")], word_spans("\tcafé, \"ok"));
+        assert_eq!(vec![(2, "Bonjour")], spans_trimming("«Bonjour»,", |c| "«»,".contains(c)));
+    }
+
+    #[test]
+    fn normalizes_words() {
+        assert_eq!("café", fold_word("Cafe\u{301}"));
+        assert_eq!(vec!["café", "café"], tokenize("café CAFE\u{301}"));
+    }
+}